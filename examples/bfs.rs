@@ -11,6 +11,7 @@ use timely::dataflow::operators::probe::Handle;
 use differential_dataflow::{Collection, AsCollection};
 use differential_dataflow::operators::*;
 use differential_dataflow::lattice::Lattice;
+use differential_dataflow::harness::Measurements;
 
 type Node = u32;
 type Edge = (Node, Node);
@@ -58,26 +59,31 @@ fn main() {
 
         println!("performing BFS on {} nodes, {} edges:", nodes, edges);
 
-        if worker.index() == 0 {
+        let mut measurements = Measurements::new("bfs");
+
+        measurements.time_load(|| {
+
+            if worker.index() == 0 {
 
-            let mut session = differential_dataflow::input::InputSession::from(&mut graph);
+                let mut session = differential_dataflow::input::InputSession::from(&mut graph);
 
-            // trickle edges in to dataflow
-            for _ in 0..(edges/1000) {
-                for _ in 0..1000 {
+                // trickle edges in to dataflow
+                for _ in 0..(edges/1000) {
+                    for _ in 0..1000 {
+                        session.insert((rng1.gen_range(0, nodes), rng1.gen_range(0, nodes)));
+                    }
+                    worker.step();
+                }
+                for _ in 0.. (edges % 1000) {
                     session.insert((rng1.gen_range(0, nodes), rng1.gen_range(0, nodes)));
                 }
-                worker.step();
-            }
-            for _ in 0.. (edges % 1000) {
-                session.insert((rng1.gen_range(0, nodes), rng1.gen_range(0, nodes)));
             }
-        }
 
-        println!("loaded; elapsed: {:?}", timer.elapsed());
+            graph.advance_to(1);
+            worker.step_while(|| probe.less_than(graph.time()));
+        });
 
-        graph.advance_to(1);
-        worker.step_while(|| probe.less_than(graph.time()));
+        println!("loaded; elapsed: {:?}", timer.elapsed());
 
         let mut session = differential_dataflow::input::InputSession::from(&mut graph);
         for round in 0 .. rounds {
@@ -88,16 +94,18 @@ fn main() {
                 }
                 session.advance_to(2 + round * batch + element);                
             }
-            session.flush();
 
-            let timer = ::std::time::Instant::now();
-            worker.step_while(|| probe.less_than(&session.time()));
+            measurements.time_round(|| {
+                session.flush();
+                worker.step_while(|| probe.less_than(&session.time()));
+            });
+        }
 
-            if worker.index() == 0 {
-                let elapsed = timer.elapsed();
-                println!("{:?}:\t{}", round, elapsed.as_secs() * 1000000000 + (elapsed.subsec_nanos() as u64));
-            }
+        if worker.index() == 0 {
+            println!("{}", Measurements::csv_header());
+            println!("{}", measurements.to_csv());
         }
+
         println!("finished; elapsed: {:?}", timer.elapsed());
     }).unwrap();
 }
@@ -10,8 +10,13 @@ use timely::dataflow::*;
 use timely::dataflow::operators::*;
 
 use differential_dataflow::Collection;
+use differential_dataflow::hashable::OrdWrapper;
 use differential_dataflow::operators::*;
+use differential_dataflow::operators::arrange::{Arrange, ArrangeByKey};
+use differential_dataflow::operators::join::JoinArranged;
 use differential_dataflow::lattice::Lattice;
+use differential_dataflow::trace::Trace;
+use differential_dataflow::trace::implementations::ord_dict::OrdValDictSpine;
 
 type Node = u32;
 type Edge = (Node, Node);
@@ -120,10 +125,13 @@ where G::Timestamp: Lattice+Ord {
 
     roots.iterate(|inner| {
 
-        let edges = edges.enter(&inner.scope());
+        let edges = edges.enter(&inner.scope()).arrange_by_key_hashed();
         let roots = roots.enter(&inner.scope());
 
-        inner.join_map(&edges, |_k,&l,&d| (d, l))
+        // the iterates have few distinct times, which the dictionary spine stores once per batch.
+        inner.map(|(n, l)| (OrdWrapper { item: n }, l))
+             .arrange(OrdValDictSpine::new())
+             .join_arranged(&edges, |_k,&l,&d| (d, l))
              .concat(&roots)
              .distinct()
      })
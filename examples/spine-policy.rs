@@ -0,0 +1,59 @@
+extern crate rand;
+extern crate differential_dataflow;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use differential_dataflow::trace::{Batch, Batcher, Cursor, Trace, TraceReader};
+use differential_dataflow::trace::implementations::ord::{OrdValBatch, OrdValSpine};
+use differential_dataflow::trace::implementations::spine::MergePolicy;
+
+// Compares the merge policies of spines, by inserting random updates in batches and then looking up random keys.
+//
+// Usage: spine-policy <keys> <batch size> <batches> <lookups> <ratio>
+fn main() {
+
+    let keys: usize = std::env::args().nth(1).unwrap().parse().unwrap();
+    let size: usize = std::env::args().nth(2).unwrap().parse().unwrap();
+    let batches: usize = std::env::args().nth(3).unwrap().parse().unwrap();
+    let lookups: usize = std::env::args().nth(4).unwrap().parse().unwrap();
+    let ratio: usize = std::env::args().nth(5).unwrap().parse().unwrap();
+
+    let policies = vec![
+        ("binary", MergePolicy::Binary),
+        ("leveled", MergePolicy::Leveled { base: size, ratio: ratio }),
+    ];
+
+    for (name, policy) in policies {
+
+        let seed: &[_] = &[1, 2, 3, 4];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let mut trace = OrdValSpine::<usize, usize, usize, isize>::new().with_merge_policy(policy);
+        let mut batcher = <OrdValBatch<usize, usize, usize, isize> as Batch<usize, usize, usize, isize>>::Batcher::new();
+
+        let timer = ::std::time::Instant::now();
+        for round in 0 .. batches {
+            let mut updates = (0 .. size).map(|_| ((rng.gen_range(0, keys), rng.gen_range(0, keys)), round, 1)).collect();
+            batcher.push_batch(&mut updates);
+            trace.insert(batcher.seal(&[round + 1]));
+            trace.distinguish_since(&[round + 1]);
+            trace.advance_by(&[round + 1]);
+        }
+        let inserted = timer.elapsed();
+
+        let mut count = 0;
+        trace.map_batches(|_| count += 1);
+
+        let timer = ::std::time::Instant::now();
+        let mut found = 0;
+        for _ in 0 .. lookups {
+            let key = rng.gen_range(0, keys);
+            let mut cursor = trace.cursor();
+            cursor.seek_key(&key);
+            if cursor.key_valid() && cursor.key() == &key { found += 1; }
+        }
+        let looked_up = timer.elapsed();
+
+        println!("{}:\tinserted in {:?},\t{} batches,\tlooked up {} of {} keys in {:?}", name, inserted, count, found, lookups, looked_up);
+    }
+}
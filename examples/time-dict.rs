@@ -0,0 +1,111 @@
+extern crate rand;
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+use std::cell::Cell;
+
+use rand::{Rng, SeedableRng, StdRng};
+
+use timely::dataflow::operators::*;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::*;
+use differential_dataflow::operators::arrange::{Arrange, ArrangeByKey};
+use differential_dataflow::operators::join::JoinArranged;
+use differential_dataflow::trace::{BatchReader, Trace};
+use differential_dataflow::trace::implementations::ord::OrdValSpine;
+use differential_dataflow::trace::implementations::ord_dict::OrdValDictSpine;
+
+// times within the iterative scope.
+type Time = Product<Product<RootTimestamp, u64>, u64>;
+
+// Compares `OrdValSpine` and `OrdValDictSpine` arranging the iterates of reachability on a random graph,
+// reporting the time to compute reachability and the updates and bytes of times in the batches produced.
+//
+// Usage: time-dict <nodes> <edges> <rounds> <dict|ord>
+fn main() {
+
+    let nodes: u32 = std::env::args().nth(1).unwrap().parse().unwrap();
+    let edges: u32 = std::env::args().nth(2).unwrap().parse().unwrap();
+    let rounds: u32 = std::env::args().nth(3).unwrap().parse().unwrap();
+    let dictionary: bool = std::env::args().nth(4).unwrap() == "dict";
+
+    timely::execute_from_args(std::env::args().skip(5), move |worker| {
+
+        // updates and bytes of times in the batches the iterates' arrangement produces.
+        let updates = Rc::new(Cell::new(0));
+        let time_bytes = Rc::new(Cell::new(0));
+
+        let mut probe = Handle::new();
+        let (mut roots, mut graph) = worker.dataflow(|scope| {
+
+            let (root_input, roots) = scope.new_input();
+            let (edge_input, graph) = scope.new_input();
+
+            let roots = roots.as_collection().map(|x: u32| (x, x));
+            let graph = graph.as_collection();
+
+            let updates = updates.clone();
+            let time_bytes = time_bytes.clone();
+
+            roots.iterate(|inner| {
+
+                let edges = graph.enter(&inner.scope()).arrange_by_key_hashed();
+                let roots = roots.enter(&inner.scope());
+                let keyed = inner.map(|(n, l)| (OrdWrapper { item: n }, l));
+
+                let next = if dictionary {
+                    let arranged = keyed.arrange(OrdValDictSpine::new());
+                    arranged.stream.inspect(move |batch| {
+                        updates.set(updates.get() + batch.item.len());
+                        time_bytes.set(time_bytes.get() + 4 * batch.item.len() + batch.item.times.len() * std::mem::size_of::<Time>());
+                    });
+                    arranged.join_arranged(&edges, |_k, &l, &d| (d, l))
+                }
+                else {
+                    let arranged = keyed.arrange(OrdValSpine::new());
+                    arranged.stream.inspect(move |batch| {
+                        updates.set(updates.get() + batch.item.len());
+                        time_bytes.set(time_bytes.get() + batch.item.len() * std::mem::size_of::<Time>());
+                    });
+                    arranged.join_arranged(&edges, |_k, &l, &d| (d, l))
+                };
+
+                next.concat(&roots).distinct()
+            })
+            .probe_with(&mut probe);
+
+            (root_input, edge_input)
+        });
+
+        let seed: &[_] = &[1, 2, 3, 4];
+        let mut rng: StdRng = SeedableRng::from_seed(seed);
+
+        let timer = ::std::time::Instant::now();
+
+        if worker.index() == 0 {
+            for _ in 0 .. edges {
+                graph.send(((rng.gen_range(0, nodes), rng.gen_range(0, nodes)), Default::default(), 1));
+            }
+        }
+
+        for round in 0 .. rounds {
+            if worker.index() == 0 {
+                let time = *roots.time();
+                roots.send((round, time, 1));
+            }
+            roots.advance_to(round as u64 + 1);
+            graph.advance_to(round as u64 + 1);
+            worker.step_while(|| probe.less_than(graph.time()));
+        }
+
+        println!("{}:\tworker {} computed in {:?},\t{} updates,\t{} bytes of times",
+            if dictionary { "dict" } else { "ord" }, worker.index(), timer.elapsed(), updates.get(), time_bytes.get());
+
+    }).unwrap();
+}
@@ -0,0 +1,114 @@
+//! Graph algorithms over collections of directed edges.
+//!
+//! The algorithms are maintained incrementally under changes to the edges. Each iterative algorithm arranges
+//! the edges once, outside of its loop, and brings the arrangement into the loop rather than re-arranging
+//! the edges in each iteration.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // the number of nodes in each class of nodes connected by edges.
+//! equivalences(&edges)
+//!     .map(|(_node, representative)| representative)
+//!     .count()
+//!     .inspect(|x| println!("class sizes: {:?}", x));
+//!
+//! // the edges within strongly connected components.
+//! scc(&edges)
+//!     .inspect(|x| println!("cyclic edge: {:?}", x));
+//! ```
+
+use std::hash::Hash;
+use std::fmt::Debug;
+
+use timely::dataflow::Scope;
+
+use ::{Collection, Data};
+use lattice::Lattice;
+use operators::{Distinct, Group, Iterate, Join};
+use operators::arrange::ArrangeByKey;
+use operators::join::JoinArranged;
+
+/// Returns each node with the representative of its equivalence class, where edges relate equivalent nodes.
+///
+/// Edges are treated as undirected, and the representative of each class is its least node. Each node
+/// repeatedly adopts the least representative proposed by itself and its neighbors, and so the number of
+/// iterations is bounded by the largest diameter of a class.
+pub fn equivalences<G, N>(edges: &Collection<G, (N, N)>) -> Collection<G, (N, N)>
+where G: Scope, G::Timestamp: Lattice+Ord+Debug, N: Data+Default+Hash {
+
+    let nodes = edges.flat_map(|(a, b)| vec![a, b])
+                     .distinct()
+                     .map(|node| (node.clone(), node));
+
+    let symmetric = edges.filter(|&(ref a, ref b)| a != b)
+                         .flat_map(|(a, b)| vec![(a.clone(), b.clone()), (b, a)])
+                         .arrange_by_key_hashed();
+
+    nodes.iterate(|labels| {
+
+        let edges = symmetric.enter(&labels.scope());
+        let nodes = nodes.enter(&labels.scope());
+
+        labels.join_arranged(&edges, |_node, label, neighbor| (neighbor.clone(), label.clone()))
+              .concat(&nodes)
+              .group(|_node, labels, output| output.push((labels[0].0.clone(), 1)))
+    })
+}
+
+/// Returns the edges whose endpoints are in the same strongly connected component.
+///
+/// Edges that cannot lie on cycles, those leaving nodes without incoming edges or entering nodes without
+/// outgoing edges, are first trimmed away. The remaining edges are then repeatedly restricted to those whose
+/// endpoints reach the same least node, first along the edges and then along their reversals, until no
+/// further edges are removed.
+pub fn scc<G, N>(edges: &Collection<G, (N, N)>) -> Collection<G, (N, N)>
+where G: Scope, G::Timestamp: Lattice+Ord+Debug, N: Data+Default+Hash {
+
+    let trimmed = trim_and_flip(&trim_and_flip(edges));
+
+    trimmed.iterate(|inner| {
+        let edges = trimmed.enter(&inner.scope());
+        let reversed = edges.map(|(a, b)| (b, a));
+        trim_edges(&trim_edges(inner, &edges), &reversed)
+    })
+}
+
+// Retains edges whose sources are reachable along edges, reversing the retained edges.
+fn trim_and_flip<G, N>(edges: &Collection<G, (N, N)>) -> Collection<G, (N, N)>
+where G: Scope, G::Timestamp: Lattice+Ord+Debug, N: Data+Default+Hash {
+    edges.iterate(|inner| {
+        let active = inner.map(|(_src, dst)| dst).distinct();
+        edges.enter(&inner.scope())
+             .semijoin(&active)
+    })
+    .map(|(a, b)| (b, a))
+}
+
+// Retains the reversals of `edges` whose endpoints reach the same least node along `cycle`.
+fn trim_edges<G, N>(cycle: &Collection<G, (N, N)>, edges: &Collection<G, (N, N)>) -> Collection<G, (N, N)>
+where G: Scope, G::Timestamp: Lattice+Ord+Debug, N: Data+Default+Hash {
+
+    let nodes = edges.map(|(_src, dst)| (dst.clone(), dst))
+                     .distinct();
+
+    let labels = reachability(cycle, &nodes);
+
+    edges.join_map(&labels, |e1, e2, l1| (e2.clone(), (e1.clone(), l1.clone())))
+         .join_map(&labels, |e2, &(ref e1, ref l1), l2| ((e1.clone(), e2.clone()), l1 == l2))
+         .filter(|&(_, same)| same)
+         .map(|((e1, e2), _)| (e2, e1))
+}
+
+// Labels each node with the least node of `nodes` reaching it along `edges`.
+fn reachability<G, N>(edges: &Collection<G, (N, N)>, nodes: &Collection<G, (N, N)>) -> Collection<G, (N, N)>
+where G: Scope, G::Timestamp: Lattice+Ord+Debug, N: Data+Default+Hash {
+    edges.filter(|_| false)
+         .iterate(|inner| {
+             let edges = edges.enter(&inner.scope());
+             let nodes = nodes.enter(&inner.scope());
+             inner.join_map(&edges, |_node, label, dst| (dst.clone(), label.clone()))
+                  .concat(&nodes)
+                  .group(|_node, labels, output| output.push((labels[0].0.clone(), 1)))
+         })
+}
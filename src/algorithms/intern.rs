@@ -0,0 +1,111 @@
+//! Interning keys as compact integer identifiers, shared by several arrangements.
+//!
+//! Arrangements of data keyed by large keys, such as strings, store and compare those keys in every batch.
+//! An `Interner` maintains a dictionary assigning each distinct key a `u64` identifier, from which collections
+//! can be translated to identifiers before being arranged, and translated back afterwards. The dictionary is
+//! arranged once by key and once by identifier, and these arrangements are shared by all translations.
+//!
+//! Identifiers are assigned by the worker responsible for each key, as the worker's index plus a multiple of
+//! the number of workers, and so are dense across workers up to their imbalance. A key keeps its identifier
+//! if it is removed and later returns.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let interner = Interner::new(&edges.map(|(src, _dst)| src));
+//! let interned = interner.intern(&edges);
+//! let degrees = interned.map(|(id, _dst)| id).count();
+//! interner.restore(&degrees)
+//!         .inspect(|x| println!("{:?}", x));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Exchange;
+
+use timely_sort::Unsigned;
+
+use ::{Collection, AsCollection, Data, Diff, Hashable};
+use hashable::OrdWrapper;
+use lattice::Lattice;
+use operators::Distinct;
+use operators::arrange::{Arranged, ArrangeByKey, TraceAgent};
+use operators::join::JoinArranged;
+use operators::stash::TimeStash;
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+
+/// A dictionary of keys and their identifiers, arranged by each.
+pub struct Interner<G: Scope, K: Data+Default+Hashable> where G::Timestamp: Lattice+Ord {
+    /// The dictionary, as `(key, id)` pairs arranged by key.
+    pub by_key: Arranged<G, OrdWrapper<K>, u64, isize, TraceAgent<OrdWrapper<K>, u64, G::Timestamp, isize, DefaultValTrace<OrdWrapper<K>, u64, G::Timestamp, isize>>>,
+    /// The dictionary, as `(id, key)` pairs arranged by identifier.
+    pub by_id: Arranged<G, OrdWrapper<u64>, K, isize, TraceAgent<OrdWrapper<u64>, K, G::Timestamp, isize, DefaultValTrace<OrdWrapper<u64>, K, G::Timestamp, isize>>>,
+}
+
+impl<G: Scope, K: Data+Default+Hashable> Interner<G, K> where G::Timestamp: Lattice+Ord+Debug {
+    /// Maintains identifiers for the distinct elements of `keys`.
+    ///
+    /// The changes of each time are applied in order once the time completes, and so the times should be
+    /// totally ordered. Identifiers are retained for keys no longer present.
+    pub fn new<R: Diff>(keys: &Collection<G, K, R>) -> Self {
+
+        let worker = keys.scope().index() as u64;
+        let peers = keys.scope().peers() as u64;
+
+        // changes received for each time, and the identifiers assigned so far.
+        let mut pending = TimeStash::new();
+        let mut assigned = BTreeMap::<K, u64>::new();
+
+        let exchange = Exchange::new(|x: &(K, G::Timestamp, isize)| x.0.hashed().as_u64());
+
+        let dictionary =
+        keys.distinct()
+            .inner
+            .unary_notify(exchange, "InternIds", vec![], move |input, output, notificator| {
+
+                input.for_each(|capability, data| {
+                    for (key, time, diff) in data.drain(..) {
+                        pending.push(&capability, &time, (key, diff), notificator);
+                    }
+                });
+
+                for (capability, changes) in pending.ready_sorted(notificator) {
+                    let time = capability.time().clone();
+                    let mut session = output.session(&capability);
+                    for (key, diff) in changes {
+                        let next = worker + peers * (assigned.len() as u64);
+                        let id = *assigned.entry(key.clone()).or_insert(next);
+                        session.give(((key, id), time.clone(), diff));
+                    }
+                }
+            })
+            .as_collection();
+
+        Interner {
+            by_key: dictionary.arrange_by_key_hashed(),
+            by_id: dictionary.map(|(key, id)| (id, key)).arrange_by_key_hashed(),
+        }
+    }
+
+    /// The dictionary, as `(key, id)` pairs.
+    pub fn dictionary(&self) -> Collection<G, (K, u64), isize> {
+        self.by_key.as_collection(|k, &id| (k.item.clone(), id))
+    }
+
+    /// Replaces the key of each record by its identifier.
+    ///
+    /// Records whose keys are not in the dictionary are dropped.
+    pub fn intern<V: Data, R: Diff>(&self, data: &Collection<G, (K, V), R>) -> Collection<G, (u64, V), R> {
+        data.join_arranged_with(&self.by_key, |_k, v, &id| (id, v.clone()), |r, _| *r)
+    }
+
+    /// Replaces the identifier of each record by its key.
+    ///
+    /// Records whose identifiers are not in the dictionary are dropped.
+    pub fn restore<V: Data, R: Diff>(&self, data: &Collection<G, (u64, V), R>) -> Collection<G, (K, V), R> {
+        data.join_arranged_with(&self.by_id, |_id, v, k| (k.clone(), v.clone()), |r, _| *r)
+    }
+}
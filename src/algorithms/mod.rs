@@ -0,0 +1,8 @@
+//! Algorithms expressed as differential dataflow computations over collections.
+//!
+//! The algorithms in this module are built from the operators in `operators`, and are incrementally
+//! maintained as their input collections change.
+
+pub mod graphs;
+pub mod intern;
+pub mod motifs;
@@ -0,0 +1,71 @@
+//! Counting small subgraphs (motifs) in graphs presented as collections of edges.
+//!
+//! Edges are treated as undirected, and self-loops are ignored. Each motif is reported once, as the
+//! sorted list of its nodes. The implementations extend partial motifs one node at a time, proposing
+//! extensions from the neighbors of the least node and validating them against the remaining nodes.
+//! They are maintained incrementally under changes to the edges.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // count triangles in `edges`, reporting changes to the count.
+//! triangles(&edges)
+//!     .map(|_| ())
+//!     .count()
+//!     .inspect(|x| println!("triangles: {:?}", x));
+//! ```
+
+use std::hash::Hash;
+
+use timely::dataflow::Scope;
+
+use ::{Collection, Data};
+use lattice::Lattice;
+use operators::{Distinct, Join};
+
+/// Returns the collection of `k`-cliques in the graph, each as a sorted list of its nodes.
+///
+/// Cliques of size `s` are formed from cliques of size `s - 1` by proposing each neighbor of the least
+/// node greater than the largest node, and validating that each other node is also adjacent to it.
+/// For `k` less than two the result is empty.
+pub fn cliques<G, N>(edges: &Collection<G, (N, N)>, k: usize) -> Collection<G, Vec<N>>
+where G: Scope, G::Timestamp: Lattice+Ord+::std::fmt::Debug, N: Data+Default+Hash+Copy {
+
+    // orient each edge from its lesser to its greater node.
+    let oriented = edges.filter(|&(a, b)| a != b)
+                        .map(|(a, b)| if a < b { (a, b) } else { (b, a) })
+                        .distinct();
+
+    let mut result = oriented.map(|(a, b)| vec![a, b]);
+
+    if k < 2 { 
+        return result.filter(|_| false);
+    }
+
+    for size in 3 .. (k + 1) {
+
+        // propose neighbors of the least node, greater than the largest node.
+        let mut proposals = 
+        result.map(|clique| (clique[0], clique))
+              .join_map(&oriented, |_, clique, &node| (clique.clone(), node))
+              .filter(|&(ref clique, node)| clique[clique.len() - 1] < node);
+
+        // validate proposals against each of the other nodes.
+        for index in 1 .. (size - 1) {
+            proposals = 
+            proposals.map(move |(clique, node)| ((clique[index], node), clique))
+                     .semijoin(&oriented)
+                     .map(|((_, node), clique)| (clique, node));
+        }
+
+        result = proposals.map(|(mut clique, node)| { clique.push(node); clique });
+    }
+
+    result
+}
+
+/// Returns the collection of triangles in the graph, each as a triple of nodes in increasing order.
+pub fn triangles<G, N>(edges: &Collection<G, (N, N)>) -> Collection<G, (N, N, N)>
+where G: Scope, G::Timestamp: Lattice+Ord+::std::fmt::Debug, N: Data+Default+Hash+Copy {
+    cliques(edges, 3).map(|clique| (clique[0], clique[1], clique[2]))
+}
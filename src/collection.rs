@@ -1,6 +1,7 @@
 //! Types and traits associated with collections of data.
 
 use std::hash::Hash;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use timely::Data;
 use timely::progress::Timestamp;
@@ -8,8 +9,16 @@ use timely::progress::nested::product::Product;
 use timely::dataflow::scopes::Child;
 use timely::dataflow::{Scope, Stream};
 use timely::dataflow::operators::*;
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
+
+use timely_sort::Unsigned;
+use timely::order::PartialOrder;
 
 use ::Diff;
+use hashable::Hashable;
+use lattice::Lattice;
+use operators::{Consolidate, consolidate_from};
+use operators::stash::TimeStash;
 
 /// A mutable collection of values of type `D`
 ///
@@ -78,6 +87,17 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
         self.inner.filter(move |&(ref data, _, _)| logic(data))
                   .as_collection()
     }
+    /// Splits the collection into `parts` collections, according to the part `route` indicates for each record.
+    ///
+    /// This is equivalent to `parts` calls to `filter`, but examines each update once. The `route` function
+    /// must return a value less than `parts` and, as with `filter`, should depend only on the record so that 
+    /// insertions and retractions of a record land in the same part.
+    pub fn partition<L: Fn(&D) -> u64 + 'static>(&self, parts: u64, route: L) -> Vec<Collection<G, D, R>> {
+        self.inner.partition(parts, move |update| { let part = route(&update.0); (part, update) })
+                  .into_iter()
+                  .map(|stream| stream.as_collection())
+                  .collect()
+    }
     /// Creates a new collection accumulating the contents of the two collections.
     ///
     /// Despite the name, differential dataflow collections are unordered. This method is so named because the 
@@ -87,6 +107,14 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
         self.inner.concat(&other.inner)
                   .as_collection()
     }
+    /// Creates a new collection containing the records of `self` less those of `other`.
+    ///
+    /// This is the accumulation of `self` and the negation of `other`. Records present in `other` more
+    /// often than in `self` result in negative counts; use `distinct` or `threshold`-like operators if 
+    /// set difference is intended.
+    pub fn subtract(&self, other: &Collection<G, D, R>) -> Collection<G, D, R> {
+        self.concat(&other.negate())
+    }
     /// Brings a Collection into a nested scope.
     pub fn enter<'a, T: Timestamp>(&self, child: &Child<'a, G, T>) -> Collection<Child<'a, G, T>, D, R> {
         self.inner.enter(child)
@@ -132,6 +160,76 @@ impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Data {
         self.inner.inspect_batch(func)
                   .as_collection()
     }
+    /// Applies a supplied function to the consolidated updates of each completed time, across all workers.
+    ///
+    /// Where `inspect` reveals the updates each worker happens to hold, this method gathers the updates to the
+    /// first worker and, once each time completes, calls `func` there with the time and its consolidated
+    /// updates, sorted by record. Times are reported in order of completion, and those completing together in
+    /// sorted order; times without net updates are not reported. The collection is passed through unchanged.
+    pub fn inspect_global<F: FnMut(&G::Timestamp, &[(D, R)])+'static>(&self, mut func: F) -> Collection<G, D, R>
+    where G::Timestamp: Ord {
+
+        // updates gathered for each time.
+        let mut stash = TimeStash::new();
+
+        let exchange = Exchange::new(|_update: &(D, G::Timestamp, R)| 0);
+        let _inspected: Stream<G, ()> = self.inner.unary_notify(exchange, "InspectGlobal", vec![], move |input, _output, notificator| {
+
+            input.for_each(|capability, data| {
+                for (datum, time, diff) in data.drain(..) {
+                    stash.push(&capability, &time, (datum, diff), notificator);
+                }
+            });
+
+            for (capability, mut updates) in stash.ready_sorted(notificator) {
+                consolidate_from(&mut updates, 0);
+                if !updates.is_empty() {
+                    func(capability.time(), &updates[..]);
+                }
+            }
+        });
+
+        self.clone()
+    }
+    /// Applies a supplied function to the frontier of the collection, each time it changes.
+    ///
+    /// The frontier is the set of lower bounds on times at which updates may still appear; it advances as the
+    /// computation makes progress. This method is intended for monitoring, for example to report a watermark
+    /// to a metrics system, and the collection is passed through unchanged. An empty frontier indicates that
+    /// the collection is complete.
+    pub fn inspect_frontier<F: FnMut(&[G::Timestamp])+'static>(&self, mut func: F) -> Collection<G, D, R> {
+        let mut frontier = vec![Default::default()];
+        self.inner.unary_notify(Pipeline, "InspectFrontier", vec![], move |input, output, notificator| {
+            input.for_each(|capability, data| {
+                let mut session = output.session(&capability);
+                for datum in data.drain(..) {
+                    session.give(datum);
+                }
+            });
+            if &frontier[..] != notificator.frontier(0) {
+                frontier = notificator.frontier(0).to_vec();
+                func(&frontier[..]);
+            }
+        })
+        .as_collection()
+    }
+    /// Reports the lag between wall-clock time and the frontier of the collection, each time the frontier changes.
+    ///
+    /// The `wall` function maps each time to the wall-clock time it represents, as a duration since the Unix
+    /// epoch; for example, inputs that advance to the current time in milliseconds could use `Duration::from_millis`.
+    /// The reported lag is the current wall-clock time less the least wall-clock time in the frontier, or zero if
+    /// the frontier is ahead of the clock. Nothing is reported once the frontier is empty.
+    ///
+    /// A lag that grows steadily indicates stalled progress, and is a natural quantity on which to alert.
+    pub fn inspect_lag<W, F>(&self, wall: W, mut func: F) -> Collection<G, D, R>
+    where W: Fn(&G::Timestamp)->Duration+'static, F: FnMut(Duration)+'static {
+        self.inspect_frontier(move |frontier| {
+            if let Some(watermark) = frontier.iter().map(|t| wall(t)).min() {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or(Duration::from_secs(0));
+                func(if now > watermark { now - watermark } else { Duration::from_secs(0) });
+            }
+        })
+    }
     /// Attaches a timely dataflow probe to the output of a Collection.
     ///
     /// This probe is used to determine when the state of the Collection has stabilized and can
@@ -163,6 +261,259 @@ impl<'a, G: Scope, T: Timestamp, D: Data, R: Diff> Collection<Child<'a, G, T>, D
     }
 }
 
+impl<G: Scope, D: ::Data+Hashable+Default, R: Diff> Collection<G, D, R> where G::Timestamp: Lattice+Ord {
+    /// Panics if any record accumulates to a non-zero count at any time.
+    ///
+    /// The collection is consolidated, and the check applies to the consolidated updates. This method is
+    /// useful for expressing invariants, for example that `a.subtract(&b)` is empty when `a` and `b` should
+    /// be equal. The collection is returned unchanged, to allow the check to be placed in the middle of a 
+    /// computation.
+    pub fn assert_empty(&self) -> Collection<G, D, R> {
+        self.consolidate()
+            .inspect(|x| panic!("assert_empty: found non-empty update {:?}", x));
+        self.clone()
+    }
+
+    /// Converts the collection to an insert-only collection of changes, for consumers that cannot retract.
+    ///
+    /// Each record `(datum, diff, deleted)` of the result has weight one. With `Retractions::Tombstones`, each
+    /// consolidated update becomes a record of its magnitude, with `deleted` set for negative updates. With
+    /// `Retractions::Corrections`, each change to the accumulated weight of a datum becomes a record of its
+    /// new weight, with `deleted` set when the weight returns to zero; the times of the collection should be
+    /// totally ordered, as the changes of each time are applied in order once the time completes.
+    pub fn suppress_retraction(&self, mode: Retractions) -> Collection<G, (D, R, bool), isize> {
+        let consolidated = self.consolidate();
+        match mode {
+            Retractions::Tombstones => {
+                consolidated.inner
+                    .map(|(datum, time, diff)| {
+                        if diff < R::zero() { ((datum, -diff, true), time, 1) }
+                        else { ((datum, diff, false), time, 1) }
+                    })
+                    .as_collection()
+            },
+            Retractions::Corrections => {
+
+                // changes received for each time, and the accumulated weight of each datum.
+                let mut pending = TimeStash::new();
+                let mut current = ::std::collections::BTreeMap::<D, R>::new();
+
+                let exchange = Exchange::new(|x: &(D, G::Timestamp, R)| x.0.hashed().as_u64());
+
+                consolidated.inner.unary_notify(exchange, "SuppressRetraction", vec![], move |input, output, notificator| {
+
+                    input.for_each(|capability, data| {
+                        for (datum, time, diff) in data.drain(..) {
+                            pending.push(&capability, &time, (datum, diff), notificator);
+                        }
+                    });
+
+                    for (capability, changes) in pending.ready_sorted(notificator) {
+                        let time = capability.time().clone();
+                        let mut session = output.session(&capability);
+                        for (datum, diff) in changes {
+                            let weight = current.remove(&datum).map(|x| x + diff).unwrap_or(diff);
+                            if weight.is_zero() {
+                                session.give(((datum, weight, true), time.clone(), 1));
+                            }
+                            else {
+                                current.insert(datum.clone(), weight);
+                                session.give(((datum, weight, false), time.clone(), 1));
+                            }
+                        }
+                    }
+                })
+                .as_collection()
+            },
+        }
+    }
+}
+
+impl<G: Scope, D: Data, R: Diff> Collection<G, D, R> where G::Timestamp: Lattice {
+    /// Moves each update to the time `align` assigns its time, held until that time completes in `other`.
+    ///
+    /// This method re-interprets the times of one collection on the timeline of another, for example moving
+    /// updates tagged by batch number to the end of the event-time window in which the batch closed. Updates
+    /// are held back until both `self` and `other` have completed the aligned time, so that at each completed
+    /// time the two collections are consistent with each other. The contents of `other` are not used.
+    ///
+    /// The function `align` must only delay times: each aligned time must be greater or equal to the original
+    /// time, which is checked for each update. It should also be monotone, so that the order of updates is
+    /// preserved; with debug assertions enabled, this is checked among the times of each received batch.
+    pub fn align_to<D2: Data, R2: Diff, F>(&self, other: &Collection<G, D2, R2>, align: F) -> Collection<G, D, R>
+    where F: Fn(&G::Timestamp)->G::Timestamp+'static {
+
+        // updates held back, for each aligned time.
+        let mut stash = TimeStash::new();
+
+        self.inner.binary_notify(&other.inner, Pipeline, Pipeline, "AlignTo", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|capability, data| {
+
+                if cfg!(debug_assertions) {
+                    let mut times: Vec<(G::Timestamp, G::Timestamp)> = data.iter().map(|x| (x.1.clone(), align(&x.1))).collect();
+                    times.dedup();
+                    for &(ref t1, ref a1) in times.iter() {
+                        for &(ref t2, ref a2) in times.iter() {
+                            if t1.less_equal(t2) && !a1.less_equal(a2) {
+                                panic!("align_to: {:?} <= {:?} aligned to {:?}, {:?}", t1, t2, a1, a2);
+                            }
+                        }
+                    }
+                }
+
+                for (datum, time, diff) in data.drain(..) {
+                    let aligned = align(&time);
+                    if !time.less_equal(&aligned) {
+                        panic!("align_to: time {:?} aligned to earlier time {:?}", time, aligned);
+                    }
+                    stash.push(&capability, &aligned, (datum, aligned.clone(), diff), notificator);
+                }
+            });
+
+            // `other` only provides progress information.
+            input2.for_each(|_capability, data| data.clear());
+
+            for (capability, updates) in stash.ready(notificator) {
+                let mut session = output.session(&capability);
+                for update in updates {
+                    session.give(update);
+                }
+            }
+        })
+        .as_collection()
+    }
+
+    /// Tags each update with whether it is provisional: received before `other` is complete through its time.
+    ///
+    /// Updates are held back until `other` is complete through `lag(time)`, bounding the slack by which `other`
+    /// may trail before updates are released. The function `lag` must return times less or equal to its
+    /// argument. Updates received once `other` is complete through their time are tagged `false`; for an
+    /// operator whose output depends on `other`, these correct the provisional updates at the same time.
+    pub fn provisional<D2: Data, R2: Diff, F>(&self, other: &Collection<G, D2, R2>, lag: F) -> Collection<G, (D, bool), R>
+    where F: Fn(&G::Timestamp)->G::Timestamp+'static {
+
+        // updates held back until `other` is complete through their lagged times.
+        let mut stash: Vec<(Capability<G::Timestamp>, Vec<(D, G::Timestamp, R)>)> = Vec::new();
+
+        self.inner.binary_notify(&other.inner, Pipeline, Pipeline, "Provisional", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|capability, data| stash.push((capability, data.drain(..).collect())));
+
+            // `other` only provides progress information.
+            input2.for_each(|_capability, data| data.clear());
+
+            let frontier = notificator.frontier(1);
+
+            let mut index = 0;
+            while index < stash.len() {
+                {
+                    let (ref capability, ref mut updates) = stash[index];
+                    let mut held = Vec::new();
+                    let mut session = output.session(capability);
+                    for (datum, time, diff) in updates.drain(..) {
+                        if frontier.iter().any(|t| t.less_equal(&lag(&time))) {
+                            held.push((datum, time, diff));
+                        }
+                        else {
+                            let provisional = frontier.iter().any(|t| t.less_equal(&time));
+                            session.give(((datum, provisional), time, diff));
+                        }
+                    }
+                    *updates = held;
+                }
+                if stash[index].1.is_empty() { stash.swap_remove(index); }
+                else { index += 1; }
+            }
+        })
+        .as_collection()
+    }
+
+    /// Releases each time's updates provisionally once `other` is complete through `lag(time)`, and corrects them
+    /// once both collections are complete through the time.
+    ///
+    /// The provisional updates at a time are the consolidated updates received by the moment `other` is complete
+    /// through its lagged time, and are released while the time may still receive updates. Once both collections
+    /// are complete through the time, the corrections retract the provisional updates and introduce the final
+    /// accumulation, consolidated against each other so that unchanged records produce no corrections. The
+    /// function `lag` must return times less or equal to its argument. Consumers that can tolerate retractions
+    /// may use `Speculation::merged`, whose accumulation at each completed time equals that of `self`.
+    pub fn speculate_then_correct<D2: Data, R2: Diff, F>(&self, other: &Collection<G, D2, R2>, lag: F) -> Speculation<G, D, R>
+    where D: Ord, F: Fn(&G::Timestamp)->G::Timestamp+'static {
+
+        // for each time, the updates received, and the updates released provisionally, if any.
+        let mut pending: TimeStash<G::Timestamp, (Vec<(D, R)>, Option<Vec<(D, R)>>)> = TimeStash::new();
+
+        let tagged = self.inner.binary_notify(&other.inner, Pipeline, Pipeline, "Speculate", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|capability, data| {
+                for (datum, time, diff) in data.drain(..) {
+                    pending.entry(&capability, &time, notificator, || (Vec::new(), None)).0.push((datum, diff));
+                }
+            });
+
+            // `other` only provides progress information.
+            input2.for_each(|_capability, data| data.clear());
+
+            // release updates provisionally once `other` is complete through their lagged time.
+            let frontier = notificator.frontier(1).to_vec();
+            for &mut (ref capability, (ref mut received, ref mut released)) in pending.iter_mut() {
+                if released.is_none() && !frontier.iter().any(|t| t.less_equal(&lag(capability.time()))) {
+                    consolidate_from(received, 0);
+                    let mut session = output.session(capability);
+                    for &(ref datum, diff) in received.iter() {
+                        session.give(((datum.clone(), true), capability.time().clone(), diff));
+                    }
+                    *released = Some(received.clone());
+                }
+            }
+
+            // once both inputs are complete through a time, correct its provisional updates. The time was
+            // released above, as `other` is complete through the time and so through its lagged time.
+            for (capability, (mut received, released)) in pending.ready(notificator) {
+                for (datum, diff) in released.unwrap_or_else(Vec::new) {
+                    received.push((datum, -diff));
+                }
+                consolidate_from(&mut received, 0);
+                let mut session = output.session(&capability);
+                for (datum, diff) in received {
+                    session.give(((datum, false), capability.time().clone(), diff));
+                }
+            }
+        })
+        .as_collection();
+
+        Speculation {
+            provisional: tagged.filter(|x| x.1).map(|x| x.0),
+            corrections: tagged.filter(|x| !x.1).map(|x| x.0),
+        }
+    }
+}
+
+/// Provisional updates of a collection, and the corrections that make them final.
+pub struct Speculation<G: Scope, D, R: Diff> {
+    /// Updates released before their times were complete.
+    pub provisional: Collection<G, D, R>,
+    /// Updates retracting the provisional updates and introducing the final updates.
+    pub corrections: Collection<G, D, R>,
+}
+
+impl<G: Scope, D: Data, R: Diff> Speculation<G, D, R> where G::Timestamp: Data {
+    /// The provisional updates and their corrections, as one collection.
+    pub fn merged(&self) -> Collection<G, D, R> {
+        self.provisional.concat(&self.corrections)
+    }
+}
+
+/// How `suppress_retraction` presents removals to insert-only consumers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Retractions {
+    /// Each removal is a record of the removed weight, marked as deleted.
+    Tombstones,
+    /// Each change is a record of the new accumulated weight, marked as deleted once the weight is zero.
+    Corrections,
+}
+
 /// Conversion to a differential dataflow Collection.
 pub trait AsCollection<G: Scope, D: Data, R: Diff> {
     /// Converts the type to a differential dataflow collection.
@@ -129,3 +129,172 @@ where <R1 as Mul<T>>::Output: Diff, <R2 as Mul<T>>::Output: Diff {
 // }
 
 impl<R1: Diff, R2: Diff> Abomonation for DiffPair<R1, R2> { }
+
+/// A fixed-point decimal difference, with four digits after the decimal point.
+///
+/// The type is intended for monetary accumulations, where floating point is inappropriate and where products
+/// of prices and counts can grow large. All arithmetic is checked, and panics on overflow rather than wrapping,
+/// so that an overflowing accumulation is reported rather than silently producing an incorrect total.
+#[derive(Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Default, Hash)]
+pub struct Decimal {
+	/// The value, in units of `1 / Decimal::SCALE`.
+	pub units: i64,
+}
+
+impl Decimal {
+	/// The number of units in one.
+	pub const SCALE: i64 = 10_000;
+	/// Creates a decimal from a number of units of `1 / Decimal::SCALE`.
+	#[inline(always)] pub fn from_units(units: i64) -> Self { Decimal { units: units } }
+	/// Creates a decimal from a whole number.
+	#[inline(always)] pub fn from_integer(integer: i64) -> Self { 
+		Decimal { units: integer.checked_mul(Decimal::SCALE).expect("Decimal overflow") } 
+	}
+	/// Creates a decimal from a floating point number, rounded to the nearest unit.
+	///
+	/// Values that are not finite, or whose units do not fit in an `i64`, overflow.
+	pub fn from_f64(value: f64) -> Self {
+		let units = (value * (Decimal::SCALE as f64)).round();
+		// `i64::MAX as f64` rounds up to 2^63, which is itself out of range.
+		if !units.is_finite() || units < (::std::i64::MIN as f64) || units >= (::std::i64::MAX as f64) {
+			panic!("Decimal overflow");
+		}
+		Decimal { units: units as i64 }
+	}
+}
+
+impl Diff for Decimal {
+	#[inline(always)] fn is_zero(&self) -> bool { self.units == 0 }
+	#[inline(always)] fn zero() -> Self { Decimal { units: 0 } }
+}
+
+impl Add<Decimal> for Decimal {
+	type Output = Self;
+	#[inline(always)] fn add(self, rhs: Self) -> Self {
+		Decimal { units: self.units.checked_add(rhs.units).expect("Decimal overflow") }
+	}
+}
+
+impl Sub<Decimal> for Decimal {
+	type Output = Self;
+	#[inline(always)] fn sub(self, rhs: Self) -> Self {
+		Decimal { units: self.units.checked_sub(rhs.units).expect("Decimal overflow") }
+	}
+}
+
+impl Neg for Decimal {
+	type Output = Self;
+	#[inline(always)] fn neg(self) -> Self {
+		Decimal { units: self.units.checked_neg().expect("Decimal overflow") }
+	}
+}
+
+impl Mul<isize> for Decimal {
+	type Output = Self;
+	#[inline(always)] fn mul(self, other: isize) -> Self {
+		Decimal { units: self.units.checked_mul(other as i64).expect("Decimal overflow") }
+	}
+}
+
+impl Mul<i64> for Decimal {
+	type Output = Self;
+	#[inline(always)] fn mul(self, other: i64) -> Self {
+		Decimal { units: self.units.checked_mul(other).expect("Decimal overflow") }
+	}
+}
+
+impl Mul<i32> for Decimal {
+	type Output = Self;
+	#[inline(always)] fn mul(self, other: i32) -> Self { self * (other as i64) }
+}
+
+/// The product of two decimals, rounded toward zero to four digits after the decimal point.
+///
+/// The intermediate product is formed with 128 bits, so only a result that does not fit in a `Decimal` overflows.
+impl Mul<Decimal> for Decimal {
+	type Output = Self;
+	#[inline(always)] fn mul(self, other: Decimal) -> Self {
+		let product = (self.units as i128) * (other.units as i128) / (Decimal::SCALE as i128);
+		if product > (::std::i64::MAX as i128) || product < (::std::i64::MIN as i128) {
+			panic!("Decimal overflow");
+		}
+		Decimal { units: product as i64 }
+	}
+}
+
+impl Mul<Decimal> for isize {
+	type Output = Decimal;
+	#[inline(always)] fn mul(self, other: Decimal) -> Decimal { other * self }
+}
+
+impl Mul<Decimal> for i64 {
+	type Output = Decimal;
+	#[inline(always)] fn mul(self, other: Decimal) -> Decimal { other * self }
+}
+
+impl Abomonation for Decimal { }
+
+/// A 128-bit integer difference.
+///
+/// The type exists for accumulations that may exceed `i64`, for example sums of products of large counts.
+/// It wraps `i128` rather than implementing `Diff` for it directly, as `Abomonation` is a foreign trait and
+/// is not implemented for `i128`. As with `Decimal`, all arithmetic is checked and panics on overflow.
+#[derive(Copy, Ord, PartialOrd, Eq, PartialEq, Debug, Clone, Default, Hash)]
+pub struct Int128 {
+	/// The wrapped value.
+	pub value: i128,
+}
+
+impl Int128 {
+	/// Creates a difference from a value.
+	#[inline(always)] pub fn new(value: i128) -> Self { Int128 { value: value } }
+}
+
+impl From<i64> for Int128 {
+	#[inline(always)] fn from(value: i64) -> Self { Int128 { value: value as i128 } }
+}
+
+impl Diff for Int128 {
+	#[inline(always)] fn is_zero(&self) -> bool { self.value == 0 }
+	#[inline(always)] fn zero() -> Self { Int128 { value: 0 } }
+}
+
+impl Add<Int128> for Int128 {
+	type Output = Self;
+	#[inline(always)] fn add(self, rhs: Self) -> Self {
+		Int128 { value: self.value.checked_add(rhs.value).expect("Int128 overflow") }
+	}
+}
+
+impl Sub<Int128> for Int128 {
+	type Output = Self;
+	#[inline(always)] fn sub(self, rhs: Self) -> Self {
+		Int128 { value: self.value.checked_sub(rhs.value).expect("Int128 overflow") }
+	}
+}
+
+impl Neg for Int128 {
+	type Output = Self;
+	#[inline(always)] fn neg(self) -> Self {
+		Int128 { value: self.value.checked_neg().expect("Int128 overflow") }
+	}
+}
+
+impl Mul<Int128> for Int128 {
+	type Output = Self;
+	#[inline(always)] fn mul(self, other: Int128) -> Self {
+		Int128 { value: self.value.checked_mul(other.value).expect("Int128 overflow") }
+	}
+}
+
+impl Mul<isize> for Int128 {
+	type Output = Self;
+	#[inline(always)] fn mul(self, other: isize) -> Self { self * Int128 { value: other as i128 } }
+}
+
+impl Mul<Int128> for isize {
+	type Output = Int128;
+	#[inline(always)] fn mul(self, other: Int128) -> Int128 { other * self }
+}
+
+impl Abomonation for Int128 { }
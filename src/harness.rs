@@ -0,0 +1,190 @@
+//! Utilities for measuring the performance of differential dataflow computations.
+//!
+//! Benchmarks of differential dataflow computations usually have two phases: a load phase, in which an
+//! initial collection is introduced and the computation reaches a fixed point, and a steady-state phase,
+//! in which small rounds of updates are introduced and the latency of each round is measured. The
+//! `Measurements` type records these quantities, summarizes the round latencies by percentiles, and
+//! prints the results as CSV or JSON so that runs can be compared across versions and configurations.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let mut measurements = Measurements::new("degrees");
+//! measurements.time_load(|| {
+//!     // introduce initial data ...
+//!     worker.step_while(|| probe.less_than(input.time()));
+//! });
+//! for round in 1 .. rounds {
+//!     measurements.time_round(|| {
+//!         // introduce updates for `round` ...
+//!         worker.step_while(|| probe.less_than(input.time()));
+//!     });
+//! }
+//! if worker.index() == 0 {
+//!     println!("{}", Measurements::csv_header());
+//!     println!("{}", measurements.to_csv());
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use timely::progress::Timestamp;
+use timely::progress::timestamp::RootTimestamp;
+use timely::progress::nested::product::Product;
+use timely::dataflow::operators::probe::Handle as ProbeHandle;
+
+/// Load and per-round latency measurements of a computation.
+pub struct Measurements {
+    name: String,
+    load: Option<Duration>,
+    latencies: Vec<Duration>,
+}
+
+impl Measurements {
+    /// Creates a new, empty set of measurements with the supplied name.
+    pub fn new(name: &str) -> Self {
+        Measurements {
+            name: name.to_owned(),
+            load: None,
+            latencies: Vec::new(),
+        }
+    }
+    /// Runs `logic` and records its duration as the load phase.
+    pub fn time_load<F: FnOnce()>(&mut self, logic: F) {
+        let timer = Instant::now();
+        logic();
+        self.load = Some(timer.elapsed());
+    }
+    /// Runs `logic` and records its duration as the latency of one round.
+    pub fn time_round<F: FnOnce()>(&mut self, logic: F) {
+        let timer = Instant::now();
+        logic();
+        self.latencies.push(timer.elapsed());
+    }
+    /// Records the latency of one round, measured elsewhere.
+    pub fn record(&mut self, latency: Duration) {
+        self.latencies.push(latency);
+    }
+    /// The number of rounds recorded.
+    pub fn rounds(&self) -> usize { self.latencies.len() }
+    /// The round latency at the `percentile`-th percentile (from `0.0` to `100.0`), if any rounds were recorded.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        percentile_of(&self.latencies[..], percentile)
+    }
+    /// The column names of the CSV representation.
+    pub fn csv_header() -> &'static str {
+        "name,load_ns,rounds,p50_ns,p90_ns,p99_ns,max_ns"
+    }
+    /// A CSV row of the measurements, with times in nanoseconds and empty fields for missing values.
+    pub fn to_csv(&self) -> String {
+        let fields = self.fields();
+        let mut result = self.name.clone();
+        for &(_, value) in fields.iter() {
+            result.push(',');
+            if let Some(value) = value { result.push_str(&value.to_string()); }
+        }
+        result
+    }
+    /// A JSON object of the measurements, with times in nanoseconds and `null` for missing values.
+    pub fn to_json(&self) -> String {
+        let mut result = format!("{{\"name\":{:?}", self.name);
+        for &(field, value) in self.fields().iter() {
+            match value {
+                Some(value) => result.push_str(&format!(",\"{}\":{}", field, value)),
+                None => result.push_str(&format!(",\"{}\":null", field)),
+            }
+        }
+        result.push('}');
+        result
+    }
+
+    fn fields(&self) -> Vec<(&'static str, Option<u64>)> {
+        vec![
+            ("load_ns", self.load.map(nanos)),
+            ("rounds", Some(self.latencies.len() as u64)),
+            ("p50_ns", self.percentile(50.0).map(nanos)),
+            ("p90_ns", self.percentile(90.0).map(nanos)),
+            ("p99_ns", self.percentile(99.0).map(nanos)),
+            ("max_ns", self.percentile(100.0).map(nanos)),
+        ]
+    }
+}
+
+/// The element at the `percentile`-th percentile (from `0.0` to `100.0`) of `latencies`, by nearest rank.
+pub fn percentile_of(latencies: &[Duration], percentile: f64) -> Option<Duration> {
+    if latencies.len() == 0 { return None; }
+    let mut sorted = latencies.to_vec();
+    sorted.sort();
+    let rank = ((percentile / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    Some(sorted[::std::cmp::min(rank, sorted.len() - 1)])
+}
+
+/// A duration as a number of nanoseconds.
+pub fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+/// Measures the latency from the introduction of each input round to its completion at a probe.
+///
+/// Each round is tagged with the wall-clock time at which it was `ingest`ed, typically immediately after
+/// the input advanced past the round. Each call to `observe` compares the pending rounds against the probe,
+/// and records the completion latency of those rounds the probe has passed. Unlike timing `step_while`
+/// loops, this allows several rounds to be in flight at once, as when inputs are introduced at a fixed rate.
+///
+/// #Examples
+///
+/// ```ignore
+/// let mut latency = LatencyProbe::new();
+/// for round in 1 .. rounds {
+///     // introduce updates for `round` ...
+///     input.advance_to(round + 1);
+///     latency.ingest(round);
+///     worker.step();
+///     latency.observe(&probe);
+/// }
+/// println!("median: {:?}", latency.percentile(50.0));
+/// ```
+pub struct LatencyProbe<T: Timestamp> {
+    pending: VecDeque<(T, Instant)>,
+    completed: Vec<(T, Duration)>,
+}
+
+impl<T: Timestamp> LatencyProbe<T> {
+    /// Creates a new latency probe with no pending rounds.
+    pub fn new() -> Self {
+        LatencyProbe {
+            pending: VecDeque::new(),
+            completed: Vec::new(),
+        }
+    }
+    /// Records the current wall-clock time as the ingestion time of `round`.
+    ///
+    /// Rounds should be ingested in order.
+    pub fn ingest(&mut self, round: T) {
+        self.pending.push_back((round, Instant::now()));
+    }
+    /// Records the completion of each pending round that `probe` has passed.
+    ///
+    /// Returns the number of rounds still pending.
+    pub fn observe(&mut self, probe: &ProbeHandle<Product<RootTimestamp, T>>) -> usize {
+        while self.pending.front().map(|x| !probe.less_equal(&Product::new(RootTimestamp, x.0.clone()))).unwrap_or(false) {
+            let (round, start) = self.pending.pop_front().unwrap();
+            self.completed.push((round, start.elapsed()));
+        }
+        self.pending.len()
+    }
+    /// The completed rounds, and their latencies.
+    pub fn completed(&self) -> &[(T, Duration)] { &self.completed[..] }
+    /// The completion latency at the `percentile`-th percentile (from `0.0` to `100.0`), if any rounds completed.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let latencies: Vec<Duration> = self.completed.iter().map(|x| x.1).collect();
+        percentile_of(&latencies[..], percentile)
+    }
+    /// Moves the completed latencies into `measurements`, for reporting.
+    pub fn drain_into(&mut self, measurements: &mut Measurements) {
+        for (_, latency) in self.completed.drain(..) {
+            measurements.record(latency);
+        }
+    }
+}
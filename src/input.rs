@@ -6,6 +6,8 @@
 //! timely dataflow capabilities, exposing more concurrency to the operator implementations
 //! than are evident from the logical times, which appear to execute in sequence.
 
+use std::collections::{VecDeque, BTreeSet};
+
 use timely::progress::Timestamp;
 use timely::progress::timestamp::RootTimestamp;
 use timely::progress::nested::product::Product;
@@ -70,6 +72,35 @@ impl<'a, T: Timestamp+Clone, D: Data, R: Diff> InputSession<'a, T, D, R> {
 		self.time = Product::new(RootTimestamp, time);
 	}
 
+	/// Advances the session to the time reported by `clock`, and flushes, if that time is in advance of the session's.
+	///
+	/// This method is intended to be called regularly from an ingestion loop, with a clock derived from the data,
+	/// for example the largest event time observed less an allowed lateness. Reports of times not in advance of the 
+	/// current time, as can happen when late data arrive, are ignored, so the clock need not be monotonic. Returns
+	/// true if the session advanced.
+	///
+	/// #Examples
+	///
+	/// ```ignore
+	/// let mut max_seen = 0;
+	/// for (record, event_time) in source {
+	///     max_seen = ::std::cmp::max(max_seen, event_time);
+	///     session.insert(record);
+	///     session.auto_advance(|| max_seen.saturating_sub(lateness));
+	/// }
+	/// ```
+	pub fn auto_advance<F: FnOnce()->T>(&mut self, clock: F) -> bool {
+		let time = clock();
+		if self.time.inner.less_than(&time) {
+			self.advance_to(time);
+			self.flush();
+			true
+		}
+		else {
+			false
+		}
+	}
+
 	/// Reveals the current time of the session.
 	pub fn epoch(&self) -> &T { &self.time.inner }
 	/// Reveals the current time of the session.
@@ -81,3 +112,186 @@ impl<'a, T: Timestamp+Clone, D: Data, R: Diff> Drop for InputSession<'a, T, D, R
 		self.flush();
 	}
 }
+
+/// An input session which bounds the amount of input that is not yet complete.
+///
+/// Ingestion loops that introduce data as fast as they can read it may run arbitrarily far ahead of the 
+/// computation, buffering input in memory. The `Throttle` wraps an `InputSession` and a probe placed at 
+/// the outputs of interest, and tracks the epochs and numbers of records that the probe has not yet seen
+/// complete. When either exceeds its bound, `must_wait` returns true, and `wait` can be used to step the
+/// computation until it returns false.
+///
+/// #Examples
+///
+/// ```ignore
+/// let mut throttle = Throttle::new(InputSession::from(&mut handle), probe.clone(), 4, 1_000_000);
+/// for round in 1 .. rounds {
+///     for record in read_round(round) { throttle.insert(record); }
+///     throttle.advance_to(round);
+///     throttle.wait(|| { worker.step(); });
+/// }
+/// ```
+pub struct Throttle<'a, T: Timestamp+Clone, D: Data, R: Diff> {
+	session: InputSession<'a, T, D, R>,
+	probe: ::timely::dataflow::operators::probe::Handle<Product<RootTimestamp, T>>,
+	/// epochs that have been closed, but that the probe has not yet passed, with their record counts.
+	pending: VecDeque<(T, usize)>,
+	/// records introduced in the current epoch.
+	current: usize,
+	/// total records in `pending`.
+	outstanding: usize,
+	max_epochs: usize,
+	max_records: usize,
+}
+
+impl<'a, T: Timestamp+Clone, D: Data, R: Diff> Throttle<'a, T, D, R> {
+
+	/// Creates a new throttle from a session, a probe, and bounds on incomplete epochs and records.
+	pub fn new(session: InputSession<'a, T, D, R>, probe: ::timely::dataflow::operators::probe::Handle<Product<RootTimestamp, T>>, max_epochs: usize, max_records: usize) -> Self {
+		Throttle {
+			session: session,
+			probe: probe,
+			pending: VecDeque::new(),
+			current: 0,
+			outstanding: 0,
+			max_epochs: max_epochs,
+			max_records: max_records,
+		}
+	}
+
+	/// Adds to the weight of an element in the collection.
+	pub fn update(&mut self, element: D, change: R) {
+		self.current += 1;
+		self.session.update(element, change);
+	}
+
+	/// Advances the logical time for future records, and flushes the session.
+	///
+	/// The records introduced since the previous call are counted against the epoch being closed.
+	pub fn advance_to(&mut self, time: T) {
+		let epoch = self.session.epoch().clone();
+		self.session.advance_to(time);
+		self.session.flush();
+		if epoch.less_than(self.session.epoch()) {
+			self.pending.push_back((epoch, self.current));
+			self.outstanding += self.current;
+			self.current = 0;
+		}
+	}
+
+	/// Indicates whether the bounds on incomplete input are exceeded.
+	pub fn must_wait(&mut self) -> bool {
+		while self.pending.front().map(|x| !self.probe.less_equal(&Product::new(RootTimestamp, x.0.clone()))).unwrap_or(false) {
+			let (_, count) = self.pending.pop_front().unwrap();
+			self.outstanding -= count;
+		}
+		self.pending.len() > self.max_epochs || self.outstanding > self.max_records
+	}
+
+	/// Calls `step` until the bounds on incomplete input are no longer exceeded.
+	///
+	/// The `step` function should advance the computation, for example by calling `worker.step()`.
+	pub fn wait<F: FnMut()>(&mut self, mut step: F) {
+		while self.must_wait() {
+			step();
+		}
+	}
+
+	/// Reveals the wrapped session.
+	///
+	/// Updates made directly to the session are not counted against the bound on records.
+	pub fn session(&mut self) -> &mut InputSession<'a, T, D, R> { &mut self.session }
+}
+
+impl<'a, T: Timestamp+Clone, D: Data> Throttle<'a, T, D, isize> {
+	/// Adds an element to the collection.
+	pub fn insert(&mut self, element: D) { self.update(element, 1); }
+	/// Removes an element from the collection.
+	pub fn remove(&mut self, element: D) { self.update(element,-1); }
+}
+
+/// An input session which suppresses updates already introduced in the current epoch.
+///
+/// Sources with at-least-once delivery may present the same update several times, for example after
+/// reconnecting. The `Deduplicate` wrapper remembers each `(element, change)` pair introduced in the current
+/// epoch, and drops pairs it has already introduced. The remembered pairs are cleared when the epoch advances,
+/// and so duplicates are only detected within an epoch; the source should not redeliver updates of closed
+/// epochs.
+///
+/// At most `max_records` distinct pairs are remembered in each epoch. Once the bound is reached, further
+/// updates in the epoch are introduced without being checked, and counted by `unchecked`.
+///
+/// #Examples
+///
+/// ```ignore
+/// let mut input = Deduplicate::new(InputSession::from(&mut handle), 1_000_000);
+/// for (record, epoch) in redelivering_source {
+///     input.advance_to(epoch);
+///     input.insert(record);
+/// }
+/// ```
+pub struct Deduplicate<'a, T: Timestamp+Clone, D: Data, R: Diff> {
+	session: InputSession<'a, T, D, R>,
+	/// pairs introduced in the current epoch.
+	seen: BTreeSet<(D, R)>,
+	/// updates introduced without checking, in the current epoch.
+	unchecked: usize,
+	max_records: usize,
+}
+
+impl<'a, T: Timestamp+Clone, D: Data, R: Diff> Deduplicate<'a, T, D, R> {
+
+	/// Creates a new deduplicating session from a session and a bound on remembered updates per epoch.
+	pub fn new(session: InputSession<'a, T, D, R>, max_records: usize) -> Self {
+		Deduplicate {
+			session: session,
+			seen: BTreeSet::new(),
+			unchecked: 0,
+			max_records: max_records,
+		}
+	}
+
+	/// Adds to the weight of an element in the collection, unless the same update was introduced in this epoch.
+	///
+	/// Returns `true` if the update was introduced.
+	pub fn update(&mut self, element: D, change: R) -> bool {
+		if self.seen.len() < self.max_records {
+			if !self.seen.insert((element.clone(), change)) {
+				return false;
+			}
+		}
+		else if !self.seen.contains(&(element.clone(), change)) {
+			self.unchecked += 1;
+		}
+		else {
+			return false;
+		}
+		self.session.update(element, change);
+		true
+	}
+
+	/// Advances the logical time for future records, forgetting updates of earlier epochs.
+	pub fn advance_to(&mut self, time: T) {
+		let epoch = self.session.epoch().clone();
+		self.session.advance_to(time);
+		if epoch.less_than(self.session.epoch()) {
+			self.seen.clear();
+			self.unchecked = 0;
+		}
+	}
+
+	/// The number of updates introduced in this epoch without checking for duplicates.
+	pub fn unchecked(&self) -> usize { self.unchecked }
+
+	/// Reveals the wrapped session.
+	///
+	/// Updates made directly to the session are not checked or remembered.
+	pub fn session(&mut self) -> &mut InputSession<'a, T, D, R> { &mut self.session }
+}
+
+impl<'a, T: Timestamp+Clone, D: Data> Deduplicate<'a, T, D, isize> {
+	/// Adds an element to the collection, unless it was added in this epoch.
+	pub fn insert(&mut self, element: D) -> bool { self.update(element, 1) }
+	/// Removes an element from the collection, unless it was removed in this epoch.
+	pub fn remove(&mut self, element: D) -> bool { self.update(element,-1) }
+}
@@ -0,0 +1,121 @@
+//! Decodes change data capture envelopes.
+//!
+//! Change data capture (CDC) systems such as Debezium describe each change to a database row with an envelope
+//! containing the row's state `before` and `after` the change, the operation performed, and the time of the
+//! change at the source database. An `Envelope` can be pushed into an `InputSession` as a retraction of the prior
+//! row and an introduction of the new row, or converted into a `(key, Option<row>)` state change for the
+//! `arrange_upsert` operator.
+//!
+//! With the `cdc-json` feature enabled, the `parse_json` function decodes Debezium's JSON serialization, using
+//! a supplied function to convert each row from a JSON value.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let envelope = cdc::parse_json(&line, |row| {
+//!     Some((row["id"].as_u64()?, row["name"].as_str()?.to_owned()))
+//! })?;
+//! envelope.push_into(&mut input);
+//! ```
+
+use timely::progress::Timestamp;
+
+use ::Data;
+use input::InputSession;
+
+/// The operation a change describes.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Op {
+    /// A row was inserted.
+    Create,
+    /// A row was updated.
+    Update,
+    /// A row was deleted.
+    Delete,
+    /// A row was read while taking an initial snapshot.
+    Read,
+}
+
+impl Op {
+    /// Decodes the single-letter operation codes used by Debezium.
+    pub fn from_code(code: &str) -> Option<Op> {
+        match code {
+            "c" => Some(Op::Create),
+            "u" => Some(Op::Update),
+            "d" => Some(Op::Delete),
+            "r" => Some(Op::Read),
+            _ => None,
+        }
+    }
+}
+
+/// A change to one row, with the row's state before and after the change.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Envelope<V> {
+    /// The operation performed.
+    pub op: Op,
+    /// The state of the row before the change, absent for insertions and snapshot reads.
+    pub before: Option<V>,
+    /// The state of the row after the change, absent for deletions.
+    pub after: Option<V>,
+    /// The time of the change at the source, in milliseconds since the Unix epoch, if reported.
+    pub source_ms: Option<u64>,
+}
+
+impl<V: Data> Envelope<V> {
+    /// The updates describing the change, as a retraction of `before` and an introduction of `after`.
+    ///
+    /// Updates that leave a row unchanged produce no updates.
+    pub fn updates(&self) -> Vec<(V, isize)> {
+        if self.before == self.after { return Vec::new(); }
+        let mut result = Vec::new();
+        if let Some(ref before) = self.before { result.push((before.clone(), -1)); }
+        if let Some(ref after) = self.after { result.push((after.clone(), 1)); }
+        result
+    }
+    /// Pushes the updates describing the change into `session`, at its current time.
+    pub fn push_into<T: Timestamp+Clone>(&self, session: &mut InputSession<T, V, isize>) {
+        for (row, diff) in self.updates() {
+            session.update(row, diff);
+        }
+    }
+    /// The change as a state change of the row's key, for `arrange_upsert`.
+    ///
+    /// The key is extracted from `after`, or from `before` for deletions. An envelope with neither returns
+    /// `None`. A change to a row's key should be presented as a deletion of the old key followed by this
+    /// change; this method reports only the new key.
+    pub fn upsert<K, F: Fn(&V)->K>(self, key: F) -> Option<(K, Option<V>)> {
+        match self.after {
+            Some(after) => Some((key(&after), Some(after))),
+            None => self.before.map(|before| (key(&before), None)),
+        }
+    }
+}
+
+/// Decodes a Debezium JSON envelope, converting rows with `decode`.
+///
+/// The envelope may be wrapped in a `"payload"` field, as it is when the schema is included. Rows for which
+/// `decode` returns `None` are reported as errors, as are missing operation codes.
+#[cfg(feature = "cdc-json")]
+pub fn parse_json<V, F: Fn(&::serde_json::Value)->Option<V>>(text: &str, decode: F) -> Result<Envelope<V>, String> {
+
+    let value: ::serde_json::Value = ::serde_json::from_str(text).map_err(|e| format!("invalid JSON: {}", e))?;
+    let payload = if value.get("payload").is_some() { &value["payload"] } else { &value };
+
+    let op = payload["op"].as_str().and_then(Op::from_code).ok_or_else(|| format!("missing or unknown op: {}", payload["op"]))?;
+
+    let row = |field: &str| -> Result<Option<V>, String> {
+        match payload.get(field) {
+            None => Ok(None),
+            Some(v) if v.is_null() => Ok(None),
+            Some(v) => decode(v).map(Some).ok_or_else(|| format!("failed to decode {}: {}", field, v)),
+        }
+    };
+
+    Ok(Envelope {
+        op: op,
+        before: row("before")?,
+        after: row("after")?,
+        source_ms: payload["source"]["ts_ms"].as_u64().or_else(|| payload["ts_ms"].as_u64()),
+    })
+}
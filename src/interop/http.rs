@@ -0,0 +1,293 @@
+//! Serves the contents and statistics of arrangements over HTTP, for debugging running computations.
+//!
+//! An `Inspector` listens on a TCP address and answers requests about the traces registered with it. Traces
+//! are not shared between threads, and so the inspector does not run a thread of its own: the worker calls
+//! `Inspector::poll` between steps, which reads and answers whatever requests it can without blocking, and
+//! keeps partially read requests and partially written responses for later calls. Each worker may run its
+//! own inspector, answering for the keys that worker holds.
+//!
+//! The inspector answers three read-only requests, each with a JSON object:
+//!
+//! * `GET /arrangements` lists the names of the registered arrangements.
+//! * `GET /arrangements/<name>/stats` reports the arrangement's statistics (see `TraceAgent::statistics`).
+//! * `GET /arrangements/<name>/lookup?key=<key>` reports the values of a key with their accumulated counts.
+//!
+//! Keys are parsed from the query string by a function supplied at registration. Values and counts are
+//! reported by their `Debug` representations.
+//!
+//! This module requires the `http` feature.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let mut inspector = Inspector::bind(("127.0.0.1", 8000 + worker.index() as u16)).unwrap();
+//! inspector.register("edges", arranged.trace.clone(), |key| key.parse::<u32>().ok().map(|k| OrdWrapper { item: k }));
+//! while worker.step() {
+//!     inspector.poll();
+//! }
+//! ```
+
+use std::fmt::Debug;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use ::Diff;
+use lattice::Lattice;
+use operators::arrange::{ArrangementStatistics, TraceAgent};
+use trace::{BatchReader, Cursor, TraceReader};
+
+// Connections not answered within this time are abandoned.
+const TIMEOUT: u64 = 5;
+// Requests are read up to the end of their head, or this many bytes.
+const REQUEST_LIMIT: usize = 8192;
+
+/// A registered arrangement, with its key type erased.
+trait Inspect {
+    fn release(&mut self);
+    fn statistics(&mut self) -> ArrangementStatistics;
+    fn lookup(&mut self, key: &str) -> Option<Vec<(String, String)>>;
+}
+
+struct Registered<K, V, T, R, Tr, F> where T: Lattice+Clone+'static, Tr: TraceReader<K, V, T, R> {
+    trace: TraceAgent<K, V, T, R, Tr>,
+    parse: F,
+}
+
+impl<K, V, T, R, Tr, F> Inspect for Registered<K, V, T, R, Tr, F>
+where
+    K: Ord+Clone,
+    V: Ord+Debug,
+    T: Lattice+Clone+'static,
+    R: Diff,
+    Tr: TraceReader<K, V, T, R>,
+    F: Fn(&str)->Option<K>,
+{
+    // Advances the handle through the trace's latest batch. Lookups accumulate all times, and so need not
+    // distinguish any of them, and the held handle should not prevent the trace from compacting and merging.
+    fn release(&mut self) {
+        let mut upper = None;
+        self.trace.map_batches(|batch| upper = Some(batch.upper().to_vec()));
+        if let Some(upper) = upper {
+            self.trace.advance_by(&upper[..]);
+            self.trace.distinguish_since(&upper[..]);
+        }
+    }
+    fn statistics(&mut self) -> ArrangementStatistics {
+        self.trace.statistics()
+    }
+    fn lookup(&mut self, key: &str) -> Option<Vec<(String, String)>> {
+        let key = (self.parse)(key)?;
+        let mut result = Vec::new();
+        let mut cursor = self.trace.cursor();
+        cursor.seek_key(&key);
+        if cursor.key_valid() && cursor.key() == &key {
+            while cursor.val_valid() {
+                let mut count = R::zero();
+                cursor.map_times(|_time, diff| count = count + diff);
+                if !count.is_zero() {
+                    result.push((format!("{:?}", cursor.val()), format!("{:?}", count)));
+                }
+                cursor.step_val();
+            }
+        }
+        Some(result)
+    }
+}
+
+/// Answers HTTP requests about registered arrangements.
+pub struct Inspector {
+    listener: TcpListener,
+    arrangements: Vec<(String, Box<Inspect>)>,
+    connections: Vec<Connection>,
+}
+
+/// An accepted connection, reading its request or writing its response.
+struct Connection {
+    stream: TcpStream,
+    accepted: Instant,
+    /// The request read so far, or once answered the response.
+    buffer: Vec<u8>,
+    /// The number of bytes of the response written, once answered.
+    written: Option<usize>,
+}
+
+impl Inspector {
+    /// Listens for requests at `address`.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        Ok(Inspector { listener: listener, arrangements: Vec::new(), connections: Vec::new() })
+    }
+
+    /// The address the inspector listens at, for example when bound to port zero.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Registers an arrangement's trace under `name`, with `parse` reading keys from lookup requests.
+    ///
+    /// The inspector holds the trace handle, and advances it through the trace's latest batch on each `poll`,
+    /// so that it does not prevent the trace from compacting. Lookups accumulate updates at all times, and so
+    /// report the contents of the trace through its latest batch.
+    pub fn register<K, V, T, R, Tr, F>(&mut self, name: &str, trace: TraceAgent<K, V, T, R, Tr>, parse: F)
+    where
+        K: Ord+Clone+'static,
+        V: Ord+Debug+'static,
+        T: Lattice+Clone+'static,
+        R: Diff+'static,
+        Tr: TraceReader<K, V, T, R>+'static,
+        F: Fn(&str)->Option<K>+'static,
+    {
+        self.arrangements.retain(|x| x.0 != name);
+        self.arrangements.push((name.to_owned(), Box::new(Registered { trace: trace, parse: parse })));
+    }
+
+    /// Makes what progress it can on each connection without blocking, returning the number of responses completed.
+    ///
+    /// Requests are read and responses written as far as the connection allows, and resumed in later calls.
+    /// Connections that fail, or that are not answered within a few seconds, are abandoned.
+    pub fn poll(&mut self) -> usize {
+
+        for &mut (_, ref mut arrangement) in self.arrangements.iter_mut() {
+            arrangement.release();
+        }
+
+        while let Ok((stream, _address)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                self.connections.push(Connection { stream: stream, accepted: Instant::now(), buffer: Vec::new(), written: None });
+            }
+        }
+
+        let mut answered = 0;
+        let mut connections = ::std::mem::replace(&mut self.connections, Vec::new());
+        for mut connection in connections.drain(..) {
+            match self.progress(&mut connection) {
+                Ok(true) => { answered += 1; },
+                Ok(false) => {
+                    if connection.accepted.elapsed() < Duration::from_secs(TIMEOUT) {
+                        self.connections.push(connection);
+                    }
+                },
+                Err(_) => { },
+            }
+        }
+        answered
+    }
+
+    // Reads the connection's request and writes its response as far as possible, indicating completion.
+    fn progress(&mut self, connection: &mut Connection) -> io::Result<bool> {
+
+        if connection.written.is_none() {
+            if !read_request(&mut connection.stream, &mut connection.buffer)? {
+                return Ok(false);
+            }
+            let response = self.respond(&connection.buffer[..]);
+            connection.buffer = response.into_bytes();
+            connection.written = Some(0);
+        }
+
+        let mut written = connection.written.unwrap_or(0);
+        while written < connection.buffer.len() {
+            match connection.stream.write(&connection.buffer[written ..]) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "connection closed")),
+                Ok(count) => written += count,
+                Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    connection.written = Some(written);
+                    return Ok(false);
+                },
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(true)
+    }
+
+    // The complete response to `request`.
+    fn respond(&mut self, request: &[u8]) -> String {
+        let request = String::from_utf8_lossy(request).into_owned();
+        let mut words = request.lines().next().unwrap_or("").split_whitespace();
+        let (status, body) = match (words.next(), words.next()) {
+            (Some("GET"), Some(target)) => self.route(target),
+            _ => ("405 Method Not Allowed", "{\"error\":\"only GET is supported\"}".to_owned()),
+        };
+        format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body)
+    }
+
+    fn route(&mut self, target: &str) -> (&'static str, String) {
+
+        let (path, query) = match target.find('?') {
+            Some(index) => (&target[.. index], &target[index + 1 ..]),
+            None => (target, ""),
+        };
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        if segments.len() == 1 && segments[0] == "arrangements" {
+            let names: Vec<String> = self.arrangements.iter().map(|x| format!("{:?}", decode(&x.0))).collect();
+            return ("200 OK", format!("{{\"arrangements\":[{}]}}", names.join(",")));
+        }
+
+        if segments.len() == 3 && segments[0] == "arrangements" {
+            let name = decode(segments[1]);
+            if let Some(&mut (_, ref mut arrangement)) = self.arrangements.iter_mut().find(|x| x.0 == name) {
+                match segments[2] {
+                    "stats" => {
+                        let stats = arrangement.statistics();
+                        return ("200 OK", format!(
+                            "{{\"batches\":{},\"keys\":{},\"vals\":{},\"updates\":{},\"inserted_batches\":{},\"inserted_updates\":{},\"recent_updates\":{}}}",
+                            stats.batches, stats.keys, stats.vals, stats.updates,
+                            stats.insertions.batches, stats.insertions.updates, stats.insertions.recent));
+                    },
+                    "lookup" => {
+                        let key = query.split('&').find(|x| x.starts_with("key=")).map(|x| decode(&x[4 ..]));
+                        return match key.and_then(|key| arrangement.lookup(&key)) {
+                            Some(vals) => {
+                                let vals: Vec<String> = vals.iter().map(|&(ref v, ref c)| format!("{{\"val\":{:?},\"count\":{:?}}}", v, c)).collect();
+                                ("200 OK", format!("{{\"vals\":[{}]}}", vals.join(",")))
+                            },
+                            None => ("400 Bad Request", "{\"error\":\"missing or unparseable key\"}".to_owned()),
+                        };
+                    },
+                    _ => { },
+                }
+            }
+        }
+
+        ("404 Not Found", "{\"error\":\"not found\"}".to_owned())
+    }
+}
+
+// Reads what is available of a request head into `buffer`, indicating whether the head is complete.
+fn read_request(stream: &mut TcpStream, buffer: &mut Vec<u8>) -> io::Result<bool> {
+    let mut chunk = [0u8; 1024];
+    loop {
+        if buffer.windows(4).any(|w| w == b"\r\n\r\n") || buffer.len() >= REQUEST_LIMIT {
+            return Ok(true);
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) => return Ok(true),
+            Ok(read) => buffer.extend_from_slice(&chunk[.. read]),
+            Err(ref error) if error.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+// Decodes `+` and percent-encoded bytes in a URL component.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => { result.push(b' '); index += 1; },
+            b'%' => {
+                match text.get(index + 1 .. index + 3).and_then(|x| u8::from_str_radix(x, 16).ok()) {
+                    Some(byte) => { result.push(byte); index += 3; },
+                    None => { result.push(b'%'); index += 1; },
+                }
+            },
+            byte => { result.push(byte); index += 1; },
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
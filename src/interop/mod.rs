@@ -0,0 +1,10 @@
+//! Adapters between differential dataflow and external systems.
+//!
+//! The modules here translate the representations used by other systems into the updates and state changes
+//! differential dataflow consumes, so that programs need not write this glue themselves.
+
+pub mod cdc;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
@@ -0,0 +1,78 @@
+//! Exports the contents of traces to SQLite databases, for inspection with external tools.
+//!
+//! The `export` function accumulates the updates of a trace at times not in advance of a chosen frontier, and
+//! writes each `(key, val)` pair with a non-zero accumulation as a row of a table. Keys, values, and counts are
+//! written as text, using their `Debug` representations, so that any trace can be exported.
+//!
+//! This module requires the `sqlite` feature.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // write the current contents of an arrangement to a table `edges`.
+//! let mut trace = arranged.trace.clone();
+//! let rows = sqlite::export(&mut trace, &[frontier_time], "edges.db", "edges").unwrap();
+//! println!("exported {} rows", rows);
+//! ```
+
+use std::fmt::Debug;
+use std::path::Path;
+
+use rusqlite::Connection;
+
+use ::Diff;
+use trace::{Cursor, TraceReader};
+use timely::order::PartialOrder;
+
+/// Writes the accumulated contents of `trace` to `table` in the SQLite database at `path`.
+///
+/// Updates are accumulated at times not in advance of any element of `frontier`, which should be a frontier
+/// the trace has been sealed through, and be in advance of the trace's compaction frontier. The table is
+/// replaced if it exists, and has text columns `key`, `val`, and `count`. Returns the number of rows written.
+pub fn export<K, V, T, R, Tr, P>(trace: &mut Tr, frontier: &[T], path: P, table: &str) -> Result<usize, String>
+where
+    K: Debug,
+    V: Debug,
+    T: PartialOrder,
+    R: Diff,
+    Tr: TraceReader<K, V, T, R>,
+    P: AsRef<Path>,
+{
+    let mut connection = Connection::open(path).map_err(|e| format!("failed to open database: {}", e))?;
+    let transaction = connection.transaction().map_err(|e| format!("failed to start transaction: {}", e))?;
+
+    let table = format!("\"{}\"", table.replace('"', "\"\""));
+    transaction.execute(&format!("DROP TABLE IF EXISTS {}", table), &[])
+               .map_err(|e| format!("failed to drop table: {}", e))?;
+    transaction.execute(&format!("CREATE TABLE {} (key TEXT NOT NULL, val TEXT NOT NULL, count TEXT NOT NULL)", table), &[])
+               .map_err(|e| format!("failed to create table: {}", e))?;
+
+    let insert = format!("INSERT INTO {} (key, val, count) VALUES (?1, ?2, ?3)", table);
+    let mut rows = 0;
+    {
+        let mut statement = transaction.prepare(&insert).map_err(|e| format!("failed to prepare insert: {}", e))?;
+        let mut cursor = trace.cursor();
+        while cursor.key_valid() {
+            let key = format!("{:?}", cursor.key());
+            while cursor.val_valid() {
+                let mut count = R::zero();
+                cursor.map_times(|time, diff| {
+                    if !frontier.iter().any(|t| t.less_equal(time)) {
+                        count = count + diff;
+                    }
+                });
+                if !count.is_zero() {
+                    let val = format!("{:?}", cursor.val());
+                    let count = format!("{:?}", count);
+                    statement.execute(&[&key, &val, &count]).map_err(|e| format!("failed to insert row: {}", e))?;
+                    rows += 1;
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+    }
+
+    transaction.commit().map_err(|e| format!("failed to commit: {}", e))?;
+    Ok(rows)
+}
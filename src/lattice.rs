@@ -158,6 +158,21 @@ impl Lattice for () {
     fn meet(&self, _other: &()) -> () { () }
 }
 
+/// A lattice whose elements are totally ordered.
+///
+/// The times of a root scope are totally ordered when its timestamp type is, whereas the times of nested scopes
+/// are ordered by the product order, and are not. Operators may require this of their times, for example to
+/// arrange their inputs with traces tuned for histories in which each time follows the last.
+pub trait TotalOrder : Lattice + Ord { }
+
+impl TotalOrder for RootTimestamp { }
+impl<T: TotalOrder> TotalOrder for Product<RootTimestamp, T> { }
+impl TotalOrder for usize { }
+impl TotalOrder for u64 { }
+impl TotalOrder for u32 { }
+impl TotalOrder for i32 { }
+impl TotalOrder for () { }
+
 /// Extends `vector` to contain all joins of pairs of elements.
 pub fn close_under_join<T: Lattice>(vector: &mut Vec<T>) {
     // compares each element to those elements after it.
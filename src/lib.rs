@@ -73,11 +73,21 @@ extern crate timely;
 extern crate timely_sort;
 extern crate timely_communication;
 extern crate abomonation;
+#[cfg(any(feature = "cdc-json", feature = "plan-json"))]
+extern crate serde_json;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
 
+pub mod algorithms;
 pub mod hashable;
 pub mod operators;
 pub mod lattice;
 pub mod trace;
 pub mod input;
 pub mod difference;
-pub mod collection;
\ No newline at end of file
+pub mod collection;
+pub mod harness;
+pub mod interop;
+pub mod plan;
+pub mod row;
+pub mod shutdown;
\ No newline at end of file
@@ -21,14 +21,15 @@ use std::rc::{Rc, Weak};
 use std::cell::RefCell;
 use std::default::Default;
 use std::ops::DerefMut;
-use std::collections::VecDeque;
+use std::collections::{VecDeque, HashMap};
+use std::any::{Any, TypeId};
 
-use timely::dataflow::operators::{Enter, Map};
+use timely::dataflow::operators::{Enter, Leave, Map};
 use timely::order::PartialOrder;
 use timely::dataflow::*;
-use timely::dataflow::operators::Unary;
-use timely::dataflow::channels::pact::{Pipeline, Exchange};
-// use timely::progress::nested::product::Product;
+use timely::dataflow::operators::{Unary, Binary};
+use timely::dataflow::channels::pact::{Pipeline, Exchange, ParallelizationContract};
+use timely::progress::nested::product::Product;
 // use timely::progress::frontier::MutableAntichain;
 use timely::progress::Timestamp;
 use timely::dataflow::operators::Capability;
@@ -36,18 +37,20 @@ use timely::dataflow::scopes::Child;
 
 use timely_sort::Unsigned;
 
-use hashable::{HashOrdered, HashableWrapper, OrdWrapper};
+use hashable::{HashOrdered, HashableWrapper, OrdWrapper, UnsignedWrapper};
 
 use ::{Data, Diff, Collection, AsCollection, Hashable};
 use lattice::Lattice;
-use trace::{Trace, TraceReader, Batch, BatchReader, Batcher, Cursor};
+use trace::{Trace, TraceReader, Batch, BatchReader, Batcher, Cursor, Description};
 // use trace::implementations::hash::HashValSpine as DefaultValTrace;
 // use trace::implementations::hash::HashKeySpine as DefaultKeyTrace;
 use trace::implementations::ord::OrdValSpine as DefaultValTrace;
 use trace::implementations::ord::OrdKeySpine as DefaultKeyTrace;
 
 use trace::wrappers::enter::{TraceEnter, BatchEnter};
-use trace::wrappers::rc::TraceBox;
+use trace::wrappers::leave::{TraceLeave, BatchLeave};
+use trace::wrappers::rc::{TraceBox, TraceChange, Truncation, Insertions};
+use trace::layers::memo::{CmpPrefix, MemoCmp};
 
 /// Wrapper type to permit transfer of `Rc` types, as in batch.
 ///
@@ -74,14 +77,28 @@ where T: Lattice+Clone+'static, Tr: Trace<K,V,T,R>, Tr::Batch: Batch<K,V,T,R> {
     phantom: ::std::marker::PhantomData<(K, V, R)>,
     trace: Weak<RefCell<TraceBox<K, V, T, R, Tr>>>,
     queues: Rc<RefCell<Vec<Weak<RefCell<VecDeque<(Vec<T>, Option<(T, Tr::Batch)>)>>>>>>,
+    frontier: Vec<T>,   // the most recently sealed frontier, checked under the `validate` feature.
+    upper: Vec<T>,      // the upper bound of the most recent batch, checked under the `validate` feature.
 }
 
 impl<K, V, T, R, Tr> TraceWriter<K, V, T, R, Tr>
 where T: Lattice+Clone+'static, Tr: Trace<K,V,T,R>, Tr::Batch: Batch<K,V,T,R> {
 
     /// Advances the trace to `frontier`, providing batch data if it exists.
+    ///
+    /// With the `validate` feature, this panics if `frontier` regresses or if a batch does not begin where the
+    /// previous batch ended.
     pub fn seal(&mut self, frontier: &[T], data: Option<(T, Tr::Batch)>) {
 
+        if cfg!(feature = "validate") {
+            assert!(frontier.iter().all(|t1| self.frontier.iter().any(|t2| t2.less_equal(t1))), "validate: sealed frontier regressed");
+            self.frontier = frontier.to_vec();
+            if let Some((_, ref batch)) = data {
+                assert!(batch.lower() == &self.upper[..], "validate: batch lower bound does not match prior upper bound");
+                self.upper = batch.upper().to_vec();
+            }
+        }
+
         // push information to each listener that still exists.
         let mut borrow = self.queues.borrow_mut();
         for queue in borrow.iter_mut() {
@@ -94,7 +111,11 @@ where T: Lattice+Clone+'static, Tr: Trace<K,V,T,R>, Tr::Batch: Batch<K,V,T,R> {
         // push data to the trace, if it still exists.
         if let Some((_time, batch)) = data {
             if let Some(trace) = self.trace.upgrade() {
-                trace.borrow_mut().trace.insert(batch);
+                let mut trace = trace.borrow_mut();
+                let change = TraceChange::Inserted { lower: batch.lower().to_vec(), upper: batch.upper().to_vec(), updates: batch.len() };
+                trace.note_insertion(batch.len());
+                trace.trace.insert(batch);
+                trace.notify(&change);
             }
         }
     }
@@ -114,6 +135,28 @@ where T: Lattice+Clone+'static, Tr: Trace<K,V,T,R>, Tr::Batch: Batch<K,V,T,R> {
 }
 
 
+/// Statistics about an arrangement, for example for a planner choosing among shared arrangements.
+#[derive(Clone, Debug, Default)]
+pub struct ArrangementStatistics {
+    /// The number of batches in the trace.
+    pub batches: usize,
+    /// The sum over batches of their numbers of distinct keys.
+    pub keys: usize,
+    /// The sum over batches of their numbers of distinct `(key, val)` pairs.
+    pub vals: usize,
+    /// The number of updates in the trace.
+    pub updates: usize,
+    /// Counts of the batches and updates inserted into the trace, including its recent rate of change.
+    pub insertions: Insertions,
+}
+
+impl ArrangementStatistics {
+    /// The average number of values for each key, or zero if there are no keys.
+    pub fn vals_per_key(&self) -> f64 {
+        if self.keys > 0 { self.vals as f64 / self.keys as f64 } else { 0.0 }
+    }
+}
+
 /// A `TraceReader` wrapper which can be imported into other dataflows.
 ///
 /// The `TraceAgent` is the default trace type produced by `arranged`, and it can be extracted
@@ -147,7 +190,17 @@ where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
     fn distinguish_frontier(&mut self) -> &[T] { 
         &self.through[..]
     }
-    fn cursor_through(&mut self, frontier: &[T]) -> Option<Tr::Cursor> { self.trace.borrow_mut().trace.cursor_through(frontier) }
+    fn cursor_through(&mut self, frontier: &[T]) -> Option<Tr::Cursor> {
+        if cfg!(feature = "validate") {
+            // no batch may have compacted times this handle can still distinguish.
+            let advance = &self.advance;
+            self.trace.borrow_mut().trace.map_batches(|batch| {
+                let since = batch.description().since();
+                assert!(advance.iter().all(|t1| since.iter().any(|t2| t2.less_equal(t1))), "validate: batch compacted beyond reader's frontier");
+            });
+        }
+        self.trace.borrow_mut().trace.cursor_through(frontier)
+    }
     fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F) { self.trace.borrow_mut().trace.map_batches(f) }
 }
 
@@ -172,11 +225,81 @@ where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
             phantom: ::std::marker::PhantomData,
             trace: Rc::downgrade(&trace),
             queues: queues,
+            frontier: vec![<T as Lattice>::min()],
+            upper: vec![<T as Lattice>::min()],
         };
 
         (reader, writer)
     }
 
+    /// Applies `logic` to the shared trace.
+    ///
+    /// This provides access to the base trace for inspection (for example, to report its size), when it
+    /// would otherwise be hidden behind the agent. Wrappers around the agent provide `inner()` methods, so
+    /// that a stack of wrappers can be narrowed to the agent and then to the base trace. The trace is
+    /// borrowed for the duration of `logic`, which must not access it through other handles.
+    pub fn with_inner<F: FnOnce(&Tr)->X, X>(&self, logic: F) -> X {
+        logic(&self.trace.borrow().trace)
+    }
+
+    /// Pauses maintenance of the shared trace, until `resume_maintenance` is called.
+    ///
+    /// While paused, the trace does not compact its updates or merge newly arrived batches, even as readers
+    /// advance their frontiers. This is intended for bulk loads, during which repeated merging of the growing
+    /// trace would be wasted work. Pausing affects all handles to the trace.
+    pub fn pause_maintenance(&mut self) {
+        self.trace.borrow_mut().pause();
+    }
+
+    /// Resumes maintenance of the shared trace, catching up on compaction and merging withheld while paused.
+    pub fn resume_maintenance(&mut self) {
+        self.trace.borrow_mut().resume();
+    }
+
+    /// Advances the handle to `frontier`, and removes updates before it from the shared trace.
+    ///
+    /// Advancing a handle permits the trace to compact updates before `frontier`, but the trace only does so as
+    /// it merges batches, and updates that cancel (for example the insertion and deletion of a record subject to
+    /// an erasure request) can remain physically present long after. A truncation instead compacts the trace as
+    /// soon as every handle has advanced to `frontier`, and as further batches become mergeable, until no updates
+    /// before `frontier` remain in their original form; the returned handle reports when this is the case. Times
+    /// before `frontier` can no longer be distinguished through this handle, exactly as with `advance_by`.
+    pub fn truncate_before(&mut self, frontier: &[T]) -> Truncation {
+        assert!(frontier.iter().all(|t1| self.advance.iter().any(|t2| t2.less_equal(t1))), "truncate_before: frontier precedes the handle's frontier");
+        self.advance_by(frontier);
+        self.trace.borrow_mut().request_truncation(frontier)
+    }
+
+    /// Registers `callback` to be informed as the shared trace changes.
+    ///
+    /// The callback learns of each inserted batch, and of each change to the frontiers through which the trace
+    /// may compact its updates and merge its batches, which is when the trace's batches may change. Caches
+    /// derived from the trace can invalidate their contents in response, rather than polling `map_batches`.
+    /// The callback lives as long as the trace rather than the agent, and is called while the trace is
+    /// borrowed, so it must not access the trace through any handle.
+    pub fn on_change<F: FnMut(&TraceChange<T>)+'static>(&mut self, callback: F) {
+        self.trace.borrow_mut().add_callback(Box::new(callback));
+    }
+
+    /// Statistics about the contents of the shared trace and the rate at which it changes.
+    ///
+    /// The counts of current contents are sums over the batches of the trace, which are maintained as the
+    /// batches are built and so are cheap to collect. Keys present in several batches are counted once for
+    /// each, and so `keys` is an upper bound on the number of distinct keys, which is approached as batches
+    /// are merged.
+    pub fn statistics(&mut self) -> ArrangementStatistics where K: Ord+Clone, V: Ord {
+        let mut statistics = ArrangementStatistics::default();
+        self.map_batches(|batch| {
+            let stats = batch.stats();
+            statistics.batches += 1;
+            statistics.keys += stats.keys;
+            statistics.vals += stats.vals;
+            statistics.updates += stats.updates;
+        });
+        statistics.insertions = self.trace.borrow().insertions().clone();
+        statistics
+    }
+
     /// Attaches a new shared queue to the trace.
     ///
     /// The queue will be immediately populated with existing historical batches from the trace, and until the reference 
@@ -256,49 +379,143 @@ where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
     /// });
     /// ```
     pub fn import<G: Scope<Timestamp=T>>(&mut self, scope: &G) -> Arranged<G, K, V, R, TraceAgent<K, V, T, R, Tr>> where T: Timestamp {
+        self.import_throttled(scope, usize::max_value())
+    }
+
+    /// Copies an existing collection into the supplied scope, sending at most about `limit` updates at a time.
+    ///
+    /// This is `import`, except that the source sends batches only until the updates sent in an activation
+    /// reach `limit`, leaving further batches for later activations. A large trace then enters the new dataflow
+    /// as a sequence of smaller sends, between which the worker schedules other operators, including those of
+    /// the dataflow serving the trace. At least one batch is sent in each activation with batches to send.
+    ///
+    /// This is not flow control: nothing bounds the updates sent but not yet processed downstream. The historical
+    /// batches are all sent at the initial time, and so downstream progress cannot be observed until all of them
+    /// are sent; pacing is therefore by updates per activation rather than by a probe.
+    pub fn import_throttled<G: Scope<Timestamp=T>>(&mut self, scope: &G, limit: usize) -> Arranged<G, K, V, R, TraceAgent<K, V, T, R, Tr>> where T: Timestamp {
+        match self.try_import_throttled(scope, limit) {
+            Ok(arranged) => arranged,
+            Err(error) => panic!("failed to import trace: {:?}", error),
+        }
+    }
+
+    /// Copies an existing collection into the supplied scope, or reports why it cannot be.
+    ///
+    /// This is `import`, except that the updates already announced to the new listener are validated before
+    /// the dataflow is constructed: each frontier must be in advance of the one before it, and each batch must
+    /// be at a time in advance of the frontier preceding it, as the source relies on holding capabilities for
+    /// these times. Announcements made after the import cannot be validated in advance; should one violate
+    /// these properties, the source defers it and all later announcements, holding its capabilities, and
+    /// prints the `ImportError` describing it once.
+    pub fn try_import<G: Scope<Timestamp=T>>(&mut self, scope: &G) -> Result<Arranged<G, K, V, R, TraceAgent<K, V, T, R, Tr>>, ImportError<T>> where T: Timestamp {
+        self.try_import_throttled(scope, usize::max_value())
+    }
+
+    /// Validates and imports the trace, as `try_import`, pacing batches as `import_throttled`.
+    pub fn try_import_throttled<G: Scope<Timestamp=T>>(&mut self, scope: &G, limit: usize) -> Result<Arranged<G, K, V, R, TraceAgent<K, V, T, R, Tr>>, ImportError<T>> where T: Timestamp {
 
         let queue = self.new_listener();
 
+        // the source begins with a capability for the least time.
+        let mut prior = vec![<T as Lattice>::min()];
+        for &(ref frontier, ref sent) in queue.borrow().iter() {
+            try!(check_announcement(&prior[..], frontier, sent.as_ref().map(|x| &x.0)));
+            prior = frontier.clone();
+        }
+
         let collection = ::timely::dataflow::operators::operator::source(scope, "ArrangedSource", move |capability| {
             
             // capabilities the source maintains.
             let mut capabilities = vec![capability];
+            // whether a deferred announcement has been reported.
+            let mut reported = false;
             
             move |output| {
 
+                // updates sent in this activation.
+                let mut sent_updates = 0;
+
                 let mut borrow = queue.borrow_mut();
-                while let Some((frontier, sent)) = borrow.pop_front() {
+                loop {
+                    let throttled = match borrow.front() {
+                        Some(&(_, Some((_, ref batch)))) => sent_updates > 0 && sent_updates + batch.len() > limit,
+                        Some(&(_, None)) => false,
+                        None => break,
+                    };
+                    if throttled { break; }
+
+                    // announcements not in advance of the held capabilities stay queued, and are reported once.
+                    let checked = {
+                        let &(ref frontier, ref sent) = borrow.front().expect("ArrangedSource: queue emptied while borrowed");
+                        let held = capabilities.iter().map(|c| c.time().clone()).collect::<Vec<_>>();
+                        check_announcement(&held[..], frontier, sent.as_ref().map(|x| &x.0))
+                    };
+                    if let Err(error) = checked {
+                        if !reported {
+                            println!("ArrangedSource: deferring announcement: {:?}", error);
+                            reported = true;
+                        }
+                        break;
+                    }
+
+                    let (frontier, sent) = borrow.pop_front().expect("ArrangedSource: queue emptied while borrowed");
                     // if data are associated, send em!
                     if let Some((time, batch)) = sent {
-                        if let Some(cap) = capabilities.iter().find(|c| c.time().less_equal(&time)) {
-                            let delayed = cap.delayed(&time);
-                            output.session(&delayed).give(BatchWrapper { item: batch });
-                        }
-                        else {
-                            panic!("failed to find capability for {:?} in {:?}", time, capabilities);
-                        }
+                        let delayed = held_capability(&capabilities[..], &time).delayed(&time);
+                        sent_updates += batch.len();
+                        output.session(&delayed).give(BatchWrapper { item: batch });
                     }
 
                     // advance capabilities to look like `frontier`.
-                    let mut new_capabilities = Vec::new();
-                    for time in frontier.iter() {
-                        if let Some(cap) = capabilities.iter().find(|c| c.time().less_equal(&time)) {
-                            new_capabilities.push(cap.delayed(&time));
-                        }
-                        else {
-                            panic!("failed to find capability for {:?} in {:?}", time, capabilities);
-                        }
-                    }
-                    capabilities = new_capabilities;
+                    capabilities = frontier.iter().map(|time| held_capability(&capabilities[..], time).delayed(time)).collect();
                 }
             }
         });
 
-        Arranged {
+        ::plan::note("Import", Some("Trace"), || (vec![], ::plan::stream_name(&collection)));
+
+        Ok(Arranged {
             stream: collection,
             trace: self.clone(),
+        })
+    }
+}
+
+// Checks that an announced batch time and frontier are in advance of the frontier `prior` held before them.
+fn check_announcement<T: Lattice+Clone>(prior: &[T], frontier: &[T], time: Option<&T>) -> Result<(), ImportError<T>> {
+    if let Some(time) = time {
+        if !prior.iter().any(|t| t.less_equal(time)) {
+            return Err(ImportError::BatchBeforeFrontier { time: time.clone(), frontier: prior.to_vec() });
         }
     }
+    if !frontier.iter().all(|time| prior.iter().any(|t| t.less_equal(time))) {
+        return Err(ImportError::FrontierRegressed { frontier: frontier.to_vec(), prior: prior.to_vec() });
+    }
+    Ok(())
+}
+
+// The capability in `capabilities` for a time not before `time`, which `check_announcement` has ensured exists.
+fn held_capability<'a, T: Timestamp+Lattice>(capabilities: &'a [Capability<T>], time: &T) -> &'a Capability<T> {
+    capabilities.iter().find(|c| c.time().less_equal(time)).expect("ArrangedSource: announcement not checked")
+}
+
+/// Reasons a trace cannot be imported, found by `TraceAgent::try_import`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ImportError<T> {
+    /// A batch is at a time not in advance of the frontier announced before it.
+    BatchBeforeFrontier {
+        /// The time of the batch.
+        time: T,
+        /// The frontier announced before the batch.
+        frontier: Vec<T>,
+    },
+    /// A frontier is not in advance of the frontier announced before it.
+    FrontierRegressed {
+        /// The announced frontier.
+        frontier: Vec<T>,
+        /// The frontier announced before it.
+        prior: Vec<T>,
+    },
 }
 
 impl<K, V, T, R, Tr> Clone for TraceAgent<K, V, T, R, Tr>
@@ -356,19 +573,42 @@ impl<G: Scope, K, V, R, T> Arranged<G, K, V, R, T> where G::Timestamp: Lattice,
     pub fn enter<'a, TInner>(&self, child: &Child<'a, G, TInner>)
         -> Arranged<Child<'a, G, TInner>, K, V, R, TraceEnter<K, V, G::Timestamp, R, T, TInner>>
         where 
-            T::Batch: Clone, 
             K: 'static, 
             V: 'static, 
             G::Timestamp: Clone+Default+'static, 
             TInner: Lattice+Timestamp+Clone+Default+'static, 
             R: 'static {
 
+        let stream = self.stream.enter(child).map(|bw| BatchWrapper { item: BatchEnter::make_from(bw.item) });
+        ::plan::note("Enter", None, || (vec![::plan::stream_name(&self.stream)], ::plan::stream_name(&stream)));
+
         Arranged {
-            stream: self.stream.enter(child).map(|bw| BatchWrapper { item: BatchEnter::make_from(bw.item) }),
+            stream: stream,
             trace: TraceEnter::make_from(self.trace.clone()),
         }
     }
 
+    /// Assigns a stable name to the arrangement, for external tooling.
+    ///
+    /// Streams are identified by their position in the dataflow, which changes as the dataflow is edited. The
+    /// name is recorded in any plan being recorded (see `plan::record`), so that tools comparing plans or
+    /// attaching to arrangements can identify the arrangement across such edits. Names should be unique among
+    /// the arrangements of a dataflow.
+    pub fn named(self, name: &str) -> Self {
+        ::plan::note_name(&self.stream, name);
+        self
+    }
+
+    /// Extracts the description of each batch in the arranged stream.
+    ///
+    /// The resulting stream carries the `lower`, `upper`, and `since` frontiers of each batch, at the
+    /// same timely dataflow times as the batches themselves. This allows operators to observe how the
+    /// arrangement advances and compacts without holding a trace handle, which would otherwise prevent
+    /// the trace from compacting.
+    pub fn descriptions(&self) -> Stream<G, Description<G::Timestamp>> {
+        self.stream.map(|bw| bw.item.description().clone())
+    }
+
     /// Flattens the stream into a `Collection`.
     ///
     /// The underlying `Stream<G, BatchWrapper<T::Batch>>` is a much more efficient way to access the data,
@@ -381,7 +621,7 @@ impl<G: Scope, K, V, R, T> Arranged<G, K, V, R, T> where G::Timestamp: Lattice,
             K: Clone, V: Clone,
             L: Fn(&K, &V) -> D+'static,
     {
-        self.stream.unary_stream(Pipeline, "AsCollection", move |input, output| {
+        let stream = self.stream.unary_stream(Pipeline, "AsCollection", move |input, output| {
 
             input.for_each(|time, data| {
                 let mut session = output.session(&time);
@@ -401,8 +641,257 @@ impl<G: Scope, K, V, R, T> Arranged<G, K, V, R, T> where G::Timestamp: Lattice,
                     }
                 }
             });
+        });
+
+        ::plan::note("AsCollection", None, || (vec![::plan::stream_name(&self.stream)], ::plan::stream_name(&stream)));
+        stream.as_collection()
+    }
+
+    /// Flattens the stream into a `Collection`, producing at most about `chunk` updates per activation.
+    ///
+    /// This is `as_collection`, but rather than flattening each batch as it arrives, the operator retains a
+    /// cursor into each unfinished batch and resumes from it in later activations, in which it is scheduled
+    /// for as long as it holds the capabilities of unfinished batches. Downstream operators see large batches
+    /// as a sequence of smaller messages, interleaved with the work of other operators. A chunk can exceed
+    /// `chunk` updates by the number of updates of one key and value.
+    pub fn as_collection_chunked<D: Data, L>(&self, chunk: usize, logic: L) -> Collection<G, D, R>
+        where
+            R: Diff,
+            T::Batch: Clone+'static,
+            <T::Batch as BatchReader<K, V, G::Timestamp, R>>::Cursor: 'static,
+            K: Clone, V: Clone,
+            L: Fn(&K, &V) -> D+'static,
+    {
+        assert!(chunk > 0, "as_collection_chunked: chunk must be positive");
+
+        // cursors into batches not yet flattened, with the capabilities of the batches.
+        let mut pending = VecDeque::new();
+
+        let stream = self.stream.unary_stream(Pipeline, "AsCollectionChunked", move |input, output| {
+
+            input.for_each(|capability, data| {
+                for wrapper in data.drain(..) {
+                    pending.push_back((capability.clone(), wrapper.item.cursor()));
+                }
+            });
+
+            let mut budget = chunk;
+            while budget > 0 && !pending.is_empty() {
+                {
+                    let (ref capability, ref mut cursor) = pending[0];
+                    let mut session = output.session(capability);
+                    while budget > 0 && cursor.key_valid() {
+                        let key: K = cursor.key().clone();
+                        while budget > 0 && cursor.val_valid() {
+                            let val: V = cursor.val().clone();
+                            cursor.map_times(|time, diff| {
+                                session.give((logic(&key, &val), time.clone(), diff.clone()));
+                                budget = budget.saturating_sub(1);
+                            });
+                            cursor.step_val();
+                        }
+                        if !cursor.val_valid() { cursor.step_key(); }
+                    }
+                }
+                if !pending[0].1.key_valid() { pending.pop_front(); }
+            }
+        });
+
+        ::plan::note("AsCollection", None, || (vec![::plan::stream_name(&self.stream)], ::plan::stream_name(&stream)));
+        stream.as_collection()
+    }
+
+    /// Applies `logic` to a cursor over each batch of the arranged stream, producing the records it pushes.
+    ///
+    /// The closure receives the batch's description, a cursor positioned at the start of the batch, and a
+    /// buffer whose contents are sent at the capability of the batch once the closure returns. This serves
+    /// custom per-batch logic, such as sampling or specialized encodings, which would otherwise re-implement
+    /// the operator and its capability handling. Records must carry any time they need themselves; the
+    /// capability is at most the times of the batch's updates, but does not otherwise relate to them.
+    pub fn flat_map_cursor<D, L>(&self, mut logic: L) -> Stream<G, D>
+        where
+            D: ::timely::Data,
+            T::Batch: Clone+'static,
+            L: FnMut(&Description<G::Timestamp>, &mut <T::Batch as BatchReader<K, V, G::Timestamp, R>>::Cursor, &mut Vec<D>)+'static,
+    {
+        let mut buffer = Vec::new();
+        let stream = self.stream.unary_stream(Pipeline, "FlatMapCursor", move |input, output| {
+            input.for_each(|time, data| {
+                for wrapper in data.drain(..) {
+                    let batch = wrapper.item;
+                    let mut cursor = batch.cursor();
+                    logic(batch.description(), &mut cursor, &mut buffer);
+                    let mut session = output.session(&time);
+                    for record in buffer.drain(..) {
+                        session.give(record);
+                    }
+                }
+            });
+        });
+
+        ::plan::note("FlatMapCursor", None, || (vec![::plan::stream_name(&self.stream)], ::plan::stream_name(&stream)));
+        stream
+    }
+
+    /// Maintains a secondary index of the arrangement, keyed by `projection` of each key and value.
+    ///
+    /// The index holds each `(key, val)` pair of the arrangement as the value of its projected key, so that, for
+    /// example, orders arranged by order identifier can also be found by customer. This is a second arrangement:
+    /// the updates of each batch of the arrangement are read out as a collection, exchanged by the projected key,
+    /// and batched, sorted, and merged into a new trace, as `arrange_by_key_hashed` would do. The only saving over
+    /// arranging the source collection by the projection is that the updates read are already consolidated, and
+    /// the source collection need not be kept around; the index costs as much memory as any other arrangement.
+    pub fn index_by<K2, P>(&self, projection: P) -> Arranged<G, OrdWrapper<K2>, (K, V), R, TraceAgent<OrdWrapper<K2>, (K, V), G::Timestamp, R, DefaultValTrace<OrdWrapper<K2>, (K, V), G::Timestamp, R>>>
+        where
+            G::Timestamp: Ord,
+            K: Data, V: Data, R: Diff,
+            K2: Data+Default+Hashable,
+            T::Batch: Clone+'static,
+            P: Fn(&K, &V) -> K2+'static,
+    {
+        self.as_collection(move |key, val| (projection(key, val), (key.clone(), val.clone())))
+            .arrange_by_key_hashed()
+    }
+}
+
+impl<G: Scope, K: Data+Hashable, V: Data, R: Diff, T> Arranged<G, K, V, R, T>
+where G::Timestamp: Lattice+Ord, T: TraceReader<K, V, G::Timestamp, R>+Clone+'static {
+
+    /// Responds to each query for a key with its values and their accumulated differences at the query's time.
+    ///
+    /// Queries are routed to the worker holding their key, as `arrange` does for updates. The queries of each
+    /// distinct time are held with their own capability, and are answered as soon as the arrangement is complete
+    /// through that time, regardless of queries at other times that may still be waiting. The trace is advanced
+    /// only as far as the outstanding queries and the frontier of the query stream allow.
+    pub fn lookup(&self, queries: &Stream<G, K>) -> Stream<G, (K, V, R)> {
+
+        let mut trace = self.trace.clone();
+
+        // queries awaiting the completion of their times, one entry for each distinct time.
+        let mut stash: Vec<(Capability<G::Timestamp>, Vec<K>)> = Vec::new();
+
+        let exchange = Exchange::new(|key: &K| key.hashed().as_u64());
+
+        queries.binary_notify(&self.stream, exchange, Pipeline, "Lookup", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|capability, data| {
+                match stash.iter().position(|x| x.0.time() == capability.time()) {
+                    Some(index) => stash[index].1.extend(data.drain(..)),
+                    None => stash.push((capability, data.drain(..).collect())),
+                }
+            });
+
+            // batches only indicate progress; responses are read from the trace.
+            input2.for_each(|_capability, data| data.clear());
+
+            // answer queries at times the arrangement has completed.
+            let mut index = 0;
+            while index < stash.len() {
+                if notificator.frontier(1).iter().any(|t| t.less_equal(stash[index].0.time())) {
+                    index += 1;
+                }
+                else {
+                    let (capability, mut keys) = stash.swap_remove(index);
+                    keys.sort();
+                    keys.dedup();
+                    let mut cursor = trace.cursor();
+                    let mut session = output.session(&capability);
+                    for key in keys {
+                        cursor.seek_key(&key);
+                        if cursor.key_valid() && cursor.key() == &key {
+                            while cursor.val_valid() {
+                                let mut sum = R::zero();
+                                cursor.map_times(|time, diff| if time.less_equal(capability.time()) { sum = sum + diff; });
+                                if !sum.is_zero() {
+                                    session.give((key.clone(), cursor.val().clone(), sum));
+                                }
+                                cursor.step_val();
+                            }
+                        }
+                    }
+                }
+            }
+
+            // the trace must accumulate correctly at outstanding and future query times.
+            let mut lower: Vec<G::Timestamp> = Vec::new();
+            let times = notificator.frontier(0).iter().cloned().chain(stash.iter().map(|x| x.0.time().clone()));
+            for time in times {
+                if !lower.iter().any(|t| t.less_equal(&time)) {
+                    lower.retain(|t| !time.less_equal(t));
+                    lower.push(time);
+                }
+            }
+            trace.advance_by(&lower[..]);
         })
-        .as_collection()
+    }
+}
+
+impl<'a, G: Scope, TInner, K, V, R, T> Arranged<Child<'a, G, TInner>, K, V, R, T>
+where
+    G::Timestamp: Lattice,
+    TInner: Lattice+Timestamp,
+    T: TraceReader<K, V, Product<G::Timestamp, TInner>, R>+Clone {
+
+    /// Brings an arranged collection out of a nested scope.
+    ///
+    /// This method produces a proxy trace handle that uses the same backing data, but acts as if the inner
+    /// coordinate of each timestamp has been discarded. Updates at all inner coordinates are accumulated
+    /// together, and so this is most appropriate for arrangements that do not vary with the inner coordinate,
+    /// for example loop-invariant indices built inside an iterative scope.
+    ///
+    /// Batches are presented outside the scope only once the outer times of all of their updates are complete,
+    /// merged with any other batches completed at the same moment, and described by the outer frontiers between
+    /// which they are presented. Describing each inner batch by its outer times alone would produce batches whose
+    /// lower and upper frontiers coincide, which consumers like `join` would misread as complete.
+    pub fn leave(&self) -> Arranged<G, K, V, R, TraceLeave<K, V, G::Timestamp, R, T, TInner>>
+        where
+            K: 'static,
+            V: 'static,
+            G::Timestamp: Clone+Default+'static,
+            TInner: Clone+Default+'static,
+            R: 'static,
+            T::Batch: Batch<K, V, Product<G::Timestamp, TInner>, R> {
+
+        // batches received but not yet presented, with their capabilities, in the order they were produced.
+        let mut stash: Vec<(Capability<G::Timestamp>, T::Batch)> = Vec::new();
+        // the upper frontier of the most recently presented batch.
+        let mut lower = vec![<G::Timestamp as Lattice>::min()];
+
+        let stream = self.stream.leave().unary_notify(Pipeline, "LeaveBatches", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                for wrapper in data.drain(..) {
+                    stash.push((capability.clone(), wrapper.item));
+                }
+            });
+
+            // a batch may be presented once no outer time of its upper frontier can still receive updates.
+            let mut count = 0;
+            while count < stash.len() && stash[count].1.description().upper().iter().all(|u| !notificator.frontier(0).iter().any(|f| f.less_equal(&u.outer))) {
+                count += 1;
+            }
+
+            if count > 0 {
+                let upper = notificator.frontier(0).to_vec();
+                let (capability, batch) = {
+                    let mut released = stash.drain(.. count);
+                    let (capability, mut batch) = released.next().unwrap();
+                    for (_, other) in released {
+                        batch = batch.merge(&other);
+                    }
+                    (capability, batch)
+                };
+                output.session(&capability).give(BatchWrapper { item: BatchLeave::make_through(batch, &lower[..], &upper[..]) });
+                lower = upper;
+            }
+        });
+
+        ::plan::note("Leave", None, || (vec![::plan::stream_name(&self.stream)], ::plan::stream_name(&stream)));
+
+        Arranged {
+            stream: stream,
+            trace: TraceLeave::make_from(self.trace.clone()),
+        }
     }
 }
 
@@ -417,6 +906,19 @@ pub trait Arrange<G: Scope, K, V, R: Diff> where G::Timestamp: Lattice {
         where 
             T: Trace<K, V, G::Timestamp, R>+'static,
             T::Batch: Batch<K, V, G::Timestamp, R>;
+    /// Arranges a stream of `(Key, Val)` updates by `Key`, coalescing small batches.
+    ///
+    /// The `arrange` operator seals batches whenever the input frontier passes a capability it holds, which
+    /// under fine-grained times can produce many tiny batches, each with overhead in the trace and in downstream
+    /// operators. This variant instead withholds sealing until at least `threshold` updates have arrived since
+    /// the previous batches were sealed, or the input is complete, so that one batch covers many times. Batches
+    /// still describe exactly the times they contain, but the trace and output are only complete for a time once
+    /// enough further updates have arrived, and so a `threshold` of more updates than arrive in a round delays
+    /// the results of that round; with a `threshold` of zero this is exactly `arrange`.
+    fn arrange_coalesced<T>(&self, empty_trace: T, threshold: usize) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>
+        where 
+            T: Trace<K, V, G::Timestamp, R>+'static,
+            T::Batch: Batch<K, V, G::Timestamp, R>;
 }
 
 impl<G: Scope, K: Data+HashOrdered, V: Data, R: Diff> Arrange<G, K, V, R> for Collection<G, (K, V), R> where G::Timestamp: Lattice+Ord {
@@ -425,107 +927,289 @@ impl<G: Scope, K: Data+HashOrdered, V: Data, R: Diff> Arrange<G, K, V, R> for Co
         where 
             T: Trace<K, V, G::Timestamp, R>+'static,
             T::Batch: Batch<K, V, G::Timestamp, R> {
+        self.arrange_coalesced(empty_trace, 0)
+    }
 
-        let (reader, mut writer) = TraceAgent::new(empty_trace);
+    fn arrange_coalesced<T>(&self, empty_trace: T, threshold: usize) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>
+        where 
+            T: Trace<K, V, G::Timestamp, R>+'static,
+            T::Batch: Batch<K, V, G::Timestamp, R> {
 
-        // Where we will deposit received updates, and from which we extract batches.
-        let mut batcher = <T::Batch as Batch<K,V,G::Timestamp,R>>::Batcher::new();
+        let exchange = Exchange::new(move |update: &((K,V),G::Timestamp,R)| (update.0).0.hashed().as_u64());
+        arrange_core(&self.inner, exchange, empty_trace, threshold)
+    }
+}
 
-        // Capabilities for the lower envelope of updates in `batcher`.
-        let mut capabilities = Vec::<Capability<G::Timestamp>>::new();
+/// Arranges a stream of `(Key, Val)` updates routed to workers by `pact`, as `arrange_coalesced` does.
+///
+/// The `arrange` methods route each update by the hash of its key; this allows other placements, for example
+/// those of `arrange_balanced`. All updates for each key must be routed to the same worker, as operators
+/// consuming the arrangement assume that each worker holds all updates for the keys it holds.
+pub fn arrange_core<G, K, V, R, T, P>(stream: &Stream<G, ((K,V),G::Timestamp,R)>, pact: P, empty_trace: T, threshold: usize) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord,
+    K: Data,
+    V: Data,
+    R: Diff,
+    T: Trace<K, V, G::Timestamp, R>+'static,
+    T::Batch: Batch<K, V, G::Timestamp, R>,
+    P: ParallelizationContract<G::Timestamp, ((K,V),G::Timestamp,R)>,
+{
+    let (reader, mut writer) = TraceAgent::new(empty_trace);
+
+    // Where we will deposit received updates, and from which we extract batches.
+    let mut batcher = <T::Batch as Batch<K,V,G::Timestamp,R>>::Batcher::new();
+
+    // Capabilities for the lower envelope of updates in `batcher`.
+    let mut capabilities = Vec::<Capability<G::Timestamp>>::new();
+
+    // Updates received since batches were last sealed, used to decide whether to coalesce.
+    let mut received = 0;
+
+    // fabricate a data-parallel operator using the `unary_notify` pattern.
+    let arranged = stream.unary_notify(pact, "Arrange", vec![], move |input, output, notificator| {
+
+        // As we receive data, we need to (i) stash the data and (ii) keep *enough* capabilities.
+        // We don't have to keep all capabilities, but we need to be able to form output messages
+        // when we realize that time intervals are complete.
+
+        input.for_each(|cap, data| {
+
+            // add the capability to our list of capabilities.
+            capabilities.retain(|c| !cap.time().less_than(&c.time()));
+            if !capabilities.iter().any(|c| c.time().less_equal(&cap.time())) { 
+                capabilities.push(cap);
+            }
 
-        // fabricate a data-parallel operator using the `unary_notify` pattern.
-        let exchange = Exchange::new(move |update: &((K,V),G::Timestamp,R)| (update.0).0.hashed().as_u64());
-        let stream = self.inner.unary_notify(exchange, "Arrange", vec![], move |input, output, notificator| {
+            received += data.len();
+            batcher.push_batch(data.deref_mut());
+        });
 
-            // As we receive data, we need to (i) stash the data and (ii) keep *enough* capabilities.
-            // We don't have to keep all capabilities, but we need to be able to form output messages
-            // when we realize that time intervals are complete.
+        // Timely dataflow currently only allows one capability per message, and we may have multiple
+        // incomparable times for which we need to send data. This would normally require shattering
+        // all updates we might send into multiple batches, each associated with a capability. 
+        //
+        // Instead! We can cheat a bit. We can extract one batch, and just make sure to send all of 
+        // capabilities along in separate messages. This is a bit dubious, and we will want to make 
+        // sure that each operator that consumes batches (group, join, as_collection) understands this.
+        // 
+        // At the moment this is painful for non-group operators, who each rely on having the correct 
+        // capabilities at hand, and must find the right capability record-by-record otherwise. But, 
+        // something like this should ease some pain. (we could also just fix timely).
+
+        // Sealing is withheld until enough updates have arrived, unless the input is complete.
+        let withheld = received < threshold && !notificator.frontier(0).is_empty();
+
+        // If there is at least one capability no longer in advance of the input frontier ...
+        if !withheld && capabilities.iter().any(|c| !notificator.frontier(0).iter().any(|t| t.less_equal(&c.time()))) {
+
+            // For each capability not in advance of the input frontier ... 
+            for index in 0 .. capabilities.len() {
+                if !notificator.frontier(0).iter().any(|t| t.less_equal(&capabilities[index].time())) {
+
+                    // Assemble the upper bound on times we can commit with this capabilities.
+                    // This is determined both by the input frontier, and by subsequent capabilities
+                    // which may shadow this capability for some times.
+                    let mut upper = notificator.frontier(0).to_vec();
+                    for capability in &capabilities[(index + 1) .. ] {
+                        let time = capability.time().clone();
+                        if !upper.iter().any(|t| t.less_equal(&time)) {
+                            upper.retain(|t| !time.less_equal(t));
+                            upper.push(time);
+                        }
+                    }
 
-            input.for_each(|cap, data| {
+                    // Extract updates not in advance of `upper`.
+                    let batch = batcher.seal(&upper[..]);
 
-                // add the capability to our list of capabilities.
-                capabilities.retain(|c| !cap.time().less_than(&c.time()));
-                if !capabilities.iter().any(|c| c.time().less_equal(&cap.time())) { 
-                    capabilities.push(cap);
+                    writer.seal(&upper[..], Some((capabilities[index].time().clone(), batch.clone())));
+
+                    // send the batch to downstream consumers, empty or not.
+                    output.session(&capabilities[index]).give(BatchWrapper { item: batch });
                 }
+            }
 
-                batcher.push_batch(data.deref_mut());
-            });
+            received = 0;
 
-            // Timely dataflow currently only allows one capability per message, and we may have multiple
-            // incomparable times for which we need to send data. This would normally require shattering
-            // all updates we might send into multiple batches, each associated with a capability. 
-            //
-            // Instead! We can cheat a bit. We can extract one batch, and just make sure to send all of 
-            // capabilities along in separate messages. This is a bit dubious, and we will want to make 
-            // sure that each operator that consumes batches (group, join, as_collection) understands this.
-            // 
-            // At the moment this is painful for non-group operators, who each rely on having the correct 
-            // capabilities at hand, and must find the right capability record-by-record otherwise. But, 
-            // something like this should ease some pain. (we could also just fix timely).
-
-            // If there is at least one capability no longer in advance of the input frontier ...
-            if capabilities.iter().any(|c| !notificator.frontier(0).iter().any(|t| t.less_equal(&c.time()))) {
-
-                // For each capability not in advance of the input frontier ... 
-                for index in 0 .. capabilities.len() {
-                    if !notificator.frontier(0).iter().any(|t| t.less_equal(&capabilities[index].time())) {
-
-                        // Assemble the upper bound on times we can commit with this capabilities.
-                        // This is determined both by the input frontier, and by subsequent capabilities
-                        // which may shadow this capability for some times.
-                        let mut upper = notificator.frontier(0).to_vec();
-                        for capability in &capabilities[(index + 1) .. ] {
-                            let time = capability.time().clone();
-                            if !upper.iter().any(|t| t.less_equal(&time)) {
-                                upper.retain(|t| !time.less_equal(t));
-                                upper.push(time);
-                            }
-                        }
+            // Having extracted and sent batches between each capability and the input frontier,
+            // we should downgrade all capabilities to match the batcher's lower update frontier.
+            // This may involve discarding capabilities, which is fine as any new updates arrive 
+            // in messages with new capabilities.
+
+            let mut new_capabilities = Vec::new();
+            for time in batcher.frontier() {
+                if let Some(capability) = capabilities.iter().find(|c| c.time().less_equal(time)) {
+                    new_capabilities.push(capability.delayed(time));
+                }
+            }
 
-                        // Extract updates not in advance of `upper`.
-                        let batch = batcher.seal(&upper[..]);
+            capabilities = new_capabilities;
 
-                        writer.seal(&upper[..], Some((capabilities[index].time().clone(), batch.clone())));
+            writer.seal(notificator.frontier(0), None);
 
-                        // send the batch to downstream consumers, empty or not.
-                        output.session(&capabilities[index]).give(BatchWrapper { item: batch });
-                    }
-                }
+            // // This very aggressively pushes frontier information along. We may want to dial it back 
+            // // if we find that we are spamming folks.
+            // queues.upgrade().map(|queues| {
+            //     let mut borrow = queues.borrow_mut();
+            //     for queue in borrow.iter_mut() {
+            //         queue.upgrade().map(|queue| {
+            //             queue.borrow_mut().push_back((notificator.frontier(0).to_vec(), None));
+            //         });
+            //     }
+            //     borrow.retain(|w| w.upgrade().is_some());
+            // });
 
-                // Having extracted and sent batches between each capability and the input frontier,
-                // we should downgrade all capabilities to match the batcher's lower update frontier.
-                // This may involve discarding capabilities, which is fine as any new updates arrive 
-                // in messages with new capabilities.
+        }
+    });
 
-                let mut new_capabilities = Vec::new();
-                for time in batcher.frontier() {
-                    if let Some(capability) = capabilities.iter().find(|c| c.time().less_equal(time)) {
-                        new_capabilities.push(capability.delayed(time));
-                    }
-                }
+    ::plan::note("Arrange", Some("Trace"), || (vec![::plan::stream_name(stream)], ::plan::stream_name(&arranged)));
+
+    Arranged { stream: arranged, trace: reader }
+}
 
-                capabilities = new_capabilities;
+/// Arranges collections, re-using any existing arrangement of the same collection into the same type of trace.
+///
+/// Arranging one collection twice builds two identical indices, each with its own communication, computation,
+/// and memory. The `arrange_cached` method consults a registry, kept for each worker, of the traces built by
+/// prior calls to `arrange_cached`, keyed by the collection and the types of its keys, values, differences,
+/// and trace. If a live trace is found, it is imported rather than rebuilt.
+///
+/// The registry holds only weak references, so it does not keep traces alive or prevent their compaction.
+/// Plain calls to `arrange` are unaffected, and neither consult nor populate the registry.
+pub trait ArrangeCached<G: Scope, K, V, R: Diff> where G::Timestamp: Lattice {
+    /// Arranges a stream of `(Key, Val)` updates by `Key`, or re-uses an existing arrangement.
+    ///
+    /// The first call for a collection behaves as `arrange`. Later calls return an arrangement sharing the same
+    /// trace, whose stream is an import of that trace into the collection's scope. As with `import`, the later
+    /// arrangements start from the trace's current contents, which at dataflow construction are empty.
+    fn arrange_cached<T>(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>
+        where
+            T: Trace<K, V, G::Timestamp, R>+'static,
+            T::Batch: Batch<K, V, G::Timestamp, R>;
+}
 
-                writer.seal(notificator.frontier(0), None);
+impl<G: Scope, K: Data+HashOrdered, V: Data, R: Diff> ArrangeCached<G, K, V, R> for Collection<G, (K, V), R> where G::Timestamp: Lattice+Ord {
 
-                // // This very aggressively pushes frontier information along. We may want to dial it back 
-                // // if we find that we are spamming folks.
-                // queues.upgrade().map(|queues| {
-                //     let mut borrow = queues.borrow_mut();
-                //     for queue in borrow.iter_mut() {
-                //         queue.upgrade().map(|queue| {
-                //             queue.borrow_mut().push_back((notificator.frontier(0).to_vec(), None));
-                //         });
-                //     }
-                //     borrow.retain(|w| w.upgrade().is_some());
-                // });
+    fn arrange_cached<T>(&self, empty_trace: T) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>
+        where
+            T: Trace<K, V, G::Timestamp, R>+'static,
+            T::Batch: Batch<K, V, G::Timestamp, R> {
 
-            }
+        let key = (::plan::stream_name(&self.inner), TypeId::of::<(K, V, R, T)>());
+
+        let cached = ARRANGEMENTS.with(|registry| {
+            registry.borrow().get(&key).and_then(|entry| {
+                entry.handle.downcast_ref::<CachedTrace<K, V, G::Timestamp, R, T>>().and_then(|x| x.upgrade())
+            })
         });
 
-        Arranged { stream: stream, trace: reader }
+        match cached {
+            Some(mut agent) => agent.import(&self.scope()),
+            None => {
+                let arranged = self.arrange(empty_trace);
+                let handle = CachedTrace {
+                    trace: Rc::downgrade(&arranged.trace.trace),
+                    queues: arranged.trace.queues.clone(),
+                };
+                let alive = handle.trace.clone();
+                ARRANGEMENTS.with(|registry| {
+                    let mut registry = registry.borrow_mut();
+                    registry.retain(|_, entry| (entry.alive)());
+                    registry.insert(key, CachedEntry {
+                        handle: Box::new(handle),
+                        alive: Box::new(move || alive.upgrade().is_some()),
+                    });
+                });
+                arranged
+            }
+        }
+    }
+}
+
+thread_local! {
+    static ARRANGEMENTS: RefCell<HashMap<(String, TypeId), CachedEntry>> = RefCell::new(HashMap::new());
+}
+
+/// A registered trace, and a test of whether it is still alive.
+struct CachedEntry {
+    handle: Box<Any>,
+    alive: Box<Fn()->bool>,
+}
+
+/// Weak references to the shared state of a `TraceAgent`.
+struct CachedTrace<K, V, T, R, Tr> where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
+    trace: Weak<RefCell<TraceBox<K, V, T, R, Tr>>>,
+    queues: Weak<RefCell<Vec<Weak<RefCell<VecDeque<(Vec<T>, Option<(T, Tr::Batch)>)>>>>>>,
+}
+
+impl<K, V, T, R, Tr> CachedTrace<K, V, T, R, Tr> where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
+    /// A new agent for the trace, holding the trace's current frontiers, if the trace is still alive.
+    fn upgrade(&self) -> Option<TraceAgent<K, V, T, R, Tr>> {
+        self.trace.upgrade().map(|trace| {
+            let advance = trace.borrow().advance_frontiers.elements().to_vec();
+            let through = trace.borrow().through_frontiers.elements().to_vec();
+            trace.borrow_mut().adjust_advance_frontier(&[], &advance[..]);
+            trace.borrow_mut().adjust_through_frontier(&[], &through[..]);
+            TraceAgent {
+                phantom: ::std::marker::PhantomData,
+                trace: trace,
+                queues: self.queues.clone(),
+                advance: advance,
+                through: through,
+            }
+        })
+    }
+}
+
+/// An arrangement built only when first used.
+///
+/// Library code may offer arranged outputs that many callers never use. Each `arrange` call constructs its
+/// operator and trace immediately, and an unused arrangement is still maintained as the collection changes.
+/// A `LazyArranged` retains the collection and the empty trace, and arranges the collection the first time
+/// one of `arranged`, `stream`, or `trace` is called; later calls share the same arrangement. As with other operators, the arrangement
+/// can only be built while the dataflow is being constructed, and so `arranged` must be called before then.
+pub struct LazyArranged<G: Scope, K, V, R: Diff, T>
+where G::Timestamp: Lattice, T: Trace<K, V, G::Timestamp, R>+'static, T::Batch: Batch<K, V, G::Timestamp, R> {
+    collection: Collection<G, (K, V), R>,
+    empty: RefCell<Option<T>>,
+    arranged: RefCell<Option<Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>>>>,
+}
+
+impl<G: Scope, K: Data+HashOrdered, V: Data, R: Diff, T> LazyArranged<G, K, V, R, T>
+where G::Timestamp: Lattice+Ord, T: Trace<K, V, G::Timestamp, R>+'static, T::Batch: Batch<K, V, G::Timestamp, R> {
+    /// Prepares to arrange `collection` into `empty_trace`, without doing so.
+    pub fn new(collection: &Collection<G, (K, V), R>, empty_trace: T) -> Self {
+        LazyArranged {
+            collection: collection.clone(),
+            empty: RefCell::new(Some(empty_trace)),
+            arranged: RefCell::new(None),
+        }
+    }
+    /// The arrangement, built by the first call.
+    pub fn arranged(&self) -> Arranged<G, K, V, R, TraceAgent<K, V, G::Timestamp, R, T>> {
+        let mut arranged = self.arranged.borrow_mut();
+        if arranged.is_none() {
+            let empty = self.empty.borrow_mut().take().expect("LazyArranged: empty trace missing");
+            *arranged = Some(self.collection.arrange(empty));
+        }
+        let arranged = arranged.as_ref().unwrap();
+        Arranged {
+            stream: arranged.stream.clone(),
+            trace: arranged.trace.clone(),
+        }
+    }
+    /// The stream of batches of the arrangement, which is built by the first access.
+    pub fn stream(&self) -> Stream<G, BatchWrapper<T::Batch>> {
+        self.arranged().stream
+    }
+    /// A handle to the trace of the arrangement, which is built by the first access.
+    pub fn trace(&self) -> TraceAgent<K, V, G::Timestamp, R, T> {
+        self.arranged().trace
+    }
+    /// Indicates whether the arrangement has been built.
+    pub fn is_arranged(&self) -> bool {
+        self.arranged.borrow().is_some()
     }
 }
 
@@ -554,13 +1238,38 @@ where G::Timestamp: Lattice+Ord {
 impl<G: Scope, K: Data+Default+Hashable, V: Data, R: Diff> ArrangeByKey<G, K, V, R> for Collection<G, (K,V), R>
 where G::Timestamp: Lattice+Ord {        
     fn arrange_by_key_hashed(&self) -> Arranged<G, OrdWrapper<K>, V, R, TraceAgent<OrdWrapper<K>, V, G::Timestamp, R, DefaultValTrace<OrdWrapper<K>, V, G::Timestamp, R>>> {
-        self.map(|(k,v)| (OrdWrapper {item:k},v))
-            .arrange(DefaultValTrace::new())
+        let arranged = self.map(|(k,v)| (OrdWrapper {item:k},v))
+            .arrange(DefaultValTrace::new());
+        ::plan::note_trace(&arranged.stream, "OrdValSpine");
+        arranged
     }
     fn arrange_by_key_hashed_cached(&self) -> Arranged<G, HashableWrapper<K>, V, R, TraceAgent<HashableWrapper<K>, V, G::Timestamp, R, DefaultValTrace<HashableWrapper<K>, V, G::Timestamp, R>>> 
     where <K as Hashable>::Output: Default+Data {
-        self.map(|(k,v)| (HashableWrapper::from(k),v))
-            .arrange(DefaultValTrace::new())
+        let arranged = self.map(|(k,v)| (HashableWrapper::from(k),v))
+            .arrange(DefaultValTrace::new());
+        ::plan::note_trace(&arranged.stream, "OrdValSpine");
+        arranged
+    }
+}
+
+/// Arranges `(Key, Val)` pairs by `Key`, memoizing data that make key comparisons cheap.
+pub trait ArrangeByKeyCmp<G: Scope, K: Data+Default+Hashable+CmpPrefix, V: Data, R: Diff>
+where G::Timestamp: Lattice+Ord, <K as Hashable>::Output: Default+Data {
+    /// Arranges a collection of `(Key, Val)` records by `Key`, wrapped in `MemoCmp`.
+    ///
+    /// Each key is wrapped with its hash and comparison prefix, computed once, so that building, merging,
+    /// and seeking in the trace rarely compare keys themselves. This helps keys with expensive comparisons,
+    /// such as long strings or enums with payloads, at the cost of the memoized data in each key.
+    fn arrange_by_key_cmp(&self) -> Arranged<G, MemoCmp<K>, V, R, TraceAgent<MemoCmp<K>, V, G::Timestamp, R, DefaultValTrace<MemoCmp<K>, V, G::Timestamp, R>>>;
+}
+
+impl<G: Scope, K: Data+Default+Hashable+CmpPrefix, V: Data, R: Diff> ArrangeByKeyCmp<G, K, V, R> for Collection<G, (K,V), R>
+where G::Timestamp: Lattice+Ord, <K as Hashable>::Output: Default+Data {
+    fn arrange_by_key_cmp(&self) -> Arranged<G, MemoCmp<K>, V, R, TraceAgent<MemoCmp<K>, V, G::Timestamp, R, DefaultValTrace<MemoCmp<K>, V, G::Timestamp, R>>> {
+        let arranged = self.map(|(k,v)| (MemoCmp::from(k),v))
+            .arrange(DefaultValTrace::new());
+        ::plan::note_trace(&arranged.stream, "OrdValSpine");
+        arranged
     }
 }
 
@@ -583,7 +1292,46 @@ where G::Timestamp: Lattice+Ord {
 impl<G: Scope, K: Data+Default+Hashable, R: Diff> ArrangeBySelf<G, K, R> for Collection<G, K, R>
 where G::Timestamp: Lattice+Ord {
     fn arrange_by_self(&self) -> Arranged<G, OrdWrapper<K>, (), R, TraceAgent<OrdWrapper<K>, (), G::Timestamp, R, DefaultKeyTrace<OrdWrapper<K>, G::Timestamp, R>>> {
-        self.map(|k| (OrdWrapper {item:k}, ()))
-            .arrange(DefaultKeyTrace::new())
+        let arranged = self.map(|k| (OrdWrapper {item:k}, ()))
+            .arrange(DefaultKeyTrace::new());
+        ::plan::note_trace(&arranged.stream, "OrdKeySpine");
+        arranged
+    }
+}
+
+/// Arranges collections keyed by unsigned integers.
+///
+/// Unsigned integer keys are their own hash values (through `UnsignedWrapper`), which avoids hashing them. The
+/// keys should be reasonably well distributed, as they are used to partition the data among workers.
+pub trait ArrangeUnsigned<G: Scope, K: Data+Unsigned+Copy+Default, V: Data, R: Diff>
+where G::Timestamp: Lattice+Ord {
+    /// Arranges a collection of `(Key, Val)` records by `Key`.
+    fn arrange_by_key_u(&self) -> Arranged<G, UnsignedWrapper<K>, V, R, TraceAgent<UnsignedWrapper<K>, V, G::Timestamp, R, DefaultValTrace<UnsignedWrapper<K>, V, G::Timestamp, R>>>;
+}
+
+impl<G: Scope, K: Data+Unsigned+Copy+Default, V: Data, R: Diff> ArrangeUnsigned<G, K, V, R> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord {
+    fn arrange_by_key_u(&self) -> Arranged<G, UnsignedWrapper<K>, V, R, TraceAgent<UnsignedWrapper<K>, V, G::Timestamp, R, DefaultValTrace<UnsignedWrapper<K>, V, G::Timestamp, R>>> {
+        let arranged = self.map(|(k,v)| (UnsignedWrapper::from(k), v))
+            .arrange(DefaultValTrace::new());
+        ::plan::note_trace(&arranged.stream, "OrdValSpine");
+        arranged
+    }
+}
+
+/// Arranges collections of unsigned integers.
+pub trait ArrangeUnsignedSelf<G: Scope, K: Data+Unsigned+Copy+Default, R: Diff>
+where G::Timestamp: Lattice+Ord {
+    /// Arranges a collection of `Key` records by `Key`.
+    fn arrange_by_self_u(&self) -> Arranged<G, UnsignedWrapper<K>, (), R, TraceAgent<UnsignedWrapper<K>, (), G::Timestamp, R, DefaultKeyTrace<UnsignedWrapper<K>, G::Timestamp, R>>>;
+}
+
+impl<G: Scope, K: Data+Unsigned+Copy+Default, R: Diff> ArrangeUnsignedSelf<G, K, R> for Collection<G, K, R>
+where G::Timestamp: Lattice+Ord {
+    fn arrange_by_self_u(&self) -> Arranged<G, UnsignedWrapper<K>, (), R, TraceAgent<UnsignedWrapper<K>, (), G::Timestamp, R, DefaultKeyTrace<UnsignedWrapper<K>, G::Timestamp, R>>> {
+        let arranged = self.map(|k| (UnsignedWrapper::from(k), ()))
+            .arrange(DefaultKeyTrace::new());
+        ::plan::note_trace(&arranged.stream, "OrdKeySpine");
+        arranged
     }
 }
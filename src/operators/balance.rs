@@ -0,0 +1,237 @@
+//! Monitors the distribution of keys and arranges keys on workers to balance their volumes.
+//!
+//! Arrangements place each key on the worker indicated by its hash. When the update volume is skewed,
+//! some workers receive much more work than others. The `balance_map` operator counts updates in each of
+//! a fixed number of hash buckets, and maintains a collection of `(bucket, worker)` pairs assigning each
+//! bucket to a worker so that the accumulated volumes are as even as possible.
+//!
+//! The assignment is a small collection, computed on worker zero and broadcast to every worker. The
+//! `arrange_balanced` operator arranges a collection with each key placed on the worker its bucket is
+//! assigned to. When a bucket is re-assigned, the updates for its keys are retracted from the worker that
+//! held them and introduced at the worker that now holds them, at the time of the re-assignment, so that
+//! the arrangement migrates without restarting the dataflow.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // assign 64 buckets of keys to workers, according to observed volume, and arrange accordingly.
+//! let assignment = edges.balance_map(64);
+//! let arranged = edges.arrange_balanced(&assignment, 64);
+//! ```
+
+use timely::dataflow::*;
+use timely::dataflow::operators::{Binary, Map, Unary};
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
+
+use timely_sort::Unsigned;
+
+use ::{Collection, AsCollection, Data, Diff, Hashable};
+use hashable::OrdWrapper;
+use lattice::Lattice;
+use operators::arrange::{Arranged, TraceAgent, arrange_core};
+use operators::group::consolidate_from;
+use operators::stash::TimeStash;
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+
+/// Extension trait for the `balance_map` method.
+pub trait Balance<G: Scope, K: Data+Hashable> where G::Timestamp: Lattice+Ord {
+    /// Maintains an assignment of key buckets to workers, balancing the volume of updates observed.
+    ///
+    /// Keys are placed in bucket `key.hashed() % buckets`. Initially bucket `b` is assigned to worker
+    /// `b % peers`, which matches hash partitioning when `buckets` is a multiple of the number of workers.
+    /// As each time completes, the buckets are re-assigned greedily, largest first, to the least loaded
+    /// worker, and changes to the assignment are produced at that time. Every worker receives the full
+    /// assignment.
+    ///
+    /// Volumes are counted as updates are received, and the operator is intended for totally ordered times.
+    fn balance_map(&self, buckets: u64) -> Collection<G, (u64, u64), isize>;
+}
+
+/// Extension trait for the `arrange_balanced` method.
+pub trait ArrangeBalanced<G: Scope, K: Data+Hashable, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+    /// Arranges the collection by key, placing each key on the worker `assignment` assigns its bucket to.
+    ///
+    /// Keys are placed in bucket `key.hashed() % buckets`, as for `balance_map`, whose output `assignment`
+    /// is intended to be; each worker must hold the full assignment. Buckets without an assignment are placed
+    /// on worker `bucket % peers`. When the assignment of a bucket changes, the accumulated updates for its
+    /// keys are moved between workers at the time of the change.
+    ///
+    /// Each worker retains the updates it has routed, consolidated, in order to move them, and the operator
+    /// is intended for totally ordered times. The arrangement places keys differently from other arrangements,
+    /// and may only be joined with arrangements placed by the same assignment.
+    fn arrange_balanced(&self, assignment: &Collection<G, (u64, u64), isize>, buckets: u64) -> Arranged<G, OrdWrapper<K>, V, R, TraceAgent<OrdWrapper<K>, V, G::Timestamp, R, DefaultValTrace<OrdWrapper<K>, V, G::Timestamp, R>>>;
+}
+
+impl<G: Scope, K: Data+Hashable, V: Data, R: Diff> Balance<G, K> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord {
+    fn balance_map(&self, buckets: u64) -> Collection<G, (u64, u64), isize> {
+
+        assert!(buckets > 0);
+        let peers = self.scope().peers() as u64;
+
+        // counts of updates in each bucket, for each held capability.
+        let mut local = TimeStash::new();
+
+        let counts = self.inner.unary_notify(Pipeline, "BalanceCount", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                let counts = local.entry(&capability, capability.time(), notificator, || vec![0u64; buckets as usize]);
+                for &((ref key, _), _, _) in data.iter() {
+                    counts[(key.hashed().as_u64() % buckets) as usize] += 1;
+                }
+            });
+
+            for (capability, counts) in local.ready(notificator) {
+                let mut session = output.session(&capability);
+                for (bucket, count) in counts.into_iter().enumerate() {
+                    if count > 0 {
+                        session.give((bucket as u64, count));
+                    }
+                }
+            }
+        });
+
+        // accumulated volumes, and the most recently produced assignment.
+        let mut totals = vec![0u64; buckets as usize];
+        let mut current: Vec<u64> = (0 .. buckets).map(|bucket| bucket % peers).collect();
+        let mut installed = false;
+
+        let assignments = counts.unary_notify(Exchange::new(|_: &(u64, u64)| 0), "BalanceMap", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                for (bucket, count) in data.drain(..) {
+                    totals[bucket as usize] += count;
+                }
+                notificator.notify_at(capability);
+            });
+
+            notificator.for_each(|capability, _count, _notificator| {
+                let assignment = assign(&totals[..], peers);
+                let time = capability.time().clone();
+                let mut session = output.session(&capability);
+                for bucket in 0 .. buckets as usize {
+                    if !installed || assignment[bucket] != current[bucket] {
+                        if installed {
+                            session.give(((bucket as u64, current[bucket]), time.clone(), -1));
+                        }
+                        session.give(((bucket as u64, assignment[bucket]), time.clone(), 1));
+                    }
+                }
+                current = assignment;
+                installed = true;
+            });
+        });
+
+        // send each change to the assignment to every worker.
+        assignments
+            .flat_map(move |update| (0 .. peers).map(move |peer| (peer, update.clone())))
+            .unary_stream(Exchange::new(|x: &(u64, ((u64, u64), G::Timestamp, isize))| x.0), "BalanceBroadcast", |input, output| {
+                input.for_each(|capability, data| {
+                    let mut session = output.session(&capability);
+                    for (_peer, update) in data.drain(..) {
+                        session.give(update);
+                    }
+                });
+            })
+            .as_collection()
+    }
+}
+
+impl<G: Scope, K: Data+Hashable, V: Data, R: Diff> ArrangeBalanced<G, K, V, R> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord {
+    fn arrange_balanced(&self, assignment: &Collection<G, (u64, u64), isize>, buckets: u64) -> Arranged<G, OrdWrapper<K>, V, R, TraceAgent<OrdWrapper<K>, V, G::Timestamp, R, DefaultValTrace<OrdWrapper<K>, V, G::Timestamp, R>>> {
+
+        assert!(buckets > 0);
+        let peers = self.scope().peers() as u64;
+
+        // updates and changes to the assignment received for each time.
+        let mut pending: TimeStash<G::Timestamp, (Vec<((K, V), R)>, Vec<((u64, u64), isize)>)> = TimeStash::new();
+
+        // the accumulated assignment of each bucket, the worker it routes to, and the updates routed for it.
+        let mut assigned: Vec<Vec<(u64, isize)>> = vec![Vec::new(); buckets as usize];
+        let mut routes: Vec<u64> = (0 .. buckets).map(|bucket| bucket % peers).collect();
+        let mut routed: Vec<Vec<((K, V), R)>> = vec![Vec::new(); buckets as usize];
+
+        let moves = self.inner.binary_notify(&assignment.inner, Pipeline, Pipeline, "BalanceRoute", vec![], move |input1, input2, output, notificator| {
+
+            input1.for_each(|capability, data| {
+                for (datum, time, diff) in data.drain(..) {
+                    pending.entry(&capability, &time, notificator, || (Vec::new(), Vec::new())).0.push((datum, diff));
+                }
+            });
+
+            input2.for_each(|capability, data| {
+                for (datum, time, diff) in data.drain(..) {
+                    pending.entry(&capability, &time, notificator, || (Vec::new(), Vec::new())).1.push((datum, diff));
+                }
+            });
+
+            for (capability, (updates, changes)) in pending.ready_sorted(notificator) {
+                let time = capability.time().clone();
+                let mut session = output.session(&capability);
+
+                // move the updates of re-assigned buckets to their new workers.
+                for ((bucket, worker), diff) in changes {
+                    let bucket = bucket as usize;
+                    match assigned[bucket].iter().position(|x| x.0 == worker) {
+                        Some(index) => assigned[bucket][index].1 += diff,
+                        None => assigned[bucket].push((worker, diff)),
+                    }
+                    assigned[bucket].retain(|x| x.1 != 0);
+                    let route = assigned[bucket].iter().find(|x| x.1 > 0).map(|x| x.0).unwrap_or(bucket as u64 % peers);
+                    if route != routes[bucket] {
+                        consolidate_from(&mut routed[bucket], 0);
+                        for &(ref datum, diff) in routed[bucket].iter() {
+                            session.give((routes[bucket], (datum.clone(), time.clone(), -diff)));
+                            session.give((route, (datum.clone(), time.clone(), diff)));
+                        }
+                        routes[bucket] = route;
+                    }
+                }
+
+                // route the updates at this time, retaining them in case their bucket moves.
+                for (datum, diff) in updates {
+                    let bucket = ((datum.0).hashed().as_u64() % buckets) as usize;
+                    session.give((routes[bucket], (datum.clone(), time.clone(), diff)));
+                    routed[bucket].push((datum, diff));
+                    if routed[bucket].len() > 1024 && routed[bucket].len().is_power_of_two() {
+                        consolidate_from(&mut routed[bucket], 0);
+                    }
+                }
+            }
+        });
+
+        // deliver routed updates to their workers, keyed as `arrange_by_key_hashed` would key them.
+        let exchange = Exchange::new(|x: &(u64, ((K, V), G::Timestamp, R))| x.0);
+        let delivered = moves.unary_stream(exchange, "BalanceDeliver", |input, output| {
+            input.for_each(|capability, data| {
+                let mut session = output.session(&capability);
+                for (_worker, ((key, val), time, diff)) in data.drain(..) {
+                    session.give(((OrdWrapper { item: key }, val), time, diff));
+                }
+            });
+        });
+
+        arrange_core(&delivered, Pipeline, DefaultValTrace::new(), 0)
+    }
+}
+
+/// Greedily assigns buckets, largest first, to the least loaded of `peers` workers.
+///
+/// Ties are broken by bucket and worker index, so that equal volumes produce equal assignments.
+fn assign(volumes: &[u64], peers: u64) -> Vec<u64> {
+    let mut order: Vec<usize> = (0 .. volumes.len()).collect();
+    order.sort_by(|&x, &y| volumes[y].cmp(&volumes[x]).then(x.cmp(&y)));
+
+    let mut loads = vec![0u64; peers as usize];
+    let mut result = vec![0u64; volumes.len()];
+    for bucket in order {
+        let mut worker = 0;
+        for index in 1 .. loads.len() {
+            if loads[index] < loads[worker] { worker = index; }
+        }
+        loads[worker] += volumes[bucket];
+        result[bucket] = worker as u64;
+    }
+    result
+}
@@ -0,0 +1,103 @@
+//! Group records from several collections by a shared key.
+//!
+//! The `cogroup3` through `cogroup6` methods generalize `group` to several input collections with the same
+//! key type but different value types. For each key, the user logic receives the accumulated values of each
+//! input as a separate slice, and produces output values as `group` does. This allows reconciliation logic
+//! over several sources of per-key data to be written directly, rather than through chains of binary joins
+//! whose intermediate results must each be arranged.
+//!
+//! The inputs are combined into one collection of tagged values, arranged once by key. The values of each
+//! input are presented in sorted order, and the slices may be empty for keys absent from some inputs.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // for each account, the balance less pending debits, unless the account is frozen.
+//! let available = balances.cogroup3(&debits, &frozen, |_account, balances, debits, frozen, output| {
+//!     if frozen.is_empty() {
+//!         let total = balances.iter().map(|&(b, c)| b * c as i64).sum::<i64>()
+//!                   - debits.iter().map(|&(d, c)| d * c as i64).sum::<i64>();
+//!         output.push((total, 1));
+//!     }
+//! });
+//! ```
+
+use std::fmt::Debug;
+
+use timely::dataflow::*;
+
+use ::{Data, Collection, Diff, Hashable};
+use lattice::Lattice;
+use operators::group::Group;
+
+// Each input is tagged by a tuple of options, in which only its own position is `Some`. The tuples of the
+// inputs sort in reverse order of the inputs, and so the values of each input are contiguous, and in order.
+macro_rules! cogroup_n {
+    ($(#[$attr:meta])* trait $Trait:ident, fn $method:ident, ($V1:ident, $slice1:ident, $index1:tt, $wrap1:expr), $(($other:ident, $V:ident, $slice:ident, $index:tt, $wrap:expr)),+) => {
+
+        $(#[$attr])*
+        pub trait $Trait<G: Scope, K: Data, $V1: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+            /// Groups the records of `self` and of the other collections by key, and applies `logic` to the
+            /// key and the values of each collection.
+            fn $method<$($V: Data,)+ D: Data, R2: Diff, L>(&self, $($other: &Collection<G, (K, $V), R>,)+ logic: L) -> Collection<G, (K, D), R2>
+                where L: Fn(&K, &[($V1, R)], $(&[($V, R)],)+ &mut Vec<(D, R2)>)+'static;
+        }
+
+        impl<G: Scope, K: Data+Default+Hashable, $V1: Data, R: Diff> $Trait<G, K, $V1, R> for Collection<G, (K, $V1), R>
+        where G::Timestamp: Lattice+Ord+Debug, <K as Hashable>::Output: Data+Default {
+            fn $method<$($V: Data,)+ D: Data, R2: Diff, L>(&self, $($other: &Collection<G, (K, $V), R>,)+ logic: L) -> Collection<G, (K, D), R2>
+                where L: Fn(&K, &[($V1, R)], $(&[($V, R)],)+ &mut Vec<(D, R2)>)+'static {
+
+                self.map(|(key, val)| (key, ($wrap1)(val)))
+                    $(.concat(&$other.map(|(key, val)| (key, ($wrap)(val)))))+
+                    .group(move |key, input, output| {
+                        let mut $slice1 = Vec::new();
+                        $(let mut $slice = Vec::new();)+
+                        for &(ref tagged, diff) in input.iter() {
+                            if let Some(ref val) = tagged.$index1 { $slice1.push((val.clone(), diff)); }
+                            $(if let Some(ref val) = tagged.$index { $slice.push((val.clone(), diff)); })+
+                        }
+                        logic(key, &$slice1[..], $(&$slice[..],)+ output);
+                    })
+            }
+        }
+    }
+}
+
+cogroup_n!(
+    /// Extension trait for the `cogroup3` differential dataflow method.
+    trait CoGroup3, fn cogroup3,
+    (V1, vals1, 0, |v| (Some(v), None, None)),
+    (other2, V2, vals2, 1, |v| (None, Some(v), None)),
+    (other3, V3, vals3, 2, |v| (None, None, Some(v)))
+);
+
+cogroup_n!(
+    /// Extension trait for the `cogroup4` differential dataflow method.
+    trait CoGroup4, fn cogroup4,
+    (V1, vals1, 0, |v| (Some(v), None, None, None)),
+    (other2, V2, vals2, 1, |v| (None, Some(v), None, None)),
+    (other3, V3, vals3, 2, |v| (None, None, Some(v), None)),
+    (other4, V4, vals4, 3, |v| (None, None, None, Some(v)))
+);
+
+cogroup_n!(
+    /// Extension trait for the `cogroup5` differential dataflow method.
+    trait CoGroup5, fn cogroup5,
+    (V1, vals1, 0, |v| (Some(v), None, None, None, None)),
+    (other2, V2, vals2, 1, |v| (None, Some(v), None, None, None)),
+    (other3, V3, vals3, 2, |v| (None, None, Some(v), None, None)),
+    (other4, V4, vals4, 3, |v| (None, None, None, Some(v), None)),
+    (other5, V5, vals5, 4, |v| (None, None, None, None, Some(v)))
+);
+
+cogroup_n!(
+    /// Extension trait for the `cogroup6` differential dataflow method.
+    trait CoGroup6, fn cogroup6,
+    (V1, vals1, 0, |v| (Some(v), None, None, None, None, None)),
+    (other2, V2, vals2, 1, |v| (None, Some(v), None, None, None, None)),
+    (other3, V3, vals3, 2, |v| (None, None, Some(v), None, None, None)),
+    (other4, V4, vals4, 3, |v| (None, None, None, Some(v), None, None)),
+    (other5, V5, vals5, 4, |v| (None, None, None, None, Some(v), None)),
+    (other6, V6, vals6, 5, |v| (None, None, None, None, None, Some(v)))
+);
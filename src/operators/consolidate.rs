@@ -19,11 +19,18 @@
 //! ```
 
 use std::fmt::Debug;
+use std::cell::Cell;
 
 use timely::dataflow::*;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Exchange;
 
-use ::{Collection, Data, Diff, Hashable};
+use timely_sort::Unsigned;
+
+use ::{Collection, AsCollection, Data, Diff, Hashable};
 use operators::arrange::ArrangeBySelf;
+use operators::stash::TimeStash;
+use trace::consolidate;
 
 /// An extension method for consolidating weighted streams.
 pub trait Consolidate<D: Data> {
@@ -44,6 +51,29 @@ pub trait Consolidate<D: Data> {
     ///     .inspect(|x| println!("{:}", x));
     /// ```
     fn consolidate(&self) -> Self where D: Hashable;
+    /// Aggregates the weights of equal records, routing records by a key extracted by `key`.
+    ///
+    /// This method allows the user to choose the data used to partition records among workers,
+    /// for example a field known to be well distributed, rather than the whole record. Equal 
+    /// records must produce equal keys. Updates are accumulated for each capability received,
+    /// and emitted once the capability's time has completed.
+    fn consolidate_by_key<K: Hashable, F: Fn(&D)->K+'static>(&self, key: F) -> Self;
+    /// Aggregates the weights of equal records, spreading each record across `salts` workers first.
+    ///
+    /// Consolidation happens in two rounds. In the first round each worker assigns each update a 
+    /// rotating salt, routes by the record's hash plus the salt, and consolidates what it receives.
+    /// In the second round the partially accumulated updates are routed by the record's hash alone,
+    /// and consolidated again. A record that dominates the input is reduced to at most `salts` 
+    /// updates per time before it reaches a single worker, which protects against heavily skewed
+    /// (e.g. zipfian) inputs at the cost of a second exchange.
+    fn consolidate_salted(&self, salts: u64) -> Self where D: Hashable;
+    /// Aggregates the weights of equal records, producing each time's records in sorted order.
+    ///
+    /// All updates are routed to the first worker, which produces the consolidated updates of each completed
+    /// capability ordered by time and then by record, with capabilities completing together produced in order
+    /// of time. The output is therefore the same sequence of updates across runs and numbers of workers, which
+    /// suits golden-file tests and consumers expecting reproducible output, but the work is not distributed.
+    fn consolidate_sorted(&self) -> Self;
 }
 
 impl<G: Scope, D, R> Consolidate<D> for Collection<G, D, R>
@@ -55,4 +85,66 @@ where
     fn consolidate(&self) -> Self where D: Hashable {
        self.arrange_by_self().as_collection(|d,_| d.item.clone())
     }
+    fn consolidate_by_key<K: Hashable, F: Fn(&D)->K+'static>(&self, key: F) -> Self {
+        consolidate_exchange(self, move |x| key(x).hashed().as_u64(), "ConsolidateByKey")
+    }
+    fn consolidate_salted(&self, salts: u64) -> Self where D: Hashable {
+        assert!(salts > 0);
+        let counter = Cell::new(0u64);
+        let salted = consolidate_exchange(self, move |x| {
+            let salt = counter.get();
+            counter.set((salt + 1) % salts);
+            x.hashed().as_u64().wrapping_add(salt)
+        }, "ConsolidateSalted");
+        consolidate_exchange(&salted, |x| x.hashed().as_u64(), "ConsolidateUnsalted")
+    }
+    fn consolidate_sorted(&self) -> Self {
+
+        // Updates received for each held capability, accumulated once the capability's time completes.
+        let mut stash: TimeStash<G::Timestamp, Vec<((G::Timestamp, D), R)>> = TimeStash::new();
+
+        let exchange = Exchange::new(|_update: &(D, G::Timestamp, R)| 0);
+        self.inner.unary_notify(exchange, "ConsolidateSorted", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                stash.entry(&capability, capability.time(), notificator, Vec::new)
+                     .extend(data.drain(..).map(|(d, t, r)| ((t, d), r)));
+            });
+
+            for (capability, mut updates) in stash.ready_sorted(notificator) {
+                consolidate(&mut updates, 0);
+                let mut session = output.session(&capability);
+                for ((t, d), r) in updates.into_iter() {
+                    session.give((d, t, r));
+                }
+            }
+        })
+        .as_collection()
+    }
+}
+
+/// Exchanges updates by `route`, and accumulates the updates received with each capability.
+fn consolidate_exchange<G: Scope, D: Data, R: Diff, F: Fn(&D)->u64+'static>(collection: &Collection<G, D, R>, route: F, name: &str) -> Collection<G, D, R>
+where G::Timestamp: ::lattice::Lattice+Ord {
+
+    // Updates received for each held capability, accumulated once the capability's time completes.
+    let mut stash: TimeStash<G::Timestamp, Vec<((D, G::Timestamp), R)>> = TimeStash::new();
+
+    let exchange = Exchange::new(move |update: &(D, G::Timestamp, R)| route(&update.0));
+    collection.inner.unary_notify(exchange, name, vec![], move |input, output, notificator| {
+
+        input.for_each(|capability, data| {
+            stash.entry(&capability, capability.time(), notificator, Vec::new)
+                 .extend(data.drain(..).map(|(d, t, r)| ((d, t), r)));
+        });
+
+        for (capability, mut updates) in stash.ready(notificator) {
+            consolidate(&mut updates, 0);
+            let mut session = output.session(&capability);
+            for ((d, t), r) in updates.into_iter() {
+                session.give((d, t, r));
+            }
+        }
+    })
+    .as_collection()
 }
@@ -0,0 +1,46 @@
+//! Budgets of work for operators in each activation.
+//!
+//! Operators with deferred work perform a bounded amount of it each time they are scheduled, and continue in
+//! later activations while they hold capabilities for the remaining output. A smaller budget returns control
+//! to the worker sooner, so that other dataflows sharing the worker remain responsive while a large batch is
+//! processed, at the cost of more activations. Budgets are counted in output records.
+//!
+//! At present only `join` draws on a budget. The `group` operators process every key whose times have
+//! completed in one activation, and are not bounded by `with_fuel`; see item 16 of the to-do list.
+//!
+//! The budget is read once, when an operator is constructed, from a per-thread setting that `with_fuel`
+//! changes for the operators constructed within it. The budget of a running operator cannot be changed.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // an interactive dataflow whose joins yield after every ten thousand results.
+//! let probe = fuel::with_fuel(10_000, || {
+//!     worker.dataflow(|scope| queries.join(&data).probe())
+//! });
+//! ```
+
+use std::cell::Cell;
+
+/// The budget of operators constructed outside of `with_fuel`.
+pub const DEFAULT_FUEL: usize = 1_000_000;
+
+thread_local! {
+    static FUEL: Cell<usize> = Cell::new(DEFAULT_FUEL);
+}
+
+/// Runs `logic`, with operators it constructs on this thread performing at most `fuel` work per activation.
+///
+/// Calls may be nested; operators use the budget of the innermost call. Panics if `fuel` is zero.
+pub fn with_fuel<F: FnOnce()->X, X>(fuel: usize, logic: F) -> X {
+    assert!(fuel > 0, "with_fuel: fuel must be positive");
+    let prior = FUEL.with(|x| x.replace(fuel));
+    let result = logic();
+    FUEL.with(|x| x.set(prior));
+    result
+}
+
+/// The budget for operators constructed now.
+pub fn current() -> usize {
+    FUEL.with(|x| x.get())
+}
@@ -83,6 +83,9 @@ impl<G: Scope, K: Data+Default+Hashable, V: Data, R: Diff> Group<G, K, V, R> for
 /// Extension trait for the `distinct` differential dataflow method.
 pub trait Distinct<G: Scope, K: Data> where G::Timestamp: Lattice+Ord {
     /// Reduces the collection to one occurrence of each distinct element.
+    ///
+    /// The input may have any difference type; each element whose accumulated difference is non-zero
+    /// is produced once, with difference `1isize`.
     fn distinct(&self) -> Collection<G, K, isize>;
     /// Reduces the collection to one occurrence of each distinct element.
     /// 
@@ -90,7 +93,7 @@ pub trait Distinct<G: Scope, K: Data> where G::Timestamp: Lattice+Ord {
     fn distinct_u(&self) -> Collection<G, K, isize> where K: Unsigned+Copy;
 }
 
-impl<G: Scope, K: Data+Default+Hashable> Distinct<G, K> for Collection<G, K, isize> 
+impl<G: Scope, K: Data+Default+Hashable, R: Diff> Distinct<G, K> for Collection<G, K, R> 
 where G::Timestamp: Lattice+Ord+::std::fmt::Debug {
     fn distinct(&self) -> Collection<G, K, isize> {
         self.arrange_by_self()
@@ -132,6 +135,198 @@ impl<G: Scope, K: Data+Default+Hashable, R: Diff> Count<G, K, R> for Collection<
 }
 
 
+/// Extension trait for the `aggregate_lattice` differential dataflow method.
+pub trait AggregateLattice<G: Scope, K: Data, V: Data> where G::Timestamp: Lattice+Ord {
+    /// Aggregates the values of each key by a lattice join, starting from `bottom`.
+    ///
+    /// This is the natural aggregation for CRDT-like values, where `join` is commutative, associative, and
+    /// idempotent, and so the number of occurrences of each value does not matter. Each key with values present
+    /// produces the join of those values, unless it equals `bottom`. As with `group`, the output is compared
+    /// against the previously produced output, and updates are only produced when the joined value changes,
+    /// which for monotonically growing inputs is exactly when it grows.
+    fn aggregate_lattice<F>(&self, bottom: V, join: F) -> Collection<G, (K, V), isize>
+        where F: Fn(&V, &V)->V+'static;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data, R: Diff> AggregateLattice<G, K, V> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord+Debug, <K as Hashable>::Output: Data+Default {
+    fn aggregate_lattice<F>(&self, bottom: V, join: F) -> Collection<G, (K, V), isize>
+        where F: Fn(&V, &V)->V+'static {
+        self.group(move |_k, s, t| {
+            let joined = s.iter().fold(bottom.clone(), |acc, &(ref v, _)| join(&acc, v));
+            if joined != bottom {
+                t.push((joined, 1));
+            }
+        })
+    }
+}
+
+/// Extension trait for the `key_histogram` differential dataflow method.
+pub trait KeyHistogram<G: Scope, K: Data> where G::Timestamp: Lattice+Ord {
+    /// Counts the keys having each number of records, as `(records_per_key, number_of_keys)`.
+    ///
+    /// This is the degree distribution of the collection viewed as a graph, and is maintained incrementally.
+    /// It is useful for diagnosing skew in the distribution of keys before arranging or joining on them; a
+    /// long tail of large counts indicates keys that will concentrate work on a single worker.
+    fn key_histogram(&self) -> Collection<G, (isize, isize), isize>;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data> KeyHistogram<G, K> for Collection<G, (K, V), isize>
+where G::Timestamp: Lattice+Ord+Debug {
+    fn key_histogram(&self) -> Collection<G, (isize, isize), isize> {
+        self.map(|(k, _v)| k)
+            .count()
+            .map(|(_k, c)| c)
+            .count()
+    }
+}
+
+/// Extension trait for the `count_distinct` differential dataflow method.
+pub trait CountDistinct<G: Scope, K: Data> where G::Timestamp: Lattice+Ord {
+    /// Counts the distinct values associated with each key.
+    ///
+    /// This produces the same result as `distinct` followed by counting keys, but with a single reduction over
+    /// the arrangement of the input by key: the values of each key with non-zero accumulations are exactly its
+    /// distinct values, and so no intermediate collection of distinct records is arranged.
+    fn count_distinct(&self) -> Collection<G, (K, isize), isize>;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data, R: Diff> CountDistinct<G, K> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord+Debug, <K as Hashable>::Output: Data+Default {
+    fn count_distinct(&self) -> Collection<G, (K, isize), isize> {
+        self.group(|_k, s, t| t.push((s.len() as isize, 1)))
+    }
+}
+
+/// Extension trait for the `group_nested` differential dataflow method.
+pub trait GroupNested<G: Scope, K1: Data, K2: Data, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+    /// Reduces records grouped by a composite key `(k1, k2)`, and then the results grouped by `k1`.
+    ///
+    /// The `inner` logic aggregates the values of each composite key, and the `outer` logic aggregates the
+    /// inner results `(k2, value)` of each `k1`, as for a report by `(region, city)` and then by `region`. The
+    /// input is arranged once, by `k1` with values ordered by `(k2, val)`, and so in the order of the composite
+    /// key; one reduction over this arrangement applies both levels of logic to each `k1`, whose records are
+    /// contiguous. A change to any record of `k1` re-evaluates all of its composite keys. Both levels are
+    /// returned, inner first.
+    fn group_nested<L1, L2, V2: Data, V3: Data>(&self, inner: L1, outer: L2) -> (Collection<G, ((K1, K2), V2), isize>, Collection<G, (K1, V3), isize>)
+        where
+            L1: Fn(&(K1, K2), &[(V, R)], &mut Vec<(V2, isize)>)+'static,
+            L2: Fn(&K1, &[((K2, V2), isize)], &mut Vec<(V3, isize)>)+'static;
+}
+
+impl<G: Scope, K1: Data+Default+Hashable, K2: Data+Default+Hashable, V: Data, R: Diff> GroupNested<G, K1, K2, V, R> for Collection<G, ((K1, K2), V), R>
+where G::Timestamp: Lattice+Ord+Debug {
+    fn group_nested<L1, L2, V2: Data, V3: Data>(&self, inner: L1, outer: L2) -> (Collection<G, ((K1, K2), V2), isize>, Collection<G, (K1, V3), isize>)
+        where
+            L1: Fn(&(K1, K2), &[(V, R)], &mut Vec<(V2, isize)>)+'static,
+            L2: Fn(&K1, &[((K2, V2), isize)], &mut Vec<(V3, isize)>)+'static {
+
+        let levels = self.map(|((k1, k2), v)| (k1, (k2, v)))
+            .group(move |k1, records, output| {
+
+                // apply `inner` to each run of records with the same `k2`.
+                let mut results = Vec::new();
+                let mut values = Vec::new();
+                let mut inner_output = Vec::new();
+                let mut index = 0;
+                while index < records.len() {
+                    let k2 = &(records[index].0).0;
+                    while index < records.len() && &(records[index].0).0 == k2 {
+                        values.push(((records[index].0).1.clone(), records[index].1));
+                        index += 1;
+                    }
+                    let key = (k1.clone(), k2.clone());
+                    inner(&key, &values[..], &mut inner_output);
+                    values.clear();
+                    for (v2, diff) in inner_output.drain(..) {
+                        results.push(((k2.clone(), v2), diff));
+                    }
+                }
+
+                // apply `outer` to the inner results of `k1`.
+                let mut outer_output = Vec::new();
+                if results.len() > 0 {
+                    results.sort();
+                    outer(k1, &results[..], &mut outer_output);
+                }
+
+                for (result, diff) in results {
+                    output.push(((Some(result), None), diff));
+                }
+                for (v3, diff) in outer_output {
+                    output.push(((None, Some(v3)), diff));
+                }
+            });
+
+        let inner = levels.flat_map(|(k1, (result, _))| result.map(|(k2, v2)| ((k1, k2), v2)));
+        let outer = levels.flat_map(|(k1, (_, result))| result.map(|v3| (k1, v3)));
+        (inner, outer)
+    }
+}
+
+/// Extension trait for the `grouping_sets` differential dataflow method.
+pub trait GroupingSets<G: Scope, D: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+    /// Aggregates records under each of several groupings, as for SQL's `GROUPING SETS`.
+    ///
+    /// The `columns` function maps each record to its grouping columns and a value, and each of `sets` lists
+    /// the positions of the columns it groups by, as produced by `rollup_sets` and `cube_sets`. The output is
+    /// keyed by the index of the set and the record's columns, with `None` in place of columns not in the set.
+    ///
+    /// The input is arranged once, by all of its grouping columns, and `aggregate` summarizes the values of
+    /// each distinct combination of columns. Each grouping set is then derived from these summaries rather than
+    /// from the records: `combine` merges the summaries of the combinations in each group, each presented with
+    /// the number of combinations having that summary. This suits aggregates that can be merged, such as sums,
+    /// counts, minima and maxima, and arranges only the summaries once for each grouping set.
+    fn grouping_sets<K, V, A, F, L, C>(&self, columns: F, sets: Vec<Vec<usize>>, aggregate: L, combine: C) -> Collection<G, ((usize, Vec<Option<K>>), A), isize>
+        where
+            K: Data+Default+::std::hash::Hash,
+            V: Data,
+            A: Data,
+            F: Fn(D)->(Vec<K>, V)+'static,
+            L: Fn(&[(V, R)])->A+'static,
+            C: Fn(&[(A, isize)])->A+'static;
+}
+
+impl<G: Scope, D: Data, R: Diff> GroupingSets<G, D, R> for Collection<G, D, R>
+where G::Timestamp: Lattice+Ord+Debug {
+    fn grouping_sets<K, V, A, F, L, C>(&self, columns: F, sets: Vec<Vec<usize>>, aggregate: L, combine: C) -> Collection<G, ((usize, Vec<Option<K>>), A), isize>
+        where
+            K: Data+Default+::std::hash::Hash,
+            V: Data,
+            A: Data,
+            F: Fn(D)->(Vec<K>, V)+'static,
+            L: Fn(&[(V, R)])->A+'static,
+            C: Fn(&[(A, isize)])->A+'static {
+
+        // summaries of each distinct combination of grouping columns.
+        let summaries = self.map(columns)
+                            .group(move |_key, vals, output| output.push((aggregate(vals), 1)));
+
+        summaries
+            .flat_map(move |(key, summary)| {
+                sets.iter()
+                    .enumerate()
+                    .map(|(index, set)| {
+                        let grouped = (0 .. key.len()).map(|c| if set.contains(&c) { Some(key[c].clone()) } else { None }).collect();
+                        ((index, grouped), summary.clone())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .group(move |_key, summaries, output| output.push((combine(summaries), 1)))
+    }
+}
+
+/// The groupings of `ROLLUP` over the first `columns` columns, for `grouping_sets`: each prefix, from longest to empty.
+pub fn rollup_sets(columns: usize) -> Vec<Vec<usize>> {
+    (0 .. columns + 1).rev().map(|length| (0 .. length).collect()).collect()
+}
+
+/// The groupings of `CUBE` over the first `columns` columns, for `grouping_sets`: each subset, from all columns to none.
+pub fn cube_sets(columns: usize) -> Vec<Vec<usize>> {
+    assert!(columns < 64, "cube_sets: too many columns");
+    (0 .. (1u64 << columns)).rev().map(|mask| (0 .. columns).filter(|c| mask & (1 << c) != 0).collect()).collect()
+}
+
 /// Extension trait for the `group_arranged` differential dataflow method.
 pub trait GroupArranged<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
     /// Applies `group` to arranged data, and returns an arrangement of output data.
@@ -146,6 +341,23 @@ pub trait GroupArranged<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp:
             T2::Batch: Batch<K, V2, G::Timestamp, R2>,
             L: Fn(&K, &[(V, R)], &mut Vec<(V2, R2)>)+'static
             ; 
+    /// Applies `group` to arranged data, producing an arrangement in a trace of type `T2`.
+    ///
+    /// This is `group_arranged` with the output trace named by its type rather than supplied, as in 
+    /// `arranged.reduce_abelian::<_,_,OrdKeySpine<_,_,_>,_>(logic)`. The output trace determines how the 
+    /// output is indexed (for example, keys only or keys and values), and the resulting arrangement can be
+    /// used by further operators without re-arranging. The reduction is "abelian" in that `logic` need only
+    /// produce the desired output for each key, with the differences from prior output determined by the
+    /// operator.
+    fn reduce_abelian<L, V2, T2, R2>(&self, logic: L) -> Arranged<G, K, V2, R2, TraceAgent<K, V2, G::Timestamp, R2, T2>>
+        where
+            V2: Data,
+            R2: Diff,
+            T2: Trace<K, V2, G::Timestamp, R2>+'static,
+            T2::Batch: Batch<K, V2, G::Timestamp, R2>,
+            L: Fn(&K, &[(V, R)], &mut Vec<(V2, R2)>)+'static {
+        self.group_arranged(logic, T2::new())
+    }
 }
 
 impl<G: Scope, K: Data, V: Data, T1, R: Diff> GroupArranged<G, K, V, R> for Arranged<G, K, V, R, T1>
@@ -479,6 +691,8 @@ where
 
         });
 
+        ::plan::note("Group", Some("Trace"), || (vec![::plan::stream_name(&self.stream)], ::plan::stream_name(&stream)));
+
         Arranged { stream: stream, trace: result_trace }
     }
 }
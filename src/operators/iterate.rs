@@ -65,9 +65,14 @@ use timely::dataflow::*;
 use timely::dataflow::scopes::Child;
 use timely::dataflow::operators::*;
 use timely::dataflow::operators::feedback::Handle;
+use timely::dataflow::channels::pact::{Pipeline, Exchange};
+use timely::order::PartialOrder;
+use timely_sort::Unsigned;
 
-use ::{Data, Collection, Diff};
+use ::{Data, Collection, AsCollection, Diff, Hashable};
+use operators::{Distinct, Join};
 use lattice::Lattice;
+use operators::stash::TimeStash;
 
 /// An extension trait for the `iterate` method.
 pub trait Iterate<G: Scope, D: Data, R: Diff> {
@@ -75,6 +80,29 @@ pub trait Iterate<G: Scope, D: Data, R: Diff> {
     fn iterate<F>(&self, logic: F) -> Collection<G, D, R>
         where G::Timestamp: Lattice,
               for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, R>)->Collection<Child<'a, G, u64>, D, R>;
+    /// Iteratively apply `logic` to the source collection until convergence, with access to another collection.
+    ///
+    /// The collection `other` is brought into the iterative scope and supplied to `logic` as its second argument,
+    /// so that `logic` need not (and cannot mistakenly fail to) `enter` it. To supply several collections, 
+    /// call this method with one and capture the others by entering them into `self.scope()`, or combine them
+    /// into one collection of an enumerated type.
+    ///
+    /// #Examples
+    ///
+    /// ```ignore
+    /// // nodes reachable from `roots` along `edges`.
+    /// let reach = roots.iterate_scoped(&edges, |reach, edges| {
+    ///     reach.map(|node| (node, ()))
+    ///          .join_map(edges, |_, &(), &dst| dst)
+    ///          .concat(reach)
+    ///          .distinct()
+    /// });
+    /// ```
+    fn iterate_scoped<D2, R2, F>(&self, other: &Collection<G, D2, R2>, logic: F) -> Collection<G, D, R>
+        where G::Timestamp: Lattice,
+              D2: Data,
+              R2: Diff,
+              for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, R>, &Collection<Child<'a, G, u64>, D2, R2>)->Collection<Child<'a, G, u64>, D, R>;
 }
 
 impl<G: Scope, D: Ord+Data+Debug, R: Diff> Iterate<G, D, R> for Collection<G, D, R> {
@@ -95,6 +123,215 @@ impl<G: Scope, D: Ord+Data+Debug, R: Diff> Iterate<G, D, R> for Collection<G, D,
             result.leave()
         })
     }
+    fn iterate_scoped<D2, R2, F>(&self, other: &Collection<G, D2, R2>, logic: F) -> Collection<G, D, R>
+        where G::Timestamp: Lattice,
+              D2: Data,
+              R2: Diff,
+              for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, R>, &Collection<Child<'a, G, u64>, D2, R2>)->Collection<Child<'a, G, u64>, D, R> {
+
+        self.inner.scope().scoped(|subgraph| {
+            let other = other.enter(subgraph);
+            let variable = Variable::from(self.enter(subgraph));
+            let result = logic(&variable, &other);
+            variable.set(&result);
+            result.leave()
+        })
+    }
+}
+
+/// An extension trait for the `iterate_detecting` method.
+pub trait IterateDetecting<G: Scope, D: Data+Hashable> where G::Timestamp: Lattice {
+    /// Iteratively applies `logic` to the source collection until convergence, or until a cycle is detected.
+    ///
+    /// Iteration only terminates when the iterated collection stops changing, and loop bodies that are not
+    /// monotone may instead oscillate forever. This method fingerprints the collection produced by `logic` in
+    /// each round, and if the fingerprint of some round equals that of an earlier round of the same outer time,
+    /// the collection has (almost certainly) entered a cycle. The second returned collection then contains the
+    /// record `(start, period)` at that outer time, where `start` is the earlier round and `period` the number
+    /// of rounds in the cycle; a period of one indicates updates that circulate without changing the collection.
+    ///
+    /// Once a cycle is detected at some round, updates at that and later rounds are no longer fed back, for the
+    /// outer time of the detection and all later outer times. The results for these times reflect at most that
+    /// many rounds of iteration, and should be treated as diagnostic rather than correct.
+    ///
+    /// Each worker retains a fingerprint of each time of the loop, and worker zero compares them, so this method
+    /// is intended for diagnosing computations rather than for long-running use.
+    fn iterate_detecting<F>(&self, logic: F) -> (Collection<G, D, isize>, Collection<G, (u64, u64), isize>)
+        where for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, isize>)->Collection<Child<'a, G, u64>, D, isize>;
+}
+
+impl<G: Scope, D: Ord+Data+Debug+Hashable> IterateDetecting<G, D> for Collection<G, D, isize> where G::Timestamp: Lattice {
+    fn iterate_detecting<F>(&self, logic: F) -> (Collection<G, D, isize>, Collection<G, (u64, u64), isize>)
+        where for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, D, isize>)->Collection<Child<'a, G, u64>, D, isize> {
+
+        self.inner.scope().scoped(|subgraph| {
+            let variable = Variable::from(self.enter(subgraph));
+            let result = logic(&variable);
+            let cycles = detect_cycles(&result);
+            variable.set(&halt_cycles(&result, &cycles));
+            (result.leave(), cycles.leave())
+        })
+    }
+}
+
+/// Reports `(start, period)` at each time whose accumulated collection repeats that of an earlier round.
+fn detect_cycles<G: Scope, D: Data+Hashable>(collection: &Collection<Child<G, u64>, D, isize>) -> Collection<Child<G, u64>, (u64, u64), isize>
+where G::Timestamp: Lattice {
+
+    // per-worker fingerprints of the updates at each time.
+    let mut local = TimeStash::new();
+    let partials = collection.inner.unary_notify(Pipeline, "CycleFingerprint", vec![], move |input, output, notificator| {
+        input.for_each(|capability, data| {
+            for &(ref datum, ref time, diff) in data.iter() {
+                let delta = datum.hashed().as_u64().wrapping_mul(diff as u64);
+                let fingerprint = local.entry(&capability, time, notificator, || 0u64);
+                *fingerprint = fingerprint.wrapping_add(delta);
+            }
+        });
+        for (capability, delta) in local.ready(notificator) {
+            output.session(&capability).give(delta);
+        }
+    });
+
+    // on worker zero, for each outer time, the fingerprints of updates by round and of the collection
+    // accumulated by round, and the fingerprints of pending times.
+    let mut deltas: Vec<(G::Timestamp, Vec<u64>)> = Vec::new();
+    let mut accumulated: Vec<(G::Timestamp, Vec<u64>)> = Vec::new();
+    let mut pending = TimeStash::new();
+    partials.unary_notify(Exchange::new(|_| 0), "CycleDetect", vec![], move |input, output, notificator| {
+        input.for_each(|capability, data| {
+            let delta = data.drain(..).fold(0u64, |sum, x| sum.wrapping_add(x));
+            let fingerprint = pending.entry(&capability, capability.time(), notificator, || 0u64);
+            *fingerprint = fingerprint.wrapping_add(delta);
+        });
+
+        // record all completed deltas before accumulating, as times may complete together in any order.
+        let ready = pending.ready(notificator);
+        for &(ref capability, delta) in ready.iter() {
+            let time = capability.time();
+            if !deltas.iter().any(|x| x.0 == time.outer) {
+                deltas.push((time.outer.clone(), Vec::new()));
+            }
+            let rounds = &mut deltas.iter_mut().find(|x| x.0 == time.outer).expect("deltas: outer time absent").1;
+            while rounds.len() <= time.inner as usize { rounds.push(0); }
+            rounds[time.inner as usize] = rounds[time.inner as usize].wrapping_add(delta);
+        }
+
+        for (capability, _) in ready {
+            let time = capability.time().clone();
+
+            // extend the accumulated fingerprints of `time.outer` through `time.inner`, each round adding the
+            // updates at that round of outer times less or equal to `time.outer`; all such times are complete.
+            if !accumulated.iter().any(|x| x.0 == time.outer) {
+                accumulated.push((time.outer.clone(), Vec::new()));
+            }
+            let history = &mut accumulated.iter_mut().find(|x| x.0 == time.outer).expect("accumulated: outer time absent").1;
+            while history.len() <= time.inner as usize {
+                let round = history.len();
+                let prior = if round > 0 { history[round - 1] } else { 0 };
+                let delta = deltas.iter()
+                                  .filter(|x| x.0.less_equal(&time.outer))
+                                  .filter_map(|x| x.1.get(round))
+                                  .fold(0u64, |sum, x| sum.wrapping_add(*x));
+                history.push(prior.wrapping_add(delta));
+            }
+
+            let current = history[time.inner as usize];
+            for round in (0 .. time.inner).rev() {
+                if history[round as usize] == current {
+                    output.session(&capability).give(((round, time.inner - round), time.clone(), 1));
+                    break;
+                }
+            }
+        }
+    })
+    .as_collection()
+}
+
+/// Discards updates at times greater or equal to the time of any reported cycle.
+fn halt_cycles<G: Scope, D: Data>(collection: &Collection<Child<G, u64>, D, isize>, cycles: &Collection<Child<G, u64>, (u64, u64), isize>) -> Collection<Child<G, u64>, D, isize>
+where G::Timestamp: Lattice {
+
+    // cycles are reported on worker zero, and must be delivered to each worker.
+    let peers = collection.scope().peers() as u64;
+    let signals = cycles.inner.flat_map(move |(_, time, _)| (0 .. peers).map(move |index| (index, time.clone())));
+
+    let mut halted: Vec<Product<G::Timestamp, u64>> = Vec::new();
+    let mut stash = TimeStash::new();
+    let exchange = Exchange::new(|x: &(u64, Product<G::Timestamp, u64>)| x.0);
+    collection.inner.binary_notify(&signals, Pipeline, exchange, "CycleHalt", vec![], move |input1, input2, output, notificator| {
+        input2.for_each(|_capability, data| {
+            halted.extend(data.drain(..).map(|x| x.1));
+        });
+        input1.for_each(|capability, data| {
+            for (datum, time, diff) in data.drain(..) {
+                stash.push(&capability, &time, (datum, time.clone(), diff), notificator);
+            }
+        });
+        for (capability, updates) in stash.ready(notificator) {
+            if !halted.iter().any(|h| h.less_equal(capability.time())) {
+                let mut session = output.session(&capability);
+                for update in updates {
+                    session.give(update);
+                }
+            }
+        }
+    })
+    .as_collection()
+}
+
+/// An extension trait for the `iterate_demanded` method.
+pub trait IterateDemanded<G: Scope, K: Data, V: Data> where G::Timestamp: Lattice {
+    /// Iteratively derives records of `(key, val)`, but only for keys in demand.
+    ///
+    /// This packages the "magic sets" pattern, in which a recursive computation is specialized to the keys a
+    /// query requires. Keys in demand start as `seeds`, and grow by the keys `propagate` produces from the keys
+    /// in demand and the records derived so far. The `logic` derives records from the collection (`self`) and
+    /// the records derived so far, as for `iterate`; only those with keys in demand are retained, and so work
+    /// is confined to the demanded keys. The records derived for all demanded keys are returned.
+    ///
+    /// #Examples
+    ///
+    /// ```ignore
+    /// // nodes reachable from `sources`, computing reachability only from those nodes.
+    /// let reach = edges.iterate_demanded(
+    ///     &sources,
+    ///     |demand, _reach| demand.filter(|_| false),
+    ///     |edges, reach| {
+    ///         reach.map(|(src, mid)| (mid, src))
+    ///              .join_map(edges, |_mid, &src, &dst| (src, dst))
+    ///              .concat(edges)
+    ///              .distinct()
+    ///     });
+    /// ```
+    fn iterate_demanded<P, F>(&self, seeds: &Collection<G, K>, propagate: P, logic: F) -> Collection<G, (K, V)>
+        where
+            for<'a> P: FnOnce(&Collection<Child<'a, G, u64>, K>, &Collection<Child<'a, G, u64>, (K, V)>)->Collection<Child<'a, G, u64>, K>,
+            for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, (K, V)>, &Collection<Child<'a, G, u64>, (K, V)>)->Collection<Child<'a, G, u64>, (K, V)>;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data> IterateDemanded<G, K, V> for Collection<G, (K, V)>
+where G::Timestamp: Lattice+Ord+Debug {
+    fn iterate_demanded<P, F>(&self, seeds: &Collection<G, K>, propagate: P, logic: F) -> Collection<G, (K, V)>
+        where
+            for<'a> P: FnOnce(&Collection<Child<'a, G, u64>, K>, &Collection<Child<'a, G, u64>, (K, V)>)->Collection<Child<'a, G, u64>, K>,
+            for<'a> F: FnOnce(&Collection<Child<'a, G, u64>, (K, V)>, &Collection<Child<'a, G, u64>, (K, V)>)->Collection<Child<'a, G, u64>, (K, V)> {
+
+        self.inner.scope().scoped(|subgraph| {
+
+            let seeds = seeds.enter(subgraph);
+            let collection = self.enter(subgraph);
+
+            let demand = Variable::from(seeds.clone());
+            let derived = Variable::from(collection.filter(|_| false));
+
+            let demand_next = propagate(&*demand, &*derived).concat(&seeds).distinct();
+            let derived_next = logic(&collection, &*derived).semijoin(&*demand);
+
+            demand.set(&demand_next);
+            derived.set(&derived_next).leave()
+        })
+    }
 }
 
 /// A differential dataflow collection variable
@@ -133,4 +370,110 @@ impl<'a, G: Scope, D: Data, R: Diff> Deref for Variable<'a, G, D, R> where G::Ti
     fn deref(&self) -> &Self::Target {
         &self.collection
     }
-}
\ No newline at end of file
+}
+
+/// A recursive collection, restricted to operations that are monotone in it.
+///
+/// Rules of a `Stratified` program receive the relation being derived in their stratum as a `Positive`
+/// collection, which can be transformed, combined, and joined, but not negated or subtracted. Negation is only
+/// possible on collections derived in earlier strata, which are supplied as ordinary collections.
+pub struct Positive<'a, G: Scope, D: Data> where G::Timestamp: Lattice {
+    collection: Collection<Child<'a, G, u64>, D>,
+}
+
+impl<'a, G: Scope, D: Data> Positive<'a, G, D> where G::Timestamp: Lattice+Ord {
+    /// Wraps a collection, for example one derived from earlier strata, for combination with recursive data.
+    pub fn from(collection: &Collection<Child<'a, G, u64>, D>) -> Self {
+        Positive { collection: collection.clone() }
+    }
+    /// Applies `logic` to each record.
+    pub fn map<D2: Data, L: Fn(D)->D2+'static>(&self, logic: L) -> Positive<'a, G, D2> {
+        Positive { collection: self.collection.map(logic) }
+    }
+    /// Retains records satisfying `logic`.
+    pub fn filter<L: Fn(&D)->bool+'static>(&self, logic: L) -> Self {
+        Positive { collection: self.collection.filter(logic) }
+    }
+    /// The records of both collections.
+    pub fn concat(&self, other: &Positive<'a, G, D>) -> Self {
+        Positive { collection: self.collection.concat(&other.collection) }
+    }
+    /// One occurrence of each distinct record.
+    pub fn distinct(&self) -> Self where D: Default+Hashable, G::Timestamp: Debug {
+        Positive { collection: Distinct::distinct(&self.collection) }
+    }
+}
+
+impl<'a, G: Scope, K: Data+Default+Hashable, V: Data> Positive<'a, G, (K, V)> where G::Timestamp: Lattice+Ord {
+    /// Joins with another positive collection, as `Join::join_map`.
+    pub fn join_map<V2: Data, D: Data, L>(&self, other: &Positive<'a, G, (K, V2)>, logic: L) -> Positive<'a, G, D>
+    where L: Fn(&K, &V, &V2)->D+'static {
+        Positive { collection: Join::join_map(&self.collection, &other.collection, logic) }
+    }
+    /// Joins with a collection derived in earlier strata, as `Join::join_map`.
+    pub fn join_map_lower<V2: Data, D: Data, L>(&self, other: &Collection<Child<'a, G, u64>, (K, V2)>, logic: L) -> Positive<'a, G, D>
+    where L: Fn(&K, &V, &V2)->D+'static {
+        Positive { collection: Join::join_map(&self.collection, other, logic) }
+    }
+}
+
+/// The rules deriving one stratum of a `Stratified` program.
+pub trait Stratum<G: Scope, D: Data> where G::Timestamp: Lattice {
+    /// Derives records from the records of earlier strata, `lower`, and those of this stratum, `recursive`.
+    fn derive<'a>(&self, lower: &Collection<Child<'a, G, u64>, D>, recursive: &Positive<'a, G, D>) -> Positive<'a, G, D>;
+}
+
+impl<G: Scope, D: Data, F> Stratum<G, D> for F
+where
+    G::Timestamp: Lattice,
+    F: for<'a> Fn(&Collection<Child<'a, G, u64>, D>, &Positive<'a, G, D>)->Positive<'a, G, D> {
+    fn derive<'a>(&self, lower: &Collection<Child<'a, G, u64>, D>, recursive: &Positive<'a, G, D>) -> Positive<'a, G, D> {
+        self(lower, recursive)
+    }
+}
+
+/// A Datalog-style program with stratified negation.
+///
+/// Each stratum is evaluated to a fixed point in its own iterative scope, starting from the records of the
+/// input and earlier strata. Its rules may negate (for example, `antijoin` or `subtract`) the records of earlier
+/// strata, which are complete before the stratum begins, but receive the recursive records of their own stratum
+/// as a `Positive` collection, so that negation cannot be nested inside recursion. Relations are distinguished
+/// within the one record type `D`, for example by an enumeration or a relation identifier.
+///
+/// #Examples
+///
+/// ```ignore
+/// // nodes reachable from the root, and then nodes not reachable from it.
+/// let result = Stratified::new()
+///     .stratum(|lower, recursive| { ... })   // derive ("reach", node) records
+///     .stratum(|lower, recursive| { ... })   // derive ("unreached", node) by negating "reach"
+///     .evaluate(&facts);
+/// ```
+pub struct Stratified<G: Scope, D: Data> where G::Timestamp: Lattice {
+    strata: Vec<Box<Stratum<G, D>>>,
+}
+
+impl<G: Scope, D: Data+Default+Hashable> Stratified<G, D> where G::Timestamp: Lattice+Ord+Debug {
+    /// A program without strata.
+    pub fn new() -> Self {
+        Stratified { strata: Vec::new() }
+    }
+    /// Adds a stratum, evaluated after those already added.
+    pub fn stratum<S: Stratum<G, D>+'static>(mut self, rules: S) -> Self {
+        self.strata.push(Box::new(rules));
+        self
+    }
+    /// Evaluates the strata in order, starting from `facts`, and returns all records derived.
+    pub fn evaluate(&self, facts: &Collection<G, D>) -> Collection<G, D> {
+        let mut result = facts.clone();
+        for stratum in self.strata.iter() {
+            let lower = result.clone();
+            result = lower.iterate(|recursive| {
+                let lower = lower.enter(&recursive.scope());
+                let derived = stratum.derive(&lower, &Positive::from(recursive));
+                Distinct::distinct(&lower.concat(&derived.collection))
+            });
+        }
+        result
+    }
+}
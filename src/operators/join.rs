@@ -5,12 +5,13 @@
 //! + (b * c), and if this is not equal to the former term, little is known about the actual output.
 use std::fmt::Debug;
 use std::ops::Mul;
+use std::rc::Rc;
 use std::cmp::Ordering;
 
 use timely::progress::Timestamp;
 use timely::dataflow::Scope;
-use timely::dataflow::operators::Binary;
-use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Binary, Concat, Map, Unary};
+use timely::dataflow::channels::pact::{Exchange, Pipeline};
 use timely::dataflow::operators::Capability;
 use timely_sort::Unsigned;
 
@@ -18,17 +19,16 @@ use timely::dataflow::operators::OutputHandle;
 use timely::dataflow::channels::pushers::tee::Tee;
 
 
-use hashable::{Hashable, UnsignedWrapper, OrdWrapper};
+use hashable::{Hashable, OrdWrapper, UnsignedWrapper};
 use ::{Data, Diff, Collection, AsCollection};
-use lattice::Lattice;
-use operators::arrange::{Arrange, Arranged, ArrangeByKey, ArrangeBySelf};
+use difference::Decimal;
+use lattice::{Lattice, TotalOrder};
+use operators::arrange::{Arrange, Arranged, TraceAgent, ArrangeByKey, ArrangeBySelf, ArrangeUnsigned, ArrangeUnsignedSelf};
 use trace::{BatchReader, Cursor, Trace, consolidate};
+use trace::implementations::hash::{HashValSpine, HashKeySpine};
 use operators::ValueHistory2;
+use operators::group::{Count, Distinct};
 
-// use trace::implementations::hash::HashValSpine as DefaultValTrace;
-// use trace::implementations::hash::HashKeySpine as DefaultKeyTrace;
-use trace::implementations::ord::OrdValSpine as DefaultValTrace;
-use trace::implementations::ord::OrdKeySpine as DefaultKeyTrace;
 
 use trace::TraceReader;
 
@@ -90,6 +90,8 @@ pub trait Join<G: Scope, K: Data, V: Data, R: Diff> {
     fn join_map<V2, R2: Diff, D, L>(&self, other: &Collection<G, (K,V2), R2>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
     where V2: Data, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static;
     /// Like `join_map`, but with a randomly distributed unsigned key.
+    ///
+    /// See `JoinUnsigned` for joins that also lay unsigned keys out densely, in scopes with totally ordered times.
     fn join_map_u<V2, R2: Diff, D, L>(&self, other: &Collection<G, (K,V2), R2>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output> 
     where K: Unsigned+Copy, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, V2: Data, D: Data, L: Fn(&K, &V, &V2)->D+'static;
     /// Matches pairs `(key,val1)` and `key` based on `key`, filtering the first collection by values present in the second.
@@ -145,6 +147,21 @@ pub trait Join<G: Scope, K: Data, V: Data, R: Diff> {
     /// Like `antijoin`, but with a randomly distributed unsigned key.
     fn antijoin_u<R2>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
     where K: Unsigned+Copy, R2: Diff, R: Mul<R2, Output=R>;
+    /// Matches pairs `(key,val1)` and `(key,val2)` drawn from the same collection, and then applies a function.
+    ///
+    /// The collection is arranged once, and the arrangement is used for both inputs of the join, rather than
+    /// arranging the collection twice as `self.join_map(&self, logic)` would. If `ordered` is set, only pairs 
+    /// with `val1 <= val2` are presented to `logic`, which avoids producing symmetric duplicates when `logic` 
+    /// does not distinguish the order of its arguments.
+    ///
+    /// #Examples
+    /// ```ignore
+    /// // pairs of distinct neighbors of each node, each pair once.
+    /// edges.self_join_map(true, |_src, dst1, dst2| (*dst1, *dst2))
+    ///      .filter(|&(x, y)| x != y);
+    /// ```
+    fn self_join_map<D, L>(&self, ordered: bool, logic: L) -> Collection<G, D, <R as Mul<R>>::Output>
+    where R: Mul<R>, <R as Mul<R>>::Output: Diff, D: Data, L: Fn(&K, &V, &V)->D+'static;
 } 
 
 
@@ -172,21 +189,25 @@ where
     where R: Mul<R2, Output=R> {
         self.concat(&self.semijoin(other).negate())
     }
+    fn self_join_map<D, L>(&self, ordered: bool, logic: L) -> Collection<G, D, <R as Mul<R>>::Output>
+    where R: Mul<R>, <R as Mul<R>>::Output: Diff, D: Data, L: Fn(&K, &V, &V)->D+'static {
+        let arranged = self.arrange_by_key_hashed();
+        arranged.join_arranged(&arranged, move |k,v1,v2| {
+                    if !ordered || v1 <= v2 { Some(logic(&k.item,v1,v2)) } else { None }
+                })
+                .flat_map(|x| x)
+    }
 
     fn join_map_u<V2, R2, D, L>(&self, other: &Collection<G, (K, V2), R2>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
     where K: Unsigned+Copy, V2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static {
-        let arranged1 = self.map(|(k,v)| (UnsignedWrapper::from(k), v))
-                            .arrange(DefaultValTrace::new());
-        let arranged2 = other.map(|(k,v)| (UnsignedWrapper::from(k), v))
-                             .arrange(DefaultValTrace::new());
+        let arranged1 = self.arrange_by_key_u();
+        let arranged2 = other.arrange_by_key_u();
         arranged1.join_arranged(&arranged2, move |k,v1,v2| logic(&k.item,v1,v2))
     }
     fn semijoin_u<R2>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
     where K: Unsigned+Copy, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff {
-        let arranged1 = self.map(|(k,v)| (UnsignedWrapper::from(k), v))
-                            .arrange(DefaultValTrace::new());
-        let arranged2 = other.map(|k| (UnsignedWrapper::from(k), ()))
-                             .arrange(DefaultKeyTrace::new());
+        let arranged1 = self.arrange_by_key_u();
+        let arranged2 = other.arrange_by_self_u();
         arranged1.join_arranged(&arranged2, |k,v,_| (k.item.clone(), v.clone()))
     }
     fn antijoin_u<R2>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
@@ -195,6 +216,520 @@ where
     }
 }
 
+/// Joins of collections keyed by unsigned integers, in scopes with totally ordered times.
+///
+/// Each key is used as its own hash, and both inputs are arranged with hash-ordered traces (`HashValSpine` and
+/// `HashKeySpine`), which lay keys out densely by value rather than ordering them by comparison. The operators
+/// apply in any scope whose times are totally ordered (see `TotalOrder`), whatever its timestamp type. The
+/// keys should be well distributed, as they also determine the partitioning of data among workers.
+///
+/// #Examples
+/// ```ignore
+/// // the names of the suppliers of each part, where both are keyed by `u32` identifiers.
+/// parts_suppliers.map(|(part, supplier)| (supplier, part))
+///                .join_map_unsigned(&suppliers, |_supplier, part, name| (*part, name.clone()));
+/// ```
+pub trait JoinUnsigned<G: Scope, K: Data+Unsigned+Copy+Default, V: Data, R: Diff> where G::Timestamp: TotalOrder+Default {
+    /// Matches pairs `(key,val1)` and `(key,val2)` based on `key`.
+    fn join_unsigned<V2: Data, R2: Diff>(&self, other: &Collection<G, (K, V2), R2>) -> Collection<G, (K, V, V2), <R as Mul<R2>>::Output>
+    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff {
+        self.join_map_unsigned(other, |k,v,v2| (*k, v.clone(), v2.clone()))
+    }
+    /// Matches pairs `(key,val1)` and `(key,val2)` based on `key` and then applies a function.
+    fn join_map_unsigned<V2: Data, R2: Diff, D: Data, L>(&self, other: &Collection<G, (K, V2), R2>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
+    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff, L: Fn(&K, &V, &V2)->D+'static;
+    /// Retains the pairs `(key,val)` whose `key` is present in `other`.
+    fn semijoin_unsigned<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
+    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff;
+    /// Retains the pairs `(key,val)` whose `key` is absent from `other`.
+    fn antijoin_unsigned<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
+    where R: Mul<R2, Output=R>;
+}
+
+impl<G, K, V, R> JoinUnsigned<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    K: Data+Unsigned+Copy+Default,
+    V: Data,
+    R: Diff,
+    G::Timestamp: TotalOrder+Default,
+{
+    fn join_map_unsigned<V2: Data, R2: Diff, D: Data, L>(&self, other: &Collection<G, (K, V2), R2>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
+    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff, L: Fn(&K, &V, &V2)->D+'static {
+        let arranged1 = arrange_unsigned(self);
+        let arranged2 = arrange_unsigned(other);
+        arranged1.join_arranged(&arranged2, move |k,v1,v2| logic(&k.item,v1,v2))
+    }
+    fn semijoin_unsigned<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), <R as Mul<R2>>::Output>
+    where R: Mul<R2>, <R as Mul<R2>>::Output: Diff {
+        let arranged1 = arrange_unsigned(self);
+        let arranged2 = other.map(|k| (UnsignedWrapper::from(k), ()))
+                             .arrange(HashKeySpine::new());
+        ::plan::note_trace(&arranged2.stream, "HashKeySpine");
+        arranged1.join_arranged(&arranged2, |k,v,_| (k.item, v.clone()))
+    }
+    fn antijoin_unsigned<R2: Diff>(&self, other: &Collection<G, K, R2>) -> Collection<G, (K, V), R>
+    where R: Mul<R2, Output=R> {
+        self.concat(&self.semijoin_unsigned(other).negate())
+    }
+}
+
+// Arranges `(key, val)` pairs with unsigned keys in a hash-ordered trace.
+fn arrange_unsigned<G, K, V, R>(collection: &Collection<G, (K, V), R>) -> Arranged<G, UnsignedWrapper<K>, V, R, TraceAgent<UnsignedWrapper<K>, V, G::Timestamp, R, HashValSpine<UnsignedWrapper<K>, V, G::Timestamp, R>>>
+where G: Scope, K: Data+Unsigned+Copy+Default, V: Data, R: Diff, G::Timestamp: TotalOrder+Default {
+    let arranged = collection.map(|(k,v)| (UnsignedWrapper::from(k), v))
+                             .arrange(HashValSpine::new());
+    ::plan::note_trace(&arranged.stream, "HashValSpine");
+    arranged
+}
+
+/// Strategies for joining collections whose keys are unevenly distributed.
+///
+/// Joins partition both inputs by key, so all records for a key meet on a single worker. When a few keys
+/// account for much of the input, that worker serializes the join. The non-default strategies spread the
+/// records of the first input across several partitions by hashing each full record, and replicate the
+/// matching records of the second input to each of those partitions.
+///
+/// Records must be placed deterministically, so that a retraction meets the same partition as the record
+/// it retracts. For this reason the set of heavy keys is fixed when the join is constructed, for example by
+/// `Skew::from_sample`, rather than detected as the computation runs. Keys that only become heavy later are
+/// not salted, and their records again serialize on one worker; the output remains correct.
+pub enum Skew<K> {
+    /// Partition both inputs by key, as `join` does.
+    Hash,
+    /// Spread the first input across as many partitions as there are workers, replicating the second input to each.
+    ///
+    /// This is appropriate when the second input is small, as each worker holds all of it. The sizes of the inputs
+    /// are not compared, and so callers must pass the smaller collection as `other`. The guard limits the number of
+    /// updates of `other` each worker replicates, catching a second input that is larger than expected.
+    Broadcast(Guard),
+    /// Spread records whose keys satisfy the predicate across the indicated number of partitions.
+    ///
+    /// Only records of the second input with heavy keys are replicated.
+    Salt(u64, Rc<Fn(&K)->bool>),
+}
+
+impl<K: ::std::hash::Hash+'static> Skew<K> {
+    /// Salts the keys that occur at least `threshold` times in `sample`, across `salts` partitions.
+    ///
+    /// Occurrences are counted with a count-min sketch, which may overestimate but never underestimates the
+    /// count of a key. Light keys may therefore be salted, which costs only the replication of their records in
+    /// the second input, but heavy keys of the sample are never missed. The sample should be drawn from the
+    /// data the join will see; keys absent from it are treated as light.
+    pub fn from_sample<I: IntoIterator<Item=K>>(salts: u64, sample: I, threshold: usize) -> Self {
+
+        const WIDTH: usize = 1 << 12;
+        const DEPTH: usize = 4;
+
+        fn index<K: ::std::hash::Hash>(key: &K, row: usize) -> usize {
+            row * WIDTH + ((key, row).hashed().as_u64() as usize % WIDTH)
+        }
+
+        let mut counts = vec![0; WIDTH * DEPTH];
+        for key in sample {
+            for row in 0 .. DEPTH {
+                counts[index(&key, row)] += 1;
+            }
+        }
+
+        Skew::Salt(salts, Rc::new(move |key: &K| {
+            (0 .. DEPTH).map(|row| counts[index(key, row)]).min().unwrap() >= threshold
+        }))
+    }
+}
+
+/// Join implementations resistant to skew in the distribution of keys.
+pub trait JoinSkewed<G: Scope, K: Data, V: Data, R: Diff> {
+    /// Like `join_map`, but with records placed according to the `skew` strategy.
+    ///
+    /// The output is the same as that of `join_map`; only the placement of work among workers differs.
+    fn join_map_skewed<V2, R2, D, L>(&self, other: &Collection<G, (K,V2), R2>, skew: Skew<K>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
+    where V2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static;
+}
+
+impl<G, K, V, R> JoinSkewed<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    K: Data+Default+Hashable+::std::hash::Hash,
+    V: Data+::std::hash::Hash,
+    R: Diff,
+    G::Timestamp: Lattice+Ord,
+{
+    fn join_map_skewed<V2, R2, D, L>(&self, other: &Collection<G, (K,V2), R2>, skew: Skew<K>, logic: L) -> Collection<G, D, <R as Mul<R2>>::Output>
+    where V2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static {
+
+        let (salts, heavy, guard): (u64, Rc<Fn(&K)->bool>, Guard) = match skew {
+            Skew::Hash => { return self.join_map(other, logic); },
+            Skew::Broadcast(guard) => (self.scope().peers() as u64, Rc::new(|_: &K| true), guard),
+            Skew::Salt(salts, heavy) => (salts, heavy, Guard::Unlimited),
+        };
+
+        assert!(salts > 0);
+
+        // records of the first input go to one salt, determined by the record.
+        let heavy1 = heavy.clone();
+        let salted1 = self.map(move |(k,v)| {
+            let salt = if heavy1(&k) { (&k, &v).hashed() % salts } else { 0 };
+            ((k, salt), v)
+        });
+
+        // records of the second input go to every salt their key may use; broadcast records count against the guard.
+        let heavy2 = heavy;
+        let replicated = ::std::cell::Cell::new(0);
+        let reported = ::std::cell::Cell::new(false);
+        let salted2 = other.flat_map(move |(k,v)| {
+            let heavy = heavy2(&k);
+            if heavy {
+                replicated.set(replicated.get() + 1);
+                match guard {
+                    Guard::Unlimited => { },
+                    Guard::Warn(limit, ref report) => {
+                        if replicated.get() > limit && !reported.get() {
+                            report(replicated.get());
+                            reported.set(true);
+                        }
+                    },
+                    Guard::Abort(limit) => {
+                        if replicated.get() > limit {
+                            panic!("join_map_skewed: {} broadcast records exceeds limit of {}", replicated.get(), limit);
+                        }
+                    },
+                }
+            }
+            let copies = if heavy { salts } else { 1 };
+            (0 .. copies).map(move |salt| ((k.clone(), salt), v.clone()))
+        });
+
+        salted1.join_map(&salted2, move |&(ref k, _), v1, v2| logic(k, v1, v2))
+    }
+}
+
+/// Joins tolerating an input that trails the other, for consumers that prefer early answers.
+pub trait JoinProvisional<G: Scope, K: Data, V: Data, R: Diff> {
+    /// Like `join_map`, but tags each result with whether it is provisional.
+    ///
+    /// A result is provisional if it is produced before `other` is complete through its time. As `other`
+    /// catches up, the join produces further results at that time, tagged `false`, which correct the
+    /// provisional results; accumulating results regardless of their tag yields the output of `join_map`.
+    ///
+    /// Results are held back until `other` is complete through `lag(time)`, bounding the slack by which
+    /// `other` may trail before results are produced. The function `lag` must return times less or equal to
+    /// its argument; the identity holds back all results until they are exact.
+    fn join_map_provisional<V2, R2, D, L, F>(&self, other: &Collection<G, (K,V2), R2>, lag: F, logic: L) -> Collection<G, (D, bool), <R as Mul<R2>>::Output>
+    where V2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static, F: Fn(&G::Timestamp)->G::Timestamp+'static;
+}
+
+impl<G, K, V, R> JoinProvisional<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    K: Data+Default+Hashable,
+    V: Data,
+    R: Diff,
+    G::Timestamp: Lattice+Ord,
+{
+    fn join_map_provisional<V2, R2, D, L, F>(&self, other: &Collection<G, (K,V2), R2>, lag: F, logic: L) -> Collection<G, (D, bool), <R as Mul<R2>>::Output>
+    where V2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff, D: Data, L: Fn(&K, &V, &V2)->D+'static, F: Fn(&G::Timestamp)->G::Timestamp+'static {
+
+        self.join_map(other, logic)
+            .provisional(other, lag)
+    }
+}
+
+/// Joins computing approximate results from a sample of keys.
+pub trait JoinSampled<G: Scope, K: Data, V: Data> {
+    /// Like `join_map`, but joining only a sample of keys, with results weighted to estimate the full join.
+    ///
+    /// Each key is retained with probability `probability(key)`, decided by a hash of the key so that both
+    /// inputs, and the insertions and retractions of each key, are sampled alike. The count of each result is
+    /// scaled by the inverse of its key's probability, so that accumulations of the output, for example sums
+    /// of counts over many keys, are unbiased estimates of those of `join_map`. Probabilities at or above one
+    /// retain every key at weight one, and those at or below zero retain none. Weights are rounded to units of
+    /// `Decimal`, and a retained key with so small a probability that its weight does not fit in a `Decimal`
+    /// panics with a `Decimal` overflow.
+    fn join_map_sampled<V2, D, L, P>(&self, other: &Collection<G, (K,V2), isize>, probability: P, logic: L) -> Collection<G, D, Decimal>
+    where V2: Data, D: Data, L: Fn(&K, &V, &V2)->D+'static, P: Fn(&K)->f64+'static;
+}
+
+impl<G, K, V> JoinSampled<G, K, V> for Collection<G, (K, V), isize>
+where
+    G: Scope,
+    K: Data+Default+Hashable+::std::hash::Hash,
+    V: Data,
+    G::Timestamp: Lattice+Ord,
+{
+    fn join_map_sampled<V2, D, L, P>(&self, other: &Collection<G, (K,V2), isize>, probability: P, logic: L) -> Collection<G, D, Decimal>
+    where V2: Data, D: Data, L: Fn(&K, &V, &V2)->D+'static, P: Fn(&K)->f64+'static {
+
+        // a key is retained if its (salted, to decorrelate from routing) hash falls below its probability.
+        let probability = Rc::new(probability);
+        fn sampled<K: ::std::hash::Hash>(key: &K, probability: f64) -> bool {
+            ((key, "join_sampled").hashed().as_u64() as f64) < probability * (::std::u64::MAX as f64)
+        }
+
+        let probability1 = probability.clone();
+        let probability2 = probability.clone();
+        let sample1 = self.filter(move |&(ref k, _)| sampled(k, probability1(k)));
+        let sample2 = other.filter(move |&(ref k, _)| sampled(k, probability2(k)));
+
+        sample1.join_map(&sample2, move |k, v1, v2| (k.clone(), logic(k, v1, v2)))
+               .inner
+               .map(move |((k, d), t, r)| {
+                   let scale = probability(&k).min(1.0);
+                   (d, t, Decimal::from_f64((r as f64) / scale))
+               })
+               .as_collection()
+    }
+}
+
+/// Joins several arranged collections on a common key, ordering the binary joins by the sizes of the inputs.
+///
+/// The size of each input is read from the statistics of its trace (see `TraceAgent::statistics`) when the
+/// dataflow is constructed, and so reflects data already arranged, for example in arrangements imported from
+/// other dataflows. The inputs are joined in increasing order of their numbers of updates, with inputs of equal
+/// size joined in the order supplied, so that the intermediate results are likely to be small. The first two
+/// inputs are joined through their arrangements, and each intermediate result is arranged to join it with the
+/// next input. The order only affects the cost of the joins: each output record lists the values of the inputs
+/// in the order the inputs are supplied.
+///
+/// Panics if there are no inputs.
+pub fn join_pipeline<G, K, V, Tr>(inputs: &[Arranged<G, OrdWrapper<K>, V, isize, TraceAgent<OrdWrapper<K>, V, G::Timestamp, isize, Tr>>]) -> Collection<G, (K, Vec<V>)>
+where
+    G: Scope,
+    K: Data+Default+Hashable,
+    V: Data,
+    G::Timestamp: Lattice+Ord+Debug,
+    Tr: TraceReader<OrdWrapper<K>, V, G::Timestamp, isize>+'static,
+    Tr::Batch: BatchReader<OrdWrapper<K>, V, G::Timestamp, isize>+'static+Debug,
+{
+    assert!(inputs.len() > 0);
+
+    if inputs.len() == 1 {
+        return inputs[0].as_collection(|k, v| (k.item.clone(), vec![v.clone()]));
+    }
+
+    // a stable sort, which retains the supplied order among inputs of equal size.
+    let sizes: Vec<usize> = inputs.iter().map(|input| input.trace.clone().statistics().updates).collect();
+    let mut order: Vec<usize> = (0 .. inputs.len()).collect();
+    order.sort_by_key(|&index| sizes[index]);
+
+    // values are tagged with the position of their input, to restore the supplied order at the end.
+    let (first, second) = (order[0], order[1]);
+    let mut result = inputs[first].join_arranged(&inputs[second], move |k, v1, v2| {
+        (k.item.clone(), vec![(first, v1.clone()), (second, v2.clone())])
+    });
+    for &index in order[2..].iter() {
+        result = result.arrange_by_key_hashed()
+                       .join_arranged(&inputs[index], move |k, vs, v| {
+                           let mut vs = vs.clone();
+                           vs.push((index, v.clone()));
+                           (k.item.clone(), vs)
+                       });
+    }
+
+    result.map(|(k, mut vs)| {
+        vs.sort_by(|x, y| x.0.cmp(&y.0));
+        (k, vs.into_iter().map(|(_, v)| v).collect())
+    })
+}
+
+/// Validation of foreign keys against an arranged collection of parent keys.
+pub trait ValidateForeignKey<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+    /// Produces the records of `self` whose key is absent from `parent`.
+    ///
+    /// The result is maintained incrementally, and so reports a record as a violation as long as its key is
+    /// absent, retracting it when the key appears or the record is removed. The parent must contain each key
+    /// at most once, as a set of primary keys does; deduplicate it with `distinct` before arranging otherwise.
+    ///
+    /// #Examples
+    /// ```ignore
+    /// // orders naming a customer that does not exist.
+    /// let customers = customers.map(|(id, _name)| id).arrange_by_self();
+    /// let dangling = orders.map(|(order, customer)| (customer, order))
+    ///                      .validate_fk(&customers);
+    /// ```
+    fn validate_fk<R2, T2>(&self, parent: &Arranged<G, OrdWrapper<K>, (), R2, T2>) -> Collection<G, (K, V), R>
+    where
+        R2: Diff,
+        R: Mul<R2, Output=R>,
+        T2: TraceReader<OrdWrapper<K>, (), G::Timestamp, R2>+Clone+'static,
+        T2::Batch: BatchReader<OrdWrapper<K>, (), G::Timestamp, R2>+'static;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data, R: Diff> ValidateForeignKey<G, K, V, R> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord {
+    fn validate_fk<R2, T2>(&self, parent: &Arranged<G, OrdWrapper<K>, (), R2, T2>) -> Collection<G, (K, V), R>
+    where
+        R2: Diff,
+        R: Mul<R2, Output=R>,
+        T2: TraceReader<OrdWrapper<K>, (), G::Timestamp, R2>+Clone+'static,
+        T2::Batch: BatchReader<OrdWrapper<K>, (), G::Timestamp, R2>+'static,
+    {
+        let present = self.arrange_by_key_hashed()
+                          .join_arranged(parent, |k, v, _| (k.item.clone(), v.clone()));
+        self.concat(&present.negate())
+    }
+}
+
+/// Enrichment of records with values from an arranged collection.
+pub trait LookupMap<G: Scope, K: Data, V: Data, R: Diff> {
+    /// Extends each record `(key, val)` with the value `arranged` associates with `key`, or `default` if none.
+    ///
+    /// Unlike `join`, each input record produces exactly one output record. This is the common case of enriching
+    /// records with attributes from a dimension table, where records without a matching entry should be kept.
+    /// The arranged collection is expected to have at most one value for each key, with count one; when debug 
+    /// assertions are enabled, a key with several values causes a panic. Otherwise, such a key produces several
+    /// outputs for each input record with that key.
+    ///
+    /// #Examples
+    /// ```ignore
+    /// let names = people.arrange_by_key_hashed();
+    /// // (order, customer, name), with "unknown" for customers not in `people`.
+    /// orders.lookup_map(&names, "unknown".to_owned());
+    /// ```
+    fn lookup_map<V2, T2>(&self, arranged: &Arranged<G, OrdWrapper<K>, V2, isize, T2>, default: V2) -> Collection<G, (K, V, V2), R>
+    where 
+        V2: Data, 
+        T2: TraceReader<OrdWrapper<K>, V2, G::Timestamp, isize>+Clone+'static,
+        T2::Batch: BatchReader<OrdWrapper<K>, V2, G::Timestamp, isize>+Clone+'static,
+        R: Mul<isize, Output=R>;
+}
+
+impl<G, K, V, R> LookupMap<G, K, V, R> for Collection<G, (K, V), R>
+where
+    G: Scope,
+    K: Data+Default+Hashable,
+    V: Data,
+    R: Diff,
+    G::Timestamp: Lattice+Ord+Debug,
+{
+    fn lookup_map<V2, T2>(&self, arranged: &Arranged<G, OrdWrapper<K>, V2, isize, T2>, default: V2) -> Collection<G, (K, V, V2), R>
+    where 
+        V2: Data, 
+        T2: TraceReader<OrdWrapper<K>, V2, G::Timestamp, isize>+Clone+'static,
+        T2::Batch: BatchReader<OrdWrapper<K>, V2, G::Timestamp, isize>+Clone+'static,
+        R: Mul<isize, Output=R> {
+
+        let matched = self.join_arranged(arranged, |k,v,v2| (k.item.clone(), v.clone(), v2.clone()));
+
+        let keys = arranged.as_collection(|k,_| k.item.clone());
+        if cfg!(debug_assertions) {
+            keys.count()
+                .filter(|&(_, count)| count != 1)
+                .inspect(|x| if x.2 > 0 { panic!("lookup_map: {:?} values for key {:?}", (x.0).1, (x.0).0); });
+        }
+
+        let unmatched = self.antijoin(&keys.distinct());
+        matched.concat(&unmatched.map(move |(k,v)| (k, v, default.clone())))
+    }
+}
+
+/// Limits on the number of records produced by `cross_join` or replicated by `Skew::Broadcast`.
+///
+/// Cross joins produce the product of the sizes of their inputs, which is easy to underestimate. The limit
+/// applies to the number of output updates produced by each worker, which is counted as input updates arrive
+/// and before they reach the join. Broadcast joins replicate their second input to every worker, and the limit
+/// applies to the number of updates of the second input each worker replicates.
+pub enum Guard {
+    /// Do not check the number of records.
+    Unlimited,
+    /// Call the function, once, with the number of records when it first exceeds the limit.
+    Warn(usize, Rc<Fn(usize)>),
+    /// Panic when the number of records would exceed the limit, before they are produced.
+    Abort(usize),
+}
+
+/// Cartesian products of collections.
+pub trait CrossJoin<G: Scope, D: Data, R: Diff> {
+    /// Matches every record of `self` with every record of `other`.
+    ///
+    /// The records of `self` are spread across workers by their hash, and the records of `other` are replicated
+    /// to each worker. The sizes of the inputs are not compared: callers must pass the smaller collection as
+    /// `other`. The number of output updates each worker will produce is checked against `guard` before the
+    /// input updates are passed to the join, so that `Guard::Abort` stops the computation before the output
+    /// is produced.
+    ///
+    /// #Examples
+    /// ```ignore
+    /// // all (fact, dimension) pairs, failing loudly if there are more than a million per worker.
+    /// facts.cross_join(&dimensions, Guard::Abort(1_000_000));
+    /// ```
+    fn cross_join<D2, R2>(&self, other: &Collection<G, D2, R2>, guard: Guard) -> Collection<G, (D, D2), <R as Mul<R2>>::Output>
+    where D2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff;
+}
+
+impl<G, D, R> CrossJoin<G, D, R> for Collection<G, D, R>
+where
+    G: Scope,
+    D: Data+::std::hash::Hash,
+    R: Diff,
+    G::Timestamp: Lattice+Ord,
+{
+    fn cross_join<D2, R2>(&self, other: &Collection<G, D2, R2>, guard: Guard) -> Collection<G, (D, D2), <R as Mul<R2>>::Output>
+    where D2: Data, R2: Diff, R: Mul<R2>, <R as Mul<R2>>::Output: Diff {
+
+        // updates of `self` go to one partition, determined by the record, and those of `other` to every partition.
+        let peers = self.scope().peers() as u64;
+        let left = self.inner.map(move |(x, t, r)| (Some(((x.hashed() % peers, x), t, r)), None));
+        let right = other.inner.flat_map(move |(y, t, r)| {
+            (0 .. peers).map(move |salt| (None, Some(((salt, y.clone()), t.clone(), r))))
+        });
+
+        // route updates as the join would, and count the output updates of each partition before passing them on.
+        let exchange = Exchange::new(|update: &(Option<((u64, D), G::Timestamp, R)>, Option<((u64, D2), G::Timestamp, R2)>)| {
+            match *update {
+                (Some(((salt, _), _, _)), _) => salt.hashed().as_u64(),
+                (_, Some(((salt, _), _, _))) => salt.hashed().as_u64(),
+                _ => 0,
+            }
+        });
+
+        let mut sizes = ::std::collections::HashMap::new();
+        let mut produced = 0;
+        let mut reported = false;
+        let guarded = left.concat(&right).unary_stream(exchange, "CrossJoinGuard", move |input, output| {
+            input.for_each(|time, data| {
+                for update in data.iter() {
+                    match *update {
+                        (Some(((salt, _), _, _)), _) => {
+                            let size = sizes.entry(salt).or_insert((0, 0));
+                            size.0 += 1;
+                            produced += size.1;
+                        },
+                        (_, Some(((salt, _), _, _))) => {
+                            let size = sizes.entry(salt).or_insert((0, 0));
+                            size.1 += 1;
+                            produced += size.0;
+                        },
+                        _ => { },
+                    }
+                }
+                match guard {
+                    Guard::Unlimited => { },
+                    Guard::Warn(limit, ref report) => {
+                        if produced > limit && !reported {
+                            report(produced);
+                            reported = true;
+                        }
+                    },
+                    Guard::Abort(limit) => {
+                        if produced > limit {
+                            panic!("cross_join: {} output records exceeds limit of {}", produced, limit);
+                        }
+                    },
+                }
+                let mut session = output.session(&time);
+                for update in data.drain(..) {
+                    session.give(update);
+                }
+            });
+        });
+
+        let left = guarded.flat_map(|(x, _)| x).as_collection();
+        let right = guarded.flat_map(|(_, y)| y).as_collection();
+        left.join_map(&right, |_, x, y| (x.clone(), y.clone()))
+    }
+}
+
 /// Matches the elements of two arranged traces.
 ///
 /// This method is used by the various `join` implementations, but it can also be used 
@@ -217,7 +752,26 @@ pub trait JoinArranged<G: Scope, K: 'static, V: 'static, R: Diff> where G::Times
         R: Mul<R2>,
         <R as Mul<R2>>::Output: Diff,
         D: Data,
-        L: Fn(&K,&V,&V2)->D+'static;
+        L: Fn(&K,&V,&V2)->D+'static
+    {
+        self.join_arranged_with(stream2, result, |r1,r2| *r1 * *r2)
+    }
+    /// Joins two arranged collections, combining differences with a supplied function.
+    ///
+    /// The `join_arranged` method multiplies the differences of matched records. This method instead applies
+    /// `mult` to the two differences, which allows the output difference type to differ from that of the inputs
+    /// without implementing `Mul` (for example, producing `i64` sums from `isize` counts and a value). For the
+    /// output to be correct, `mult` must distribute over addition in each argument.
+    fn join_arranged_with<V2,T2,R2,R3,D,L,M> (&self, stream2: &Arranged<G,K,V2,R2,T2>, result: L, mult: M) -> Collection<G,D,R3>
+    where 
+        V2: Ord+Clone+Debug+'static,
+        T2: TraceReader<K, V2, G::Timestamp, R2>+Clone+'static,
+        T2::Batch: BatchReader<K, V2, G::Timestamp, R2>+'static,
+        R2: Diff,
+        R3: Diff,
+        D: Data,
+        L: Fn(&K,&V,&V2)->D+'static,
+        M: Fn(&R,&R2)->R3+'static;
 }
 
 
@@ -229,19 +783,19 @@ where
     R: Diff,
     G::Timestamp: Lattice+Ord,
 {
-    fn join_arranged<V2,T2,R2,D,L> (&self, stream2: &Arranged<G,OrdWrapper<K>,V2,R2,T2>, result: L) -> Collection<G,D,<R as Mul<R2>>::Output>
+    fn join_arranged_with<V2,T2,R2,R3,D,L,M> (&self, stream2: &Arranged<G,OrdWrapper<K>,V2,R2,T2>, result: L, mult: M) -> Collection<G,D,R3>
     where 
         V2: Ord+Clone+Debug+'static,
         T2: TraceReader<OrdWrapper<K>, V2, G::Timestamp, R2>+Clone+'static,
         T2::Batch: BatchReader<OrdWrapper<K>, V2, G::Timestamp, R2>+'static,
         R2: Diff,
-        R: Mul<R2>,
-        <R as Mul<R2>>::Output: Diff,
+        R3: Diff,
         D: Data,
-        L: Fn(&OrdWrapper<K>,&V,&V2)->D+'static {
+        L: Fn(&OrdWrapper<K>,&V,&V2)->D+'static,
+        M: Fn(&R,&R2)->R3+'static {
 
         self.arrange_by_key_hashed()
-            .join_arranged(stream2, result)
+            .join_arranged_with(stream2, result, mult)
 
     }
 }
@@ -256,16 +810,19 @@ impl<G, K, V, R1, T1> JoinArranged<G, K, V, R1> for Arranged<G,K,V,R1,T1>
         R1: Diff,
         T1: TraceReader<K,V,G::Timestamp, R1>+Clone+'static,
         T1::Batch: BatchReader<K,V,G::Timestamp,R1>+'static+Debug {
-    fn join_arranged<V2,T2,R2,D,L>(&self, other: &Arranged<G,K,V2,R2,T2>, result: L) -> Collection<G,D,<R1 as Mul<R2>>::Output> 
+    fn join_arranged_with<V2,T2,R2,R3,D,L,M>(&self, other: &Arranged<G,K,V2,R2,T2>, result: L, mult: M) -> Collection<G,D,R3> 
     where 
         V2: Ord+Clone+Debug+'static,
         T2: TraceReader<K,V2,G::Timestamp,R2>+Clone+'static,
         T2::Batch: BatchReader<K, V2, G::Timestamp, R2>+'static,
         R2: Diff,
-        R1: Mul<R2>,
-        <R1 as Mul<R2>>::Output: Diff,
+        R3: Diff,
         D: Data,
-        L: Fn(&K,&V,&V2)->D+'static {
+        L: Fn(&K,&V,&V2)->D+'static,
+        M: Fn(&R1,&R2)->R3+'static {
+
+        // shared by the deferred work of both inputs.
+        let mult = Rc::new(mult);
 
         // handles to shared trace data structures.
         let mut trace1 = Some(self.trace.clone());
@@ -279,7 +836,10 @@ impl<G, K, V, R1, T1> JoinArranged<G, K, V, R1> for Arranged<G,K,V,R1,T1>
         let mut todo1 = Vec::new();
         let mut todo2 = Vec::new();
 
-        self.stream.binary_notify(&other.stream, Pipeline, Pipeline, "Join", vec![], move |input1, input2, output, notificator| {
+        // work performed per activation, with the remainder deferred to later activations.
+        let budget = ::operators::fuel::current();
+
+        let stream = self.stream.binary_notify(&other.stream, Pipeline, Pipeline, "Join", vec![], move |input1, input2, output, notificator| {
 
             // The join computation repeatedly accepts batches of updates from each of its inputs.
             //
@@ -294,7 +854,8 @@ impl<G, K, V, R1, T1> JoinArranged<G, K, V, R1> for Arranged<G,K,V,R1,T1>
                     for batch1 in data.drain(..) {
                         let trace2_cursor = trace2.cursor_through(&acknowledged2[..]).unwrap();
                         let batch1_cursor = batch1.item.cursor();
-                        todo1.push(Deferred::new(trace2_cursor, batch1_cursor, capability.clone(), |r2,r1| *r1 * *r2));
+                        let mult = mult.clone();
+                        todo1.push(Deferred::new(trace2_cursor, batch1_cursor, capability.clone(), move |r2,r1| mult(r1,r2)));
                         debug_assert!(batch1.item.description().lower() == &acknowledged1[..]);
                         acknowledged1 = batch1.item.description().upper().to_vec();
                     }
@@ -307,7 +868,8 @@ impl<G, K, V, R1, T1> JoinArranged<G, K, V, R1> for Arranged<G,K,V,R1,T1>
                     for batch2 in data.drain(..) {
                         let trace1_cursor = trace1.cursor_through(&acknowledged1[..]).unwrap();
                         let batch2_cursor = batch2.item.cursor();
-                        todo2.push(Deferred::new(trace1_cursor, batch2_cursor, capability.clone(), |r1,r2| *r1 * *r2));
+                        let mult = mult.clone();
+                        todo2.push(Deferred::new(trace1_cursor, batch2_cursor, capability.clone(), move |r1,r2| mult(r1,r2)));
                         debug_assert!(batch2.item.description().lower() == &acknowledged2[..]);
                         acknowledged2 = batch2.item.description().upper().to_vec();
                     }
@@ -319,18 +881,16 @@ impl<G, K, V, R1, T1> JoinArranged<G, K, V, R1> for Arranged<G,K,V,R1,T1>
             // as we may still use them as thresholds (ie we must preserve `le` wrt `acknowledged`).
             if trace2.is_some() && notificator.frontier(0).len() == 0 { trace2 = None; }
             if let Some(ref mut trace2) = trace2 {
-                trace2.advance_by(notificator.frontier(0));
-                trace2.distinguish_since(&acknowledged2[..]);
+                trace2.set_read_frontiers(notificator.frontier(0), &acknowledged2[..]);
             }
 
             // shut down or advance trace1.
             if trace1.is_some() && notificator.frontier(1).len() == 0 { trace1 = None; }
             if let Some(ref mut trace1) = trace1 {
-                trace1.advance_by(notificator.frontier(1));
-                trace1.distinguish_since(&acknowledged1[..]);
+                trace1.set_read_frontiers(notificator.frontier(1), &acknowledged1[..]);
             }
 
-            let mut fuel = 1_000_000;
+            let mut fuel = budget;
 
             // perform some amount of outstanding work. 
             while todo1.len() > 0 && fuel > 0 {
@@ -344,8 +904,10 @@ impl<G, K, V, R1, T1> JoinArranged<G, K, V, R1> for Arranged<G,K,V,R1,T1>
                 if !todo2[0].work_remains() { todo2.remove(0); }
             }
 
-        })
-        .as_collection()
+        });
+
+        ::plan::note("Join", None, || (vec![::plan::stream_name(&self.stream), ::plan::stream_name(&other.stream)], ::plan::stream_name(&stream)));
+        stream.as_collection()
     }
 }
 
@@ -0,0 +1,79 @@
+//! Intersects the keys and values of several sorted cursors.
+//!
+//! Leapfrog intersection repeatedly seeks each cursor to the largest key among the cursors, until all cursors
+//! agree on a key. Cursors seek by galloping, and so the work is proportional to the size of the smallest
+//! input rather than the largest, up to logarithmic factors. Applied first to keys and then to values, with
+//! cursors cloned to save positions, this is the building block of worst-case optimal multi-way joins.
+//!
+//! Cursors present all keys and values with updates, including those whose updates accumulate to zero at the
+//! times of interest. The logic supplied to these functions should consult `map_times` where this matters.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // the keys present in each of three traces.
+//! let mut cursors = vec![trace1.cursor(), trace2.cursor(), trace3.cursor()];
+//! leapfrog::intersect_keys(&mut cursors[..], |key, _cursors| println!("{:?}", key));
+//! ```
+
+use trace::Cursor;
+
+/// Calls `logic` for each key present in every cursor, with the cursors positioned at the key.
+///
+/// The logic may move among the values of the cursors, but must not move their keys.
+pub fn intersect_keys<K, V, T, R, C, F>(cursors: &mut [C], mut logic: F)
+where K: Ord+Clone, C: Cursor<K, V, T, R>, F: FnMut(&K, &mut [C]) {
+
+    if cursors.len() == 0 { return; }
+
+    while let Some(max) = largest(cursors.iter().map(|c| if c.key_valid() { Some(c.key()) } else { None })) {
+
+        let mut agree = true;
+        for cursor in cursors.iter_mut() {
+            cursor.seek_key(&max);
+            if !cursor.key_valid() { return; }
+            if cursor.key() != &max { agree = false; }
+        }
+
+        if agree {
+            logic(&max, &mut cursors[..]);
+            cursors[0].step_key();
+        }
+    }
+}
+
+/// Calls `logic` for each value present in every cursor at its current key, with the cursors positioned at the value.
+///
+/// The logic must not move the cursors.
+pub fn intersect_vals<K, V, T, R, C, F>(cursors: &mut [C], mut logic: F)
+where V: Ord+Clone, C: Cursor<K, V, T, R>, F: FnMut(&V, &mut [C]) {
+
+    if cursors.len() == 0 { return; }
+
+    while let Some(max) = largest(cursors.iter().map(|c| if c.val_valid() { Some(c.val()) } else { None })) {
+
+        let mut agree = true;
+        for cursor in cursors.iter_mut() {
+            cursor.seek_val(&max);
+            if !cursor.val_valid() { return; }
+            if cursor.val() != &max { agree = false; }
+        }
+
+        if agree {
+            logic(&max, &mut cursors[..]);
+            cursors[0].step_val();
+        }
+    }
+}
+
+/// The largest of the elements, or `None` if any element is `None`.
+fn largest<'a, X: Ord+Clone+'a, I: Iterator<Item=Option<&'a X>>>(elements: I) -> Option<X> {
+    let mut result: Option<&X> = None;
+    for element in elements {
+        match element {
+            Some(x) => { if result.map(|r| r < x).unwrap_or(true) { result = Some(x); } },
+            None => { return None; },
+        }
+    }
+    result.cloned()
+}
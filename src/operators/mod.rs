@@ -10,10 +10,20 @@ pub use self::iterate::Iterate;
 pub use self::join::Join;
 
 pub mod arrange;
+pub mod fuel;
+pub mod balance;
+pub mod queue;
+pub mod upsert;
+pub mod purge;
+pub mod throttle;
+pub mod sink;
+pub mod stash;
 pub mod group;
+pub mod cogroup_n;
 pub mod consolidate;
 pub mod iterate;
 pub mod join;
+pub mod leapfrog;
 
 use ::Diff;
 use lattice::Lattice;
@@ -0,0 +1,56 @@
+//! Retracts every value of keys named by a control collection.
+//!
+//! Erasure requests usually name a key, for example a user identifier, rather than the records held for it.
+//! The `purge_keys` operator reads the current values of each named key from a shared arrangement, and
+//! produces their retractions, so that an application can remove the records without knowing them.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // the profile records of users who asked to be forgotten.
+//! let profiles = profiles.arrange_by_key_hashed();
+//! let erased = profiles.purge_keys(&forget);
+//! profiles.as_collection(|k, v| (k.item.clone(), v.clone()))
+//!         .concat(&erased)
+//! ```
+
+use std::fmt::Debug;
+use std::ops::Mul;
+
+use timely::dataflow::Scope;
+
+use ::{Data, Diff, Collection, Hashable};
+use hashable::OrdWrapper;
+use lattice::Lattice;
+use operators::arrange::{Arranged, ArrangeBySelf};
+use operators::group::Distinct;
+use operators::join::JoinArranged;
+use trace::{BatchReader, TraceReader};
+
+/// Extension trait for the `purge_keys` method.
+pub trait PurgeKeys<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+    /// Retracts each `(key, val)` of the arrangement whose key is present in `keys`.
+    ///
+    /// The result is maintained incrementally: while a key is present in `keys` the result retracts its
+    /// accumulated values, including values that arrive later, and when the key leaves `keys` the
+    /// retractions are withdrawn. Concatenating the result with the arranged collection therefore presents
+    /// the collection without the purged keys. Keys present several times in `keys` are purged once.
+    fn purge_keys<R2: Diff>(&self, keys: &Collection<G, K, R2>) -> Collection<G, (K, V), R>;
+}
+
+impl<G, K, V, R, T> PurgeKeys<G, K, V, R> for Arranged<G, OrdWrapper<K>, V, R, T>
+where
+    G: Scope,
+    G::Timestamp: Lattice+Ord+Debug,
+    K: Data+Default+Hashable,
+    V: Data,
+    R: Diff+Mul<isize, Output=R>,
+    T: TraceReader<OrdWrapper<K>, V, G::Timestamp, R>+Clone+'static,
+    T::Batch: BatchReader<OrdWrapper<K>, V, G::Timestamp, R>+Debug+'static,
+{
+    fn purge_keys<R2: Diff>(&self, keys: &Collection<G, K, R2>) -> Collection<G, (K, V), R> {
+        let keys = keys.distinct().arrange_by_self();
+        self.join_arranged(&keys, |key, val, _| (key.item.clone(), val.clone()))
+            .negate()
+    }
+}
@@ -0,0 +1,59 @@
+//! Extracts the least elements of per-key priority queues.
+//!
+//! A collection of `(key, value)` pairs can be viewed as a priority queue for each key, ordered by value. The
+//! `pop_min` operator takes a second collection of requests, one record for each element to be removed from
+//! a key's queue, and produces the least elements of each queue, as many as requested. Both the queues and the
+//! requests are arranged by key, and the output is maintained incrementally as either changes.
+//!
+//! The operator does not itself remove elements: a computation pops elements by subtracting the output from the
+//! queue, typically inside an iterative scope. This supports discrete-event computations, where the earliest
+//! pending events of each key are processed and may schedule further events.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // each machine processes its earliest pending job, and the rest remain queued.
+//! let next = jobs.map(|(machine, job)| (machine, (job.start, job.id)))
+//!                .pop_min(&machines);
+//! let remaining = jobs.subtract(&next);
+//! ```
+
+use std::fmt::Debug;
+
+use timely::dataflow::*;
+
+use ::{Data, Collection, Hashable};
+use lattice::Lattice;
+use operators::group::Group;
+
+/// Extension trait for the `pop_min` method.
+pub trait PopMin<G: Scope, K: Data, V: Data> where G::Timestamp: Lattice+Ord {
+    /// Produces, for each key, its least values, one for each record of the key in `requests`.
+    ///
+    /// Values are ordered by `Ord`, and values present multiple times may be produced multiple times. Keys with
+    /// no requests (or a non-positive number of requests) produce nothing, and keys with more requests than
+    /// values produce all of their values.
+    fn pop_min(&self, requests: &Collection<G, K, isize>) -> Collection<G, (K, V), isize>;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data> PopMin<G, K, V> for Collection<G, (K, V), isize>
+where G::Timestamp: Lattice+Ord+Debug, <K as Hashable>::Output: Data+Default {
+    fn pop_min(&self, requests: &Collection<G, K, isize>) -> Collection<G, (K, V), isize> {
+        // requests are encoded as `None`, which orders before all values.
+        requests.map(|key| (key, None))
+                .concat(&self.map(|(key, val)| (key, Some(val))))
+                .group(|_key, input, output| {
+                    let mut remaining = if input[0].0.is_none() { input[0].1 } else { 0 };
+                    for &(ref value, count) in input.iter() {
+                        if let Some(ref value) = *value {
+                            if remaining <= 0 { break; }
+                            if count > 0 {
+                                let taken = ::std::cmp::min(count, remaining);
+                                output.push((value.clone(), taken));
+                                remaining -= taken;
+                            }
+                        }
+                    }
+                })
+    }
+}
@@ -0,0 +1,205 @@
+//! Delivers consolidated collection updates to external systems exactly once.
+//!
+//! An external system consuming the output of a collection usually wants each time's updates once, in full,
+//! and only once no further updates at that time can arrive. The `sink_transactional` operator hands each
+//! worker's consolidated updates to a `TransactionalSink` in two phases: once a time is complete on the worker,
+//! its updates are prepared; once every worker has prepared the time, the worker commits it. A sink that can
+//! report which times it has already committed, for example after a restart, is not handed them again.
+//!
+//! `FileSink` is a reference implementation writing each time's updates to a file in a directory, which it
+//! records in a log and then renames when the time commits.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let sink = FileSink::new("output", worker.index(), |time: &u64| time.to_string()).unwrap();
+//! let committed = collection.sink_transactional(sink);
+//! committed.probe()
+//! ```
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Pipeline;
+
+use ::{Collection, Data};
+use ::difference::Diff;
+use operators::consolidate_from;
+use operators::stash::TimeStash;
+
+/// A destination accepting updates in two phases.
+///
+/// Each worker drives its own sink with the updates that worker holds. Times are prepared in order, and each
+/// prepared time is committed once all workers have prepared it; times at which a worker holds no updates are
+/// neither prepared nor committed by that worker.
+pub trait TransactionalSink<D, T, R> {
+    /// Stages the consolidated updates at `time`, which must not yet become visible.
+    ///
+    /// A time may be prepared again if the computation restarts before committing it.
+    fn prepare(&mut self, time: &T, updates: &[(D, R)]) -> io::Result<()>;
+    /// Makes the updates prepared at `time` visible.
+    fn commit(&mut self, time: &T) -> io::Result<()>;
+    /// Indicates that `time` was committed by an earlier run, and should not be prepared again.
+    fn is_committed(&self, _time: &T) -> bool { false }
+}
+
+/// Extension trait for the `sink_transactional` method.
+pub trait SinkTransactional<G: Scope, D: Data, R: Diff> where G::Timestamp: Ord {
+    /// Delivers the consolidated updates of each time to `sink`, returning the stream of committed times.
+    ///
+    /// Updates are delivered from the worker holding them; exchange the collection first to route updates to
+    /// a particular worker's sink. The operator panics if the sink reports an error, as updates could not
+    /// otherwise be delivered exactly once.
+    fn sink_transactional<S: TransactionalSink<D, G::Timestamp, R>+'static>(&self, sink: S) -> Stream<G, G::Timestamp>;
+}
+
+impl<G: Scope, D: Data, R: Diff> SinkTransactional<G, D, R> for Collection<G, D, R> where G::Timestamp: Ord {
+    fn sink_transactional<S: TransactionalSink<D, G::Timestamp, R>+'static>(&self, sink: S) -> Stream<G, G::Timestamp> {
+
+        let sink = Rc::new(RefCell::new(sink));
+        let sink2 = sink.clone();
+
+        // updates received for each time.
+        let mut pending = TimeStash::new();
+
+        // prepares the updates of complete times, announcing each prepared time.
+        let prepared = self.inner.unary_notify(Pipeline, "SinkPrepare", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                for (datum, time, diff) in data.drain(..) {
+                    pending.push(&capability, &time, (datum, diff), notificator);
+                }
+            });
+
+            for (capability, mut updates) in pending.ready_sorted(notificator) {
+                consolidate_from(&mut updates, 0);
+                let mut sink = sink.borrow_mut();
+                if !updates.is_empty() && !sink.is_committed(capability.time()) {
+                    sink.prepare(capability.time(), &updates[..]).expect("failed to prepare sink updates");
+                    output.session(&capability).give(capability.time().clone());
+                }
+            }
+        });
+
+        // commits prepared times once no worker can still prepare them.
+        prepared.unary_notify(Pipeline, "SinkCommit", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                data.drain(..);
+                notificator.notify_at(capability);
+            });
+
+            let mut ready = Vec::new();
+            notificator.for_each(|capability, _count, _notificator| ready.push(capability));
+            ready.sort_by(|x, y| x.time().cmp(y.time()));
+
+            for capability in ready {
+                sink2.borrow_mut().commit(capability.time()).expect("failed to commit sink updates");
+                output.session(&capability).give(capability.time().clone());
+            }
+        })
+    }
+}
+
+/// A sink writing each time's updates to a file.
+///
+/// Each time is identified by a name, supplied by the caller, which must be distinct for distinct times and
+/// usable in a file name. Prepared updates are written, one `(datum, diff)` per line, to the file
+/// `worker-<worker>-<name>.pending` in `directory`. Committing a time first appends its name to the worker's
+/// log of committed times and syncs the log, and only then renames the file to end in `.data`.
+///
+/// A run interrupted between these steps is repaired when the sink is next opened: pending files whose times
+/// the log records are renamed, and other pending files are removed. Because files are named by their time,
+/// committing a time again replaces its file rather than adding a second copy of its updates.
+pub struct FileSink<T> {
+    directory: PathBuf,
+    worker: usize,
+    name: Box<Fn(&T)->String>,
+    log: File,
+    committed: HashSet<String>,
+    prepared: HashSet<String>,
+}
+
+impl<T> FileSink<T> {
+    /// Opens a sink in `directory` for the worker with index `worker`, naming times with `name`.
+    ///
+    /// Times recorded in the worker's log by an earlier run are reported as committed, and their files are
+    /// completed if the run did not complete them. Files prepared but not committed by an earlier run are removed.
+    pub fn new<P: Into<PathBuf>, F: Fn(&T)->String+'static>(directory: P, worker: usize, name: F) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        // only names followed by a newline were completely recorded.
+        let log_path = directory.join(format!("worker-{}.log", worker));
+        let mut committed = HashSet::new();
+        if log_path.exists() {
+            let mut contents = String::new();
+            File::open(&log_path)?.read_to_string(&mut contents)?;
+            let mut lines: Vec<&str> = contents.split('\n').collect();
+            lines.pop();
+            for line in lines {
+                committed.insert(line.to_owned());
+            }
+        }
+
+        let prefix = format!("worker-{}-", worker);
+        for entry in fs::read_dir(&directory)? {
+            let path = entry?.path();
+            let file = path.file_name().and_then(|x| x.to_str()).unwrap_or("").to_owned();
+            if file.starts_with(&prefix) && file.ends_with(".pending") {
+                let time = &file[prefix.len() .. file.len() - ".pending".len()];
+                if committed.contains(time) { fs::rename(&path, path.with_extension("data"))?; }
+                else { fs::remove_file(&path)?; }
+            }
+        }
+
+        let log = OpenOptions::new().create(true).append(true).open(&log_path)?;
+
+        Ok(FileSink {
+            directory: directory,
+            worker: worker,
+            name: Box::new(name),
+            log: log,
+            committed: committed,
+            prepared: HashSet::new(),
+        })
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(format!("worker-{}-{}.pending", self.worker, name))
+    }
+}
+
+impl<D: Debug, T, R: Debug> TransactionalSink<D, T, R> for FileSink<T> {
+    fn prepare(&mut self, time: &T, updates: &[(D, R)]) -> io::Result<()> {
+        let name = (self.name)(time);
+        let mut file = File::create(self.path(&name))?;
+        for &(ref datum, ref diff) in updates.iter() {
+            writeln!(file, "{:?}\t{:?}", datum, diff)?;
+        }
+        file.sync_all()?;
+        self.prepared.insert(name);
+        Ok(())
+    }
+    fn commit(&mut self, time: &T) -> io::Result<()> {
+        let name = (self.name)(time);
+        if self.prepared.remove(&name) {
+            writeln!(self.log, "{}", name)?;
+            self.log.sync_all()?;
+            let path = self.path(&name);
+            fs::rename(&path, path.with_extension("data"))?;
+            self.committed.insert(name);
+        }
+        Ok(())
+    }
+    fn is_committed(&self, time: &T) -> bool {
+        self.committed.contains(&(self.name)(time))
+    }
+}
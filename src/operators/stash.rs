@@ -0,0 +1,91 @@
+//! Per-time state held by an operator until each time completes.
+//!
+//! Many operators buffer what they receive at each time, request a notification for the time, and act on the
+//! buffered state once the notification arrives: they consolidate the updates, apply them to some state, or
+//! deliver them elsewhere. The `TimeStash` type holds a capability and some state for each time, requests the
+//! notifications, and hands back the state of each time once it completes.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let mut stash = TimeStash::new();
+//! stream.unary_notify(Pipeline, "Example", vec![], move |input, output, notificator| {
+//!     input.for_each(|capability, data| {
+//!         for (datum, time, diff) in data.drain(..) {
+//!             stash.push(&capability, &time, (datum, diff), notificator);
+//!         }
+//!     });
+//!     for (capability, mut updates) in stash.ready_sorted(notificator) {
+//!         consolidate_from(&mut updates, 0);
+//!         // act on the updates of `capability.time()` ...
+//!     }
+//! });
+//! ```
+
+use timely::progress::Timestamp;
+use timely::dataflow::operators::{Capability, Notificator};
+
+/// A capability and some state for each time, released once the time completes.
+pub struct TimeStash<T: Timestamp, S> {
+    pending: Vec<(Capability<T>, S)>,
+}
+
+impl<T: Timestamp, S> TimeStash<T, S> {
+    /// Creates a new stash holding no times.
+    pub fn new() -> Self {
+        TimeStash { pending: Vec::new() }
+    }
+    /// The state of `time`, which is created by `init` if the stash does not yet hold the time.
+    ///
+    /// A new time is held with a capability delayed from `capability`, which must be less or equal to `time`,
+    /// and a notification is requested for it.
+    pub fn entry<I: FnOnce()->S>(&mut self, capability: &Capability<T>, time: &T, notificator: &mut Notificator<T>, init: I) -> &mut S {
+        let index = match self.pending.iter().position(|x| x.0.time() == time) {
+            Some(index) => index,
+            None => {
+                let delayed = capability.delayed(time);
+                notificator.notify_at(delayed.clone());
+                self.pending.push((delayed, init()));
+                self.pending.len() - 1
+            }
+        };
+        &mut self.pending[index].1
+    }
+    /// Removes and returns the state of each time the notificator reports complete, in the order reported.
+    pub fn ready(&mut self, notificator: &mut Notificator<T>) -> Vec<(Capability<T>, S)> {
+        let mut ready = Vec::new();
+        notificator.for_each(|capability, _count, _notificator| ready.push(capability));
+        let mut result = Vec::with_capacity(ready.len());
+        for capability in ready {
+            if let Some(position) = self.pending.iter().position(|x| x.0.time() == capability.time()) {
+                let (_, state) = self.pending.swap_remove(position);
+                result.push((capability, state));
+            }
+        }
+        result
+    }
+    /// Removes and returns the state of each time the notificator reports complete, in order of time.
+    ///
+    /// Operators that apply the changes of each time to retained state use this method, so that times
+    /// completing together are applied in order.
+    pub fn ready_sorted(&mut self, notificator: &mut Notificator<T>) -> Vec<(Capability<T>, S)> where T: Ord {
+        let mut result = self.ready(notificator);
+        result.sort_by(|x, y| x.0.time().cmp(y.0.time()));
+        result
+    }
+    /// The held capabilities and their states, for operators that act before times complete.
+    pub fn iter_mut(&mut self) -> ::std::slice::IterMut<(Capability<T>, S)> {
+        self.pending.iter_mut()
+    }
+    /// The number of times held.
+    pub fn len(&self) -> usize { self.pending.len() }
+    /// Returns true if no times are held.
+    pub fn is_empty(&self) -> bool { self.pending.is_empty() }
+}
+
+impl<T: Timestamp, D> TimeStash<T, Vec<D>> {
+    /// Stashes `datum` at `time`, which must be greater or equal to the time of `capability`.
+    pub fn push(&mut self, capability: &Capability<T>, time: &T, datum: D, notificator: &mut Notificator<T>) {
+        self.entry(capability, time, notificator, Vec::new).push(datum);
+    }
+}
@@ -0,0 +1,99 @@
+//! Limits the rate at which individual keys change.
+//!
+//! Bulk corrections often concentrate many updates on a few keys, and each of these updates must be merged
+//! into the arrangements downstream. The `throttle_per_key` operator passes at most a fixed number of updates
+//! for each key at each time, and defers the remainder to subsequent times. No updates are lost, and so the
+//! throttled collection accumulates to the same contents as its input, only later for hot keys.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // at most 1,000 updates per key per round; the excess moves to the next round.
+//! let smoothed = corrections.throttle_per_key(1000, |round| round + 1);
+//! ```
+
+use std::collections::BTreeMap;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Exchange;
+use timely::order::PartialOrder;
+
+use timely_sort::Unsigned;
+
+use ::{Data, Diff, Collection, AsCollection, Hashable};
+use lattice::Lattice;
+use operators::stash::TimeStash;
+
+/// Extension trait for the `throttle_per_key` method.
+pub trait ThrottlePerKey<G: Scope, K: Data, V: Data, R: Diff> where G::Timestamp: Lattice+Ord {
+    /// Passes at most `max_updates_per_epoch` updates for each key at each time, deferring the rest.
+    ///
+    /// Updates at a time are released once the time completes, in order of time. Excess updates for a key
+    /// move to the time `next` assigns, which must be strictly greater than its argument, where they precede
+    /// updates originally at that time. The times should be totally ordered.
+    fn throttle_per_key<F>(&self, max_updates_per_epoch: usize, next: F) -> Collection<G, (K, V), R>
+    where F: Fn(&G::Timestamp)->G::Timestamp+'static;
+}
+
+impl<G: Scope, K: Data+Hashable, V: Data, R: Diff> ThrottlePerKey<G, K, V, R> for Collection<G, (K, V), R>
+where G::Timestamp: Lattice+Ord {
+    fn throttle_per_key<F>(&self, max_updates_per_epoch: usize, next: F) -> Collection<G, (K, V), R>
+    where F: Fn(&G::Timestamp)->G::Timestamp+'static {
+
+        assert!(max_updates_per_epoch > 0);
+
+        // updates received or deferred for each time.
+        let mut pending = TimeStash::new();
+
+        let exchange = Exchange::new(|x: &((K, V), G::Timestamp, R)| (x.0).0.hashed().as_u64());
+
+        self.inner.unary_notify(exchange, "ThrottlePerKey", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                for ((key, val), time, diff) in data.drain(..) {
+                    pending.push(&capability, &time, (key, val, diff), notificator);
+                }
+            });
+
+            // deferred updates may move to a time completing in this same activation.
+            let mut ready = pending.ready_sorted(notificator);
+            for index in 0 .. ready.len() {
+
+                let updates = ::std::mem::replace(&mut ready[index].1, Vec::new());
+                let time = ready[index].0.time().clone();
+
+                let mut counts = BTreeMap::<K, usize>::new();
+                let mut deferred = Vec::new();
+                {
+                    let mut session = output.session(&ready[index].0);
+                    for (key, val, diff) in updates {
+                        let count = counts.entry(key.clone()).or_insert(0);
+                        if *count < max_updates_per_epoch {
+                            *count += 1;
+                            session.give(((key, val), time.clone(), diff));
+                        }
+                        else {
+                            deferred.push((key, val, diff));
+                        }
+                    }
+                }
+
+                if deferred.len() > 0 {
+                    let later = next(&time);
+                    if !time.less_than(&later) {
+                        panic!("throttle_per_key: next time {:?} not greater than {:?}", later, time);
+                    }
+                    let position = ready[index + 1 ..].iter().position(|x| x.0.time() == &later);
+                    let stashed = match position {
+                        Some(offset) => &mut ready[index + 1 + offset].1,
+                        None => pending.entry(&ready[index].0, &later, notificator, Vec::new),
+                    };
+                    deferred.extend(stashed.drain(..));
+                    *stashed = deferred;
+                }
+            }
+        })
+        .as_collection()
+    }
+}
@@ -0,0 +1,120 @@
+//! Arranges streams of last-write-wins state changes.
+//!
+//! Many external sources, for example database change feeds, describe a table not by differences but by the
+//! new state of each key: a key is set to a value, or deleted. The `arrange_upsert` operator consumes a timely
+//! stream of `(key, Option<value>)` changes and produces the retraction of the prior value and the introduction
+//! of the new value, arranged by key as for `arrange_by_key`. The prior value of each key is read back from the
+//! arrangement itself, so that the operator keeps no state beyond the trace and the changes of incomplete times.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // changes to a table of account balances, where `None` deletes an account.
+//! let balances = changes.arrange_upsert();
+//! balances.as_collection(|k,v| (k.item.clone(), v.clone()))
+//!         .inspect(|x| println!("{:?}", x));
+//! ```
+
+use timely::dataflow::*;
+use timely::dataflow::operators::Unary;
+use timely::dataflow::channels::pact::Exchange;
+
+use timely_sort::Unsigned;
+
+use ::{Data, Hashable};
+use hashable::OrdWrapper;
+use lattice::Lattice;
+use operators::arrange::{Arranged, TraceAgent, BatchWrapper};
+use operators::stash::TimeStash;
+use trace::{Batch, Batcher, Cursor, TraceReader};
+use trace::implementations::ord::OrdValBatch;
+use trace::implementations::ord::OrdValSpine as DefaultValTrace;
+
+/// Extension trait for the `arrange_upsert` method.
+pub trait ArrangeUpsert<G: Scope, K: Data+Default+Hashable, V: Data> where G::Timestamp: Lattice+Ord {
+    /// Arranges a stream of `(key, Option<value>)` state changes by key.
+    ///
+    /// Each change replaces the current value of its key, with `None` removing the key. The changes of each
+    /// time are applied once the time completes, in order of time, and so the times should be totally ordered.
+    /// If a key changes several times at the same time, the last change received wins; as the order in which
+    /// changes are received is not deterministic, each key should change at most once at each time.
+    ///
+    /// The operator maintains the arranged trace itself, and reads the current value of each changed key from
+    /// it, rather than keeping a second copy of the state.
+    fn arrange_upsert(&self) -> Arranged<G, OrdWrapper<K>, V, isize, TraceAgent<OrdWrapper<K>, V, G::Timestamp, isize, DefaultValTrace<OrdWrapper<K>, V, G::Timestamp, isize>>>;
+}
+
+impl<G: Scope, K: Data+Default+Hashable, V: Data> ArrangeUpsert<G, K, V> for Stream<G, (K, Option<V>)>
+where G::Timestamp: Lattice+Ord {
+    fn arrange_upsert(&self) -> Arranged<G, OrdWrapper<K>, V, isize, TraceAgent<OrdWrapper<K>, V, G::Timestamp, isize, DefaultValTrace<OrdWrapper<K>, V, G::Timestamp, isize>>> {
+
+        let (reader, mut writer) = TraceAgent::new(DefaultValTrace::new());
+
+        // our own handle to the trace, from which we read prior values.
+        let mut trace = reader.clone();
+
+        // where we deposit the updates of each completed time, and from which we extract batches.
+        let mut batcher = <OrdValBatch<OrdWrapper<K>, V, G::Timestamp, isize> as Batch<OrdWrapper<K>, V, G::Timestamp, isize>>::Batcher::new();
+
+        // changes received for each time.
+        let mut pending: TimeStash<G::Timestamp, Vec<(K, Option<V>)>> = TimeStash::new();
+
+        let exchange = Exchange::new(|x: &(K, Option<V>)| x.0.hashed().as_u64());
+
+        let stream = self.unary_notify(exchange, "UpsertArrange", vec![], move |input, output, notificator| {
+
+            input.for_each(|capability, data| {
+                pending.entry(&capability, capability.time(), notificator, Vec::new).extend(data.drain(..));
+            });
+
+            let ready = pending.ready_sorted(notificator);
+            let times: Vec<G::Timestamp> = ready.iter().map(|x| x.0.time().clone()).collect();
+
+            for (index, (capability, changes)) in ready.into_iter().enumerate() {
+
+                let time = capability.time().clone();
+
+                // the last change received for each key wins; sorting is stable, so reverse first.
+                let mut changes: Vec<(OrdWrapper<K>, Option<V>)> = changes.into_iter().rev().map(|(k,v)| (OrdWrapper { item: k }, v)).collect();
+                changes.sort_by(|x,y| x.0.cmp(&y.0));
+                changes.dedup_by(|x,y| x.0 == y.0);
+
+                // the trace holds all updates at prior times; read the current value of each key from it.
+                let mut updates = Vec::new();
+                let mut cursor = trace.cursor();
+                for (key, value) in changes {
+                    cursor.seek_key(&key);
+                    if cursor.key_valid() && cursor.key() == &key {
+                        while cursor.val_valid() {
+                            let mut count = 0;
+                            cursor.map_times(|_time, diff| count += diff);
+                            if count != 0 {
+                                updates.push(((key.clone(), cursor.val().clone()), time.clone(), -count));
+                            }
+                            cursor.step_val();
+                        }
+                    }
+                    if let Some(value) = value {
+                        updates.push(((key, value), time.clone(), 1));
+                    }
+                }
+                batcher.push_batch(&mut updates);
+
+                // the batch of this time extends to the next completed time, or to the input frontier.
+                let upper = if index + 1 < times.len() { vec![times[index + 1].clone()] } else { notificator.frontier(0).to_vec() };
+                let batch = batcher.seal(&upper[..]);
+                writer.seal(&upper[..], Some((time, batch.clone())));
+                output.session(&capability).give(BatchWrapper { item: batch });
+            }
+
+            // we only read the trace at completed times, and only their accumulations.
+            trace.advance_by(notificator.frontier(0));
+            trace.distinguish_since(notificator.frontier(0));
+        });
+
+        ::plan::note("UpsertArrange", Some("Trace"), || (vec![::plan::stream_name(self)], ::plan::stream_name(&stream)));
+        ::plan::note_trace(&stream, "OrdValSpine");
+
+        Arranged { stream: stream, trace: reader }
+    }
+}
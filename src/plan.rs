@@ -0,0 +1,146 @@
+//! Records the differential structure of a dataflow, for visualization and comparison.
+//!
+//! Timely dataflow can describe the operators and channels of a dataflow, but not the structure specific to
+//! differential dataflow: which operators maintain arrangements, what types of traces back them, and which
+//! operators share each arrangement. While a dataflow is constructed inside `plan::record`, the differential
+//! operators note themselves in a `Plan`, whose nodes name their input and output streams. An arrangement is
+//! shared exactly when its output stream is the input of several nodes.
+//!
+//! Plans are recorded per worker thread, and streams are named by the address of their scope and the index
+//! and port of the operator producing them, so plans of the same program can be compared across runs. With
+//! the `plan-json` feature, `Plan::to_json` serializes a plan for visualization tools.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let (probe, plan) = plan::record(|| {
+//!     worker.dataflow(|scope| {
+//!         let edges = scope.new_collection_from(edges).1.arrange_by_key_hashed();
+//!         edges.join_arranged(&edges).probe()
+//!     })
+//! });
+//! if worker.index() == 0 {
+//!     println!("{:?}", plan.shared());
+//! }
+//! ```
+
+use std::cell::RefCell;
+
+use timely::dataflow::{Scope, Stream};
+
+thread_local! {
+    static PLAN: RefCell<Option<Plan>> = RefCell::new(None);
+}
+
+/// A differential operator in a recorded plan.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Node {
+    /// The kind of operator, for example `"Arrange"` or `"Join"`.
+    pub operator: &'static str,
+    /// Names of the streams the operator reads.
+    pub inputs: Vec<String>,
+    /// Name of the stream the operator produces.
+    pub output: String,
+    /// The type of trace maintained by the operator, if it maintains one.
+    ///
+    /// Operators record the generic `"Trace"` when they do not know the type of their trace.
+    pub trace: Option<&'static str>,
+    /// A name supplied by the user, stable across edits to the dataflow that change stream names.
+    pub name: Option<String>,
+}
+
+/// The differential operators of a dataflow, in order of construction.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Plan {
+    /// The recorded operators.
+    pub nodes: Vec<Node>,
+}
+
+impl Plan {
+    /// Nodes reading the stream named `stream`.
+    pub fn readers(&self, stream: &str) -> Vec<&Node> {
+        self.nodes.iter().filter(|n| n.inputs.iter().any(|i| i == stream)).collect()
+    }
+    /// The node given the name `name`, if any.
+    pub fn named(&self, name: &str) -> Option<&Node> {
+        self.nodes.iter().find(|n| n.name.as_ref().map(|x| &x[..]) == Some(name))
+    }
+    /// Nodes maintaining a trace read by more than one other node.
+    pub fn shared(&self) -> Vec<&Node> {
+        self.nodes.iter().filter(|n| n.trace.is_some() && self.readers(&n.output).len() > 1).collect()
+    }
+    /// A JSON representation of the plan, as an object with a list of nodes.
+    ///
+    /// Each node lists its operator, input and output streams, trace type and name (or `null`), and the number
+    /// of recorded nodes reading its output. This method requires the `plan-json` feature.
+    #[cfg(feature = "plan-json")]
+    pub fn to_json(&self) -> String {
+        let nodes = self.nodes.iter().map(|node| {
+            let mut object = ::serde_json::Map::new();
+            object.insert("operator".to_owned(), ::serde_json::Value::from(node.operator));
+            object.insert("inputs".to_owned(), ::serde_json::Value::from(node.inputs.clone()));
+            object.insert("output".to_owned(), ::serde_json::Value::from(node.output.clone()));
+            object.insert("trace".to_owned(), node.trace.map(::serde_json::Value::from).unwrap_or(::serde_json::Value::Null));
+            object.insert("name".to_owned(), node.name.clone().map(::serde_json::Value::from).unwrap_or(::serde_json::Value::Null));
+            object.insert("readers".to_owned(), ::serde_json::Value::from(self.readers(&node.output).len()));
+            ::serde_json::Value::Object(object)
+        }).collect::<Vec<_>>();
+        let mut plan = ::serde_json::Map::new();
+        plan.insert("nodes".to_owned(), ::serde_json::Value::Array(nodes));
+        ::serde_json::Value::Object(plan).to_string()
+    }
+}
+
+/// Runs `logic`, recording the differential operators it constructs on this thread.
+///
+/// Calls may be nested; operators are recorded only in the innermost plan.
+pub fn record<F: FnOnce()->X, X>(logic: F) -> (X, Plan) {
+    let prior = PLAN.with(|plan| plan.borrow_mut().take());
+    PLAN.with(|plan| *plan.borrow_mut() = Some(Plan::default()));
+    let result = logic();
+    let recorded = PLAN.with(|plan| plan.borrow_mut().take()).unwrap_or_default();
+    PLAN.with(|plan| *plan.borrow_mut() = prior);
+    (result, recorded)
+}
+
+/// A name for `stream`, unique among the streams of a worker.
+pub fn stream_name<G: Scope, D>(stream: &Stream<G, D>) -> String {
+    format!("{:?}:{}.{}", stream.scope().addr(), stream.name().index, stream.name().port)
+}
+
+/// Notes a differential operator, if a plan is being recorded.
+///
+/// The names of the operator's input and output streams are produced by `streams`, which is only called
+/// while a plan is being recorded.
+pub fn note<F: FnOnce()->(Vec<String>, String)>(operator: &'static str, trace: Option<&'static str>, streams: F) {
+    PLAN.with(|plan| {
+        if let Some(ref mut plan) = *plan.borrow_mut() {
+            let (inputs, output) = streams();
+            plan.nodes.push(Node { operator: operator, inputs: inputs, output: output, trace: trace, name: None });
+        }
+    });
+}
+
+/// Notes the type of trace maintained by the most recent node producing `stream`.
+pub fn note_trace<G: Scope, D>(stream: &Stream<G, D>, trace: &'static str) {
+    PLAN.with(|plan| {
+        if let Some(ref mut plan) = *plan.borrow_mut() {
+            let name = stream_name(stream);
+            if let Some(node) = plan.nodes.iter_mut().rev().find(|n| n.output == name) {
+                node.trace = Some(trace);
+            }
+        }
+    });
+}
+
+/// Notes a user-supplied name for the most recent node producing `stream`.
+pub fn note_name<G: Scope, D>(stream: &Stream<G, D>, name: &str) {
+    PLAN.with(|plan| {
+        if let Some(ref mut plan) = *plan.borrow_mut() {
+            let stream = stream_name(stream);
+            if let Some(node) = plan.nodes.iter_mut().rev().find(|n| n.output == stream) {
+                node.name = Some(name.to_owned());
+            }
+        }
+    });
+}
@@ -0,0 +1,215 @@
+//! Rows of dynamically typed values, for dataflows whose schemas are only known at runtime.
+//!
+//! Differential dataflow operators are generic in the types of their data, and each schema instantiates them
+//! anew. Systems that construct dataflows at runtime, for example from queries, cannot know their schemas when
+//! compiled. A `Row` is a sequence of `Datum` values, encoded into a single byte vector, and is a valid data
+//! type for all operators. Collections of rows can be keyed by some of their columns with `key_by`, after which
+//! they may be arranged, joined, and grouped as any collection of `(key, val)` pairs.
+//!
+//! Rows are ordered by their encodings, which is consistent with equality but not with the order of their
+//! values; in particular, integers do not order numerically. Operators only require a consistent order.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! // (id, name) rows joined with (id, score) rows on their first column.
+//! let names = names.key_by(vec![0], vec![1]);
+//! let scores = scores.key_by(vec![0], vec![1]);
+//! names.join_map(&scores, |id, name, score| Row::pack(id.iter().chain(name.iter()).chain(score.iter())));
+//! ```
+
+use abomonation::Abomonation;
+
+use timely::dataflow::Scope;
+
+use ::{Collection, Diff};
+
+/// A dynamically typed value.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Datum {
+    /// The absence of a value.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A signed integer.
+    Int(i64),
+    /// A string.
+    String(String),
+    /// A sequence of bytes.
+    Bytes(Vec<u8>),
+}
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_INT: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_BYTES: u8 = 5;
+
+/// A sequence of values, encoded as bytes.
+///
+/// Each value is encoded as a tag byte, followed for integers by a variable-length zig-zag encoding, and
+/// for strings and bytes by a variable-length length and the bytes themselves.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Row {
+    bytes: Vec<u8>,
+}
+
+impl Row {
+    /// An empty row.
+    pub fn new() -> Self { Row { bytes: Vec::new() } }
+    /// A row containing the supplied values.
+    pub fn pack<I: IntoIterator<Item=Datum>>(data: I) -> Self {
+        let mut row = Row::new();
+        for datum in data {
+            row.push(&datum);
+        }
+        row
+    }
+    /// Appends a value to the row.
+    pub fn push(&mut self, datum: &Datum) {
+        match *datum {
+            Datum::Null => self.bytes.push(TAG_NULL),
+            Datum::Bool(false) => self.bytes.push(TAG_FALSE),
+            Datum::Bool(true) => self.bytes.push(TAG_TRUE),
+            Datum::Int(int) => {
+                self.bytes.push(TAG_INT);
+                push_varint(&mut self.bytes, ((int << 1) ^ (int >> 63)) as u64);
+            },
+            Datum::String(ref string) => {
+                self.bytes.push(TAG_STRING);
+                push_varint(&mut self.bytes, string.len() as u64);
+                self.bytes.extend_from_slice(string.as_bytes());
+            },
+            Datum::Bytes(ref bytes) => {
+                self.bytes.push(TAG_BYTES);
+                push_varint(&mut self.bytes, bytes.len() as u64);
+                self.bytes.extend_from_slice(bytes);
+            },
+        }
+    }
+    /// The values of the row, in order.
+    pub fn iter(&self) -> RowIter {
+        RowIter { bytes: &self.bytes[..], position: 0 }
+    }
+    /// The values of the row, as a vector.
+    pub fn unpack(&self) -> Vec<Datum> { self.iter().collect() }
+    /// The value at `index`, if the row has that many values.
+    pub fn get(&self, index: usize) -> Option<Datum> { self.iter().nth(index) }
+    /// The number of values in the row.
+    pub fn len(&self) -> usize { self.iter().count() }
+    /// A row containing the values at `columns`, in the order listed.
+    ///
+    /// Values are copied in their encoded form, without decoding them. Panics if the row has too few values.
+    pub fn project(&self, columns: &[usize]) -> Row {
+        let mut bounds = Vec::new();
+        let mut position = 0;
+        while position < self.bytes.len() {
+            let next = skip(&self.bytes[..], position);
+            bounds.push((position, next));
+            position = next;
+        }
+        let mut result = Row::new();
+        for &column in columns {
+            let (lower, upper) = bounds[column];
+            result.bytes.extend_from_slice(&self.bytes[lower .. upper]);
+        }
+        result
+    }
+}
+
+/// An iterator over the values of a row.
+pub struct RowIter<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Iterator for RowIter<'a> {
+    type Item = Datum;
+    fn next(&mut self) -> Option<Datum> {
+        if self.position < self.bytes.len() {
+            let tag = self.bytes[self.position];
+            self.position += 1;
+            Some(match tag {
+                TAG_NULL => Datum::Null,
+                TAG_FALSE => Datum::Bool(false),
+                TAG_TRUE => Datum::Bool(true),
+                TAG_INT => {
+                    let zigzag = read_varint(self.bytes, &mut self.position);
+                    Datum::Int(((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64))
+                },
+                TAG_STRING => {
+                    let bytes = read_slice(self.bytes, &mut self.position);
+                    Datum::String(String::from_utf8(bytes.to_vec()).expect("Row: invalid string encoding"))
+                },
+                TAG_BYTES => Datum::Bytes(read_slice(self.bytes, &mut self.position).to_vec()),
+                _ => panic!("Row: invalid tag {}", tag),
+            })
+        }
+        else {
+            None
+        }
+    }
+}
+
+fn push_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], position: &mut usize) -> u64 {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[*position];
+        *position += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 { return result; }
+        shift += 7;
+    }
+}
+
+fn read_slice<'a>(bytes: &'a [u8], position: &mut usize) -> &'a [u8] {
+    let len = read_varint(bytes, position) as usize;
+    let result = &bytes[*position .. *position + len];
+    *position += len;
+    result
+}
+
+// The position following the value encoded at `position`.
+fn skip(bytes: &[u8], mut position: usize) -> usize {
+    let tag = bytes[position];
+    position += 1;
+    match tag {
+        TAG_INT => { read_varint(bytes, &mut position); },
+        TAG_STRING | TAG_BYTES => { read_slice(bytes, &mut position); },
+        _ => { },
+    }
+    position
+}
+
+impl Abomonation for Row {
+    #[inline] unsafe fn entomb(&self, _writer: &mut Vec<u8>) {
+        self.bytes.entomb(_writer);
+    }
+    #[inline] unsafe fn embalm(&mut self) {
+        self.bytes.embalm();
+    }
+    #[inline] unsafe fn exhume<'a,'b>(&'a mut self, mut bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+        let temp = bytes; 
+        bytes = if let Some(bytes) = self.bytes.exhume(temp) { bytes } else { return None };
+        Some(bytes)
+    }
+}
+
+impl<G: Scope, R: Diff> Collection<G, Row, R> where G::Timestamp: ::timely::Data {
+    /// Keys each row by the values at `key`, with the values at `val` as its value.
+    ///
+    /// The result may be arranged, joined, and grouped by key. Panics if a row has too few values.
+    pub fn key_by(&self, key: Vec<usize>, val: Vec<usize>) -> Collection<G, (Row, Row), R> {
+        self.map(move |row| (row.project(&key[..]), row.project(&val[..])))
+    }
+}
@@ -0,0 +1,54 @@
+//! Orderly completion of differential dataflow computations.
+//!
+//! A computation completes once all of its inputs are closed and the consequences of their updates have
+//! been computed. At that point each `arrange` operator has sealed its trace through the empty frontier, and
+//! listeners (for example, imports into other dataflows) have been told that no further batches will arrive.
+//! Stopping a worker earlier, for example by returning from the worker closure while inputs remain open,
+//! leaves updates in flight and traces sealed only through some earlier frontier.
+//!
+//! The `shutdown` function closes a set of timely dataflow input handles and steps the worker until all of
+//! its dataflows have completed. Any `InputSession`s borrowing the handles must be dropped first, which
+//! flushes their buffered updates.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let (mut edges, mut roots, probe) = worker.dataflow(|scope| { ... });
+//! {
+//!     let mut session = InputSession::from(&mut edges);
+//!     // introduce updates ...
+//! }
+//! shutdown::shutdown(worker, vec![Box::new(edges) as Box<Close>, Box::new(roots)]);
+//! ```
+
+use timely::dataflow::scopes::Root;
+use timely::dataflow::operators::input::Handle;
+use timely::progress::Timestamp;
+use timely_communication::Allocate;
+
+/// An input that can be closed, indicating that it will produce no further data.
+pub trait Close {
+    /// Closes the input.
+    fn close_boxed(self: Box<Self>);
+}
+
+impl<T: Timestamp, D: ::timely::Data> Close for Handle<T, D> {
+    fn close_boxed(self: Box<Self>) {
+        (*self).close();
+    }
+}
+
+/// Closes `inputs`, and steps `worker` until all of its dataflows have completed.
+///
+/// Dataflows with other open inputs do not complete, and so all inputs of the worker should be supplied.
+/// Returns the number of steps taken.
+pub fn shutdown<A: Allocate, I: IntoIterator<Item=Box<Close>>>(worker: &mut Root<A>, inputs: I) -> usize {
+    for input in inputs {
+        input.close_boxed();
+    }
+    let mut steps = 0;
+    while worker.step() {
+        steps += 1;
+    }
+    steps
+}
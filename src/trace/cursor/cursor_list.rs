@@ -20,6 +20,19 @@ pub struct CursorList<K, V, T, R, C: Cursor<K, V, T, R>> {
 	valid_vals: usize,	// cursors[..valid_vals] all have valid_val() true.
 }
 
+impl<K, V, T, R, C: Cursor<K, V, T, R>> Clone for CursorList<K, V, T, R, C> {
+	fn clone(&self) -> Self {
+		CursorList {
+			_phantom: ::std::marker::PhantomData,
+			cursors: self.cursors.clone(),
+			equiv_keys: self.equiv_keys,
+			equiv_vals: self.equiv_vals,
+			valid_keys: self.valid_keys,
+			valid_vals: self.valid_vals,
+		}
+	}
+}
+
 impl<K, V, T, R, C: Cursor<K, V, T, R>> CursorList<K, V, T, R, C> where K: Ord, V: Ord {
 	/// Creates a new cursor list from pre-existing cursors.
 	pub fn new(cursors: Vec<C>) -> Self {
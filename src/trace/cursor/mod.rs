@@ -10,7 +10,10 @@ pub mod cursor_list;
 // pub mod cursor_pair;
 
 /// A cursor for navigating ordered `(key, val, time, diff)` updates.
-pub trait Cursor<K, V, T, R> {
+///
+/// Cursors are cheaply cloned, and a clone records the position of the cursor, so that operators which
+/// backtrack, for example to intersect several cursors, can return to a saved position.
+pub trait Cursor<K, V, T, R> : Clone {
 	
 	/// Indicates if the current key is valid.
 	///
@@ -83,6 +83,12 @@ impl<Time: Clone> Description<Time> {
 			since: since.to_vec(),
 		}
 	}
+	/// Replaces the frontier from which the interval may be observed.
+	///
+	/// This is appropriate when the times of the updates are unchanged by advancing them by `since`.
+	pub fn set_since(&mut self, since: &[Time]) {
+		self.since = since.to_vec();
+	}
 }
 
 impl<Time> Description<Time> {
@@ -92,4 +98,23 @@ impl<Time> Description<Time> {
 	pub fn upper(&self) -> &[Time] { &self.upper[..] }
 	/// Times from whose future the interval may be observed.
 	pub fn since(&self) -> &[Time] { &self.since[..] }
-}
\ No newline at end of file
+}
+
+impl<Time: ::abomonation::Abomonation> ::abomonation::Abomonation for Description<Time> {
+	#[inline] unsafe fn entomb(&self, writer: &mut Vec<u8>) {
+		self.lower.entomb(writer);
+		self.upper.entomb(writer);
+		self.since.entomb(writer);
+	}
+	#[inline] unsafe fn embalm(&mut self) {
+		self.lower.embalm();
+		self.upper.embalm();
+		self.since.embalm();
+	}
+	#[inline] unsafe fn exhume<'a,'b>(&'a mut self, mut bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+		let temp = bytes; bytes = if let Some(bytes) = self.lower.exhume(temp) { bytes } else { return None };
+		let temp = bytes; bytes = if let Some(bytes) = self.upper.exhume(temp) { bytes } else { return None };
+		let temp = bytes; bytes = if let Some(bytes) = self.since.exhume(temp) { bytes } else { return None };
+		Some(bytes)
+	}
+}
@@ -0,0 +1,256 @@
+//! Approximate membership filters over the keys of a batch.
+//!
+//! A `KeyFilter` is a Bloom filter over hashes of keys. It may report that a key is present when it is not,
+//! at a rate determined by the number of bits per key, but never reports a present key as absent. Traces
+//! use filters to avoid searching batches for keys they do not contain, which matters for lookups of many
+//! missing keys in a trace with many batches.
+//!
+//! A `FilteredCursorList` merges the cursors of several batches, as a `CursorList` does, but defers searching
+//! a batch for a sought key its filter excludes. The batch cannot contain the key, and so need only be searched
+//! once the merged cursor moves past the key. Operators seeking keys in a trace, for example `join` and `group`,
+//! then search only those batches that may contain each key they find. A key absent from every batch still
+//! searches every batch, as the cursor must then present the key following it.
+
+use std::rc::Rc;
+
+use trace::cursor::Cursor;
+
+/// A Bloom filter over 64 bit key hashes.
+#[derive(Clone, Debug)]
+pub struct KeyFilter {
+	bits: Vec<u64>,
+	hashes: u64,
+}
+
+impl KeyFilter {
+	/// Allocates an empty filter for `keys` keys, using `bits_per_key` bits for each.
+	///
+	/// Ten bits per key give a false positive rate of about one percent.
+	pub fn new(keys: usize, bits_per_key: usize) -> Self {
+		let words = ::std::cmp::max(1, (keys * bits_per_key + 63) / 64);
+		// the optimal number of hash functions is `ln 2` times the bits per key.
+		let hashes = ::std::cmp::max(1, (bits_per_key as u64 * 69) / 100);
+		KeyFilter { bits: vec![0; words], hashes: hashes }
+	}
+	/// Builds a filter holding the key hashes `hashes`, using `bits_per_key` bits for each.
+	pub fn from_hashes(hashes: &[u64], bits_per_key: usize) -> Self {
+		let mut filter = KeyFilter::new(hashes.len(), bits_per_key);
+		for &hash in hashes.iter() {
+			filter.insert(hash);
+		}
+		filter
+	}
+	/// Records the presence of a key with hash `hash`.
+	pub fn insert(&mut self, hash: u64) {
+		let len = (self.bits.len() * 64) as u64;
+		let (h1, h2) = Self::split(hash);
+		for index in 0 .. self.hashes {
+			let bit = h1.wrapping_add(index.wrapping_mul(h2)) % len;
+			self.bits[(bit / 64) as usize] |= 1 << (bit % 64);
+		}
+	}
+	/// Indicates whether a key with hash `hash` may be present.
+	pub fn may_contain(&self, hash: u64) -> bool {
+		let len = (self.bits.len() * 64) as u64;
+		let (h1, h2) = Self::split(hash);
+		(0 .. self.hashes).all(|index| {
+			let bit = h1.wrapping_add(index.wrapping_mul(h2)) % len;
+			self.bits[(bit / 64) as usize] & (1 << (bit % 64)) != 0
+		})
+	}
+	// derives two hashes from one, for double hashing.
+	fn split(hash: u64) -> (u64, u64) {
+		let h2 = hash.rotate_left(32) ^ 0x9e3779b97f4a7c15;
+		(hash, h2 | 1)
+	}
+}
+
+/// A cursor merging the cursors of several batches, which consults each batch's key filter before seeking keys.
+///
+/// A cursor whose filter excludes a sought key is not searched, but records the key as deferred: its next key
+/// is greater than the deferred key, and it is searched only once the merged cursor would present a key
+/// greater than the deferred key.
+pub struct FilteredCursorList<K, V, T, R, C: Cursor<K, V, T, R>> {
+	phantom: ::std::marker::PhantomData<(V, T, R)>,
+	cursors: Vec<C>,
+	filters: Vec<Rc<KeyFilter>>,
+	hasher: fn(&K)->u64,
+	deferred: Vec<Option<K>>,	// sought keys not yet sought in the corresponding cursors.
+	keys: Vec<usize>,			// cursors presenting the least key, none of them deferred.
+	vals: Vec<usize>,			// cursors presenting the least key and the least value.
+}
+
+impl<K: Ord+Clone, V: Ord, T, R, C: Cursor<K, V, T, R>> FilteredCursorList<K, V, T, R, C> {
+	/// Merges `cursors`, each with the filter over its keys, and the function hashing keys for the filters.
+	pub fn new(cursors: Vec<C>, filters: Vec<Rc<KeyFilter>>, hasher: fn(&K)->u64) -> Self {
+		assert_eq!(cursors.len(), filters.len());
+		let deferred = cursors.iter().map(|_| None).collect();
+		let mut result = FilteredCursorList {
+			phantom: ::std::marker::PhantomData,
+			cursors: cursors,
+			filters: filters,
+			hasher: hasher,
+			deferred: deferred,
+			keys: Vec::new(),
+			vals: Vec::new(),
+		};
+		result.tidy_keys();
+		result
+	}
+
+	// Finds the cursors presenting the least key, first searching any deferred cursor that may present a lesser key.
+	fn tidy_keys(&mut self) {
+		loop {
+			// the cursor with the least key, among those not deferred.
+			let mut least: Option<usize> = None;
+			for index in 0 .. self.cursors.len() {
+				if self.deferred[index].is_none() && self.cursors[index].key_valid() {
+					if least.map(|least| self.cursors[index].key() < self.cursors[least].key()).unwrap_or(true) {
+						least = Some(index);
+					}
+				}
+			}
+
+			// a deferred cursor is known only to present a key greater than its deferred key.
+			let mut searched = false;
+			for index in 0 .. self.cursors.len() {
+				let behind = match self.deferred[index] {
+					Some(ref key) => least.map(|least| key < self.cursors[least].key()).unwrap_or(true),
+					None => false,
+				};
+				if behind {
+					let key = self.deferred[index].take().unwrap();
+					self.cursors[index].seek_key(&key);
+					searched = true;
+				}
+			}
+
+			if !searched {
+				self.keys.clear();
+				if let Some(least) = least {
+					for index in 0 .. self.cursors.len() {
+						if self.deferred[index].is_none() && self.cursors[index].key_valid() && self.cursors[index].key() == self.cursors[least].key() {
+							self.keys.push(index);
+						}
+					}
+				}
+				self.tidy_vals();
+				return;
+			}
+		}
+	}
+
+	// Finds the cursors presenting the least value, among those presenting the least key.
+	fn tidy_vals(&mut self) {
+		let mut least: Option<usize> = None;
+		for &index in self.keys.iter() {
+			if self.cursors[index].val_valid() {
+				if least.map(|least| self.cursors[index].val() < self.cursors[least].val()).unwrap_or(true) {
+					least = Some(index);
+				}
+			}
+		}
+		self.vals.clear();
+		if let Some(least) = least {
+			for &index in self.keys.iter() {
+				if self.cursors[index].val_valid() && self.cursors[index].val() == self.cursors[least].val() {
+					self.vals.push(index);
+				}
+			}
+		}
+	}
+}
+
+impl<K: Clone, V, T, R, C: Cursor<K, V, T, R>> Clone for FilteredCursorList<K, V, T, R, C> {
+	fn clone(&self) -> Self {
+		FilteredCursorList {
+			phantom: ::std::marker::PhantomData,
+			cursors: self.cursors.clone(),
+			filters: self.filters.clone(),
+			hasher: self.hasher,
+			deferred: self.deferred.clone(),
+			keys: self.keys.clone(),
+			vals: self.vals.clone(),
+		}
+	}
+}
+
+impl<K: ::std::fmt::Debug, V, T, R, C: Cursor<K, V, T, R>+::std::fmt::Debug> ::std::fmt::Debug for FilteredCursorList<K, V, T, R, C> {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "FilteredCursorList {{ cursors: {:?}, deferred: {:?} }}", self.cursors, self.deferred)
+	}
+}
+
+impl<K, V, T, R, C> Cursor<K, V, T, R> for FilteredCursorList<K, V, T, R, C> where K: Ord+Clone, V: Ord, C: Cursor<K, V, T, R> {
+
+	fn key_valid(&self) -> bool { self.keys.len() > 0 }
+	fn val_valid(&self) -> bool { self.vals.len() > 0 }
+
+	fn key(&self) -> &K {
+		debug_assert!(self.key_valid());
+		self.cursors[self.keys[0]].key()
+	}
+	fn val(&self) -> &V {
+		debug_assert!(self.val_valid());
+		self.cursors[self.vals[0]].val()
+	}
+	fn map_times<L: FnMut(&T, R)>(&mut self, mut logic: L) {
+		for &index in self.vals.iter() {
+			self.cursors[index].map_times(|t,d| logic(t,d));
+		}
+	}
+
+	fn step_key(&mut self) {
+		for &index in self.keys.iter() {
+			self.cursors[index].step_key();
+		}
+		self.tidy_keys();
+	}
+	fn seek_key(&mut self, key: &K) {
+		let hash = (self.hasher)(key);
+		for index in 0 .. self.cursors.len() {
+			// a cursor deferred at `key` or beyond, or already at or past it, has nothing to seek.
+			let ahead = match self.deferred[index] {
+				Some(ref deferred) => key <= deferred,
+				None => !self.cursors[index].key_valid() || key <= self.cursors[index].key(),
+			};
+			if !ahead {
+				if self.filters[index].may_contain(hash) {
+					self.deferred[index] = None;
+					self.cursors[index].seek_key(key);
+				}
+				else {
+					self.deferred[index] = Some(key.clone());
+				}
+			}
+		}
+		self.tidy_keys();
+	}
+
+	fn step_val(&mut self) {
+		for &index in self.vals.iter() {
+			self.cursors[index].step_val();
+		}
+		self.tidy_vals();
+	}
+	fn seek_val(&mut self, val: &V) {
+		for &index in self.keys.iter() {
+			self.cursors[index].seek_val(val);
+		}
+		self.tidy_vals();
+	}
+
+	fn rewind_keys(&mut self) {
+		for index in 0 .. self.cursors.len() {
+			self.deferred[index] = None;
+			self.cursors[index].rewind_keys();
+		}
+		self.tidy_keys();
+	}
+	fn rewind_vals(&mut self) {
+		for &index in self.keys.iter() {
+			self.cursors[index].rewind_vals();
+		}
+		self.tidy_vals();
+	}
+}
@@ -86,7 +86,7 @@ impl<K: HashOrdered, V: Ord, T: Lattice+Ord+Clone, R> Clone for HashValBatch<K,
 }
 
 /// A cursor for navigating a single layer.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HashValCursor<K: Clone+HashOrdered, V: Ord+Clone, T: Lattice+Ord+Clone, R: Copy> {
 	cursor: HashedCursor<K, OrderedCursor<V, UnorderedCursor<(T, R)>>>,
 }
@@ -201,7 +201,7 @@ impl<K: HashOrdered, T: Lattice+Ord+Clone, R> Clone for HashKeyBatch<K, T, R> {
 }
 
 /// A cursor for navigating a single layer.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HashKeyCursor<K: Clone+HashOrdered, T: Lattice+Ord+Clone, R: Copy> {
 	valid: bool,
 	empty: (),
@@ -46,6 +46,7 @@ mod batcher_merge;
 pub use self::batcher::RadixBatcher as Batcher;
 
 pub mod ord;
+pub mod ord_dict;
 pub mod hash;
 
 // pub mod rhh;
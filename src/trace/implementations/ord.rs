@@ -10,6 +10,8 @@
 
 use std::rc::Rc;
 
+use timely::progress::frontier::Antichain;
+
 use ::Diff;
 use hashable::Hashable;
 
@@ -20,7 +22,7 @@ use trace::layers::ordered::{OrderedLayer, OrderedBuilder, OrderedCursor};
 use trace::layers::unordered::{UnorderedLayer, UnorderedBuilder, UnorderedCursor};
 
 use lattice::Lattice;
-use trace::{Batch, BatchReader, Builder, Cursor};
+use trace::{Batch, BatchReader, BatchStats, Builder, Cursor};
 use trace::description::Description;
 
 use super::spine::Spine;
@@ -39,6 +41,11 @@ pub struct OrdValBatch<K: Ord+Hashable, V: Ord, T: Lattice, R> {
 	pub layer: Rc<OrderedLayer<K, OrderedLayer<V, UnorderedLayer<(T, R)>>>>,
 	/// Description of the update times this layer represents.
 	pub desc: Description<T>,
+	/// The lower envelope of the update times present in the layer, once computed.
+	///
+	/// The envelope is computed by `advance_mut`, or by merging the envelopes of merged batches, rather than
+	/// as batches are built, as it is only consulted when a batch is advanced.
+	pub times: Option<Vec<T>>,
 }
 
 impl<K, V, T, R> BatchReader<K, V, T, R> for OrdValBatch<K, V, T, R> 
@@ -49,6 +56,15 @@ where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
 	}
 	fn len(&self) -> usize { self.layer.tuples() }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn times(&self) -> Option<&[T]> { self.times.as_ref().map(|times| &times[..]) }
+	fn stats(&self) -> BatchStats<K> {
+		BatchStats {
+			keys: self.layer.keys(),
+			vals: self.layer.vals.keys(),
+			updates: self.layer.tuples(),
+			bounds: self.layer.keys.first().map(|min| (min.clone(), self.layer.keys[self.layer.keys.len() - 1].clone())),
+		}
+	}
 }
 
 impl<K, V, T, R> Batch<K, V, T, R> for OrdValBatch<K, V, T, R> 
@@ -71,7 +87,20 @@ where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
 		OrdValBatch {
 			layer: Rc::new(self.layer.merge(&other.layer)),
 			desc: Description::new(self.desc.lower(), other.desc.upper(), since),
+			times: merge_times(&self.times, &other.times),
+		}
+	}
+	fn advance_mut(&mut self, frontier: &[T]) {
+		if self.times.is_none() {
+			self.times = Some(envelope(self.layer.vals.vals.vals.iter().map(|&(ref time, _)| time)));
 		}
+		if !(self.is_advanced_by(frontier) && self.advance_since(frontier)) {
+			*self = self.advance_ref(frontier);
+		}
+	}
+	fn advance_since(&mut self, frontier: &[T]) -> bool {
+		self.desc.set_since(frontier);
+		true
 	}
 }
 
@@ -80,12 +109,13 @@ impl<K: Ord+Hashable, V: Ord, T: Lattice+Ord+Clone, R> Clone for OrdValBatch<K,
 		OrdValBatch {
 			layer: self.layer.clone(),
 			desc: self.desc.clone(),
+			times: self.times.clone(),
 		}
 	}
 }
 
 /// A cursor for navigating a single layer.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OrdValCursor<K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Copy> {
 	cursor: OrderedCursor<K, OrderedCursor<V, UnorderedCursor<(T, R)>>>,
 }
@@ -140,7 +170,8 @@ where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
 	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> OrdValBatch<K, V, T, R> {
 		OrdValBatch {
 			layer: Rc::new(self.builder.done()),
-			desc: Description::new(lower, upper, since)
+			desc: Description::new(lower, upper, since),
+			times: None,
 		}
 	}
 }
@@ -155,6 +186,11 @@ pub struct OrdKeyBatch<K: Ord+Hashable, T: Lattice, R> {
 	pub layer: Rc<OrderedLayer<K, UnorderedLayer<(T, R)>>>,
 	/// Description of the update times this layer represents.
 	pub desc: Description<T>,
+	/// The lower envelope of the update times present in the layer, once computed.
+	///
+	/// The envelope is computed by `advance_mut`, or by merging the envelopes of merged batches, rather than
+	/// as batches are built, as it is only consulted when a batch is advanced.
+	pub times: Option<Vec<T>>,
 }
 
 impl<K, T, R> BatchReader<K, (), T, R> for OrdKeyBatch<K, T, R> 
@@ -165,6 +201,15 @@ where K: Ord+Clone+Hashable, T: Lattice+Ord+Clone, R: Diff {
 	}
 	fn len(&self) -> usize { self.layer.tuples() }
 	fn description(&self) -> &Description<T> { &self.desc }
+	fn times(&self) -> Option<&[T]> { self.times.as_ref().map(|times| &times[..]) }
+	fn stats(&self) -> BatchStats<K> {
+		BatchStats {
+			keys: self.layer.keys(),
+			vals: self.layer.keys(),
+			updates: self.layer.tuples(),
+			bounds: self.layer.keys.first().map(|min| (min.clone(), self.layer.keys[self.layer.keys.len() - 1].clone())),
+		}
+	}
 }
 
 impl<K, T, R> Batch<K, (), T, R> for OrdKeyBatch<K, T, R> 
@@ -187,8 +232,21 @@ where K: Ord+Clone+Hashable, T: Lattice+Ord+Clone, R: Diff {
 		OrdKeyBatch {
 			layer: Rc::new(self.layer.merge(&other.layer)),
 			desc: Description::new(self.desc.lower(), other.desc.upper(), since),
+			times: merge_times(&self.times, &other.times),
 		}
 	}
+	fn advance_mut(&mut self, frontier: &[T]) {
+		if self.times.is_none() {
+			self.times = Some(envelope(self.layer.vals.vals.iter().map(|&(ref time, _)| time)));
+		}
+		if !(self.is_advanced_by(frontier) && self.advance_since(frontier)) {
+			*self = self.advance_ref(frontier);
+		}
+	}
+	fn advance_since(&mut self, frontier: &[T]) -> bool {
+		self.desc.set_since(frontier);
+		true
+	}
 }
 
 impl<K: Ord+Hashable, T: Lattice+Ord+Clone, R> Clone for OrdKeyBatch<K, T, R> {
@@ -196,12 +254,13 @@ impl<K: Ord+Hashable, T: Lattice+Ord+Clone, R> Clone for OrdKeyBatch<K, T, R> {
 		OrdKeyBatch {
 			layer: self.layer.clone(),
 			desc: self.desc.clone(),
+			times: self.times.clone(),
 		}
 	}
 }
 
 /// A cursor for navigating a single layer.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OrdKeyCursor<K: Ord+Clone+Hashable, T: Lattice+Ord+Clone, R: Copy> {
 	valid: bool,
 	empty: (),
@@ -258,8 +317,25 @@ where K: Ord+Clone+Hashable, T: Lattice+Ord+Clone, R: Diff {
 	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> OrdKeyBatch<K, T, R> {
 		OrdKeyBatch {
 			layer: Rc::new(self.builder.done()),
-			desc: Description::new(lower, upper, since)
+			desc: Description::new(lower, upper, since),
+			times: None,
 		}
 	}
 }
 
+/// The lower envelope of the times of two batches, if both are known.
+fn merge_times<T: Lattice+Clone>(times1: &Option<Vec<T>>, times2: &Option<Vec<T>>) -> Option<Vec<T>> {
+	match (times1, times2) {
+		(&Some(ref times1), &Some(ref times2)) => Some(envelope(times1.iter().chain(times2.iter()))),
+		_ => None,
+	}
+}
+
+/// The lower envelope of a sequence of times.
+fn envelope<'a, T: Lattice+Clone+'a, I: Iterator<Item=&'a T>>(times: I) -> Vec<T> {
+	let mut envelope = Antichain::new();
+	for time in times {
+		envelope.insert(time.clone());
+	}
+	envelope.elements().to_vec()
+}
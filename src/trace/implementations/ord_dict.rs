@@ -0,0 +1,201 @@
+//! Trace and batch implementations based on sorted ranges, with times stored once per batch.
+//!
+//! The types in this module are like the `OrdVal` types, except that each batch records its distinct times
+//! in a dictionary, and each update refers to its time by a 32 bit index into the dictionary. In iterative
+//! scopes, where times are products of outer times and iteration counters, batches contain few distinct
+//! times relative to their numbers of updates, and the dictionary substantially reduces their size.
+//!
+//! Batches are merged as `OrdVal` batches are, after re-indexing the times of the second batch into the
+//! dictionary of the first, which copies the time indices (but not the keys or values) of the second batch.
+
+use std::rc::Rc;
+use std::collections::BTreeMap;
+
+use ::Diff;
+use hashable::Hashable;
+
+use trace::layers::{Trie, TupleBuilder};
+use trace::layers::Builder as TrieBuilder;
+use trace::layers::Cursor as TrieCursor;
+use trace::layers::ordered::{OrderedLayer, OrderedBuilder, OrderedCursor};
+use trace::layers::unordered::{UnorderedLayer, UnorderedBuilder, UnorderedCursor};
+
+use lattice::Lattice;
+use trace::{Batch, BatchReader, Builder, Cursor};
+use trace::description::Description;
+
+use super::spine::Spine;
+use super::batcher::RadixBatcher;
+
+/// A trace implementation using a spine of batches with time dictionaries.
+pub type OrdValDictSpine<K, V, T, R> = Spine<K, V, T, R, OrdValDictBatch<K, V, T, R>>;
+
+
+/// An immutable collection of update tuples, from a contiguous interval of logical times.
+#[derive(Debug)]
+pub struct OrdValDictBatch<K: Ord+Hashable, V: Ord, T: Lattice, R> {
+	/// Where all the dataz is, with times replaced by indices into `times`.
+	pub layer: Rc<OrderedLayer<K, OrderedLayer<V, UnorderedLayer<(u32, R)>>>>,
+	/// The distinct times of the batch.
+	pub times: Rc<Vec<T>>,
+	/// Description of the update times this layer represents.
+	pub desc: Description<T>,
+}
+
+impl<K, V, T, R> BatchReader<K, V, T, R> for OrdValDictBatch<K, V, T, R> 
+where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+	type Cursor = OrdValDictCursor<K, V, T, R>;
+	fn cursor(&self) -> Self::Cursor { 
+		OrdValDictCursor { cursor: self.layer.cursor(), times: self.times.clone() } 
+	}
+	fn len(&self) -> usize { self.layer.tuples() }
+	fn description(&self) -> &Description<T> { &self.desc }
+}
+
+impl<K, V, T, R> Batch<K, V, T, R> for OrdValDictBatch<K, V, T, R> 
+where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+	type Batcher = RadixBatcher<K, V, T, R, Self>;
+	type Builder = OrdValDictBuilder<K, V, T, R>;
+	fn merge(&self, other: &Self) -> Self {
+
+		// Things are horribly wrong if this is not true.
+		assert!(self.desc.upper() == other.desc.lower());
+
+		// one of self.desc.since or other.desc.since needs to be not behind the other...
+		let since = if self.desc.since().iter().all(|t1| other.desc.since().iter().any(|t2| t2.less_equal(t1))) {
+			other.desc.since()
+		}
+		else {
+			self.desc.since()
+		};
+
+		// the merged dictionary extends that of `self` with the times of `other` it lacks.
+		let mut times = (*self.times).clone();
+		let mut indices = BTreeMap::new();
+		for (index, time) in times.iter().enumerate() {
+			indices.insert(time.clone(), index as u32);
+		}
+		let mut remap = Vec::with_capacity(other.times.len());
+		for time in other.times.iter() {
+			let index = match indices.get(time) {
+				Some(&index) => index,
+				None => {
+					assert!(times.len() < u32::max_value() as usize);
+					times.push(time.clone());
+					times.len() as u32 - 1
+				}
+			};
+			indices.insert(time.clone(), index);
+			remap.push(index);
+		}
+
+		// `other` with its time indices re-indexed, sharing its keys, values, and offsets.
+		let remapped = OrderedLayer {
+			keys: other.layer.keys.clone(),
+			offs: other.layer.offs.clone(),
+			vals: OrderedLayer {
+				keys: other.layer.vals.keys.clone(),
+				offs: other.layer.vals.offs.clone(),
+				vals: UnorderedLayer {
+					vals: Rc::new(other.layer.vals.vals.vals.iter().map(|&(index, diff)| (remap[index as usize], diff)).collect()),
+				},
+			},
+		};
+
+		OrdValDictBatch {
+			layer: Rc::new(self.layer.merge(&remapped)),
+			times: Rc::new(times),
+			desc: Description::new(self.desc.lower(), other.desc.upper(), since),
+		}
+	}
+}
+
+impl<K: Ord+Hashable, V: Ord, T: Lattice+Ord+Clone, R> Clone for OrdValDictBatch<K, V, T, R> {
+	fn clone(&self) -> Self {
+		OrdValDictBatch {
+			layer: self.layer.clone(),
+			times: self.times.clone(),
+			desc: self.desc.clone(),
+		}
+	}
+}
+
+/// A cursor for navigating a single layer.
+#[derive(Debug, Clone)]
+pub struct OrdValDictCursor<K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Copy> {
+	cursor: OrderedCursor<K, OrderedCursor<V, UnorderedCursor<(u32, R)>>>,
+	times: Rc<Vec<T>>,
+}
+
+impl<K, V, T, R> Cursor<K, V, T, R> for OrdValDictCursor<K, V, T, R> 
+where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Copy {
+	fn key(&self) -> &K { &self.cursor.key() }
+	fn val(&self) -> &V { &self.cursor.child.key() }
+	fn map_times<L: FnMut(&T, R)>(&mut self, mut logic: L) {
+		self.cursor.child.child.rewind();
+		while self.cursor.child.child.valid() {
+			let (index, diff) = *self.cursor.child.child.key();
+			logic(&self.times[index as usize], diff);
+			self.cursor.child.child.step();
+		}
+	}
+	fn key_valid(&self) -> bool { self.cursor.valid() }
+	fn val_valid(&self) -> bool { self.cursor.child.valid() }
+	fn step_key(&mut self){ self.cursor.step(); }
+	fn seek_key(&mut self, key: &K) { self.cursor.seek(key); }
+	fn step_val(&mut self) { self.cursor.child.step(); }
+	fn seek_val(&mut self, val: &V) { self.cursor.child.seek(val); }
+	fn rewind_keys(&mut self) { self.cursor.rewind(); }
+	fn rewind_vals(&mut self) { self.cursor.child.rewind(); }
+}
+
+
+/// A builder for creating layers from unsorted update tuples.
+pub struct OrdValDictBuilder<K: Ord+Hashable, V: Ord, T: Ord, R: Diff> {
+	builder: OrderedBuilder<K, OrderedBuilder<V, UnorderedBuilder<(u32, R)>>>,
+	times: Vec<T>,
+	indices: BTreeMap<T, u32>,
+}
+
+impl<K, V, T, R> Builder<K, V, T, R, OrdValDictBatch<K, V, T, R>> for OrdValDictBuilder<K, V, T, R> 
+where K: Ord+Clone+Hashable, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+
+	fn new() -> Self { 
+		OrdValDictBuilder { 
+			builder: OrderedBuilder::<K, OrderedBuilder<V, UnorderedBuilder<(u32, R)>>>::new(),
+			times: Vec::new(),
+			indices: BTreeMap::new(),
+		} 
+	}
+	fn with_capacity(cap: usize) -> Self { 
+		OrdValDictBuilder { 
+			builder: OrderedBuilder::<K, OrderedBuilder<V, UnorderedBuilder<(u32, R)>>>::with_capacity(cap),
+			times: Vec::new(),
+			indices: BTreeMap::new(),
+		} 
+	}
+
+	#[inline(always)]
+	fn push(&mut self, (key, val, time, diff): (K, V, T, R)) {
+		let index = match self.indices.get(&time) {
+			Some(&index) => index,
+			None => {
+				assert!(self.times.len() < u32::max_value() as usize);
+				let index = self.times.len() as u32;
+				self.times.push(time.clone());
+				self.indices.insert(time, index);
+				index
+			}
+		};
+		self.builder.push_tuple((key, (val, (index, diff))));
+	}
+
+	#[inline(never)]
+	fn done(self, lower: &[T], upper: &[T], since: &[T]) -> OrdValDictBatch<K, V, T, R> {
+		OrdValDictBatch {
+			layer: Rc::new(self.builder.done()),
+			times: Rc::new(self.times),
+			desc: Description::new(lower, upper, since)
+		}
+	}
+}
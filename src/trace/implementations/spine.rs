@@ -3,11 +3,21 @@
 //! The `Spine` is a general-purpose trace implementation based on collection and merging 
 //! immutable batches of updates. It is generic with respect to the batch type, and can be 
 //! instantiated for any implementor of `trace::Batch`.
+//!
+//! The spine merges its batches according to a `MergePolicy`, which trades the work of merging updates
+//! against the number of batches a cursor must consult.
+
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use timely_sort::Unsigned;
 
 use ::Diff;
+use hashable::Hashable;
 use lattice::Lattice;
-use trace::{Batch, BatchReader, Trace, TraceReader};
+use trace::{Batch, BatchReader, Cursor, Trace, TraceReader};
 use trace::cursor::cursor_list::CursorList;
+use trace::filter::{KeyFilter, FilteredCursorList};
 
 /// An append-only collection of update tuples.
 ///
@@ -17,6 +27,11 @@ use trace::cursor::cursor_list::CursorList;
 #[derive(Debug)]
 pub struct Spine<K, V, T: Lattice+Ord, R: Diff, B: Batch<K, V, T, R>> {
 	phantom: ::std::marker::PhantomData<(K, V, R)>,
+	filter: CompactionFilter<K, V, T>,	// Updates to drop when compacting.
+	policy: MergePolicy,		// When to merge batches in `merging`.
+	merge_logger: MergeLogger,	// Observes merges as they start and complete.
+	merges: MergeStatistics,	// Merges performed so far.
+	key_filters: Option<KeyFilters<K, B>>,	// Filters over the keys of `merging` and `pending`.
 	advance_frontier: Vec<T>,	// Times after which the trace must accumulate correctly.
 	through_frontier: Vec<T>,	// Times after which the trace must be able to subset its inputs.
 	merging: Vec<B>,			// Several possibly shared collections of updates.
@@ -32,7 +47,7 @@ where
 	B: Batch<K, V, T, R>+Clone+'static,
 {
 	type Batch = B;
-	type Cursor = CursorList<K, V, T, R, <B as BatchReader<K, V, T, R>>::Cursor>;
+	type Cursor = SpineCursor<K, V, T, R, <B as BatchReader<K, V, T, R>>::Cursor>;
 
 	fn cursor_through(&mut self, upper: &[T]) -> Option<Self::Cursor> {
 
@@ -44,8 +59,16 @@ where
 		if upper.iter().all(|t1| self.through_frontier.iter().any(|t2| t2.less_equal(t1))) {
 
 			let mut cursors = Vec::new();
-			cursors.extend(self.merging.iter().filter(|b| b.len() > 0).map(|b| b.cursor()));
-			for batch in &self.pending {
+			let mut filters = Vec::new();
+			for (index, batch) in self.merging.iter().enumerate() {
+				if batch.len() > 0 {
+					cursors.push(batch.cursor());
+					if let Some(ref key_filters) = self.key_filters {
+						filters.push(key_filters.merging[index].clone());
+					}
+				}
+			}
+			for (index, batch) in self.pending.iter().enumerate() {
 				let include_lower = upper.iter().all(|t1| batch.lower().iter().any(|t2| t2.less_equal(t1)));
 				let include_upper = upper.iter().all(|t1| batch.upper().iter().any(|t2| t2.less_equal(t1)));
 
@@ -57,9 +80,15 @@ where
 				// include pending batches 
 				if include_upper {
 					cursors.push(batch.cursor());
+					if let Some(ref key_filters) = self.key_filters {
+						filters.push(key_filters.pending[index].clone());
+					}
 				}
 			}
-			Some(CursorList::new(cursors))
+			match self.key_filters {
+				Some(ref key_filters) => Some(SpineCursor::Filtered(FilteredCursorList::new(cursors, filters, key_filters.hasher))),
+				None => Some(SpineCursor::Plain(CursorList::new(cursors))),
+			}
 		}
 		else {
 			None
@@ -70,6 +99,10 @@ where
 		if self.advance_frontier.len() == 0 {
 			self.pending.clear();
 			self.merging.clear();
+			if let Some(ref mut key_filters) = self.key_filters {
+				key_filters.pending.clear();
+				key_filters.merging.clear();
+			}
 		}
 	}
 	fn advance_frontier(&mut self) -> &[T] { &self.advance_frontier[..] }
@@ -78,6 +111,19 @@ where
 		self.consider_merges();
 	}
 	fn distinguish_frontier(&mut self) -> &[T] { &self.through_frontier[..] }
+	fn compact(&mut self) -> bool {
+		if self.merging.len() == 1 {
+			let batch = self.pop_merging();
+			let batch = self.advance_batch(batch);
+			self.push_merging(batch, None);
+		}
+		while self.merging.len() > 1 {
+			self.merge_last();
+		}
+		// batches not yet distinguishable must hold only times in advance of the frontier.
+		let frontier = &self.advance_frontier[..];
+		self.pending.iter().all(|batch| batch.lower().iter().all(|t1| frontier.iter().any(|t2| t2.less_equal(t1))))
+	}
 
 	fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) {
 		for batch in self.merging.iter() {
@@ -103,6 +149,11 @@ where
 	fn new() -> Self {
 		Spine { 
 			phantom: ::std::marker::PhantomData,
+			filter: CompactionFilter { drop: None },
+			policy: MergePolicy::Binary,
+			merge_logger: MergeLogger { logger: None },
+			merges: MergeStatistics::default(),
+			key_filters: None,
 			advance_frontier: vec![<T as Lattice>::min()],
 			through_frontier: vec![<T as Lattice>::min()],
 			merging: Vec::new(),
@@ -114,6 +165,10 @@ where
 
 		// we can ignore degenerate batches (TODO: learn where they come from; suppress them?)
 		if batch.lower() != batch.upper() {
+			if let Some(ref mut key_filters) = self.key_filters {
+				let filter = key_filters.build(&batch);
+				key_filters.pending.push(filter);
+			}
 			self.pending.push(batch);
 			self.consider_merges();
 		}
@@ -124,6 +179,72 @@ where
 	}
 }
 
+impl<K, V, T, R, B> Spine<K, V, T, R, B> 
+where 
+	K: Ord+Clone,
+	V: Ord+Clone,
+	T: Lattice+Ord+Clone,
+	R: Diff,
+	B: Batch<K, V, T, R>+Clone+'static,
+{
+	/// Drops updates satisfying `drop` when compacting, for example to purge expired records.
+	///
+	/// The predicate is applied to each update of the trace's largest batch, with its time advanced, each
+	/// time that batch is compacted by a merge. Dropped updates are not retracted in any output stream, so
+	/// readers of the trace observe the collection changing without a corresponding update; the predicate
+	/// should only drop updates that readers will no longer consult, for example those whose key or value
+	/// records an expiration before the advanced time.
+	pub fn with_compaction_filter<F: Fn(&K, &V, &T)->bool+'static>(mut self, drop: F) -> Self {
+		self.filter = CompactionFilter { drop: Some(Box::new(drop)) };
+		self
+	}
+	/// Merges batches according to `policy`, rather than the default `MergePolicy::Binary`.
+	///
+	/// The policy applies to batches merged after the call, and so is best set on an empty trace.
+	pub fn with_merge_policy(mut self, policy: MergePolicy) -> Self {
+		if let MergePolicy::Leveled { base, ratio } = policy {
+			assert!(base > 0 && ratio > 1, "leveled merging requires a positive base and a ratio of at least two");
+		}
+		self.policy = policy;
+		self
+	}
+	/// Reports each merge to `logger` as it starts and as it completes.
+	///
+	/// Merges happen within `insert` and `distinguish_since`, and a long merge delays the operator performing
+	/// it; the events allow such delays to be attributed to the trace and its merge sizes. Merges are timed
+	/// only while a logger is installed. Each merge runs to completion within one call, so there are no
+	/// progress events between `Started` and `Completed`.
+	pub fn with_merge_logger<F: Fn(&MergeEvent)+'static>(mut self, logger: F) -> Self {
+		self.merge_logger = MergeLogger { logger: Some(Box::new(logger)) };
+		self
+	}
+	/// Statistics about the merges the trace has performed.
+	pub fn merge_statistics(&self) -> MergeStatistics { self.merges }
+	/// Maintains a filter over the keys of each batch, using `bits_per_key` bits for each key.
+	///
+	/// Filters are built with one pass over the keys of each batch as it is inserted or produced by a merge, and
+	/// are consulted by the trace's cursors when seeking a key, so that batches whose filters exclude the key are
+	/// not searched while the key is found in other batches. This benefits `join`, `group`, and other operators
+	/// seeking keys each present in few of a trace's many batches, at the cost of building the filters and of
+	/// `bits_per_key` bits per key. A key absent from every batch is still sought in every batch, as the cursor
+	/// must then present the following key.
+	pub fn with_key_filters(mut self, bits_per_key: usize) -> Self where K: Hashable {
+		let mut key_filters = KeyFilters {
+			builder: build_key_filter::<K, V, T, R, B> as fn(&B, usize)->KeyFilter,
+			hasher: hash_key::<K> as fn(&K)->u64,
+			bits_per_key: bits_per_key,
+			merging: Vec::new(),
+			pending: Vec::new(),
+		};
+		let merging = self.merging.iter().map(|b| key_filters.build(b)).collect();
+		let pending = self.pending.iter().map(|b| key_filters.build(b)).collect();
+		key_filters.merging = merging;
+		key_filters.pending = pending;
+		self.key_filters = Some(key_filters);
+		self
+	}
+}
+
 impl<K, V, T, R, B> Spine<K, V, T, R, B> 
 where 
 	K: Ord+Clone,			// Clone is required by `advance_mut`.
@@ -143,32 +264,289 @@ where
         {
         	// this could be a VecDeque, if we ever notice this.
 			let batch = self.pending.remove(0);
+			let filter = self.key_filters.as_mut().map(|f| f.pending.remove(0));
+
+			if let MergePolicy::Leveled { base, ratio } = self.policy {
+				// merge the batch into the smallest level, and each level into the next once it outgrows its own.
+				self.push_merging(batch, filter);
+				let mut len = self.merging.len();
+				while len >= 2 && level(self.merging[len - 1].len(), base, ratio) >= level(self.merging[len - 2].len(), base, ratio) {
+					self.merge_last();
+					len = self.merging.len();
+				}
+				continue;
+			}
 
 			// while last two elements exist, both less than batch.len()
 			while self.merging.len() >= 2 && self.merging[self.merging.len() - 2].len() < batch.len() {
-				let batch1 = self.merging.pop().unwrap();
-				let batch2 = self.merging.pop().unwrap();
-				let result = batch2.merge(&batch1);
-				self.merging.push(result);
+				let result = self.merge_pair();
+				self.push_merging(result, None);
 			}
 
-			self.merging.push(batch);
+			self.push_merging(batch, filter);
 
 			// `len` exists only to narrow while condition.
 			let mut len = self.merging.len();
 			while len >= 2 && self.merging[len - 2].len() < 2 * self.merging[len - 1].len() {
-				let batch1 = self.merging.pop().unwrap();
-				let batch2 = self.merging.pop().unwrap();
-				let mut result = batch2.merge(&batch1);
-
-				// if we just merged the last batch, `advance_by` it.
-				if self.merging.len() == 0 {
-					result.advance_mut(&self.advance_frontier[..]);
-				}
-
-				self.merging.push(result);
+				self.merge_last();
 				len = self.merging.len();
 			}
 		}
 	}
+
+	// Merges the last two batches of `self.merging`, compacting the result if it is the only batch.
+	fn merge_last(&mut self) {
+		let mut result = self.merge_pair();
+
+		// if we just merged the last batch, `advance_by` it.
+		if self.merging.len() == 0 {
+			result = self.advance_batch(result);
+		}
+
+		self.push_merging(result, None);
+	}
+
+	// Advances the times of `batch` by the advance frontier, dropping updates selected by the compaction filter.
+	fn advance_batch(&self, mut batch: B) -> B {
+		match self.filter.drop {
+			Some(ref drop) => batch.advance_filter_ref(&self.advance_frontier[..], |k,v,t| drop(k,v,t)),
+			None => { batch.advance_mut(&self.advance_frontier[..]); batch },
+		}
+	}
+
+	// Pops and merges the last two batches of `self.merging`, reporting and recording the merge.
+	fn merge_pair(&mut self) -> B {
+		let batch1 = self.pop_merging();
+		let batch2 = self.pop_merging();
+		let updates = batch1.len() + batch2.len();
+		self.merge_logger.log(&MergeEvent::Started { updates: updates });
+
+		// merges are only timed when observed, as reading the clock is not free.
+		let timer = if self.merge_logger.logger.is_some() { Some(Instant::now()) } else { None };
+		let result = batch2.merge(&batch1);
+		let elapsed = timer.map(|timer| timer.elapsed()).unwrap_or(Duration::new(0, 0));
+
+		self.merges.merges += 1;
+		self.merges.updates += updates;
+		self.merges.elapsed += elapsed;
+		if updates > self.merges.largest { self.merges.largest = updates; }
+		if elapsed > self.merges.longest { self.merges.longest = elapsed; }
+		self.merge_logger.log(&MergeEvent::Completed { updates: updates, produced: result.len(), elapsed: elapsed });
+
+		result
+	}
+
+	// Pushes a batch on to `self.merging`, with its key filter if maintained, building the filter if not supplied.
+	fn push_merging(&mut self, batch: B, filter: Option<Rc<KeyFilter>>) {
+		if let Some(ref mut key_filters) = self.key_filters {
+			let filter = filter.unwrap_or_else(|| key_filters.build(&batch));
+			key_filters.merging.push(filter);
+		}
+		self.merging.push(batch);
+	}
+
+	// Pops a batch from `self.merging`, with its key filter if maintained.
+	fn pop_merging(&mut self) -> B {
+		if let Some(ref mut key_filters) = self.key_filters {
+			key_filters.merging.pop();
+		}
+		self.merging.pop().unwrap()
+	}
+}
+
+/// How a spine merges its batches.
+///
+/// Batches are merged in the order of their times, and each merge rewrites the updates of both batches. A
+/// policy that merges eagerly keeps few batches, which speeds cursors that seek keys in every batch (as the
+/// lookups of joins and groups do), at the cost of rewriting each update more often as the trace grows.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MergePolicy {
+	/// Merges adjacent batches whose sizes are within a factor of two.
+	///
+	/// Each update is rewritten about once per doubling of the trace, and the trace holds about one batch
+	/// per doubling, which is about `log2(n)` batches for `n` updates. This is the default.
+	Binary,
+	/// Maintains one batch per level, each level able to hold `ratio` times the updates of the level before.
+	///
+	/// The first level holds up to `base` updates, and each inserted batch is merged into it; a batch larger
+	/// than its level is merged into the next level. The trace holds about `log(n / base) / log(ratio)`
+	/// batches, fewer than `Binary` for ratios above two, but each update is rewritten about `ratio / 2`
+	/// times per level and each insertion rewrites the first level. This suits traces read much more than
+	/// they are written, with `base` around the size of a typical inserted batch.
+	Leveled {
+		/// The number of updates the first level holds.
+		base: usize,
+		/// The factor by which each level's capacity exceeds that of the level before.
+		ratio: usize,
+	},
+}
+
+impl Default for MergePolicy {
+	fn default() -> Self { MergePolicy::Binary }
+}
+
+// The level of a batch of `len` updates under `MergePolicy::Leveled`.
+fn level(len: usize, base: usize, ratio: usize) -> usize {
+	let mut level = 0;
+	let mut capacity = base;
+	while len > capacity {
+		capacity = capacity.saturating_mul(ratio);
+		level += 1;
+	}
+	level
+}
+
+/// A merge of two batches, reported to a spine's merge logger.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MergeEvent {
+	/// A merge of batches holding `updates` updates in total has started.
+	Started {
+		/// The number of updates in the merged batches.
+		updates: usize,
+	},
+	/// A merge has completed.
+	Completed {
+		/// The number of updates in the merged batches.
+		updates: usize,
+		/// The number of updates in the resulting batch, fewer than `updates` if some consolidated.
+		produced: usize,
+		/// The time spent merging.
+		elapsed: Duration,
+	},
+}
+
+/// Cumulative statistics about the merges of a spine.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergeStatistics {
+	/// The number of merges performed.
+	pub merges: usize,
+	/// The number of updates read by all merges.
+	pub updates: usize,
+	/// The number of updates read by the largest merge.
+	pub largest: usize,
+	/// The time spent in all merges, measured only while a merge logger is installed.
+	pub elapsed: Duration,
+	/// The time spent in the longest merge, measured only while a merge logger is installed.
+	pub longest: Duration,
+}
+
+/// A function observing merges.
+struct MergeLogger {
+	logger: Option<Box<Fn(&MergeEvent)>>,
+}
+
+impl MergeLogger {
+	fn log(&self, event: &MergeEvent) {
+		if let Some(ref logger) = self.logger { logger(event); }
+	}
+}
+
+impl ::std::fmt::Debug for MergeLogger {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "MergeLogger {{ present: {} }}", self.logger.is_some())
+	}
+}
+
+/// A predicate selecting updates to drop when compacting.
+struct CompactionFilter<K, V, T> {
+	drop: Option<Box<Fn(&K, &V, &T)->bool>>,
+}
+
+impl<K, V, T> ::std::fmt::Debug for CompactionFilter<K, V, T> {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "CompactionFilter {{ present: {} }}", self.drop.is_some())
+	}
+}
+
+/// Filters over the keys of the batches of a spine, in the same order as the batches.
+struct KeyFilters<K, B> {
+	builder: fn(&B, usize)->KeyFilter,
+	hasher: fn(&K)->u64,
+	bits_per_key: usize,
+	merging: Vec<Rc<KeyFilter>>,
+	pending: Vec<Rc<KeyFilter>>,
+}
+
+impl<K, B> KeyFilters<K, B> {
+	fn build(&self, batch: &B) -> Rc<KeyFilter> { Rc::new((self.builder)(batch, self.bits_per_key)) }
+}
+
+impl<K, B> ::std::fmt::Debug for KeyFilters<K, B> {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "KeyFilters {{ bits_per_key: {}, filters: {} }}", self.bits_per_key, self.merging.len() + self.pending.len())
+	}
+}
+
+/// Builds a filter over the keys of `batch`, in one pass over its keys.
+fn build_key_filter<K, V, T, R, B>(batch: &B, bits_per_key: usize) -> KeyFilter
+where K: Hashable, B: BatchReader<K, V, T, R> {
+	let mut hashes = Vec::new();
+	let mut cursor = batch.cursor();
+	while cursor.key_valid() {
+		hashes.push(cursor.key().hashed().as_u64());
+		cursor.step_key();
+	}
+	KeyFilter::from_hashes(&hashes[..], bits_per_key)
+}
+
+/// A cursor over the batches of a spine, which consults their key filters if the spine maintains them.
+#[derive(Debug)]
+pub enum SpineCursor<K, V, T, R, C: Cursor<K, V, T, R>> {
+	/// Merges the cursors of the batches.
+	Plain(CursorList<K, V, T, R, C>),
+	/// Merges the cursors of the batches, searching a batch for a key only if its filter may contain the key.
+	Filtered(FilteredCursorList<K, V, T, R, C>),
+}
+
+impl<K: Clone, V, T, R, C: Cursor<K, V, T, R>> Clone for SpineCursor<K, V, T, R, C> {
+	fn clone(&self) -> Self {
+		match *self {
+			SpineCursor::Plain(ref cursor) => SpineCursor::Plain(cursor.clone()),
+			SpineCursor::Filtered(ref cursor) => SpineCursor::Filtered(cursor.clone()),
+		}
+	}
+}
+
+impl<K, V, T, R, C> Cursor<K, V, T, R> for SpineCursor<K, V, T, R, C> where K: Ord+Clone, V: Ord, C: Cursor<K, V, T, R> {
+
+	fn key_valid(&self) -> bool {
+		match *self { SpineCursor::Plain(ref c) => c.key_valid(), SpineCursor::Filtered(ref c) => c.key_valid() }
+	}
+	fn val_valid(&self) -> bool {
+		match *self { SpineCursor::Plain(ref c) => c.val_valid(), SpineCursor::Filtered(ref c) => c.val_valid() }
+	}
+
+	fn key(&self) -> &K {
+		match *self { SpineCursor::Plain(ref c) => c.key(), SpineCursor::Filtered(ref c) => c.key() }
+	}
+	fn val(&self) -> &V {
+		match *self { SpineCursor::Plain(ref c) => c.val(), SpineCursor::Filtered(ref c) => c.val() }
+	}
+	fn map_times<L: FnMut(&T, R)>(&mut self, logic: L) {
+		match *self { SpineCursor::Plain(ref mut c) => c.map_times(logic), SpineCursor::Filtered(ref mut c) => c.map_times(logic) }
+	}
+
+	fn step_key(&mut self) {
+		match *self { SpineCursor::Plain(ref mut c) => c.step_key(), SpineCursor::Filtered(ref mut c) => c.step_key() }
+	}
+	fn seek_key(&mut self, key: &K) {
+		match *self { SpineCursor::Plain(ref mut c) => c.seek_key(key), SpineCursor::Filtered(ref mut c) => c.seek_key(key) }
+	}
+
+	fn step_val(&mut self) {
+		match *self { SpineCursor::Plain(ref mut c) => c.step_val(), SpineCursor::Filtered(ref mut c) => c.step_val() }
+	}
+	fn seek_val(&mut self, val: &V) {
+		match *self { SpineCursor::Plain(ref mut c) => c.seek_val(val), SpineCursor::Filtered(ref mut c) => c.seek_val(val) }
+	}
+
+	fn rewind_keys(&mut self) {
+		match *self { SpineCursor::Plain(ref mut c) => c.rewind_keys(), SpineCursor::Filtered(ref mut c) => c.rewind_keys() }
+	}
+	fn rewind_vals(&mut self) {
+		match *self { SpineCursor::Plain(ref mut c) => c.rewind_vals(), SpineCursor::Filtered(ref mut c) => c.rewind_vals() }
+	}
 }
+
+/// Hashes `key` as filters expect.
+fn hash_key<K: Hashable>(key: &K) -> u64 { key.hashed().as_u64() }
@@ -408,6 +408,18 @@ pub struct HashedCursor<K: HashOrdered, L: Cursor> {
 	pub child: L,
 }
 
+impl<K: HashOrdered, L: Cursor> Clone for HashedCursor<K, L> {
+	fn clone(&self) -> Self {
+		HashedCursor {
+			shift: self.shift,
+			bounds: self.bounds,
+			pos: self.pos,
+			keys: self.keys.clone(),
+			child: self.child.clone(),
+		}
+	}
+}
+
 impl<K: HashOrdered, L: Cursor> Cursor for HashedCursor<K, L> {
 	type Key = K;
 	fn key(&self) -> &Self::Key { &self.keys[self.pos].key }
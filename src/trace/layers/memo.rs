@@ -0,0 +1,89 @@
+//! Keys with memoized comparison data, for keys whose comparison is expensive.
+//!
+//! Ordered layers compare keys repeatedly when building, merging, and seeking. For keys such as long strings,
+//! or enums whose variants carry payloads, each comparison may inspect much of both keys. The `MemoCmp` wrapper
+//! records a hash of the key and a cheap comparison prefix (for example a length or variant) when it is
+//! wrapped, and orders keys by these before the keys themselves, which are only compared when both agree.
+//! Unlike `OrdWrapper`, which orders by hash but recomputes it for each comparison, the hash is computed once.
+//!
+//! The resulting order is not the order of the keys, but it is a total order consistent with their equality,
+//! which is all that traces require. As keys are ordered first by hash, `MemoCmp` is `HashOrdered`.
+
+use std::ops::Deref;
+
+use abomonation::Abomonation;
+
+use hashable::{Hashable, HashOrdered};
+
+/// Types with a cheap summary that distinguishes many unequal values.
+///
+/// Equal values must have equal prefixes; unequal values may, but should rarely, have equal prefixes.
+pub trait CmpPrefix {
+	/// A summary of the value, compared before the value itself.
+	fn cmp_prefix(&self) -> u64;
+}
+
+impl CmpPrefix for String {
+	#[inline(always)] fn cmp_prefix(&self) -> u64 { self.len() as u64 }
+}
+
+impl<T> CmpPrefix for Vec<T> {
+	#[inline(always)] fn cmp_prefix(&self) -> u64 { self.len() as u64 }
+}
+
+impl<T: CmpPrefix> CmpPrefix for Option<T> {
+	#[inline(always)] fn cmp_prefix(&self) -> u64 {
+		match *self {
+			None => 0,
+			Some(ref item) => item.cmp_prefix().saturating_add(1),
+		}
+	}
+}
+
+/// A key with its hash and comparison prefix, ordered by these before the key.
+#[derive(Clone, Default, Ord, PartialOrd, Eq, PartialEq, Debug)]
+pub struct MemoCmp<T: Hashable> {
+	hash: T::Output,
+	prefix: u64,
+	/// The key.
+	pub item: T,
+}
+
+impl<T: Hashable+CmpPrefix> From<T> for MemoCmp<T> {
+	#[inline(always)]
+	fn from(item: T) -> Self {
+		MemoCmp {
+			hash: item.hashed(),
+			prefix: item.cmp_prefix(),
+			item: item,
+		}
+	}
+}
+
+impl<T: Hashable> Hashable for MemoCmp<T> {
+	type Output = T::Output;
+	#[inline(always)]
+	fn hashed(&self) -> T::Output { self.hash }
+}
+
+impl<T: Ord+Hashable> HashOrdered for MemoCmp<T> { }
+
+impl<T: Hashable> Deref for MemoCmp<T> {
+	type Target = T;
+	#[inline(always)]
+	fn deref(&self) -> &T { &self.item }
+}
+
+impl<T: Hashable+Abomonation> Abomonation for MemoCmp<T> {
+	#[inline] unsafe fn entomb(&self, _writer: &mut Vec<u8>) {
+		self.item.entomb(_writer);
+	}
+	#[inline] unsafe fn embalm(&mut self) {
+		self.item.embalm();
+	}
+	#[inline] unsafe fn exhume<'a,'b>(&'a mut self, mut bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+		let temp = bytes;
+		bytes = if let Some(bytes) = self.item.exhume(temp) { bytes } else { return None };
+		Some(bytes)
+	}
+}
@@ -8,6 +8,7 @@ pub mod ordered;
 pub mod hashed;
 pub mod weighted;
 pub mod unordered;
+pub mod memo;
 
 /// A collection of tuples, and types for building and enumerating them.
 ///
@@ -87,8 +88,8 @@ pub trait TupleBuilder : Builder {
 ///
 /// The precise meaning of this navigation is not defined by the trait. It is likely that having 
 /// navigated around, the cursor will be different in some other way, but the `Cursor` trait does
-/// not explain how this is so.
-pub trait Cursor {
+/// not explain how this is so. Clones of a cursor share its data and record its position.
+pub trait Cursor : Clone {
 	/// The type revealed by the cursor.
 	type Key;
 	/// Reveals the current key.
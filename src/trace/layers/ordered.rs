@@ -197,6 +197,18 @@ pub struct OrderedCursor<K: Ord, L: Cursor> {
 	pub child: L,
 }
 
+impl<K: Ord, L: Cursor> Clone for OrderedCursor<K, L> {
+	fn clone(&self) -> Self {
+		OrderedCursor {
+			keys: self.keys.clone(),
+			offs: self.offs.clone(),
+			pos: self.pos,
+			bounds: self.bounds,
+			child: self.child.clone(),
+		}
+	}
+}
+
 impl<K: Ord, L: Cursor> Cursor for OrderedCursor<K, L> {
 	type Key = K;
 	fn key(&self) -> &Self::Key { &self.keys[self.pos] }
@@ -72,6 +72,16 @@ pub struct UnorderedCursor<K> {
 	bounds: (usize, usize),
 }
 
+impl<K> Clone for UnorderedCursor<K> {
+	fn clone(&self) -> Self {
+		UnorderedCursor {
+			vals: self.vals.clone(),
+			pos: self.pos,
+			bounds: self.bounds,
+		}
+	}
+}
+
 impl<K: Clone> Cursor for UnorderedCursor<K> {
 	type Key = K;
 	fn key(&self) -> &Self::Key { &self.vals[self.pos] }
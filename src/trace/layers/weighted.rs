@@ -151,6 +151,17 @@ impl<K: Ord> WeightedCursor<K> {
 	pub fn weight(&self) -> isize { self.wgts[self.bounds.0] }
 }
 
+impl<K: Ord> Clone for WeightedCursor<K> {
+	fn clone(&self) -> Self {
+		WeightedCursor {
+			keys: self.keys.clone(),
+			wgts: self.wgts.clone(),
+			pos: self.pos,
+			bounds: self.bounds,
+		}
+	}
+}
+
 impl<K: Ord> Cursor for WeightedCursor<K> {
 	type Key = K;
 	fn key(&self) -> &Self::Key { &self.keys[self.pos] }
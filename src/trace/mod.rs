@@ -9,6 +9,7 @@
 
 pub mod cursor;
 pub mod description;
+pub mod filter;
 pub mod implementations;
 pub mod layers;
 pub mod wrappers;
@@ -41,6 +42,9 @@ pub use self::description::Description;
 pub trait TraceReader<Key, Val, Time, R> {
 
 	/// The type of an immutable collection of updates.
+	///
+	/// Batches are cloned as they are handed to listeners and wrappers, and so should be cheap to clone.
+	/// Batches backed by storage that cannot be cloned can be wrapped in an `Rc`; see `rc_blanket_impls`.
 	type Batch: BatchReader<Key, Val, Time, R>+Clone+'static;
 
 	/// The type used to enumerate the collections contents.
@@ -98,6 +102,16 @@ pub trait TraceReader<Key, Val, Time, R> {
 	/// this frontier are not guaranteed to return a cursor.
 	fn distinguish_frontier(&mut self) -> &[Time];
 
+	/// Applies the advance frontier to as many updates as possible now, rather than as batches are merged.
+	///
+	/// Updates at times not in advance of the advance frontier are only advanced, and updates that cancel only
+	/// then removed, as batches are merged. This method merges the batches the trace may merge, and advances
+	/// the result, so that updates prior to the frontier are physically removed where possible. It returns
+	/// true if no updates at times not in advance of the frontier remain in their original form.
+	///
+	/// The default implementation does nothing, and returns false.
+	fn compact(&mut self) -> bool { false }
+
 	/// Maps some logic across the batches the collection manages.
 	///
 	/// This is currently used only to extract historical data to prime late-starting operators who want to reproduce
@@ -105,6 +119,26 @@ pub trait TraceReader<Key, Val, Time, R> {
 	/// cursor methods, as they (by default) just move through batches accumulating cursors into a cursor list.
 	fn map_batches<F: FnMut(&Self::Batch)>(&mut self, f: F);
 
+	/// Advances both read frontiers, first by `advance_by(advance)` and then by `distinguish_since(through)`.
+	///
+	/// Readers should only ever advance their frontiers; a reader whose frontiers lag behind the times it still
+	/// needs is correct, but prevents the trace from compacting and so retains memory indefinitely. A frontier
+	/// that regresses usually indicates that a reader has confused the two frontiers or the inputs they track;
+	/// this method prints the old and new frontiers when either regresses, and in debug builds also panics.
+	fn set_read_frontiers(&mut self, advance: &[Time], through: &[Time]) where Time: Lattice+::std::fmt::Debug {
+		check_frontier("advance", self.advance_frontier(), advance);
+		check_frontier("through", self.distinguish_frontier(), through);
+		self.advance_by(advance);
+		self.distinguish_since(through);
+	}
+}
+
+// Reports a `new` frontier with an element not greater or equal to some element of `old`.
+fn check_frontier<T: Lattice+::std::fmt::Debug>(name: &str, old: &[T], new: &[T]) {
+	if !new.iter().all(|t1| old.iter().any(|t2| t2.less_equal(t1))) {
+		println!("set_read_frontiers: {} frontier regressed from {:?} to {:?}", name, old, new);
+		debug_assert!(false, "set_read_frontiers: {} frontier regressed", name);
+	}
 }
 
 /// An append-only collection of `(key, val, time, diff)` tuples.
@@ -151,7 +185,58 @@ pub trait BatchReader<K, V, T, R> {
 	fn lower(&self) -> &[T] { self.description().lower() }
 	/// All times in the batch are not greater or equal to any element of `upper`.
 	fn upper(&self) -> &[T] { self.description().upper() }
+	/// The lower envelope of the times of the batch's updates, if the batch records it.
+	///
+	/// Unlike `lower`, which bounds the interval the batch describes, this bounds the times actually present.
+	fn times(&self) -> Option<&[T]> { None }
 
+	/// Summarizes the keys and values of the batch.
+	///
+	/// The default implementation scans the batch; implementations whose layout records the statistics
+	/// should report them directly.
+	fn stats(&self) -> BatchStats<K> where K: Ord+Clone, V: Ord {
+		let mut stats = BatchStats { keys: 0, vals: 0, updates: self.len(), bounds: None };
+		let mut cursor = self.cursor();
+		while cursor.key_valid() {
+			stats.keys += 1;
+			stats.bounds = match stats.bounds {
+				None => Some((cursor.key().clone(), cursor.key().clone())),
+				Some((lower, _)) => Some((lower, cursor.key().clone())),
+			};
+			while cursor.val_valid() {
+				stats.vals += 1;
+				cursor.step_val();
+			}
+			cursor.step_key();
+		}
+		stats
+	}
+}
+
+/// Summary statistics of the contents of a batch.
+///
+/// Key bounds allow readers to skip batches that cannot contain keys of interest, for example the keys of
+/// a join's other input, and the counts allow the size of arrangements to be monitored cheaply.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchStats<K> {
+	/// The number of distinct keys.
+	pub keys: usize,
+	/// The number of distinct `(key, val)` pairs.
+	pub vals: usize,
+	/// The number of updates.
+	pub updates: usize,
+	/// The least and greatest keys, in the order of the batch's cursor, if the batch has keys.
+	pub bounds: Option<(K, K)>,
+}
+
+impl<K> BatchStats<K> {
+	/// Indicates whether the batch may contain keys in the interval from `lower` to `upper`, inclusive.
+	pub fn may_overlap(&self, lower: &K, upper: &K) -> bool where K: Ord {
+		match self.bounds {
+			Some((ref min, ref max)) => min <= upper && lower <= max,
+			None => false,
+		}
+	}
 }
 
 /// An immutable collection of updates.
@@ -192,16 +277,67 @@ pub trait Batch<K, V, T, R> : BatchReader<K, V, T, R> where Self: ::std::marker:
 
 		builder.done(self.description().lower(), self.description().upper(), frontier)
 	}
+	/// Advance times to `frontier` creating a new batch, dropping updates for which `drop` returns true.
+	///
+	/// The predicate is applied to each update after its time is advanced.
+	fn advance_filter_ref<F>(&self, frontier: &[T], drop: F) -> Self 
+	where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff, F: Fn(&K, &V, &T)->bool {
+
+		assert!(frontier.len() > 0);
+
+		let mut builder = Self::Builder::with_capacity(self.len());
+
+		let mut times = Vec::new();
+		let mut cursor = self.cursor();
+
+		while cursor.key_valid() {
+			while cursor.val_valid() {
+				cursor.map_times(|time: &T, diff| times.push((time.advance_by(frontier), diff)));
+				consolidate(&mut times, 0);
+				for (time, diff) in times.drain(..) {
+					if !drop(cursor.key(), cursor.val(), &time) {
+						builder.push((cursor.key().clone(), cursor.val().clone(), time, diff));
+					}
+				}
+				cursor.step_val();
+			}
+			cursor.step_key();
+		}
+
+		builder.done(self.description().lower(), self.description().upper(), frontier)
+	}
 	/// Advance times to `frontier` updating this batch.
 	///
 	/// This method gives batches the ability to collapse in-place when possible, and is the common 
 	/// entry point to advance batches. Most types of batches do have shared state, but `advance` is 
 	/// commonly invoked just after a batch is formed from a merge and when there is a unique owner 
 	/// of the shared state. 
+	///
+	/// Batches whose times are all unchanged by advancing are not rebuilt, but have their `since` frontier
+	/// replaced by `frontier`. This is common once the times of a batch have been advanced and the frontier
+	/// has not moved far since.
 	#[inline(never)]
 	fn advance_mut(&mut self, frontier: &[T]) where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
-		*self = self.advance_ref(frontier);
+		if !(self.is_advanced_by(frontier) && self.advance_since(frontier)) {
+			*self = self.advance_ref(frontier);
+		}
 	}
+	/// Indicates whether advancing times by `frontier` would certainly leave all times in the batch unchanged.
+	///
+	/// A time greater or equal to an element of `frontier` is unchanged by advancing, and so this consults
+	/// the lower envelope of the batch's times rather than the times themselves. Batches that do not record
+	/// the envelope are never reported as advanced.
+	fn is_advanced_by(&self, frontier: &[T]) -> bool where T: Lattice {
+		match self.times() {
+			Some(times) => times.iter().all(|t1| frontier.iter().any(|t2| t2.less_equal(t1))),
+			None => false,
+		}
+	}
+	/// Replaces the batch's `since` frontier with `frontier`, without changing its times, if supported.
+	///
+	/// This is only correct when `is_advanced_by(frontier)` holds, and returns false if the batch could
+	/// not be updated in place.
+	fn advance_since(&mut self, _frontier: &[T]) -> bool { false }
 }
 
 /// Functionality for collecting and batching updates.
@@ -239,20 +375,90 @@ pub fn consolidate<T: Ord+Clone, R: Diff>(vec: &mut Vec<(T, R)>, off: usize) {
 
 
 /// Scans `vec[off..]` and consolidates differences of adjacent equivalent elements.
+///
+/// The sort is skipped when `vec[off..]` is already sorted, which is common when consolidating repeatedly.
+/// Otherwise `sort_by` is a stable merge sort that detects existing runs, and is cheap on nearly sorted input.
+/// Accumulated elements are moved into place rather than cloned.
 pub fn consolidate_by<T: Eq+Clone, L: Fn(&T, &T)->::std::cmp::Ordering, R: Diff>(vec: &mut Vec<(T, R)>, off: usize, cmp: L) {
-	vec[off..].sort_by(|x,y| cmp(&x.0, &y.0));
-	for index in (off + 1) .. vec.len() {
-		if vec[index].0 == vec[index - 1].0 {
-			vec[index].1 = vec[index].1 + vec[index - 1].1;
-			vec[index - 1].1 = R::zero();
-		}
+	if !vec[off..].windows(2).all(|w| cmp(&w[0].0, &w[1].0) != ::std::cmp::Ordering::Greater) {
+		vec[off..].sort_by(|x,y| cmp(&x.0, &y.0));
 	}
 	let mut cursor = off;
-	for index in off .. vec.len() {
-		if !vec[index].1.is_zero() {
-			vec[cursor] = vec[index].clone();
+	let mut index = off;
+	while index < vec.len() {
+		let mut diff = vec[index].1;
+		let mut next = index + 1;
+		while next < vec.len() && vec[next].0 == vec[index].0 {
+			diff = diff + vec[next].1;
+			next += 1;
+		}
+		if !diff.is_zero() {
+			vec.swap(cursor, index);
+			vec[cursor].1 = diff;
 			cursor += 1;
 		}
+		index = next;
 	}
 	vec.truncate(cursor);
-}
\ No newline at end of file
+}
+
+/// Blanket implementations for reference counted batches.
+///
+/// A batch whose storage cannot be cheaply cloned (for example, an owned region of memory) can still be
+/// used as the batch type of a trace by wrapping it in an `Rc`. The wrapped batch is shared by the trace,
+/// its listeners, and any wrappers (e.g. `TraceEnter`), rather than copied between them.
+pub mod rc_blanket_impls {
+
+	use std::rc::Rc;
+
+	use ::Diff;
+	use lattice::Lattice;
+	use super::{Batch, BatchReader, Batcher, Builder, Description};
+
+	impl<K, V, T, R, B: BatchReader<K,V,T,R>> BatchReader<K,V,T,R> for Rc<B> {
+		type Cursor = B::Cursor;
+		fn cursor(&self) -> Self::Cursor { (**self).cursor() }
+		fn len(&self) -> usize { (**self).len() }
+		fn description(&self) -> &Description<T> { (**self).description() }
+		fn times(&self) -> Option<&[T]> { (**self).times() }
+	}
+
+	impl<K, V, T, R, B: Batch<K,V,T,R>> Batch<K,V,T,R> for Rc<B> {
+		type Batcher = RcBatcher<K,V,T,R,B>;
+		type Builder = RcBuilder<K,V,T,R,B>;
+		fn merge(&self, other: &Self) -> Self { Rc::new((**self).merge(other)) }
+		fn advance_ref(&self, frontier: &[T]) -> Self where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+			Rc::new((**self).advance_ref(frontier))
+		}
+		fn advance_mut(&mut self, frontier: &[T]) where K: Ord+Clone, V: Ord+Clone, T: Lattice+Ord+Clone, R: Diff {
+			// advance in place if we are the only owner, and otherwise build a new batch.
+			let unique = Rc::get_mut(self).map(|batch| batch.advance_mut(frontier)).is_some();
+			if !unique {
+				*self = self.advance_ref(frontier);
+			}
+		}
+		fn advance_since(&mut self, frontier: &[T]) -> bool {
+			Rc::get_mut(self).map(|batch| batch.advance_since(frontier)).unwrap_or(false)
+		}
+	}
+
+	/// Wrapper type for batching reference counted batches.
+	pub struct RcBatcher<K,V,T,R,B:Batch<K,V,T,R>> { batcher: B::Batcher }
+
+	impl<K,V,T,R,B:Batch<K,V,T,R>> Batcher<K, V, T, R, Rc<B>> for RcBatcher<K,V,T,R,B> {
+		fn new() -> Self { RcBatcher { batcher: <B::Batcher as Batcher<K,V,T,R,B>>::new() } }
+		fn push_batch(&mut self, batch: &mut Vec<((K, V), T, R)>) { self.batcher.push_batch(batch) }
+		fn seal(&mut self, upper: &[T]) -> Rc<B> { Rc::new(self.batcher.seal(upper)) }
+		fn frontier(&mut self) -> &[T] { self.batcher.frontier() }
+	}
+
+	/// Wrapper type for building reference counted batches.
+	pub struct RcBuilder<K,V,T,R,B:Batch<K,V,T,R>> { builder: B::Builder }
+
+	impl<K,V,T,R,B:Batch<K,V,T,R>> Builder<K, V, T, R, Rc<B>> for RcBuilder<K,V,T,R,B> {
+		fn new() -> Self { RcBuilder { builder: <B::Builder as Builder<K,V,T,R,B>>::new() } }
+		fn with_capacity(cap: usize) -> Self { RcBuilder { builder: <B::Builder as Builder<K,V,T,R,B>>::with_capacity(cap) } }
+		fn push(&mut self, element: (K, V, T, R)) { self.builder.push(element) }
+		fn done(self, lower: &[T], upper: &[T], since: &[T]) -> Rc<B> { Rc::new(self.builder.done(lower, upper, since)) }
+	}
+}
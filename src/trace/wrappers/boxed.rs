@@ -0,0 +1,175 @@
+//! Wrappers erasing the types of traces, batches, and cursors.
+//!
+//! Traces, batches, and cursors are usually statically typed, which requires the choice of trace implementation
+//! to be made when the program is compiled. The wrappers in this module present any implementation for fixed
+//! key, value, time, and difference types as one type, `TraceReaderBox`, whose batches and cursors are also
+//! boxed. A program may then choose an implementation at runtime, for example from a schema or statistics.
+//!
+//! Each call through a wrapper is dynamically dispatched, and so boxed traces are slower to read than the
+//! traces they wrap.
+//!
+//! #Examples
+//!
+//! ```ignore
+//! let trace: TraceReaderBox<K, V, T, isize> = if hashed {
+//!     TraceReaderBox::new(hashed_arrangement.trace.clone())
+//! } else {
+//!     TraceReaderBox::new(ordered_arrangement.trace.clone())
+//! };
+//! ```
+
+use std::rc::Rc;
+
+use trace::{TraceReader, BatchReader, Description};
+use trace::cursor::Cursor;
+
+/// An object-safe version of `Cursor`.
+trait CursorDyn<K, V, T, R> {
+    fn key_valid(&self) -> bool;
+    fn val_valid(&self) -> bool;
+    fn key(&self) -> &K;
+    fn val(&self) -> &V;
+    fn map_times(&mut self, logic: &mut FnMut(&T, R));
+    fn step_key(&mut self);
+    fn seek_key(&mut self, key: &K);
+    fn step_val(&mut self);
+    fn seek_val(&mut self, val: &V);
+    fn rewind_keys(&mut self);
+    fn rewind_vals(&mut self);
+    fn clone_box(&self) -> Box<CursorDyn<K, V, T, R>>;
+}
+
+impl<K, V, T, R, C: Cursor<K, V, T, R>+'static> CursorDyn<K, V, T, R> for C {
+    fn key_valid(&self) -> bool { Cursor::key_valid(self) }
+    fn val_valid(&self) -> bool { Cursor::val_valid(self) }
+    fn key(&self) -> &K { Cursor::key(self) }
+    fn val(&self) -> &V { Cursor::val(self) }
+    fn map_times(&mut self, logic: &mut FnMut(&T, R)) { Cursor::map_times(self, |t, r| logic(t, r)) }
+    fn step_key(&mut self) { Cursor::step_key(self) }
+    fn seek_key(&mut self, key: &K) { Cursor::seek_key(self, key) }
+    fn step_val(&mut self) { Cursor::step_val(self) }
+    fn seek_val(&mut self, val: &V) { Cursor::seek_val(self, val) }
+    fn rewind_keys(&mut self) { Cursor::rewind_keys(self) }
+    fn rewind_vals(&mut self) { Cursor::rewind_vals(self) }
+    fn clone_box(&self) -> Box<CursorDyn<K, V, T, R>> { Box::new(self.clone()) }
+}
+
+/// A cursor of any type, for fixed key, value, time, and difference types.
+pub struct CursorBox<K, V, T, R> {
+    cursor: Box<CursorDyn<K, V, T, R>>,
+}
+
+impl<K, V, T, R> CursorBox<K, V, T, R> {
+    /// Boxes a cursor.
+    pub fn new<C: Cursor<K, V, T, R>+'static>(cursor: C) -> Self {
+        CursorBox { cursor: Box::new(cursor) }
+    }
+}
+
+impl<K, V, T, R> Clone for CursorBox<K, V, T, R> {
+    fn clone(&self) -> Self {
+        CursorBox { cursor: self.cursor.clone_box() }
+    }
+}
+
+impl<K, V, T, R> Cursor<K, V, T, R> for CursorBox<K, V, T, R> {
+    fn key_valid(&self) -> bool { self.cursor.key_valid() }
+    fn val_valid(&self) -> bool { self.cursor.val_valid() }
+    fn key(&self) -> &K { self.cursor.key() }
+    fn val(&self) -> &V { self.cursor.val() }
+    fn map_times<L: FnMut(&T, R)>(&mut self, mut logic: L) { self.cursor.map_times(&mut logic) }
+    fn step_key(&mut self) { self.cursor.step_key() }
+    fn seek_key(&mut self, key: &K) { self.cursor.seek_key(key) }
+    fn step_val(&mut self) { self.cursor.step_val() }
+    fn seek_val(&mut self, val: &V) { self.cursor.seek_val(val) }
+    fn rewind_keys(&mut self) { self.cursor.rewind_keys() }
+    fn rewind_vals(&mut self) { self.cursor.rewind_vals() }
+}
+
+/// An object-safe version of `BatchReader`.
+trait BatchDyn<K, V, T, R> {
+    fn cursor(&self) -> CursorBox<K, V, T, R>;
+    fn len(&self) -> usize;
+    fn description(&self) -> &Description<T>;
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> BatchDyn<K, V, T, R> for B where B::Cursor: 'static {
+    fn cursor(&self) -> CursorBox<K, V, T, R> { CursorBox::new(BatchReader::cursor(self)) }
+    fn len(&self) -> usize { BatchReader::len(self) }
+    fn description(&self) -> &Description<T> { BatchReader::description(self) }
+}
+
+/// A batch of any type, for fixed key, value, time, and difference types.
+pub struct BatchBox<K, V, T, R> {
+    batch: Rc<BatchDyn<K, V, T, R>>,
+}
+
+impl<K, V, T, R> BatchBox<K, V, T, R> {
+    /// Boxes a batch.
+    pub fn new<B: BatchReader<K, V, T, R>+'static>(batch: B) -> Self where B::Cursor: 'static {
+        BatchBox { batch: Rc::new(batch) }
+    }
+}
+
+impl<K, V, T, R> Clone for BatchBox<K, V, T, R> {
+    fn clone(&self) -> Self {
+        BatchBox { batch: self.batch.clone() }
+    }
+}
+
+impl<K, V, T, R> BatchReader<K, V, T, R> for BatchBox<K, V, T, R> {
+    type Cursor = CursorBox<K, V, T, R>;
+    fn cursor(&self) -> Self::Cursor { (*self.batch).cursor() }
+    fn len(&self) -> usize { (*self.batch).len() }
+    fn description(&self) -> &Description<T> { (*self.batch).description() }
+}
+
+/// An object-safe version of `TraceReader`.
+trait TraceDyn<K, V, T, R> {
+    fn cursor_through(&mut self, upper: &[T]) -> Option<CursorBox<K, V, T, R>>;
+    fn advance_by(&mut self, frontier: &[T]);
+    fn advance_frontier(&mut self) -> &[T];
+    fn distinguish_since(&mut self, frontier: &[T]);
+    fn distinguish_frontier(&mut self) -> &[T];
+    fn map_batches(&mut self, logic: &mut FnMut(&BatchBox<K, V, T, R>));
+}
+
+impl<K, V, T, R, Tr: TraceReader<K, V, T, R>> TraceDyn<K, V, T, R> for Tr
+where Tr::Cursor: 'static, <Tr::Batch as BatchReader<K, V, T, R>>::Cursor: 'static {
+    fn cursor_through(&mut self, upper: &[T]) -> Option<CursorBox<K, V, T, R>> {
+        TraceReader::cursor_through(self, upper).map(|cursor| CursorBox::new(cursor))
+    }
+    fn advance_by(&mut self, frontier: &[T]) { TraceReader::advance_by(self, frontier) }
+    fn advance_frontier(&mut self) -> &[T] { TraceReader::advance_frontier(self) }
+    fn distinguish_since(&mut self, frontier: &[T]) { TraceReader::distinguish_since(self, frontier) }
+    fn distinguish_frontier(&mut self) -> &[T] { TraceReader::distinguish_frontier(self) }
+    fn map_batches(&mut self, logic: &mut FnMut(&BatchBox<K, V, T, R>)) {
+        TraceReader::map_batches(self, |batch| logic(&BatchBox::new(batch.clone())))
+    }
+}
+
+/// A trace of any type, for fixed key, value, time, and difference types.
+///
+/// The batches and cursors of the trace are `BatchBox` and `CursorBox`, respectively.
+pub struct TraceReaderBox<K, V, T, R> {
+    trace: Box<TraceDyn<K, V, T, R>>,
+}
+
+impl<K, V, T, R> TraceReaderBox<K, V, T, R> {
+    /// Boxes a trace.
+    pub fn new<Tr: TraceReader<K, V, T, R>+'static>(trace: Tr) -> Self
+    where Tr::Cursor: 'static, <Tr::Batch as BatchReader<K, V, T, R>>::Cursor: 'static {
+        TraceReaderBox { trace: Box::new(trace) }
+    }
+}
+
+impl<K: 'static, V: 'static, T: 'static, R: 'static> TraceReader<K, V, T, R> for TraceReaderBox<K, V, T, R> {
+    type Batch = BatchBox<K, V, T, R>;
+    type Cursor = CursorBox<K, V, T, R>;
+    fn cursor_through(&mut self, upper: &[T]) -> Option<Self::Cursor> { self.trace.cursor_through(upper) }
+    fn advance_by(&mut self, frontier: &[T]) { self.trace.advance_by(frontier) }
+    fn advance_frontier(&mut self) -> &[T] { self.trace.advance_frontier() }
+    fn distinguish_since(&mut self, frontier: &[T]) { self.trace.distinguish_since(frontier) }
+    fn distinguish_frontier(&mut self) -> &[T] { self.trace.distinguish_frontier() }
+    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) { self.trace.map_batches(&mut f) }
+}
@@ -1,197 +1,53 @@
 //! Wrappers to provide trace access to nested scopes.
+//!
+//! These wrappers are instances of the `map_time` wrappers, which extend each time with a default
+//! inner coordinate and map frontiers back to the outer scope by discarding the inner coordinate.
 
-use timely::progress::nested::product::Product;
-
-use lattice::Lattice;
-use trace::{TraceReader, BatchReader, Description};
-use trace::cursor::Cursor;
-
-/// Wrapper to provide trace to nested scope.
-pub struct TraceEnter<K, V, T, R, Tr, TInner> where Tr: TraceReader<K, V, T, R>, T: Lattice+Clone+'static {
-    phantom: ::std::marker::PhantomData<(K, V, R, TInner)>,
-    trace: Tr,
-    stash1: Vec<T>,
-    stash2: Vec<Product<T, TInner>>,
-}
-
-impl<K,V,T,R,Tr,TInner> Clone for TraceEnter<K, V, T, R, Tr, TInner> where Tr: TraceReader<K, V, T, R>+Clone, T: Lattice+Clone+'static {
-    fn clone(&self) -> Self {
-        TraceEnter {
-            phantom: ::std::marker::PhantomData,
-            trace: self.trace.clone(),
-            stash1: Vec::new(),
-            stash2: Vec::new(),
-        }
-    }
-}
+use std::rc::Rc;
 
-impl<K, V, T, R, Tr, TInner> TraceReader<K, V, Product<T, TInner>, R> for TraceEnter<K, V, T, R, Tr, TInner>
-where
-    Tr: TraceReader<K, V, T, R>, 
-    Tr::Batch: Clone, 
-    K: 'static, 
-    V: 'static, 
-    T: Lattice+Clone+Default+'static, 
-    TInner: Clone+Default+'static, 
-    R: 'static {
+use timely::progress::nested::product::Product;
 
-    type Batch = BatchEnter<K, V, T, R, Tr::Batch, TInner>;
-    type Cursor = CursorEnter<K, V, T, R, Tr::Cursor, TInner>;
+use trace::{TraceReader, BatchReader};
+use trace::wrappers::map_time::{TraceMapTime, BatchMapTime, CursorMapTime, TimeMap, FrontierMap};
+use trace::wrappers::leave::LeaveTime;
 
-    fn map_batches<F: FnMut(&Self::Batch)>(&mut self, mut f: F) { 
-        self.trace.map_batches(|batch| {
-            f(&Self::Batch::make_from(batch.clone()));
-        })
-    }
+/// Logic extending an outer time with a default inner coordinate.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EnterTime;
 
-    fn advance_by(&mut self, frontier: &[Product<T, TInner>]) { 
-        self.stash1.clear();
-        for time in frontier.iter() {
-            self.stash1.push(time.outer.clone());
-        }
-        self.trace.advance_by(&self.stash1[..]);
-    }
-    fn advance_frontier(&mut self) -> &[Product<T, TInner>] {
-        self.stash2.clear();
-        for time in self.trace.advance_frontier().iter() {
-            self.stash2.push(Product::new(time.clone(), Default::default()));
-        }
-        &self.stash2[..]
-    }
-
-    fn distinguish_since(&mut self, frontier: &[Product<T, TInner>]) { 
-        self.stash1.clear();
-        for time in frontier.iter() {
-            self.stash1.push(time.outer.clone());
-        }
-        self.trace.distinguish_since(&self.stash1[..]);
-    }
-    fn distinguish_frontier(&mut self) -> &[Product<T, TInner>] {
-        self.stash2.clear();
-        for time in self.trace.distinguish_frontier().iter() {
-            self.stash2.push(Product::new(time.clone(), Default::default()));
-        }
-        &self.stash2[..]
-    }
-
-    fn cursor_through(&mut self, upper: &[Product<T, TInner>]) -> Option<Self::Cursor> {
-        self.stash1.clear();
-        for time in upper.iter() {
-            self.stash1.push(time.outer.clone());
-        }
-        self.trace.cursor_through(&self.stash1[..]).map(|x| CursorEnter::new(x))
+impl<T: Clone, TInner: Default> TimeMap<T, Product<T, TInner>> for EnterTime {
+    #[inline(always)]
+    fn map_time(&self, time: &T) -> Option<Product<T, TInner>> {
+        Some(Product::new(time.clone(), Default::default()))
     }
 }
 
-impl<K, V, T, R, Tr, TInner> TraceEnter<K, V, T, R, Tr, TInner>
-where Tr: TraceReader<K, V, T, R>, Tr::Batch: Clone, K: 'static, V: 'static, T: Lattice+Clone+Default+'static, TInner: Clone+Default+'static, R: 'static {
-    /// Makes a new trace wrapper
-    pub fn make_from(trace: Tr) -> Self {
-        TraceEnter {
-            phantom: ::std::marker::PhantomData,
-            trace: trace,
-            stash1: Vec::new(),
-            stash2: Vec::new(),
-        }
+impl<T: Clone, TInner: Default> FrontierMap<T, Product<T, TInner>> for EnterTime {
+    #[inline(always)]
+    fn map_frontier(&self, time: &T) -> Product<T, TInner> {
+        Product::new(time.clone(), Default::default())
     }
 }
 
-
+/// Wrapper to provide trace to nested scope.
+pub type TraceEnter<K, V, T, R, Tr, TInner> = TraceMapTime<K, V, T, R, Tr, Product<T, TInner>, EnterTime, LeaveTime>;
 /// Wrapper to provide batch to nested scope.
-pub struct BatchEnter<K, V, T, R, B, TInner> {
-    phantom: ::std::marker::PhantomData<(K, V, R)>,
-    batch: B,
-    description: Description<Product<T, TInner>>,
-}
+pub type BatchEnter<K, V, T, R, B, TInner> = BatchMapTime<K, V, T, R, B, Product<T, TInner>, EnterTime>;
+/// Wrapper to provide cursor to nested scope.
+pub type CursorEnter<K, V, T, R, C, TInner> = CursorMapTime<K, V, T, R, C, Product<T, TInner>, EnterTime>;
 
-impl<K, V, T: Clone, R, B: Clone, TInner: Clone> Clone for BatchEnter<K, V, T, R, B, TInner> {
-    fn clone(&self) -> Self { 
-        BatchEnter {
-            phantom: ::std::marker::PhantomData,
-            batch: self.batch.clone(),
-            description: self.description.clone(),
-        }
+impl<K, V, T, R, Tr, TInner> TraceMapTime<K, V, T, R, Tr, Product<T, TInner>, EnterTime, LeaveTime>
+where Tr: TraceReader<K, V, T, R>, T: Clone, TInner: Default {
+    /// Makes a new trace wrapper
+    pub fn make_from(trace: Tr) -> Self {
+        TraceMapTime::new(trace, EnterTime, LeaveTime)
     }
 }
 
-impl<K, V, T, R, B, TInner> BatchReader<K, V, Product<T, TInner>, R> for BatchEnter<K, V, T, R, B, TInner> 
-where B: BatchReader<K, V, T, R>, T: Clone+Default, TInner: Default {
-
-    type Cursor = CursorEnter<K, V, T, R, B::Cursor, TInner>;
-
-    fn cursor(&self) -> Self::Cursor { CursorEnter::new(self.batch.cursor()) }
-    fn len(&self) -> usize { self.batch.len() }
-    fn description(&self) -> &Description<Product<T, TInner>> { &self.description }
-}
-
-impl<K, V, T, R, B, TInner> BatchEnter<K, V, T, R, B, TInner> 
+impl<K, V, T, R, B, TInner> BatchMapTime<K, V, T, R, B, Product<T, TInner>, EnterTime>
 where B: BatchReader<K, V, T, R>, T: Clone, TInner: Clone+Default {
     /// Makes a new batch wrapper
     pub fn make_from(batch: B) -> Self {
-        let lower: Vec<_> = batch.description().lower().iter().map(|x| Product::new((*x).clone(), Default::default())).collect();
-        let upper: Vec<_> = batch.description().upper().iter().map(|x| Product::new((*x).clone(), Default::default())).collect();
-        let since: Vec<_> = batch.description().since().iter().map(|x| Product::new((*x).clone(), Default::default())).collect();
-
-        BatchEnter {
-            phantom: ::std::marker::PhantomData,
-            batch: batch,
-            description: Description::new(&lower[..], &upper[..], &since[..])
-        }
-    }
-}
-
-/// Wrapper to provide cursor to nested scope.
-pub struct CursorEnter<K, V, T, R, C: Cursor<K, V, T, R>, TInner> {
-    phantom: ::std::marker::PhantomData<(K, V, R)>,
-    cursor: C,
-    time: Product<T, TInner>,
-}
-
-impl<K, V, T, R, C: Cursor<K, V, T, R>, TInner> CursorEnter<K, V, T, R, C, TInner> where T: Default, TInner: Default {
-    fn new(cursor: C) -> Self {
-        CursorEnter {
-            phantom: ::std::marker::PhantomData,
-            cursor: cursor,
-            time: Default::default(),
-        }
+        BatchMapTime::new(batch, Rc::new(EnterTime))
     }
 }
-
-impl<K, V, T, R, C: Cursor<K, V, T, R>, TInner> Cursor<K, V, Product<T, TInner>, R> for CursorEnter<K, V, T, R, C, TInner> where T: Clone {
-
-    #[inline(always)]
-    fn key_valid(&self) -> bool { self.cursor.key_valid() }
-    #[inline(always)]
-    fn val_valid(&self) -> bool { self.cursor.val_valid() }
-
-    #[inline(always)]
-    fn key(&self) -> &K { self.cursor.key() }
-    #[inline(always)]
-    fn val(&self) -> &V { self.cursor.val() }
-
-    #[inline(always)]
-    fn map_times<L: FnMut(&Product<T, TInner>, R)>(&mut self, mut logic: L) {
-
-        let stash = &mut self.time;
-        self.cursor.map_times(|time, diff| {
-            stash.outer = time.clone();
-            logic(stash, diff)
-        })
-
-    }
-
-    #[inline(always)]
-    fn step_key(&mut self) { self.cursor.step_key() }
-    #[inline(always)]
-    fn seek_key(&mut self, key: &K) { self.cursor.seek_key(key) }
-    
-    #[inline(always)]
-    fn step_val(&mut self) { self.cursor.step_val() }
-    #[inline(always)]
-    fn seek_val(&mut self, val: &V) { self.cursor.seek_val(val) }
-
-    #[inline(always)]
-    fn rewind_keys(&mut self) { self.cursor.rewind_keys() }
-    #[inline(always)]
-    fn rewind_vals(&mut self) { self.cursor.rewind_vals() }
-}
\ No newline at end of file
@@ -0,0 +1,70 @@
+//! Wrappers to provide trace access from nested scopes to their containing scope.
+//!
+//! These wrappers are instances of the `map_time` wrappers, which discard the inner coordinate of each
+//! time and map frontiers into the nested scope by extending them with a default inner coordinate. The
+//! result is that updates at all inner coordinates are accumulated together, which is most sensible when
+//! the arranged collection does not vary with the inner coordinate (for example, a loop-invariant index
+//! built inside an iterative scope).
+//!
+//! Discarding the inner coordinate of a batch's frontiers does not describe the batch: a batch from `(t, 0)`
+//! to `(t, 1)` would claim to span from `t` to `t`. Batches leaving a scope are instead described by the outer
+//! frontiers between which they are presented, which `Arranged::leave` does only once their outer times are
+//! complete.
+
+use std::rc::Rc;
+
+use timely::progress::nested::product::Product;
+
+use trace::{TraceReader, BatchReader, Description};
+use trace::wrappers::map_time::{TraceMapTime, BatchMapTime, CursorMapTime, TimeMap, FrontierMap};
+use trace::wrappers::enter::EnterTime;
+
+/// Logic discarding the inner coordinate of a time.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LeaveTime;
+
+impl<T: Clone, TInner> TimeMap<Product<T, TInner>, T> for LeaveTime {
+    #[inline(always)]
+    fn map_time(&self, time: &Product<T, TInner>) -> Option<T> {
+        Some(time.outer.clone())
+    }
+}
+
+impl<T: Clone, TInner> FrontierMap<Product<T, TInner>, T> for LeaveTime {
+    #[inline(always)]
+    fn map_frontier(&self, time: &Product<T, TInner>) -> T {
+        time.outer.clone()
+    }
+}
+
+/// Wrapper to provide trace to the containing scope.
+pub type TraceLeave<K, V, T, R, Tr, TInner> = TraceMapTime<K, V, Product<T, TInner>, R, Tr, T, LeaveTime, EnterTime>;
+/// Wrapper to provide batch to the containing scope.
+pub type BatchLeave<K, V, T, R, B, TInner> = BatchMapTime<K, V, Product<T, TInner>, R, B, T, LeaveTime>;
+/// Wrapper to provide cursor to the containing scope.
+pub type CursorLeave<K, V, T, R, C, TInner> = CursorMapTime<K, V, Product<T, TInner>, R, C, T, LeaveTime>;
+
+impl<K, V, T, R, Tr, TInner> TraceMapTime<K, V, Product<T, TInner>, R, Tr, T, LeaveTime, EnterTime>
+where Tr: TraceReader<K, V, Product<T, TInner>, R>, T: Clone, TInner: Default {
+    /// Makes a new trace wrapper
+    pub fn make_from(trace: Tr) -> Self {
+        TraceMapTime::new(trace, LeaveTime, EnterTime)
+    }
+}
+
+impl<K, V, T, R, B, TInner> BatchMapTime<K, V, Product<T, TInner>, R, B, T, LeaveTime>
+where B: BatchReader<K, V, Product<T, TInner>, R>, T: Clone {
+    /// Makes a new batch wrapper
+    pub fn make_from(batch: B) -> Self {
+        BatchMapTime::new(batch, Rc::new(LeaveTime))
+    }
+    /// Makes a new batch wrapper describing the batch as spanning outer times from `lower` to `upper`.
+    ///
+    /// All updates in the batch should have outer times greater or equal to an element of `lower`, and not
+    /// greater or equal to any element of `upper`.
+    pub fn make_through(batch: B, lower: &[T], upper: &[T]) -> Self {
+        let since: Vec<_> = batch.description().since().iter().map(|x| x.outer.clone()).collect();
+        let description = Description::new(lower, upper, &since[..]);
+        BatchMapTime::with_description(batch, Rc::new(LeaveTime), description)
+    }
+}
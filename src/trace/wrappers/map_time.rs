@@ -0,0 +1,283 @@
+//! Wrappers to present a trace with transformed times.
+//!
+//! The `TraceMapTime` wrapper presents the updates of a trace with each time transformed by a function
+//! `logic: Fn(&T) -> Option<T2>`. Updates whose times map to `None` are suppressed. As readers of the
+//! wrapper speak in terms of `T2` when they advance their frontiers, the wrapper also requires a function
+//! `frontier: Fn(&T2) -> T` which maps these frontier elements back to times of the wrapped trace.
+//!
+//! For the wrapper to behave sensibly, the two functions should be related: for any `t1: T` and `t2: T2`
+//! where `logic(t1)` is `Some(t)`, we need `t.less_equal(t2)` only if `t1.less_equal(frontier(t2))`. The
+//! wrapper does not check this, and it is up to the user to ensure it holds. The `enter` wrapper, which
+//! extends times with a new default coordinate and maps frontiers back by discarding the coordinate, is
+//! one example of such a pair.
+//!
+//! The functions are described by the `TimeMap` and `FrontierMap` traits, which closures implement. Wrappers
+//! with named types, such as `enter` and `leave`, implement them for unit structs instead, so that their
+//! logic is statically dispatched and can be inlined.
+
+use std::rc::Rc;
+
+use trace::{TraceReader, BatchReader, Description};
+use trace::cursor::Cursor;
+
+/// Logic transforming a time, or suppressing updates at the time by returning `None`.
+pub trait TimeMap<T, T2> {
+    /// Transforms `time`.
+    fn map_time(&self, time: &T) -> Option<T2>;
+}
+
+impl<T, T2, F: TimeMap<T, T2>> TimeMap<T, T2> for F {
+    #[inline(always)]
+    fn map_time(&self, time: &T) -> Option<T2> { self(time) }
+}
+
+/// Logic mapping a frontier element of transformed times back to a time of the wrapped trace.
+pub trait FrontierMap<T2, T> {
+    /// Maps `time` back to a time of the wrapped trace.
+    fn map_frontier(&self, time: &T2) -> T;
+}
+
+impl<T2, T, B: FrontierMap<T2, T>> FrontierMap<T2, T> for B {
+    #[inline(always)]
+    fn map_frontier(&self, time: &T2) -> T { self(time) }
+}
+
+/// Wrapper to present a trace with transformed times.
+pub struct TraceMapTime<K, V, T, R, Tr, T2, F, B>
+where Tr: TraceReader<K, V, T, R>, F: TimeMap<T, T2>, B: FrontierMap<T2, T> {
+    phantom: ::std::marker::PhantomData<(K, V, R)>,
+    trace: Tr,
+    logic: Rc<F>,
+    frontier: Rc<B>,
+    stash1: Vec<T>,
+    stash2: Vec<T2>,
+}
+
+impl<K, V, T, R, Tr, T2, F, B> Clone for TraceMapTime<K, V, T, R, Tr, T2, F, B>
+where Tr: TraceReader<K, V, T, R>+Clone, F: TimeMap<T, T2>, B: FrontierMap<T2, T> {
+    fn clone(&self) -> Self {
+        TraceMapTime {
+            phantom: ::std::marker::PhantomData,
+            trace: self.trace.clone(),
+            logic: self.logic.clone(),
+            frontier: self.frontier.clone(),
+            stash1: Vec::new(),
+            stash2: Vec::new(),
+        }
+    }
+}
+
+impl<K, V, T, R, Tr, T2, F, B> TraceReader<K, V, T2, R> for TraceMapTime<K, V, T, R, Tr, T2, F, B>
+where
+    Tr: TraceReader<K, V, T, R>,
+    K: 'static,
+    V: 'static,
+    T: Clone+'static,
+    T2: Clone+'static,
+    R: 'static,
+    F: TimeMap<T, T2>+'static,
+    B: FrontierMap<T2, T>+'static {
+
+    type Batch = BatchMapTime<K, V, T, R, Tr::Batch, T2, F>;
+    type Cursor = CursorMapTime<K, V, T, R, Tr::Cursor, T2, F>;
+
+    fn map_batches<L: FnMut(&Self::Batch)>(&mut self, mut f: L) {
+        let logic = self.logic.clone();
+        self.trace.map_batches(|batch| {
+            f(&BatchMapTime::new(batch.clone(), logic.clone()));
+        })
+    }
+
+    fn advance_by(&mut self, frontier: &[T2]) {
+        self.stash1.clear();
+        for time in frontier.iter() {
+            self.stash1.push(self.frontier.map_frontier(time));
+        }
+        self.trace.advance_by(&self.stash1[..]);
+    }
+    fn advance_frontier(&mut self) -> &[T2] {
+        self.stash2.clear();
+        for time in self.trace.advance_frontier().iter() {
+            if let Some(time) = self.logic.map_time(time) {
+                self.stash2.push(time);
+            }
+        }
+        &self.stash2[..]
+    }
+
+    fn distinguish_since(&mut self, frontier: &[T2]) {
+        self.stash1.clear();
+        for time in frontier.iter() {
+            self.stash1.push(self.frontier.map_frontier(time));
+        }
+        self.trace.distinguish_since(&self.stash1[..]);
+    }
+    fn distinguish_frontier(&mut self) -> &[T2] {
+        self.stash2.clear();
+        for time in self.trace.distinguish_frontier().iter() {
+            if let Some(time) = self.logic.map_time(time) {
+                self.stash2.push(time);
+            }
+        }
+        &self.stash2[..]
+    }
+
+    fn cursor_through(&mut self, upper: &[T2]) -> Option<Self::Cursor> {
+        self.stash1.clear();
+        for time in upper.iter() {
+            self.stash1.push(self.frontier.map_frontier(time));
+        }
+        let logic = self.logic.clone();
+        self.trace.cursor_through(&self.stash1[..]).map(move |x| CursorMapTime::new(x, logic))
+    }
+}
+
+impl<K, V, T, R, Tr, T2, F, B> TraceMapTime<K, V, T, R, Tr, T2, F, B>
+where Tr: TraceReader<K, V, T, R>, F: TimeMap<T, T2>, B: FrontierMap<T2, T> {
+    /// Makes a new trace wrapper from a trace, time logic, and frontier logic.
+    pub fn new(trace: Tr, logic: F, frontier: B) -> Self {
+        TraceMapTime {
+            phantom: ::std::marker::PhantomData,
+            trace: trace,
+            logic: Rc::new(logic),
+            frontier: Rc::new(frontier),
+            stash1: Vec::new(),
+            stash2: Vec::new(),
+        }
+    }
+    /// Reveals the wrapped trace.
+    pub fn inner(&self) -> &Tr { &self.trace }
+    /// Reveals the wrapped trace, mutably.
+    ///
+    /// Advancing the frontiers of the wrapped trace directly bypasses the wrapper's frontier logic.
+    pub fn inner_mut(&mut self) -> &mut Tr { &mut self.trace }
+}
+
+
+/// Wrapper to present a batch with transformed times.
+pub struct BatchMapTime<K, V, T, R, B, T2, F> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R)>,
+    batch: B,
+    logic: Rc<F>,
+    description: Description<T2>,
+}
+
+impl<K, V, T, R, B: Clone, T2: Clone, F> Clone for BatchMapTime<K, V, T, R, B, T2, F> {
+    fn clone(&self) -> Self {
+        BatchMapTime {
+            phantom: ::std::marker::PhantomData,
+            batch: self.batch.clone(),
+            logic: self.logic.clone(),
+            description: self.description.clone(),
+        }
+    }
+}
+
+impl<K, V, T, R, B, T2, F> BatchReader<K, V, T2, R> for BatchMapTime<K, V, T, R, B, T2, F>
+where B: BatchReader<K, V, T, R>, F: TimeMap<T, T2> {
+
+    type Cursor = CursorMapTime<K, V, T, R, B::Cursor, T2, F>;
+
+    fn cursor(&self) -> Self::Cursor { CursorMapTime::new(self.batch.cursor(), self.logic.clone()) }
+    fn len(&self) -> usize { self.batch.len() }
+    fn description(&self) -> &Description<T2> { &self.description }
+}
+
+impl<K, V, T, R, B, T2, F> BatchMapTime<K, V, T, R, B, T2, F>
+where B: BatchReader<K, V, T, R>, T2: Clone, F: TimeMap<T, T2> {
+    /// Makes a new batch wrapper from a batch and shared time logic.
+    ///
+    /// The description of the batch is transformed by `logic`, with times mapping to `None` removed
+    /// from each frontier. The lower frontier should not be entirely removed. If `logic` maps the lower and
+    /// upper frontiers to the same frontier the description is degenerate, and `with_description` should be
+    /// used instead.
+    pub fn new(batch: B, logic: Rc<F>) -> Self {
+        let lower: Vec<_> = batch.description().lower().iter().filter_map(|x| logic.map_time(x)).collect();
+        let upper: Vec<_> = batch.description().upper().iter().filter_map(|x| logic.map_time(x)).collect();
+        let since: Vec<_> = batch.description().since().iter().filter_map(|x| logic.map_time(x)).collect();
+
+        BatchMapTime {
+            phantom: ::std::marker::PhantomData,
+            batch: batch,
+            logic: logic,
+            description: Description::new(&lower[..], &upper[..], &since[..])
+        }
+    }
+    /// Makes a new batch wrapper from a batch, shared time logic, and the description of the transformed batch.
+    ///
+    /// Transforming the frontiers of a description need not produce a sensible description; for example,
+    /// discarding a time coordinate can map distinct `lower` and `upper` frontiers to the same frontier. This
+    /// constructor allows the caller, who may know more about the batch, to describe it directly.
+    pub fn with_description(batch: B, logic: Rc<F>, description: Description<T2>) -> Self {
+        BatchMapTime {
+            phantom: ::std::marker::PhantomData,
+            batch: batch,
+            logic: logic,
+            description: description,
+        }
+    }
+    /// Reveals the wrapped batch.
+    pub fn inner(&self) -> &B { &self.batch }
+}
+
+/// Wrapper to present a cursor with transformed times.
+pub struct CursorMapTime<K, V, T, R, C: Cursor<K, V, T, R>, T2, F> {
+    phantom: ::std::marker::PhantomData<(K, V, T, R, T2)>,
+    cursor: C,
+    logic: Rc<F>,
+}
+
+impl<K, V, T, R, C: Cursor<K, V, T, R>, T2, F> CursorMapTime<K, V, T, R, C, T2, F> {
+    fn new(cursor: C, logic: Rc<F>) -> Self {
+        CursorMapTime {
+            phantom: ::std::marker::PhantomData,
+            cursor: cursor,
+            logic: logic,
+        }
+    }
+}
+
+impl<K, V, T, R, C: Cursor<K, V, T, R>, T2, F> Clone for CursorMapTime<K, V, T, R, C, T2, F> {
+    fn clone(&self) -> Self {
+        CursorMapTime::new(self.cursor.clone(), self.logic.clone())
+    }
+}
+
+impl<K, V, T, R, C: Cursor<K, V, T, R>, T2, F> Cursor<K, V, T2, R> for CursorMapTime<K, V, T, R, C, T2, F>
+where F: TimeMap<T, T2> {
+
+    #[inline(always)]
+    fn key_valid(&self) -> bool { self.cursor.key_valid() }
+    #[inline(always)]
+    fn val_valid(&self) -> bool { self.cursor.val_valid() }
+
+    #[inline(always)]
+    fn key(&self) -> &K { self.cursor.key() }
+    #[inline(always)]
+    fn val(&self) -> &V { self.cursor.val() }
+
+    #[inline(always)]
+    fn map_times<L: FnMut(&T2, R)>(&mut self, mut logic: L) {
+        let map = &*self.logic;
+        self.cursor.map_times(|time, diff| {
+            if let Some(time) = map.map_time(time) {
+                logic(&time, diff);
+            }
+        })
+    }
+
+    #[inline(always)]
+    fn step_key(&mut self) { self.cursor.step_key() }
+    #[inline(always)]
+    fn seek_key(&mut self, key: &K) { self.cursor.seek_key(key) }
+
+    #[inline(always)]
+    fn step_val(&mut self) { self.cursor.step_val() }
+    #[inline(always)]
+    fn seek_val(&mut self, val: &V) { self.cursor.seek_val(val) }
+
+    #[inline(always)]
+    fn rewind_keys(&mut self) { self.cursor.rewind_keys() }
+    #[inline(always)]
+    fn rewind_vals(&mut self) { self.cursor.rewind_vals() }
+}
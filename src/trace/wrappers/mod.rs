@@ -1,4 +1,8 @@
 //! Wrappers around trace implementations, providing derived views of updates.
 
+pub mod boxed;
 pub mod enter;
-pub mod rc;
\ No newline at end of file
+pub mod leave;
+pub mod map_time;
+pub mod rc;
+pub mod window;
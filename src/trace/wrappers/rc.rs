@@ -12,7 +12,7 @@
 //! exception that the trace may not compact its representation as fast as if it were exclusively owned.
 
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
 use timely::progress::frontier::MutableAntichain;
 
@@ -33,6 +33,71 @@ where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
     pub through_frontiers: MutableAntichain<T>,
     /// The wrapped trace.
     pub trace: Tr,
+    /// Whether frontier changes are withheld from the wrapped trace.
+    paused: bool,
+    /// Requested truncations not yet physically complete.
+    truncations: Vec<(Vec<T>, Truncation)>,
+    /// Counts of batches and updates inserted into the wrapped trace.
+    insertions: Insertions,
+    /// Functions informed of changes to the wrapped trace.
+    callbacks: Vec<Box<FnMut(&TraceChange<T>)>>,
+    /// The advance and through frontiers most recently given to the wrapped trace.
+    reported: (Vec<T>, Vec<T>),
+}
+
+/// A change to a shared trace, reported to callbacks registered with its `TraceBox`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TraceChange<T> {
+    /// A batch of `updates` updates spanning `lower` to `upper` was inserted.
+    Inserted {
+        /// The lower bound of the batch's times.
+        lower: Vec<T>,
+        /// The upper bound of the batch's times.
+        upper: Vec<T>,
+        /// The number of updates in the batch.
+        updates: usize,
+    },
+    /// The trace may now compact times not in advance of the frontier.
+    Compacted(Vec<T>),
+    /// The trace may now merge batches whose upper bounds are not in advance of the frontier.
+    Distinguished(Vec<T>),
+}
+
+/// Counts of the batches and updates inserted into a trace.
+#[derive(Clone, Debug, Default)]
+pub struct Insertions {
+    /// The number of batches inserted.
+    pub batches: usize,
+    /// The number of updates inserted.
+    pub updates: usize,
+    /// A moving average of the number of updates in recently inserted batches.
+    ///
+    /// Each inserted batch contributes one eighth of the average, so the average reflects roughly the
+    /// most recent eight batches.
+    pub recent: f64,
+}
+
+impl Insertions {
+    /// Records the insertion of a batch of `updates` updates.
+    pub fn record(&mut self, updates: usize) {
+        self.recent = if self.batches == 0 { updates as f64 } else { (7.0 * self.recent + updates as f64) / 8.0 };
+        self.batches += 1;
+        self.updates += updates;
+    }
+}
+
+/// A handle reporting whether a requested truncation of a trace has physically completed.
+///
+/// The handle is returned by `TraceAgent::truncate_before`, and indicates completion once no updates at times
+/// before the truncation frontier remain in the trace in their original form.
+#[derive(Clone, Debug)]
+pub struct Truncation {
+    complete: Rc<Cell<bool>>,
+}
+
+impl Truncation {
+    /// Indicates whether updates before the truncation frontier have been physically removed.
+    pub fn is_complete(&self) -> bool { self.complete.get() }
 }
 
 impl<K,V,T,R,Tr> TraceBox<K,V,T,R,Tr>
@@ -53,24 +118,126 @@ where T: Lattice+Clone+'static, Tr: TraceReader<K,V,T,R> {
             through.update(time, 1);
         }
 
+        let reported = (advance.elements().to_vec(), through.elements().to_vec());
+
         TraceBox {
             phantom: ::std::marker::PhantomData,
             advance_frontiers: advance,
             through_frontiers: through,
             trace: trace,
+            paused: false,
+            truncations: Vec::new(),
+            insertions: Insertions::default(),
+            callbacks: Vec::new(),
+            reported: reported,
         }
     }
     /// Replaces elements of `lower` with those of `upper`.
+    ///
+    /// With the `validate` feature, this panics if a non-empty `lower` is not behind `upper`.
     pub fn adjust_advance_frontier(&mut self, lower: &[T], upper: &[T]) {
+        validate_adjustment(lower, upper);
         for element in upper { self.advance_frontiers.update_and(element, 1, |_,_| {}); }
         for element in lower { self.advance_frontiers.update_and(element, -1, |_,_| {}); }
-        self.trace.advance_by(self.advance_frontiers.elements());
+        if !self.paused {
+            self.trace.advance_by(self.advance_frontiers.elements());
+            self.report();
+        }
+        self.truncate();
     }
     /// Replaces elements of `lower` with those of `upper`.
+    ///
+    /// With the `validate` feature, this panics if a non-empty `lower` is not behind `upper`.
     pub fn adjust_through_frontier(&mut self, lower: &[T], upper: &[T]) {
+        validate_adjustment(lower, upper);
         for element in upper { self.through_frontiers.update_and(element, 1, |_,_| {}); }
         for element in lower { self.through_frontiers.update_and(element, -1, |_,_| {}); }
-        self.trace.distinguish_since(self.through_frontiers.elements());
+        if !self.paused {
+            self.trace.distinguish_since(self.through_frontiers.elements());
+            self.report();
+        }
+        self.truncate();
+    }
+    /// Withholds changes to the referees' frontiers from the wrapped trace, until `resume` is called.
+    ///
+    /// While paused, the trace is neither permitted to compact updates nor to merge newly inserted batches
+    /// (for traces that, like `Spine`, merge batches only once they are no longer distinguished). This avoids
+    /// repeated merging during a bulk load, at the cost of holding every inserted batch separately.
+    pub fn pause(&mut self) { self.paused = true; }
+    /// Resumes informing the wrapped trace of the referees' frontiers, and informs it of the current frontiers.
+    pub fn resume(&mut self) {
+        if self.paused {
+            self.paused = false;
+            self.trace.advance_by(self.advance_frontiers.elements());
+            self.trace.distinguish_since(self.through_frontiers.elements());
+            self.report();
+            self.truncate();
+        }
+    }
+    /// Indicates whether frontier changes are currently withheld from the wrapped trace.
+    pub fn is_paused(&self) -> bool { self.paused }
+    /// Counts of batches and updates inserted into the wrapped trace, as recorded by `note_insertion`.
+    pub fn insertions(&self) -> &Insertions { &self.insertions }
+    /// Records the insertion of a batch of `updates` updates into the wrapped trace.
+    pub fn note_insertion(&mut self, updates: usize) { self.insertions.record(updates); }
+    /// Requests that updates at times not in advance of `frontier` be physically removed from the trace.
+    ///
+    /// The trace is compacted once every referee has advanced to `frontier`, and again as batches become
+    /// mergeable, until the returned handle reports completion.
+    pub fn request_truncation(&mut self, frontier: &[T]) -> Truncation {
+        let truncation = Truncation { complete: Rc::new(Cell::new(false)) };
+        self.truncations.push((frontier.to_vec(), truncation.clone()));
+        self.truncate();
+        truncation
+    }
+    // Compacts the trace if some requested truncation's frontier has been reached by all referees.
+    fn truncate(&mut self) {
+        if self.paused || self.truncations.is_empty() { return; }
+        let reached = {
+            let advance = self.advance_frontiers.elements();
+            let reached = |frontier: &[T]| advance.iter().all(|t1| frontier.iter().any(|t2| t2.less_equal(t1)));
+            self.truncations.iter().any(|x| reached(&x.0[..]))
+        };
+        if reached && self.trace.compact() {
+            let advance = self.advance_frontiers.elements().to_vec();
+            self.truncations.retain(|&(ref frontier, ref truncation)| {
+                let complete = advance.iter().all(|t1| frontier.iter().any(|t2| t2.less_equal(t1)));
+                if complete { truncation.complete.set(true); }
+                !complete
+            });
+        }
+    }
+    /// Registers `callback` to be informed of changes to the wrapped trace, for as long as the trace exists.
+    ///
+    /// Callbacks are called while the trace is borrowed, and must not access it through any handle.
+    pub fn add_callback(&mut self, callback: Box<FnMut(&TraceChange<T>)>) { self.callbacks.push(callback); }
+    /// Informs each registered callback of `change`.
+    pub fn notify(&mut self, change: &TraceChange<T>) {
+        for callback in self.callbacks.iter_mut() {
+            callback(change);
+        }
+    }
+    // Informs callbacks of frontiers given to the wrapped trace that differ from those last reported.
+    fn report(&mut self) {
+        if self.callbacks.is_empty() { return; }
+        if self.reported.0 != self.advance_frontiers.elements() {
+            self.reported.0 = self.advance_frontiers.elements().to_vec();
+            let change = TraceChange::Compacted(self.reported.0.clone());
+            self.notify(&change);
+        }
+        if self.reported.1 != self.through_frontiers.elements() {
+            self.reported.1 = self.through_frontiers.elements().to_vec();
+            let change = TraceChange::Distinguished(self.reported.1.clone());
+            self.notify(&change);
+        }
+    }
+}
+
+// Checks that a reader replacing its frontier `lower` with `upper` does not move backwards, under the `validate`
+// feature. An empty `lower` introduces a new reader, which may start anywhere.
+fn validate_adjustment<T: Lattice>(lower: &[T], upper: &[T]) {
+    if cfg!(feature = "validate") && !lower.is_empty() {
+        assert!(upper.iter().all(|t1| lower.iter().any(|t2| t2.less_equal(t1))), "validate: reader frontier regressed");
     }
 }
 
@@ -131,6 +298,12 @@ impl<K,V,T,R,Tr> TraceRc<K,V,T,R,Tr> where T: Lattice+Clone+'static, Tr: TraceRe
 
         (handle, wrapped)
     }
+    /// Applies `logic` to the shared trace.
+    ///
+    /// The trace is borrowed for the duration of `logic`, which must not access it through other handles.
+    pub fn with_inner<F: FnOnce(&Tr)->X, X>(&self, logic: F) -> X {
+        logic(&self.wrapper.borrow().trace)
+    }
 }
 
 impl<K, V, T: Lattice+Clone, R, Tr> Clone for TraceRc<K, V, T, R, Tr> where Tr: TraceReader<K, V, T, R> {
@@ -0,0 +1,201 @@
+//! Wrappers to present only recent updates of a trace.
+//!
+//! The `TraceWindow` wrapper hides updates at times that have fallen out of a window trailing the reader's
+//! advance frontier. The window is determined by a function `lag: Fn(&T) -> T` applied to each element of
+//! the frontier; updates at times less or equal to some lagged element are hidden.
+//!
+//! The wrapper only advances the wrapped trace to the lagged frontier, which allows the trace to compact
+//! updates before the window (they all collapse to the lagged frontier, where they are hidden) while still
+//! distinguishing the times of updates within the window.
+//!
+//! Updates do not produce retractions when they leave the window; a reader simply observes the contents
+//! of the window as of its most recent `advance_by`. The wrapper is intended for totally ordered times,
+//! for which "before the window" is unambiguous.
+
+use std::rc::Rc;
+
+use lattice::Lattice;
+use trace::{TraceReader, BatchReader, Description};
+use trace::cursor::Cursor;
+
+/// Wrapper to present only recent updates of a trace.
+pub struct TraceWindow<K, V, T, R, Tr, F> where Tr: TraceReader<K, V, T, R>, F: Fn(&T)->T {
+    phantom: ::std::marker::PhantomData<(K, V, R)>,
+    trace: Tr,
+    lag: Rc<F>,
+    advance: Vec<T>,
+    window: Rc<Vec<T>>,
+}
+
+impl<K, V, T, R, Tr, F> Clone for TraceWindow<K, V, T, R, Tr, F>
+where Tr: TraceReader<K, V, T, R>+Clone, T: Clone, F: Fn(&T)->T {
+    fn clone(&self) -> Self {
+        TraceWindow {
+            phantom: ::std::marker::PhantomData,
+            trace: self.trace.clone(),
+            lag: self.lag.clone(),
+            advance: self.advance.clone(),
+            window: self.window.clone(),
+        }
+    }
+}
+
+impl<K, V, T, R, Tr, F> TraceReader<K, V, T, R> for TraceWindow<K, V, T, R, Tr, F>
+where
+    Tr: TraceReader<K, V, T, R>,
+    K: 'static,
+    V: 'static,
+    T: Lattice+Clone+'static,
+    R: 'static,
+    F: Fn(&T)->T+'static {
+
+    type Batch = BatchWindow<K, V, T, R, Tr::Batch>;
+    type Cursor = CursorWindow<K, V, T, R, Tr::Cursor>;
+
+    fn map_batches<L: FnMut(&Self::Batch)>(&mut self, mut f: L) {
+        let window = self.window.clone();
+        self.trace.map_batches(|batch| {
+            f(&BatchWindow::make_from(batch.clone(), window.clone()));
+        })
+    }
+
+    fn advance_by(&mut self, frontier: &[T]) {
+        self.advance = frontier.to_vec();
+        self.window = Rc::new(frontier.iter().map(|t| (*self.lag)(t)).collect());
+        self.trace.advance_by(&self.window[..]);
+    }
+    fn advance_frontier(&mut self) -> &[T] { &self.advance[..] }
+
+    fn distinguish_since(&mut self, frontier: &[T]) { self.trace.distinguish_since(frontier) }
+    fn distinguish_frontier(&mut self) -> &[T] { self.trace.distinguish_frontier() }
+
+    fn cursor_through(&mut self, upper: &[T]) -> Option<Self::Cursor> {
+        let window = self.window.clone();
+        self.trace.cursor_through(upper).map(move |x| CursorWindow::make_from(x, window))
+    }
+}
+
+impl<K, V, T, R, Tr, F> TraceWindow<K, V, T, R, Tr, F>
+where Tr: TraceReader<K, V, T, R>, T: Clone, F: Fn(&T)->T {
+    /// Makes a new trace wrapper, which hides updates behind the frontier as lagged by `lag`.
+    pub fn make_from(mut trace: Tr, lag: F) -> Self {
+        let advance = trace.advance_frontier().to_vec();
+        let window = advance.iter().map(|t| lag(t)).collect();
+        TraceWindow {
+            phantom: ::std::marker::PhantomData,
+            trace: trace,
+            lag: Rc::new(lag),
+            advance: advance,
+            window: Rc::new(window),
+        }
+    }
+    /// Reveals the frontier at and before which updates are hidden.
+    pub fn window(&self) -> &[T] { &self.window[..] }
+    /// Reveals the wrapped trace.
+    pub fn inner(&self) -> &Tr { &self.trace }
+    /// Reveals the wrapped trace, mutably.
+    ///
+    /// Advancing the frontiers of the wrapped trace directly bypasses the wrapper's window.
+    pub fn inner_mut(&mut self) -> &mut Tr { &mut self.trace }
+}
+
+
+/// Wrapper to present only recent updates of a batch.
+pub struct BatchWindow<K, V, T, R, B> {
+    phantom: ::std::marker::PhantomData<(K, V, R)>,
+    batch: B,
+    window: Rc<Vec<T>>,
+}
+
+impl<K, V, T, R, B: Clone> Clone for BatchWindow<K, V, T, R, B> {
+    fn clone(&self) -> Self {
+        BatchWindow {
+            phantom: ::std::marker::PhantomData,
+            batch: self.batch.clone(),
+            window: self.window.clone(),
+        }
+    }
+}
+
+impl<K, V, T: Lattice, R, B: BatchReader<K, V, T, R>> BatchReader<K, V, T, R> for BatchWindow<K, V, T, R, B> {
+
+    type Cursor = CursorWindow<K, V, T, R, B::Cursor>;
+
+    fn cursor(&self) -> Self::Cursor { CursorWindow::make_from(self.batch.cursor(), self.window.clone()) }
+    fn len(&self) -> usize { self.batch.len() }
+    fn description(&self) -> &Description<T> { self.batch.description() }
+}
+
+impl<K, V, T, R, B: BatchReader<K, V, T, R>> BatchWindow<K, V, T, R, B> {
+    /// Makes a new batch wrapper, hiding updates at times less or equal to elements of `window`.
+    pub fn make_from(batch: B, window: Rc<Vec<T>>) -> Self {
+        BatchWindow {
+            phantom: ::std::marker::PhantomData,
+            batch: batch,
+            window: window,
+        }
+    }
+    /// Reveals the wrapped batch.
+    pub fn inner(&self) -> &B { &self.batch }
+}
+
+/// Wrapper to present only recent updates of a cursor.
+pub struct CursorWindow<K, V, T, R, C: Cursor<K, V, T, R>> {
+    phantom: ::std::marker::PhantomData<(K, V, R)>,
+    cursor: C,
+    window: Rc<Vec<T>>,
+}
+
+impl<K, V, T, R, C: Cursor<K, V, T, R>> CursorWindow<K, V, T, R, C> {
+    fn make_from(cursor: C, window: Rc<Vec<T>>) -> Self {
+        CursorWindow {
+            phantom: ::std::marker::PhantomData,
+            cursor: cursor,
+            window: window,
+        }
+    }
+}
+
+impl<K, V, T, R, C: Cursor<K, V, T, R>> Clone for CursorWindow<K, V, T, R, C> {
+    fn clone(&self) -> Self {
+        CursorWindow::make_from(self.cursor.clone(), self.window.clone())
+    }
+}
+
+impl<K, V, T: Lattice, R, C: Cursor<K, V, T, R>> Cursor<K, V, T, R> for CursorWindow<K, V, T, R, C> {
+
+    #[inline(always)]
+    fn key_valid(&self) -> bool { self.cursor.key_valid() }
+    #[inline(always)]
+    fn val_valid(&self) -> bool { self.cursor.val_valid() }
+
+    #[inline(always)]
+    fn key(&self) -> &K { self.cursor.key() }
+    #[inline(always)]
+    fn val(&self) -> &V { self.cursor.val() }
+
+    #[inline(always)]
+    fn map_times<L: FnMut(&T, R)>(&mut self, mut logic: L) {
+        let window = &self.window;
+        self.cursor.map_times(|time, diff| {
+            if !window.iter().any(|t| time.less_equal(t)) {
+                logic(time, diff);
+            }
+        })
+    }
+
+    #[inline(always)]
+    fn step_key(&mut self) { self.cursor.step_key() }
+    #[inline(always)]
+    fn seek_key(&mut self, key: &K) { self.cursor.seek_key(key) }
+
+    #[inline(always)]
+    fn step_val(&mut self) { self.cursor.step_val() }
+    #[inline(always)]
+    fn seek_val(&mut self, val: &V) { self.cursor.seek_val(val) }
+
+    #[inline(always)]
+    fn rewind_keys(&mut self) { self.cursor.rewind_keys() }
+    #[inline(always)]
+    fn rewind_vals(&mut self) { self.cursor.rewind_vals() }
+}
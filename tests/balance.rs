@@ -0,0 +1,86 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Input, Exchange, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::balance::{Balance, ArrangeBalanced};
+use differential_dataflow::trace::consolidate;
+
+#[test]
+fn balance_map_single_worker() {
+
+    let data = timely::example(|scope| {
+        let updates = vec![
+            ((0u64, 0u64), RootTimestamp::new(0), 1),
+            ((1u64, 0u64), RootTimestamp::new(0), 1),
+            ((0u64, 0u64), RootTimestamp::new(1), -1),
+        ];
+        updates.into_iter().to_stream(scope).as_collection().balance_map(4).inner.capture()
+    });
+
+    // with one worker, every bucket is assigned to it once, and never re-assigned.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, (0 .. 4).map(|bucket| ((bucket, 0), RootTimestamp::new(0), 1)).collect::<Vec<_>>());
+}
+
+// rounds of updates where key zero dominates, and is partly retracted.
+fn skewed() -> Vec<Vec<((u64, u64), isize)>> {
+    vec![
+        (0 .. 6).map(|val| ((0, val), 1)).chain((1 .. 4).map(|key| ((key, 0), 1))).collect(),
+        vec![((0, 0), -1), ((0, 1), -1), ((4, 0), 1), ((5, 0), 1)],
+        vec![((1, 0), -1), ((0, 9), 1), ((0, 2), -1)],
+    ]
+}
+
+#[test]
+fn arrange_balanced_moves_keys() {
+
+    let captured = timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let (mut input, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let updates = updates.as_collection();
+            let assignment = updates.balance_map(4);
+            let captured = updates.arrange_balanced(&assignment, 4)
+                                  .as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                  .inner
+                                  .exchange(|_| 0)
+                                  .capture();
+            (input, captured)
+        });
+
+        for (round, updates) in skewed().into_iter().enumerate() {
+            if round != input.time().inner {
+                input.advance_to(round);
+            }
+            if worker.index() == 0 {
+                for (data, diff) in updates {
+                    input.send((data, RootTimestamp::new(round), diff));
+                }
+            }
+        }
+        input.close();
+
+        captured
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    let mut output = captured.extract()
+                             .into_iter()
+                             .flat_map(|(_, data)| data)
+                             .map(|(data, time, diff)| ((data, time.inner), diff))
+                             .collect::<Vec<_>>();
+    consolidate(&mut output, 0);
+
+    // keys moved between workers are retracted from one and introduced at the other, which cancel.
+    let mut expected = skewed().into_iter()
+                               .enumerate()
+                               .flat_map(|(round, updates)| updates.into_iter().map(move |(data, diff)| ((data, round), diff)))
+                               .collect::<Vec<_>>();
+    consolidate(&mut expected, 0);
+    assert_eq!(output, expected);
+}
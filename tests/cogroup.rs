@@ -0,0 +1,154 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::cogroup_n::{CoGroup3, CoGroup4, CoGroup5, CoGroup6};
+
+#[test]
+fn cogroup3() {
+
+    let data = timely::example(|scope| {
+
+        let balances = vec![((0, 100i64), Default::default(), 1), ((1, 50), Default::default(), 1), ((2, 10), Default::default(), 1)]
+                            .into_iter().to_stream(scope).as_collection();
+        let debits = vec![((0, 30i64), Default::default(), 1), ((0, 20), Default::default(), 1), ((1, 5), Default::default(), 2)]
+                            .into_iter().to_stream(scope).as_collection();
+        let frozen = vec![((2, ()), Default::default(), 1)]
+                            .into_iter().to_stream(scope).as_collection();
+
+        // for each account, the balance less pending debits, unless the account is frozen.
+        balances.cogroup3(&debits, &frozen, |_account, balances, debits, frozen, output| {
+            if frozen.is_empty() {
+                let total = balances.iter().map(|&(b, c)| b * c as i64).sum::<i64>()
+                          - debits.iter().map(|&(d, c)| d * c as i64).sum::<i64>();
+                output.push((total, 1));
+            }
+        }).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0, 50), Default::default(), 1), ((1, 40), Default::default(), 1)]);
+}
+
+#[test]
+fn cogroup3_changes() {
+
+    let data = timely::example(|scope| {
+
+        let input1 = vec![((0, 'a'), RootTimestamp::new(0), 1), ((0, 'a'), RootTimestamp::new(1), -1)]
+                            .into_iter().to_stream(scope).as_collection();
+        let input2 = vec![((0, 1u64), RootTimestamp::new(0), 1), ((0, 2u64), RootTimestamp::new(0), 1)]
+                            .into_iter().to_stream(scope).as_collection();
+        let input3 = vec![((0, "x".to_owned()), RootTimestamp::new(1), 1)]
+                            .into_iter().to_stream(scope).as_collection();
+
+        // the number of values of each input, as a tuple.
+        input1.cogroup3(&input2, &input3, |_key, vals1, vals2, vals3, output| {
+            output.push(((vals1.len(), vals2.len(), vals3.len()), 1));
+        }).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((0, (0, 2, 1)), RootTimestamp::new(1), 1),
+        ((0, (1, 2, 0)), RootTimestamp::new(0), 1),
+        ((0, (1, 2, 0)), RootTimestamp::new(1), -1),
+    ]);
+}
+
+#[test]
+fn cogroup4() {
+
+    let data = timely::example(|scope| {
+
+        let input1 = vec![((0, 1u64), Default::default(), 1), ((1, 1u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input2 = vec![((0, 2u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input3 = vec![((0, 3u64), Default::default(), 1), ((2, 3u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input4 = vec![((0, 4u64), Default::default(), 1), ((0, 40u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+
+        // each value, tagged by the input presenting it.
+        input1.cogroup4(&input2, &input3, &input4, |_key, vals1, vals2, vals3, vals4, output| {
+            for &(val, diff) in vals1.iter() { output.push(((1, val), diff)); }
+            for &(val, diff) in vals2.iter() { output.push(((2, val), diff)); }
+            for &(val, diff) in vals3.iter() { output.push(((3, val), diff)); }
+            for &(val, diff) in vals4.iter() { output.push(((4, val), diff)); }
+        }).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![
+        ((0, (1, 1)), Default::default(), 1),
+        ((0, (2, 2)), Default::default(), 1),
+        ((0, (3, 3)), Default::default(), 1),
+        ((0, (4, 4)), Default::default(), 1),
+        ((0, (4, 40)), Default::default(), 1),
+        ((1, (1, 1)), Default::default(), 1),
+        ((2, (3, 3)), Default::default(), 1),
+    ]);
+}
+
+#[test]
+fn cogroup5() {
+
+    let data = timely::example(|scope| {
+
+        let input1 = vec![((0, 1u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input2 = vec![((0, 2u64), Default::default(), 3)].into_iter().to_stream(scope).as_collection();
+        let input3 = vec![((0, 3u64), Default::default(), 1), ((0, 3u64), Default::default(), -1)].into_iter().to_stream(scope).as_collection();
+        let input4 = vec![((1, 4u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input5 = vec![((0, 5u64), Default::default(), 2)].into_iter().to_stream(scope).as_collection();
+
+        // each value, tagged by the input presenting it, with its accumulated count.
+        input1.cogroup5(&input2, &input3, &input4, &input5, |_key, vals1, vals2, vals3, vals4, vals5, output| {
+            for &(val, diff) in vals1.iter() { output.push(((1, val, diff), 1)); }
+            for &(val, diff) in vals2.iter() { output.push(((2, val, diff), 1)); }
+            for &(val, diff) in vals3.iter() { output.push(((3, val, diff), 1)); }
+            for &(val, diff) in vals4.iter() { output.push(((4, val, diff), 1)); }
+            for &(val, diff) in vals5.iter() { output.push(((5, val, diff), 1)); }
+        }).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![
+        ((0, (1, 1, 1)), Default::default(), 1),
+        ((0, (2, 2, 3)), Default::default(), 1),
+        ((0, (5, 5, 2)), Default::default(), 1),
+        ((1, (4, 4, 1)), Default::default(), 1),
+    ]);
+}
+
+#[test]
+fn cogroup6() {
+
+    let data = timely::example(|scope| {
+
+        let input1 = vec![((0, 1u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input2 = vec![((0, 'b'), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input3 = vec![((0, "c".to_owned()), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input4 = vec![((0, 4i32), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input5 = vec![((0, true), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+        let input6 = vec![((0, 6usize), Default::default(), 1), ((1, 6usize), Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+
+        // the values of each input, in order, where each input has at most one value.
+        input1.cogroup6(&input2, &input3, &input4, &input5, &input6, |_key, vals1, vals2, vals3, vals4, vals5, vals6, output| {
+            let description = format!("{:?} {:?} {:?} {:?} {:?} {:?}",
+                vals1.first().map(|x| x.0), vals2.first().map(|x| x.0), vals3.first().map(|x| &x.0),
+                vals4.first().map(|x| x.0), vals5.first().map(|x| x.0), vals6.first().map(|x| x.0));
+            output.push((description, 1));
+        }).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![
+        ((0, "Some(1) Some('b') Some(\"c\") Some(4) Some(true) Some(6)".to_owned()), Default::default(), 1),
+        ((1, "None None None None None Some(6)".to_owned()), Default::default(), 1),
+    ]);
+}
@@ -0,0 +1,320 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::Duration;
+
+use timely::dataflow::operators::{ToStream, Input, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::collection::Retractions;
+use differential_dataflow::operators::Consolidate;
+use differential_dataflow::trace::consolidate;
+
+#[test]
+fn subtract() {
+
+    let data = timely::example(|scope| {
+
+        let a = vec![(0u64, RootTimestamp::new(0), 1), (1, RootTimestamp::new(0), 1), (1, RootTimestamp::new(1), -1)]
+                    .into_iter().to_stream(scope).as_collection();
+        let b = vec![(1u64, RootTimestamp::new(0), 1), (2, RootTimestamp::new(0), 1), (2, RootTimestamp::new(1), -1)]
+                    .into_iter().to_stream(scope).as_collection();
+
+        a.subtract(&b).consolidate().inner.capture()
+    });
+
+    // records of `b` absent from `a` have negative counts, until they are retracted.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        (0, RootTimestamp::new(0), 1),
+        (1, RootTimestamp::new(1), -1),
+        (2, RootTimestamp::new(0), -1),
+        (2, RootTimestamp::new(1), 1),
+    ]);
+}
+
+#[test]
+fn assert_empty_equal() {
+
+    timely::example(|scope| {
+
+        // the same collection, presented with different updates.
+        let a = vec![(0u64, RootTimestamp::new(0), 1), (1, RootTimestamp::new(0), 1), (1, RootTimestamp::new(1), -1)]
+                    .into_iter().to_stream(scope).as_collection();
+        let b = vec![(0u64, RootTimestamp::new(0), 2), (1, RootTimestamp::new(0), 1), (0, RootTimestamp::new(0), -1), (1, RootTimestamp::new(1), -1)]
+                    .into_iter().to_stream(scope).as_collection();
+
+        a.subtract(&b).assert_empty();
+    });
+}
+
+#[test]
+#[should_panic(expected = "assert_empty: found non-empty update")]
+fn assert_empty_unequal() {
+
+    timely::example(|scope| {
+
+        // the retraction in `b` happens one time later than in `a`.
+        let a = vec![(0u64, RootTimestamp::new(0), 1), (0, RootTimestamp::new(1), -1)]
+                    .into_iter().to_stream(scope).as_collection();
+        let b = vec![(0u64, RootTimestamp::new(0), 1), (0, RootTimestamp::new(2), -1)]
+                    .into_iter().to_stream(scope).as_collection();
+
+        a.subtract(&b).assert_empty();
+    });
+}
+
+// rounds of updates, each round at its own time, with retractions.
+fn rounds() -> Vec<Vec<(u64, isize)>> {
+    vec![
+        vec![(0, 1), (1, 1)],
+        vec![(1, -1), (2, 1)],
+        vec![(0, -1)],
+    ]
+}
+
+#[test]
+fn inspect_frontier_and_lag() {
+
+    let (frontiers, lags, delays, output) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let frontiers = Rc::new(RefCell::new(Vec::new()));
+        let lags = Rc::new(RefCell::new(Vec::new()));
+        let delays = Rc::new(RefCell::new(Vec::new()));
+
+        let mut probe = Handle::new();
+        let (mut input, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let (frontiers, lags, delays) = (frontiers.clone(), lags.clone(), delays.clone());
+            let captured = updates.as_collection()
+                .inspect_frontier(move |frontier| frontiers.borrow_mut().push(frontier.iter().map(|t| t.inner).collect::<Vec<_>>()))
+                // each time is a second since the epoch, long past.
+                .inspect_lag(|time| Duration::from_secs(time.inner as u64), move |lag| lags.borrow_mut().push(lag))
+                // each time is far in the future.
+                .inspect_lag(|_time| Duration::from_secs(1 << 62), move |lag| delays.borrow_mut().push(lag))
+                .probe_with(&mut probe)
+                .inner
+                .capture();
+            (input, captured)
+        });
+
+        for (round, updates) in rounds().into_iter().enumerate() {
+            for (datum, diff) in updates {
+                input.send((datum, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(datum, time, diff)| ((datum, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        let frontiers = frontiers.borrow().clone();
+        let lags = lags.borrow().clone();
+        let delays = delays.borrow().clone();
+        (frontiers, lags, delays, output)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    // each frontier is reported once, ending with the empty frontier of a complete collection.
+    assert_eq!(frontiers, vec![vec![1], vec![2], vec![3], vec![]]);
+
+    // lags are reported for each non-empty frontier, and are zero for times ahead of the clock.
+    assert_eq!(lags.len(), 3);
+    assert!(lags.iter().all(|lag| *lag > Duration::from_secs(365 * 24 * 60 * 60)));
+    assert_eq!(delays, vec![Duration::from_secs(0); 3]);
+
+    // updates pass through unchanged.
+    assert_eq!(output, vec![
+        ((0, 0), 1), ((0, 2), -1),
+        ((1, 0), 1), ((1, 1), -1),
+        ((2, 1), 1),
+    ]);
+}
+
+#[test]
+fn partition() {
+
+    let data = timely::example(|scope| {
+
+        let updates = vec![
+            (0u64, RootTimestamp::new(0), 1),
+            (1, RootTimestamp::new(0), 1),
+            (4, RootTimestamp::new(0), 2),
+            (5, RootTimestamp::new(0), 1),
+            (4, RootTimestamp::new(1), -2),
+            (0, RootTimestamp::new(1), -1),
+            (3, RootTimestamp::new(1), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        updates.partition(3, |x| x % 3).into_iter().map(|part| part.inner.capture()).collect::<Vec<_>>()
+    });
+
+    // each part receives the insertions and retractions of its records.
+    let parts = data.into_iter().map(|part| {
+        let mut results = part.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+        results.sort();
+        results
+    }).collect::<Vec<_>>();
+
+    assert_eq!(parts, vec![
+        vec![(0, RootTimestamp::new(0), 1), (0, RootTimestamp::new(1), -1), (3, RootTimestamp::new(1), 1)],
+        vec![(1, RootTimestamp::new(0), 1), (4, RootTimestamp::new(0), 2), (4, RootTimestamp::new(1), -2)],
+        vec![(5, RootTimestamp::new(0), 1)],
+    ]);
+}
+
+#[test]
+fn align_to() {
+
+    let data = timely::example(|scope| {
+
+        let updates = vec![
+            ('a', RootTimestamp::new(0), 1),
+            ('b', RootTimestamp::new(1), 1),
+            ('a', RootTimestamp::new(2), -1),
+            ('c', RootTimestamp::new(2), 1),
+            ('b', RootTimestamp::new(4), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let windows = vec![((), RootTimestamp::new(0), 1)].into_iter().to_stream(scope).as_collection();
+
+        // moves each update to the end of its window of three times.
+        updates.align_to(&windows, |time| RootTimestamp::new((time.inner + 2) / 3 * 3)).consolidate().inner.capture()
+    });
+
+    // the retraction of `a` lands in the same window as `b` and `c`, but not the retraction of `b`.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ('a', RootTimestamp::new(0), 1),
+        ('a', RootTimestamp::new(3), -1),
+        ('b', RootTimestamp::new(3), 1),
+        ('b', RootTimestamp::new(6), -1),
+        ('c', RootTimestamp::new(3), 1),
+    ]);
+}
+
+#[test]
+fn speculate_then_correct() {
+
+    let (provisional, corrections, merged) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let (mut input, mut other, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let (other, others) = scope.new_input::<(u64, _, isize)>();
+            // updates are released once `other` is complete through the prior time.
+            let speculation = updates.as_collection().speculate_then_correct(&others.as_collection(), |t| RootTimestamp::new(t.inner.saturating_sub(1)));
+            let captured = (speculation.provisional.inner.capture(), speculation.corrections.inner.capture(), speculation.merged().consolidate().inner.capture());
+            (input, other, captured)
+        });
+
+        input.send((0u64, RootTimestamp::new(0), 1));
+        input.send((1, RootTimestamp::new(0), 1));
+        input.advance_to(1u64);
+        other.advance_to(1u64);
+        for _ in 0 .. 10 { worker.step(); }
+
+        // updates at time 1 are released while `other` is incomplete through it, and then changed.
+        input.send((0, RootTimestamp::new(1), -1));
+        input.send((1, RootTimestamp::new(1), -1));
+        for _ in 0 .. 10 { worker.step(); }
+        input.send((1, RootTimestamp::new(1), 1));
+        input.send((2, RootTimestamp::new(1), 1));
+        input.advance_to(2);
+        other.advance_to(2);
+        for _ in 0 .. 10 { worker.step(); }
+
+        input.close();
+        other.close();
+        while worker.step() { }
+
+        let (provisional, corrections, merged) = captured;
+        let mut provisional = provisional.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+        let mut corrections = corrections.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+        let mut merged = merged.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+        provisional.sort();
+        corrections.sort();
+        merged.sort();
+        (provisional, corrections, merged)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    // the retraction of 1 was released provisionally, and is withdrawn by a correction.
+    assert_eq!(provisional, vec![
+        (0, RootTimestamp::new(0), 1),
+        (0, RootTimestamp::new(1), -1),
+        (1, RootTimestamp::new(0), 1),
+        (1, RootTimestamp::new(1), -1),
+    ]);
+    assert_eq!(corrections, vec![
+        (1, RootTimestamp::new(1), 1),
+        (2, RootTimestamp::new(1), 1),
+    ]);
+    assert_eq!(merged, vec![
+        (0, RootTimestamp::new(0), 1),
+        (0, RootTimestamp::new(1), -1),
+        (1, RootTimestamp::new(0), 1),
+        (2, RootTimestamp::new(1), 1),
+    ]);
+}
+
+// the insert-only changes `suppress_retraction` produces in `mode`, for a collection with retractions.
+fn suppressed(mode: Retractions) -> Vec<((char, isize, bool), Product<RootTimestamp, u64>, isize)> {
+
+    let data = timely::example(move |scope| {
+
+        let updates = vec![
+            ('a', RootTimestamp::new(0), 1),
+            ('a', RootTimestamp::new(0), 1),
+            ('b', RootTimestamp::new(0), 1),
+            ('a', RootTimestamp::new(1), -1),
+            ('b', RootTimestamp::new(1), -1),
+            ('a', RootTimestamp::new(2), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        updates.suppress_retraction(mode).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    results
+}
+
+#[test]
+fn suppress_retraction_tombstones() {
+    // each consolidated update is a record of its magnitude, marked as deleted if it is a retraction.
+    assert_eq!(suppressed(Retractions::Tombstones), vec![
+        (('a', 1, true), RootTimestamp::new(1), 1),
+        (('a', 1, true), RootTimestamp::new(2), 1),
+        (('a', 2, false), RootTimestamp::new(0), 1),
+        (('b', 1, false), RootTimestamp::new(0), 1),
+        (('b', 1, true), RootTimestamp::new(1), 1),
+    ]);
+}
+
+#[test]
+fn suppress_retraction_corrections() {
+    // each change is a record of the new weight, marked as deleted once the weight is zero.
+    assert_eq!(suppressed(Retractions::Corrections), vec![
+        (('a', 0, true), RootTimestamp::new(2), 1),
+        (('a', 1, false), RootTimestamp::new(1), 1),
+        (('a', 2, false), RootTimestamp::new(0), 1),
+        (('b', 0, true), RootTimestamp::new(1), 1),
+        (('b', 1, false), RootTimestamp::new(0), 1),
+    ]);
+}
@@ -0,0 +1,144 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Input, Capture};
+use timely::dataflow::operators::capture::{Extract, Event};
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::Consolidate;
+use differential_dataflow::trace::{consolidate, consolidate_by};
+
+#[test]
+fn consolidate_unsorted() {
+    let mut updates = vec![(3, 1), (1, 1), (2, 1), (1, -1), (3, 2), (0, 1)];
+    consolidate(&mut updates, 0);
+    assert_eq!(updates, vec![(0, 1), (2, 1), (3, 3)]);
+}
+
+#[test]
+fn consolidate_sorted_input() {
+    let mut updates = vec![(0, 1), (0, 1), (1, 1), (1, -1), (2, 5)];
+    consolidate(&mut updates, 0);
+    assert_eq!(updates, vec![(0, 2), (2, 5)]);
+}
+
+#[test]
+fn consolidate_offset() {
+    // elements before the offset are left as they are.
+    let mut updates = vec![(5, 1), (5, 1), (2, 1), (1, 1), (2, -1)];
+    consolidate(&mut updates, 2);
+    assert_eq!(updates, vec![(5, 1), (5, 1), (1, 1)]);
+}
+
+#[test]
+fn consolidate_empty() {
+    let mut updates: Vec<(u64, isize)> = vec![(0, 1), (0, -1)];
+    consolidate(&mut updates, 0);
+    assert!(updates.is_empty());
+    consolidate(&mut updates, 0);
+    assert!(updates.is_empty());
+}
+
+#[test]
+fn consolidate_by_order() {
+    // consolidates in the order of the comparator, here descending.
+    let mut updates = vec![((1, 'a'), 1), ((3, 'c'), 1), ((2, 'b'), 1), ((3, 'c'), -1), ((1, 'a'), 1)];
+    consolidate_by(&mut updates, 0, |x, y| y.cmp(x));
+    assert_eq!(updates, vec![((2, 'b'), 1), ((1, 'a'), 2)]);
+}
+
+type Update = ((u64, char), Product<RootTimestamp, u64>, isize);
+
+// updates with repeats, cancellations, and a retraction at a later time.
+fn updates() -> Vec<Update> {
+    vec![
+        ((0, 'a'), RootTimestamp::new(0), 1),
+        ((0, 'a'), RootTimestamp::new(0), 1),
+        ((0, 'b'), RootTimestamp::new(0), 1),
+        ((1, 'c'), RootTimestamp::new(0), 1),
+        ((0, 'b'), RootTimestamp::new(0), -1),
+        ((0, 'a'), RootTimestamp::new(1), -1),
+        ((1, 'c'), RootTimestamp::new(1), -1),
+        ((1, 'c'), RootTimestamp::new(1), 1),
+        ((2, 'd'), RootTimestamp::new(1), 3),
+    ]
+}
+
+// the updates `consolidate_by_key` and `consolidate_salted` should produce from `updates()`.
+fn expected() -> Vec<Update> {
+    vec![
+        ((0, 'a'), RootTimestamp::new(0), 2),
+        ((0, 'a'), RootTimestamp::new(1), -1),
+        ((1, 'c'), RootTimestamp::new(0), 1),
+        ((2, 'd'), RootTimestamp::new(1), 3),
+    ]
+}
+
+#[test]
+fn consolidate_by_key() {
+
+    let data = timely::example(|scope| {
+        // partitions by the first field alone.
+        updates().into_iter().to_stream(scope).as_collection().consolidate_by_key(|x: &(u64, char)| x.0).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, expected());
+}
+
+#[test]
+fn consolidate_salted() {
+
+    let data = timely::example(|scope| {
+        updates().into_iter().to_stream(scope).as_collection().consolidate_salted(3).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, expected());
+}
+
+#[test]
+fn consolidate_sorted() {
+
+    let sequence = timely::execute(timely::Configuration::Process(2), |worker| {
+
+        let (mut input, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            (input, updates.as_collection().consolidate_sorted().inner.capture())
+        });
+
+        // each worker introduces updates out of order, with repeats and cancellations across workers.
+        let updates = if worker.index() == 0 {
+            vec![(5u64, 0, 1), (3, 1, 1), (1, 0, 1)]
+        }
+        else {
+            vec![(5, 0, 1), (1, 1, -1), (4, 0, 1), (3, 1, -1)]
+        };
+        for (datum, time, diff) in updates {
+            input.send((datum, RootTimestamp::new(time), diff));
+        }
+        input.close();
+        while worker.step() { }
+
+        let mut sequence = Vec::new();
+        for event in captured.try_iter() {
+            if let Event::Messages(_, data) = event {
+                sequence.extend(data);
+            }
+        }
+        sequence
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    // the first worker produces every update, ordered by time and then by record, without sorting here.
+    assert_eq!(sequence, vec![
+        (1, RootTimestamp::new(0), 1),
+        (4, RootTimestamp::new(0), 1),
+        (5, RootTimestamp::new(0), 2),
+        (1, RootTimestamp::new(1), -1),
+    ]);
+}
@@ -0,0 +1,158 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::difference::{Decimal, Int128};
+use differential_dataflow::operators::{Count, Group, Join};
+
+#[test]
+fn decimal_count() {
+
+    let data = timely::example(|scope| {
+
+        let updates = vec![
+            (1u64, RootTimestamp::new(0), Decimal::from_units(15_000)),
+            (1u64, RootTimestamp::new(0), Decimal::from_units(22_500)),
+            (2u64, RootTimestamp::new(0), Decimal::from_units(1_000)),
+            (2u64, RootTimestamp::new(1), Decimal::from_units(2_000)),
+            (1u64, RootTimestamp::new(1), -Decimal::from_units(37_500)),
+        ];
+
+        // the accumulated decimal of each key, which is retracted when it changes.
+        updates.into_iter().to_stream(scope).as_collection().count().inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, Decimal::from_units(37_500)), RootTimestamp::new(0), 1),
+        ((1, Decimal::from_units(37_500)), RootTimestamp::new(1), -1),
+        ((2, Decimal::from_units(1_000)), RootTimestamp::new(0), 1),
+        ((2, Decimal::from_units(1_000)), RootTimestamp::new(1), -1),
+        ((2, Decimal::from_units(3_000)), RootTimestamp::new(1), 1),
+    ]);
+}
+
+#[test]
+fn int128_count() {
+
+    let data = timely::example(|scope| {
+
+        let updates = vec![
+            (0u64, RootTimestamp::new(0), Int128::from(::std::i64::MAX)),
+            (0u64, RootTimestamp::new(0), Int128::from(::std::i64::MAX)),
+            (0u64, RootTimestamp::new(1), -Int128::from(::std::i64::MAX)),
+        ];
+
+        // accumulations beyond the range of `i64` are exact.
+        updates.into_iter().to_stream(scope).as_collection().count().inner.capture()
+    });
+
+    let max = ::std::i64::MAX as i128;
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((0, Int128::new(max)), RootTimestamp::new(1), 1),
+        ((0, Int128::new(2 * max)), RootTimestamp::new(0), 1),
+        ((0, Int128::new(2 * max)), RootTimestamp::new(1), -1),
+    ]);
+}
+
+#[test]
+fn decimal_arithmetic() {
+    let price = Decimal::from_units(12_345);
+    assert_eq!(Decimal::from_integer(3).units, 30_000);
+    assert_eq!(price + price, Decimal::from_units(24_690));
+    assert_eq!(price - Decimal::from_integer(2), Decimal::from_units(-7_655));
+    assert_eq!(price * 3isize, Decimal::from_units(37_035));
+    // products of decimals round toward zero.
+    assert_eq!(price * Decimal::from_units(5_000), Decimal::from_units(6_172));
+    assert_eq!(-price * Decimal::from_units(5_000), Decimal::from_units(-6_172));
+}
+
+#[test]
+#[should_panic(expected = "Decimal overflow")]
+fn decimal_add_overflow() {
+    let _ = Decimal::from_units(::std::i64::MAX) + Decimal::from_units(1);
+}
+
+#[test]
+#[should_panic(expected = "Decimal overflow")]
+fn decimal_mul_overflow() {
+    let _ = Decimal::from_integer(1_000_000_000) * Decimal::from_integer(1_000_000_000);
+}
+
+#[test]
+fn decimal_from_f64() {
+    assert_eq!(Decimal::from_f64(1.5), Decimal::from_units(15_000));
+    assert_eq!(Decimal::from_f64(-0.00004), Decimal::from_units(0));
+    assert_eq!(Decimal::from_f64(-0.00006), Decimal::from_units(-1));
+}
+
+#[test]
+#[should_panic(expected = "Decimal overflow")]
+fn decimal_from_f64_overflow() {
+    let _ = Decimal::from_f64(1e30);
+}
+
+#[test]
+#[should_panic(expected = "Decimal overflow")]
+fn decimal_from_f64_nan() {
+    let _ = Decimal::from_f64(::std::f64::NAN);
+}
+
+#[test]
+#[should_panic(expected = "Int128 overflow")]
+fn int128_add_overflow() {
+    let _ = Int128::new(::std::i128::MAX) + Int128::new(1);
+}
+
+#[test]
+fn decimal_through_operators() {
+
+    let data = timely::example(|scope| {
+
+        let time = |t: usize| RootTimestamp::new(t);
+        let shares = vec![
+            ((1u64, 0u64), time(0), Decimal::from_integer(2)),
+            ((1u64, 9u64), time(0), Decimal::from_integer(5)),
+        ].into_iter().to_stream(scope).as_collection();
+        let more_shares = vec![
+            ((1u64, 1u64), time(0), Decimal::from_units(15_000)),
+            ((2u64, 0u64), time(0), Decimal::from_integer(1)),
+        ].into_iter().to_stream(scope).as_collection();
+        let prices = vec![
+            ((0u64, ()), time(0), Decimal::from_integer(10)),
+            ((1u64, ()), time(0), Decimal::from_integer(4)),
+            ((0u64, ()), time(1), -Decimal::from_integer(10)),
+            ((0u64, ()), time(1), Decimal::from_integer(12)),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // the value of each account's shares, other than those of asset 9, where differences are decimal.
+        shares.filter(|&(_account, asset)| asset != 9)
+              .concat(&more_shares)
+              .map(|(account, asset)| (asset, account))
+              .join(&prices)
+              .map(|(_asset, account, ())| (account, ()))
+              .group(|_account, values, output| {
+                  let total = values.iter().fold(Decimal::from_units(0), |total, &((), value)| total + value);
+                  output.push((total, 1isize));
+              })
+              .inner
+              .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, Decimal::from_integer(26)), RootTimestamp::new(0), 1),
+        ((1, Decimal::from_integer(26)), RootTimestamp::new(1), -1),
+        ((1, Decimal::from_integer(30)), RootTimestamp::new(1), 1),
+        ((2, Decimal::from_integer(10)), RootTimestamp::new(0), 1),
+        ((2, Decimal::from_integer(10)), RootTimestamp::new(1), -1),
+        ((2, Decimal::from_integer(12)), RootTimestamp::new(1), 1),
+    ]);
+}
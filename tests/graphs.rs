@@ -0,0 +1,99 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::Consolidate;
+use differential_dataflow::algorithms::graphs::{equivalences, scc};
+
+#[test]
+fn equivalences_small() {
+
+    let data = timely::example(|scope| {
+
+        // two classes, {0,1,2} and {3,4}, with one edge presented in both directions.
+        let edges = vec![((2u32,1u32), Default::default(), 1), ((1,0), Default::default(), 1), ((0,1), Default::default(), 1), ((4,3), Default::default(), 1)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        equivalences(&edges).consolidate().inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    let mut labels = extracted[0].1.clone();
+    labels.sort();
+    assert_eq!(labels, vec![
+        ((0,0), Default::default(), 1),
+        ((1,0), Default::default(), 1),
+        ((2,0), Default::default(), 1),
+        ((3,3), Default::default(), 1),
+        ((4,3), Default::default(), 1),
+    ]);
+}
+
+#[test]
+fn equivalences_changing() {
+
+    let data = timely::example(|scope| {
+
+        // classes {0,1,2} and {3,4}; node 2 is split off, and then joined to {3,4}.
+        let edges = vec![
+            ((0u32, 1u32), RootTimestamp::new(0), 1),
+            ((1, 2), RootTimestamp::new(0), 1),
+            ((3, 4), RootTimestamp::new(0), 1),
+            ((1, 2), RootTimestamp::new(1), -1),
+            ((2, 3), RootTimestamp::new(2), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        equivalences(&edges).consolidate().inner.capture()
+    });
+
+    // nodes whose class changes retract their former representative.
+    let mut labels = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    labels.sort();
+    assert_eq!(labels, vec![
+        ((0, 0), RootTimestamp::new(0), 1),
+        ((1, 0), RootTimestamp::new(0), 1),
+        ((2, 0), RootTimestamp::new(0), 1),
+        ((2, 0), RootTimestamp::new(1), -1),
+        ((2, 2), RootTimestamp::new(1), 1),
+        ((3, 2), RootTimestamp::new(2), 1),
+        ((3, 3), RootTimestamp::new(0), 1),
+        ((3, 3), RootTimestamp::new(2), -1),
+        ((4, 2), RootTimestamp::new(2), 1),
+        ((4, 3), RootTimestamp::new(0), 1),
+        ((4, 3), RootTimestamp::new(2), -1),
+    ]);
+}
+
+#[test]
+fn scc_small() {
+
+    let data = timely::example(|scope| {
+
+        // a cycle (0,1,2), a cycle (3,4), and edges into, between, and out of them.
+        let edges = vec![(5u32,0u32), (0,1), (1,2), (2,0), (2,3), (3,4), (4,3), (4,6)]
+                        .into_iter()
+                        .map(|edge| (edge, Default::default(), 1))
+                        .to_stream(scope)
+                        .as_collection();
+
+        scc(&edges).consolidate().inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    let mut edges = extracted[0].1.clone();
+    edges.sort();
+    assert_eq!(edges, vec![
+        ((0,1), Default::default(), 1),
+        ((1,2), Default::default(), 1),
+        ((2,0), Default::default(), 1),
+        ((3,4), Default::default(), 1),
+        ((4,3), Default::default(), 1),
+    ]);
+}
@@ -1,11 +1,16 @@
 extern crate timely;
 extern crate differential_dataflow;
 
+use timely::progress::nested::product::Product;
 use timely::progress::timestamp::RootTimestamp;
 use timely::dataflow::operators::{ToStream, Capture, Map};
 use timely::dataflow::operators::capture::Extract;
 use differential_dataflow::AsCollection;
-use differential_dataflow::operators::{Group, Count};
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::{Group, Count, Consolidate};
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::operators::group::{AggregateLattice, GroupArranged, KeyHistogram, GroupNested, GroupingSets, CountDistinct, rollup_sets, cube_sets};
+use differential_dataflow::trace::implementations::ord::OrdKeySpine;
 
 #[test]
 fn group() {
@@ -43,4 +48,201 @@ fn group_scaling() {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 1);
-}
\ No newline at end of file
+}
+
+#[test]
+fn aggregate_lattice() {
+
+    let data = timely::example(|scope| {
+
+        let values = vec![
+            ((1u64, 3u64), RootTimestamp::new(0), 1),
+            ((1, 5), RootTimestamp::new(0), 1),
+            ((2, 0), RootTimestamp::new(0), 1),
+            ((1, 5), RootTimestamp::new(1), -1),
+            ((2, 2), RootTimestamp::new(2), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // the maximum value of each key, omitting keys whose maximum is zero.
+        values.aggregate_lattice(0, |x, y| ::std::cmp::max(*x, *y)).consolidate().inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 3), RootTimestamp::new(1), 1),
+        ((1, 5), RootTimestamp::new(0), 1),
+        ((1, 5), RootTimestamp::new(1), -1),
+        ((2, 2), RootTimestamp::new(2), 1),
+    ]);
+}
+
+#[test]
+fn reduce_abelian() {
+
+    let data = timely::example(|scope| {
+
+        let values = vec![
+            ((1u64, 3u64), RootTimestamp::new(0), 1),
+            ((1, 5), RootTimestamp::new(0), 1),
+            ((2, 0), RootTimestamp::new(0), 1),
+            ((1, 5), RootTimestamp::new(1), -1),
+            ((2, 2), RootTimestamp::new(2), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // the keys with at least two values, arranged by key alone.
+        values.arrange_by_key_hashed()
+              .reduce_abelian::<_,_,OrdKeySpine<OrdWrapper<u64>, Product<RootTimestamp, u64>, isize>,_>(|_k, s, t| if s.len() > 1 { t.push(((), 1)); })
+              .as_collection(|k: &OrdWrapper<u64>, _: &()| k.item)
+              .inner
+              .capture()
+    });
+
+    // key 1 is retracted once it loses a value, and key 2 is introduced once it gains one.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        (1, RootTimestamp::new(0), 1),
+        (1, RootTimestamp::new(1), -1),
+        (2, RootTimestamp::new(2), 1),
+    ]);
+}
+
+#[test]
+fn key_histogram() {
+
+    let data = timely::example(|scope| {
+
+        let edges = vec![
+            ((1u64, 2u64), RootTimestamp::new(0), 1),
+            ((1, 3), RootTimestamp::new(0), 1),
+            ((2, 3), RootTimestamp::new(0), 1),
+            ((1, 3), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        edges.key_histogram().inner.capture()
+    });
+
+    // once key 1 loses a record, both keys have one record each.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 1), RootTimestamp::new(0), 1),
+        ((1, 1), RootTimestamp::new(1), -1),
+        ((1, 2), RootTimestamp::new(1), 1),
+        ((2, 1), RootTimestamp::new(0), 1),
+        ((2, 1), RootTimestamp::new(1), -1),
+    ]);
+}
+
+#[test]
+fn group_nested() {
+
+    let (inner, outer) = timely::example(|scope| {
+
+        // amounts by `(region, city)`, where one city's amount is retracted.
+        let amounts = vec![
+            (((1u64, 10u64), 5u64), RootTimestamp::new(0), 1),
+            (((1, 11), 3), RootTimestamp::new(0), 1),
+            (((2, 20), 4), RootTimestamp::new(0), 1),
+            (((1, 11), 3), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let (inner, outer) = amounts.group_nested(
+            |_city, vals, output| output.push((vals.iter().map(|&(v, r)| v * r as u64).sum::<u64>(), 1)),
+            |_region, cities, output| output.push((cities.iter().map(|&((_, v), r)| v * r as u64).sum::<u64>(), 1)),
+        );
+        (inner.inner.capture(), outer.inner.capture())
+    });
+
+    // the retracted city's total is retracted, and its region's total is corrected.
+    let mut inner = inner.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    inner.sort();
+    assert_eq!(inner, vec![
+        (((1, 10), 5), RootTimestamp::new(0), 1),
+        (((1, 11), 3), RootTimestamp::new(0), 1),
+        (((1, 11), 3), RootTimestamp::new(1), -1),
+        (((2, 20), 4), RootTimestamp::new(0), 1),
+    ]);
+
+    let mut outer = outer.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    outer.sort();
+    assert_eq!(outer, vec![
+        ((1, 5), RootTimestamp::new(1), 1),
+        ((1, 8), RootTimestamp::new(0), 1),
+        ((1, 8), RootTimestamp::new(1), -1),
+        ((2, 4), RootTimestamp::new(0), 1),
+    ]);
+}
+
+#[test]
+fn grouping_sets_rollup() {
+
+    assert_eq!(rollup_sets(2), vec![vec![0, 1], vec![0], vec![]]);
+    assert_eq!(cube_sets(2), vec![vec![0, 1], vec![1], vec![0], vec![]]);
+
+    let data = timely::example(|scope| {
+
+        // amounts by region and city, where one city's amount is retracted.
+        let amounts = vec![
+            ((1u64, 10u64, 5u64), RootTimestamp::new(0), 1),
+            ((1, 11, 3), RootTimestamp::new(0), 1),
+            ((2, 20, 4), RootTimestamp::new(0), 1),
+            ((1, 11, 3), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        amounts.grouping_sets(
+            |(region, city, amount)| (vec![region, city], amount),
+            rollup_sets(2),
+            |vals| vals.iter().map(|&(v, r)| v * r as u64).sum::<u64>(),
+            |sums| sums.iter().map(|&(a, n)| a * n as u64).sum::<u64>(),
+        ).inner.capture()
+    });
+
+    // totals by region and city, by region, and overall, each corrected after the retraction.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        (((0, vec![Some(1), Some(10)]), 5), RootTimestamp::new(0), 1),
+        (((0, vec![Some(1), Some(11)]), 3), RootTimestamp::new(0), 1),
+        (((0, vec![Some(1), Some(11)]), 3), RootTimestamp::new(1), -1),
+        (((0, vec![Some(2), Some(20)]), 4), RootTimestamp::new(0), 1),
+        (((1, vec![Some(1), None]), 5), RootTimestamp::new(1), 1),
+        (((1, vec![Some(1), None]), 8), RootTimestamp::new(0), 1),
+        (((1, vec![Some(1), None]), 8), RootTimestamp::new(1), -1),
+        (((1, vec![Some(2), None]), 4), RootTimestamp::new(0), 1),
+        (((2, vec![None, None]), 9), RootTimestamp::new(1), 1),
+        (((2, vec![None, None]), 12), RootTimestamp::new(0), 1),
+        (((2, vec![None, None]), 12), RootTimestamp::new(1), -1),
+    ]);
+}
+
+#[test]
+fn count_distinct() {
+
+    let data = timely::example(|scope| {
+
+        // key 1 has a repeated value, of which one copy is retracted.
+        let values = vec![
+            ((1u64, 'a'), RootTimestamp::new(0), 2),
+            ((1, 'b'), RootTimestamp::new(0), 1),
+            ((2, 'a'), RootTimestamp::new(0), 1),
+            ((1, 'a'), RootTimestamp::new(1), -1),
+            ((1, 'b'), RootTimestamp::new(1), -1),
+            ((2, 'a'), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        values.count_distinct().inner.capture()
+    });
+
+    // key 1 keeps one copy of its repeated value, and key 2 loses its only value.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 1), RootTimestamp::new(1), 1),
+        ((1, 2), RootTimestamp::new(0), 1),
+        ((1, 2), RootTimestamp::new(1), -1),
+        ((2, 1), RootTimestamp::new(0), 1),
+        ((2, 1), RootTimestamp::new(1), -1),
+    ]);
+}
@@ -0,0 +1,119 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::time::Duration;
+
+use timely::dataflow::operators::{Input, Probe};
+use timely::dataflow::operators::probe::Handle;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::Count;
+use differential_dataflow::harness::{Measurements, LatencyProbe, percentile_of};
+
+#[test]
+fn percentiles() {
+    let latencies = (1 .. 11).map(|x| Duration::from_millis(x)).collect::<Vec<_>>();
+    assert_eq!(percentile_of(&latencies[..], 0.0), Some(Duration::from_millis(1)));
+    assert_eq!(percentile_of(&latencies[..], 50.0), Some(Duration::from_millis(6)));
+    assert_eq!(percentile_of(&latencies[..], 100.0), Some(Duration::from_millis(10)));
+    assert_eq!(percentile_of(&[], 50.0), None);
+}
+
+#[test]
+fn measurements_unrecorded() {
+    let measurements = Measurements::new("empty");
+    assert_eq!(measurements.to_csv(), "empty,,0,,,,");
+    assert_eq!(measurements.to_json(), "{\"name\":\"empty\",\"load_ns\":null,\"rounds\":0,\"p50_ns\":null,\"p90_ns\":null,\"p99_ns\":null,\"max_ns\":null}");
+}
+
+#[test]
+fn measurements_of_rounds() {
+
+    let measurements = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let mut input = worker.dataflow(|scope| {
+            let (input, words) = scope.new_input();
+            words.as_collection().count().inner.probe_with(&mut probe);
+            input
+        });
+
+        let mut measurements = Measurements::new("count");
+
+        measurements.time_load(|| {
+            for word in 0 .. 100u64 {
+                input.send((word % 10, Default::default(), 1));
+            }
+            input.advance_to(1);
+            worker.step_while(|| probe.less_than(input.time()));
+        });
+
+        // each round retracts one word and introduces another.
+        for round in 1 .. 6u64 {
+            measurements.time_round(|| {
+                let time = *input.time();
+                input.send((round, time, -1));
+                input.send((round + 10, time, 1));
+                input.advance_to(round + 1);
+                worker.step_while(|| probe.less_than(input.time()));
+            });
+        }
+
+        measurements
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(measurements.rounds(), 5);
+    assert!(measurements.percentile(50.0).unwrap() <= measurements.percentile(100.0).unwrap());
+
+    // the row has a value for each column, and the object a field for each column.
+    let csv = measurements.to_csv();
+    assert!(csv.starts_with("count,"));
+    assert_eq!(csv.split(',').count(), Measurements::csv_header().split(',').count());
+    assert!(csv.split(',').all(|field| !field.is_empty()));
+    assert!(measurements.to_json().contains("\"rounds\":5"));
+}
+
+#[test]
+fn latency_probe_rounds_in_flight() {
+
+    let (pending, completed, measurements) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let mut input = worker.dataflow(|scope| {
+            let (input, words) = scope.new_input();
+            words.as_collection().count().inner.probe_with(&mut probe);
+            input
+        });
+
+        // a step installs the initial frontier at the probe.
+        worker.step();
+
+        // introduce several rounds, each retracting the word of the previous round, before any complete.
+        let mut latency = LatencyProbe::new();
+        for round in 0 .. 5u64 {
+            let time = *input.time();
+            if round > 0 { input.send((round - 1, time, -1)); }
+            input.send((round, time, 1));
+            input.advance_to(round + 1);
+            latency.ingest(round);
+        }
+        let pending = latency.observe(&probe);
+
+        input.close();
+        while worker.step() { }
+        assert_eq!(latency.observe(&probe), 0);
+
+        let completed = latency.completed().iter().map(|x| x.0).collect::<Vec<_>>();
+        let mut measurements = Measurements::new("latency");
+        latency.drain_into(&mut measurements);
+        assert!(latency.completed().is_empty());
+
+        (pending, completed, measurements.rounds())
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(pending, 5);
+    assert_eq!(completed, vec![0, 1, 2, 3, 4]);
+    assert_eq!(measurements, 5);
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "http")]
+
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+
+use timely::dataflow::operators::{Input, Capture, Probe};
+use timely::dataflow::operators::capture::Extract;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::interop::http::Inspector;
+use differential_dataflow::trace::consolidate;
+
+// sends `target` to the inspector, polling it until it has answered, and returns the response body.
+fn request(inspector: &mut Inspector, address: &SocketAddr, target: &str) -> String {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).as_bytes()).unwrap();
+    while inspector.poll() == 0 { }
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    response.split("\r\n\r\n").nth(1).unwrap().to_owned()
+}
+
+#[test]
+fn inspect_after_retraction() {
+
+    let (output, bodies) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut inspector = Inspector::bind(("127.0.0.1", 0)).unwrap();
+        let address = inspector.local_addr().unwrap();
+
+        let mut probe = Handle::new();
+        let (mut input, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection().arrange_by_key_hashed();
+            inspector.register("edges", arranged.trace.clone(), |key| key.parse::<u64>().ok().map(|k| OrdWrapper { item: k }));
+            let captured = arranged.as_collection(|key, val: &u64| (key.item, *val)).inner.probe_with(&mut probe).capture();
+            (input, captured)
+        });
+
+        // key 1 loses one of its values, and key 2 loses its only value.
+        let rounds = vec![
+            vec![((1u64, 10u64), 1), ((1, 11), 1), ((2, 20), 1)],
+            vec![((1, 10), -1), ((2, 20), -1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+
+        let bodies = vec![
+            request(&mut inspector, &address, "/arrangements"),
+            request(&mut inspector, &address, "/arrangements/edges/lookup?key=1"),
+            request(&mut inspector, &address, "/arrangements/edges/lookup?key=2"),
+        ];
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, bodies)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(output, vec![(((1, 10), 0), 1), (((1, 10), 1), -1), (((1, 11), 0), 1), (((2, 20), 0), 1), (((2, 20), 1), -1)]);
+
+    // lookups report only the values whose counts have not been retracted to zero.
+    assert_eq!(bodies, vec![
+        "{\"arrangements\":[\"edges\"]}".to_owned(),
+        "{\"vals\":[{\"val\":\"11\",\"count\":\"1\"}]}".to_owned(),
+        "{\"vals\":[]}".to_owned(),
+    ]);
+}
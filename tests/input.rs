@@ -0,0 +1,57 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{Input, Capture};
+use timely::dataflow::operators::capture::Extract;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::input::{InputSession, Deduplicate};
+use differential_dataflow::trace::consolidate;
+
+#[test]
+fn deduplicate_within_epochs() {
+
+    let (introduced, unchecked, output) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let (mut handle, captured) = worker.dataflow(|scope| {
+            let (handle, updates) = scope.new_input();
+            (handle, updates.as_collection().inner.capture())
+        });
+
+        let mut introduced = Vec::new();
+        let unchecked = {
+            // at most two distinct updates are remembered in each epoch.
+            let mut input = Deduplicate::new(InputSession::from(&mut handle), 2);
+            introduced.push(input.insert(1u64));
+            introduced.push(input.insert(1));
+            introduced.push(input.insert(2));
+
+            // the insertion of 1 is introduced again in a new epoch, and the repeated removal of 2 is not.
+            input.advance_to(1u64);
+            introduced.push(input.remove(2));
+            introduced.push(input.remove(2));
+            introduced.push(input.insert(1));
+
+            // beyond the bound, repeated updates are introduced without being checked.
+            introduced.push(input.insert(3));
+            introduced.push(input.insert(3));
+            input.unchecked()
+        };
+        handle.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (introduced, unchecked, output)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(introduced, vec![true, false, true, true, false, true, true, true]);
+    assert_eq!(unchecked, 2);
+    assert_eq!(output, vec![((1, 0), 1), ((1, 1), 1), ((2, 0), 1), ((2, 1), -1), ((3, 1), 2)]);
+}
@@ -0,0 +1,67 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::Consolidate;
+use differential_dataflow::algorithms::intern::Interner;
+
+#[test]
+fn intern_and_restore() {
+
+    let (dictionary, interned, restored) = timely::example(|scope| {
+
+        // keys first appear at distinct times, and key "b" leaves and later returns.
+        let edges = vec![
+            (("a".to_owned(), 1u64), RootTimestamp::new(0), 1),
+            (("a".to_owned(), 3), RootTimestamp::new(0), 1),
+            (("b".to_owned(), 2), RootTimestamp::new(1), 1),
+            (("b".to_owned(), 2), RootTimestamp::new(2), -1),
+            (("c".to_owned(), 4), RootTimestamp::new(3), 1),
+            (("b".to_owned(), 5), RootTimestamp::new(4), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let interner = Interner::new(&edges.map(|(src, _dst)| src));
+        let interned = interner.intern(&edges);
+        let restored = interner.restore(&interned);
+
+        (interner.dictionary().inner.capture(), interned.consolidate().inner.capture(), restored.consolidate().inner.capture())
+    });
+
+    // "b" is removed from the dictionary while absent, and keeps its identifier when it returns.
+    let mut dictionary = dictionary.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    dictionary.sort();
+    assert_eq!(dictionary, vec![
+        (("a".to_owned(), 0), RootTimestamp::new(0), 1),
+        (("b".to_owned(), 1), RootTimestamp::new(1), 1),
+        (("b".to_owned(), 1), RootTimestamp::new(2), -1),
+        (("b".to_owned(), 1), RootTimestamp::new(4), 1),
+        (("c".to_owned(), 2), RootTimestamp::new(3), 1),
+    ]);
+
+    let mut interned = interned.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    interned.sort();
+    assert_eq!(interned, vec![
+        ((0, 1), RootTimestamp::new(0), 1),
+        ((0, 3), RootTimestamp::new(0), 1),
+        ((1, 2), RootTimestamp::new(1), 1),
+        ((1, 2), RootTimestamp::new(2), -1),
+        ((1, 5), RootTimestamp::new(4), 1),
+        ((2, 4), RootTimestamp::new(3), 1),
+    ]);
+
+    // restoring the interned records recovers the original records, including the retraction.
+    let mut restored = restored.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    restored.sort();
+    assert_eq!(restored, vec![
+        (("a".to_owned(), 1), RootTimestamp::new(0), 1),
+        (("a".to_owned(), 3), RootTimestamp::new(0), 1),
+        (("b".to_owned(), 2), RootTimestamp::new(1), 1),
+        (("b".to_owned(), 2), RootTimestamp::new(2), -1),
+        (("b".to_owned(), 5), RootTimestamp::new(4), 1),
+        (("c".to_owned(), 4), RootTimestamp::new(3), 1),
+    ]);
+}
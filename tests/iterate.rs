@@ -0,0 +1,200 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::fmt::Debug;
+
+use timely::dataflow::Scope;
+use timely::dataflow::scopes::Child;
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::{AsCollection, Collection};
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::{Consolidate, Distinct, Iterate, Join};
+use differential_dataflow::operators::iterate::{IterateDemanded, IterateDetecting, Positive, Stratified};
+
+#[test]
+fn demanded_reachability() {
+
+    let data = timely::example(|scope| {
+
+        // paths 0 -> 1 -> 2 and 3 -> 4, with reachability demanded only from 0.
+        let edges = vec![(0u32,1u32), (1,2), (3,4)]
+                        .into_iter()
+                        .map(|edge| (edge, Default::default(), 1))
+                        .to_stream(scope)
+                        .as_collection();
+
+        let seeds = vec![(0u32, Default::default(), 1)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        edges.iterate_demanded(
+                &seeds,
+                |demand, _reach| demand.filter(|_| false),
+                |edges, reach| {
+                    reach.map(|(src, mid)| (mid, src))
+                         .join_map(edges, |_mid, &src, &dst| (src, dst))
+                         .concat(edges)
+                         .distinct()
+                })
+             .consolidate()
+             .inner
+             .capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    let mut reach = extracted[0].1.clone();
+    reach.sort();
+    assert_eq!(reach, vec![
+        ((0,1), Default::default(), 1),
+        ((0,2), Default::default(), 1),
+    ]);
+}
+
+#[test]
+fn detecting_oscillation() {
+
+    let (result, cycles) = timely::example(|scope| {
+
+        let input = vec![(0u64, Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+
+        // alternates between zero and one forever, were the cycle not detected.
+        let (result, cycles) = input.iterate_detecting(|inner| inner.map(|x| 1 - x));
+        (result.consolidate().inner.capture(), cycles.inner.capture())
+    });
+
+    // the collection at round two repeats that at round zero.
+    let cycles = cycles.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    assert_eq!(cycles, vec![((0, 2), Default::default(), 1)]);
+
+    let result = result.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    assert_eq!(result, vec![(1, Default::default(), 1)]);
+}
+
+#[test]
+fn detecting_fixed_point() {
+
+    let (result, cycles) = timely::example(|scope| {
+
+        let input = vec![(8u64, Default::default(), 1)].into_iter().to_stream(scope).as_collection();
+
+        // halves the value until it reaches zero, and then stops changing.
+        let (result, cycles) = input.iterate_detecting(|inner| inner.map(|x| x / 2).distinct());
+        (result.consolidate().inner.capture(), cycles.inner.capture())
+    });
+
+    assert!(cycles.extract().into_iter().all(|(_, data)| data.is_empty()));
+
+    let result = result.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    assert_eq!(result, vec![(0, Default::default(), 1)]);
+}
+
+#[test]
+fn iterate_scoped_reachability() {
+
+    let data = timely::example(|scope| {
+
+        // paths 0 -> 1 -> 2 -> 3 and 4 -> 5, where the edge (1, 2) is removed at time 1.
+        let edges = vec![
+            ((0u32, 1u32), RootTimestamp::new(0), 1),
+            ((1, 2), RootTimestamp::new(0), 1),
+            ((2, 3), RootTimestamp::new(0), 1),
+            ((4, 5), RootTimestamp::new(0), 1),
+            ((1, 2), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let roots = vec![(0u32, RootTimestamp::new(0), 1)].into_iter().to_stream(scope).as_collection();
+
+        roots.iterate_scoped(&edges, |reach, edges| {
+                 reach.map(|node| (node, ()))
+                      .join_map(edges, |_src, &(), &dst| dst)
+                      .concat(reach)
+                      .distinct()
+             })
+             .consolidate()
+             .inner
+             .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        (0, RootTimestamp::new(0), 1),
+        (1, RootTimestamp::new(0), 1),
+        (2, RootTimestamp::new(0), 1),
+        (2, RootTimestamp::new(1), -1),
+        (3, RootTimestamp::new(0), 1),
+        (3, RootTimestamp::new(1), -1),
+    ]);
+}
+
+// records of a stratified program: a relation and the node it concerns, or the edge for `EDGE`.
+type Fact = (u32, (u32, u32));
+
+const NODE: u32 = 0;
+const EDGE: u32 = 1;
+const ROOT: u32 = 2;
+const REACH: u32 = 3;
+const UNREACHED: u32 = 4;
+
+// nodes reachable from a root, derived recursively.
+fn reach<'a, G: Scope>(lower: &Collection<Child<'a, G, u64>, Fact>, recursive: &Positive<'a, G, Fact>) -> Positive<'a, G, Fact>
+where G::Timestamp: Lattice+Ord+Debug {
+    let edges = lower.filter(|&(rel, _)| rel == EDGE).map(|(_, edge)| edge);
+    let roots = lower.filter(|&(rel, _)| rel == ROOT).map(|(_, (node, _))| (REACH, (node, 0)));
+    recursive.filter(|&(rel, _)| rel == REACH)
+             .map(|(_, (node, _))| (node, ()))
+             .join_map_lower(&edges, |_src, &(), &dst| (REACH, (dst, 0)))
+             .concat(&Positive::from(&roots))
+}
+
+// nodes not reachable from any root, which negates the complete `REACH` relation of the previous stratum.
+fn unreached<'a, G: Scope>(lower: &Collection<Child<'a, G, u64>, Fact>, _recursive: &Positive<'a, G, Fact>) -> Positive<'a, G, Fact>
+where G::Timestamp: Lattice+Ord+Debug {
+    let nodes = lower.filter(|&(rel, _)| rel == NODE).map(|(_, (node, _))| (node, ()));
+    let reached = lower.filter(|&(rel, _)| rel == REACH).map(|(_, (node, _))| node);
+    Positive::from(&nodes.antijoin(&reached).map(|(node, ())| (UNREACHED, (node, 0))))
+}
+
+#[test]
+fn stratified_negation() {
+
+    let data = timely::example(|scope| {
+
+        // nodes 0 through 4 and root 0; the edge (0,1) is replaced by (2,3) at time 1.
+        let mut facts = (0 .. 5).map(|node| ((NODE, (node, 0)), RootTimestamp::new(0), 1)).collect::<Vec<_>>();
+        facts.push(((ROOT, (0, 0)), RootTimestamp::new(0), 1));
+        facts.push(((EDGE, (0, 1)), RootTimestamp::new(0), 1));
+        facts.push(((EDGE, (1, 2)), RootTimestamp::new(0), 1));
+        facts.push(((EDGE, (3, 4)), RootTimestamp::new(0), 1));
+        facts.push(((EDGE, (0, 1)), RootTimestamp::new(1), -1));
+        facts.push(((EDGE, (2, 3)), RootTimestamp::new(1), 1));
+        let facts = facts.into_iter().to_stream(scope).as_collection();
+
+        Stratified::new()
+            .stratum(reach)
+            .stratum(unreached)
+            .evaluate(&facts)
+            .filter(|&(rel, _)| rel == REACH || rel == UNREACHED)
+            .consolidate()
+            .inner
+            .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((REACH, (0, 0)), RootTimestamp::new(0), 1),
+        ((REACH, (1, 0)), RootTimestamp::new(0), 1),
+        ((REACH, (1, 0)), RootTimestamp::new(1), -1),
+        ((REACH, (2, 0)), RootTimestamp::new(0), 1),
+        ((REACH, (2, 0)), RootTimestamp::new(1), -1),
+        ((UNREACHED, (1, 0)), RootTimestamp::new(1), 1),
+        ((UNREACHED, (2, 0)), RootTimestamp::new(1), 1),
+        ((UNREACHED, (3, 0)), RootTimestamp::new(0), 1),
+        ((UNREACHED, (4, 0)), RootTimestamp::new(0), 1),
+    ]);
+}
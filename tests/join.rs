@@ -1,11 +1,17 @@
 extern crate timely;
 extern crate differential_dataflow;
 
+use timely::progress::nested::product::Product;
 use timely::progress::timestamp::RootTimestamp;
-use timely::dataflow::operators::{ToStream, Capture, Map};
-use timely::dataflow::operators::capture::Extract;
+use timely::dataflow::operators::{ToStream, Capture, Map, Input};
+use timely::dataflow::operators::capture::{Extract, Event};
 use differential_dataflow::AsCollection;
 use differential_dataflow::operators::{Consolidate, Join, Count};
+use differential_dataflow::operators::arrange::{ArrangeBySelf, ArrangeByKey, ArrangeByKeyCmp};
+use differential_dataflow::operators::join::{ValidateForeignKey, JoinUnsigned, JoinSampled, JoinSkewed, Skew, CrossJoin, Guard, LookupMap, JoinArranged, JoinProvisional, join_pipeline};
+use differential_dataflow::difference::Decimal;
+use differential_dataflow::operators::fuel::with_fuel;
+use differential_dataflow::trace::layers::memo::MemoCmp;
 
 #[test]
 fn join() {
@@ -77,6 +83,121 @@ fn antijoin() {
     assert_eq!(extracted[0].1, vec![((1,2), Default::default(),1)]);
 }
 
+#[test]
+fn validate_fk() {
+    let data = timely::example(|scope| {
+        let child = vec![((0,'a'), Default::default(),1),((1,'b'), Default::default(),1),((1,'c'), Default::default(),1)].into_iter().to_stream(scope).as_collection();
+        let parent = vec![(0, Default::default(),1)].into_iter().to_stream(scope).as_collection().arrange_by_self();
+
+        // should report records `(1,'b')` and `(1,'c')`, whose key is not a parent.
+        child.validate_fk(&parent).consolidate().inner.capture()
+    });
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((1,'b'), Default::default(),1), ((1,'c'), Default::default(),1)]);
+}
+
+#[test]
+fn join_unsigned() {
+
+    let data = timely::example(|scope| {
+
+        let parts = vec![
+            ((1u32, 'a'), RootTimestamp::new(0), 1),
+            ((2u32, 'b'), RootTimestamp::new(0), 1),
+            ((2u32, 'b'), RootTimestamp::new(1), -1),
+            ((3u32, 'c'), RootTimestamp::new(1), 1),
+        ].into_iter().to_stream(scope).as_collection();
+        let suppliers = vec![
+            ((1u32, 10u64), RootTimestamp::new(0), 1),
+            ((2u32, 20u64), RootTimestamp::new(0), 1),
+            ((3u32, 30u64), RootTimestamp::new(0), 1),
+            ((1u32, 10u64), RootTimestamp::new(2), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        parts.join_unsigned(&suppliers).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 'a', 10), RootTimestamp::new(0), 1),
+        ((1, 'a', 10), RootTimestamp::new(2), -1),
+        ((2, 'b', 20), RootTimestamp::new(0), 1),
+        ((2, 'b', 20), RootTimestamp::new(1), -1),
+        ((3, 'c', 30), RootTimestamp::new(1), 1),
+    ]);
+}
+
+#[test]
+fn semijoin_antijoin_unsigned() {
+
+    let data = timely::example(|scope| {
+
+        let records = vec![
+            ((0u64, 0), RootTimestamp::new(0), 1),
+            ((1u64, 2), RootTimestamp::new(0), 1),
+            ((2u64, 4), RootTimestamp::new(0), 1),
+        ].into_iter().to_stream(scope).as_collection();
+        let keys = vec![
+            (0u64, RootTimestamp::new(0), 1),
+            (2u64, RootTimestamp::new(1), 1),
+            (0u64, RootTimestamp::new(2), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // records whose keys are present, tagged `true`, and those whose keys are absent, tagged `false`.
+        let present = records.semijoin_unsigned(&keys).map(|x| (true, x));
+        let absent = records.antijoin_unsigned(&keys).map(|x| (false, x));
+        present.concat(&absent).consolidate().inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((false, (0, 0)), RootTimestamp::new(2), 1),
+        ((false, (1, 2)), RootTimestamp::new(0), 1),
+        ((false, (2, 4)), RootTimestamp::new(0), 1),
+        ((false, (2, 4)), RootTimestamp::new(1), -1),
+        ((true, (0, 0)), RootTimestamp::new(0), 1),
+        ((true, (0, 0)), RootTimestamp::new(2), -1),
+        ((true, (2, 4)), RootTimestamp::new(1), 1),
+    ]);
+}
+
+#[test]
+fn join_pipeline_arranged() {
+
+    let data = timely::example(|scope| {
+
+        let names = vec![
+            ((0u64, 10u64), RootTimestamp::new(0), 1),
+            ((1u64, 11u64), RootTimestamp::new(0), 1),
+        ].into_iter().to_stream(scope).as_collection();
+        let cities = vec![
+            ((0u64, 20u64), RootTimestamp::new(0), 1),
+            ((1u64, 21u64), RootTimestamp::new(0), 1),
+            ((1u64, 21u64), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+        let ages = vec![
+            ((0u64, 30u64), RootTimestamp::new(0), 1),
+            ((1u64, 31u64), RootTimestamp::new(0), 1),
+            ((2u64, 32u64), RootTimestamp::new(0), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // values are listed in the order of the inputs, whatever order the joins are performed in.
+        let inputs = vec![names.arrange_by_key_hashed(), cities.arrange_by_key_hashed(), ages.arrange_by_key_hashed()];
+        join_pipeline(&inputs[..]).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((0, vec![10, 20, 30]), RootTimestamp::new(0), 1),
+        ((1, vec![11, 21, 31]), RootTimestamp::new(0), 1),
+        ((1, vec![11, 21, 31]), RootTimestamp::new(1), -1),
+    ]);
+}
+
 #[test] fn join_scale_1() { join_scaling(1); }
 #[test] fn join_scale_10() { join_scaling(10); }
 #[test] fn join_scale_100() { join_scaling(100); }
@@ -102,4 +223,396 @@ fn join_scaling(scale: u64) {
 
     let extracted = data.extract();
     assert_eq!(extracted.len(), 0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn join_map_sampled_weights() {
+
+    let data = timely::example(|scope| {
+
+        let orders = vec![
+            ((0u64, 'a'), RootTimestamp::new(0), 1),
+            ((1u64, 'b'), RootTimestamp::new(0), 1),
+            ((1u64, 'b'), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+        let customers = vec![
+            ((0u64, "x"), RootTimestamp::new(0), 2),
+            ((1u64, "y"), RootTimestamp::new(0), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // keys are all retained at weight one, or none are retained.
+        let all = orders.join_map_sampled(&customers, |_| 1.5, |k, o, c| (*k, *o, *c));
+        let none = orders.join_map_sampled(&customers, |_| 0.0, |k, o, c| (*k, *o, *c));
+        (all.consolidate().inner.capture(), none.consolidate().inner.capture())
+    });
+
+    let mut all = (data.0).extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    all.sort();
+    assert_eq!(all, vec![
+        ((0, 'a', "x"), RootTimestamp::new(0), Decimal::from_integer(2)),
+        ((1, 'b', "y"), RootTimestamp::new(0), Decimal::from_integer(1)),
+        ((1, 'b', "y"), RootTimestamp::new(1), Decimal::from_integer(-1)),
+    ]);
+    assert!((data.1).extract().into_iter().all(|(_, data)| data.is_empty()));
+}
+
+// the output of `join_map_skewed` with the strategy `skew`, over inputs with a heavy key and retractions.
+fn skewed(skew: Skew<u64>) -> Vec<((u64, char, char), Product<RootTimestamp, u64>, isize)> {
+
+    let data = timely::example(move |scope| {
+
+        let input1 = vec![
+            ((0u64, 'a'), RootTimestamp::new(0), 1),
+            ((0u64, 'b'), RootTimestamp::new(0), 1),
+            ((0u64, 'c'), RootTimestamp::new(0), 1),
+            ((1u64, 'd'), RootTimestamp::new(0), 1),
+            ((0u64, 'b'), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+        let input2 = vec![
+            ((0u64, 'x'), RootTimestamp::new(0), 1),
+            ((1u64, 'y'), RootTimestamp::new(0), 1),
+            ((0u64, 'z'), RootTimestamp::new(1), 1),
+            ((1u64, 'y'), RootTimestamp::new(2), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        input1.join_map_skewed(&input2, skew, |k, v1, v2| (*k, *v1, *v2))
+              .consolidate()
+              .inner
+              .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    results
+}
+
+#[test]
+fn join_map_skewed_strategies() {
+
+    let expected = skewed(Skew::Hash);
+    assert_eq!(expected, vec![
+        ((0, 'a', 'x'), RootTimestamp::new(0), 1),
+        ((0, 'a', 'z'), RootTimestamp::new(1), 1),
+        ((0, 'b', 'x'), RootTimestamp::new(0), 1),
+        ((0, 'b', 'x'), RootTimestamp::new(1), -1),
+        ((0, 'c', 'x'), RootTimestamp::new(0), 1),
+        ((0, 'c', 'z'), RootTimestamp::new(1), 1),
+        ((1, 'd', 'y'), RootTimestamp::new(0), 1),
+        ((1, 'd', 'y'), RootTimestamp::new(2), -1),
+    ]);
+
+    assert_eq!(skewed(Skew::Broadcast(Guard::Unlimited)), expected);
+    assert_eq!(skewed(Skew::Salt(3, ::std::rc::Rc::new(|k: &u64| *k == 0))), expected);
+    assert_eq!(skewed(Skew::from_sample(3, vec![0, 0, 0, 1], 2)), expected);
+}
+
+#[test]
+fn join_map_skewed_sample() {
+
+    // keys occurring at least the threshold number of times in the sample are salted.
+    if let Skew::Salt(salts, heavy) = Skew::from_sample(4, vec![7u64, 7, 7, 8, 9, 9], 2) {
+        assert_eq!(salts, 4);
+        assert!(heavy(&7));
+        assert!(heavy(&9));
+        assert!(!heavy(&8));
+        assert!(!heavy(&10));
+    }
+    else {
+        panic!("from_sample should salt keys");
+    }
+}
+
+#[test]
+fn join_map_skewed_broadcast_guard() {
+
+    // the guard reports once, when the updates of the second input replicated by the worker exceed the limit.
+    let reports = ::std::rc::Rc::new(::std::cell::RefCell::new(Vec::new()));
+    let reports2 = reports.clone();
+    skewed(Skew::Broadcast(Guard::Warn(2, ::std::rc::Rc::new(move |count| reports2.borrow_mut().push(count)))));
+    assert_eq!(*reports.borrow(), vec![3]);
+}
+
+#[test]
+fn self_join_map() {
+
+    let (ordered, unordered, joined) = timely::example(|scope| {
+
+        let edges = vec![
+            ((0u64, 1u64), RootTimestamp::new(0), 1),
+            ((0u64, 2u64), RootTimestamp::new(0), 1),
+            ((1u64, 3u64), RootTimestamp::new(0), 1),
+            ((0u64, 2u64), RootTimestamp::new(1), -1),
+            ((1u64, 4u64), RootTimestamp::new(1), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let ordered = edges.self_join_map(true, |k, v1, v2| (*k, *v1, *v2)).consolidate().inner.capture();
+        let unordered = edges.self_join_map(false, |k, v1, v2| (*k, *v1, *v2)).consolidate().inner.capture();
+        let joined = edges.join_map(&edges, |k, v1, v2| (*k, *v1, *v2)).consolidate().inner.capture();
+        (ordered, unordered, joined)
+    });
+
+    let mut ordered = ordered.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    ordered.sort();
+    assert_eq!(ordered, vec![
+        ((0, 1, 1), RootTimestamp::new(0), 1),
+        ((0, 1, 2), RootTimestamp::new(0), 1),
+        ((0, 1, 2), RootTimestamp::new(1), -1),
+        ((0, 2, 2), RootTimestamp::new(0), 1),
+        ((0, 2, 2), RootTimestamp::new(1), -1),
+        ((1, 3, 3), RootTimestamp::new(0), 1),
+        ((1, 3, 4), RootTimestamp::new(1), 1),
+        ((1, 4, 4), RootTimestamp::new(1), 1),
+    ]);
+
+    // without ordering, the output is that of joining the collection with itself.
+    let mut unordered = unordered.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    let mut joined = joined.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    unordered.sort();
+    joined.sort();
+    assert_eq!(unordered.len(), 11);
+    assert_eq!(unordered, joined);
+}
+
+// the output of `cross_join` with `guard`, over inputs with a retraction, and the counts the guard reported.
+fn cross_joined(limit: usize) -> (Vec<((char, u64), Product<RootTimestamp, u64>, isize)>, Vec<usize>) {
+
+    let reports = ::std::rc::Rc::new(::std::cell::RefCell::new(Vec::new()));
+    let reports2 = reports.clone();
+
+    let data = timely::example(move |scope| {
+
+        let left = vec![
+            ('a', RootTimestamp::new(0), 1),
+            ('b', RootTimestamp::new(0), 1),
+            ('b', RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+        let right = vec![
+            (0u64, RootTimestamp::new(0), 1),
+            (1u64, RootTimestamp::new(1), 2),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let guard = Guard::Warn(limit, ::std::rc::Rc::new(move |count| reports2.borrow_mut().push(count)));
+        left.cross_join(&right, guard).consolidate().inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    let reports = reports.borrow().clone();
+    (results, reports)
+}
+
+#[test]
+fn cross_join_guarded() {
+
+    let (results, reports) = cross_joined(6);
+    assert_eq!(results, vec![
+        (('a', 0), RootTimestamp::new(0), 1),
+        (('a', 1), RootTimestamp::new(1), 2),
+        (('b', 0), RootTimestamp::new(0), 1),
+        (('b', 0), RootTimestamp::new(1), -1),
+    ]);
+    assert!(reports.is_empty());
+
+    // three updates of one input and two of the other may produce six output updates.
+    let (_, reports) = cross_joined(5);
+    assert_eq!(reports, vec![6]);
+}
+
+#[test]
+fn lookup_map() {
+
+    let data = timely::example(|scope| {
+
+        // orders of customers, one of which is later cancelled.
+        let orders = vec![
+            ((1u64, 'a'), RootTimestamp::new(0), 1),
+            ((2u64, 'b'), RootTimestamp::new(0), 1),
+            ((1u64, 'a'), RootTimestamp::new(3), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // the name of each customer, where customer 1 is renamed and customer 2 only later named.
+        let names = vec![
+            ((1u64, "ann".to_owned()), RootTimestamp::new(0), 1),
+            ((1u64, "ann".to_owned()), RootTimestamp::new(1), -1),
+            ((1u64, "bob".to_owned()), RootTimestamp::new(1), 1),
+            ((2u64, "cat".to_owned()), RootTimestamp::new(2), 1),
+        ].into_iter().to_stream(scope).as_collection().arrange_by_key_hashed();
+
+        orders.lookup_map(&names, "unknown".to_owned()).consolidate().inner.capture()
+    });
+
+    // each order has exactly one name at each time.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 'a', "ann".to_owned()), RootTimestamp::new(0), 1),
+        ((1, 'a', "ann".to_owned()), RootTimestamp::new(1), -1),
+        ((1, 'a', "bob".to_owned()), RootTimestamp::new(1), 1),
+        ((1, 'a', "bob".to_owned()), RootTimestamp::new(3), -1),
+        ((2, 'b', "cat".to_owned()), RootTimestamp::new(2), 1),
+        ((2, 'b', "unknown".to_owned()), RootTimestamp::new(0), 1),
+        ((2, 'b', "unknown".to_owned()), RootTimestamp::new(2), -1),
+    ]);
+}
+
+#[test]
+fn join_arranged_with() {
+
+    let data = timely::example(|scope| {
+
+        // counts of items sold at stores, of which one sale is later returned.
+        let sales = vec![
+            ((1u64, 'x'), RootTimestamp::new(0), 2isize),
+            ((2u64, 'y'), RootTimestamp::new(0), 1),
+            ((1u64, 'x'), RootTimestamp::new(2), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // the price of each item as its weight, where the price of item 1 rises from 3 to 5.
+        let prices = vec![
+            ((1u64, ()), RootTimestamp::new(0), 3i64),
+            ((2u64, ()), RootTimestamp::new(0), 7),
+            ((1u64, ()), RootTimestamp::new(1), 2),
+        ].into_iter().to_stream(scope).as_collection().arrange_by_key_hashed();
+
+        // revenue of each item at each store.
+        sales.join_arranged_with(&prices, |item, store, &()| (item.item, *store), |count, price| *count as i64 * *price)
+             .consolidate()
+             .inner
+             .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 'x'), RootTimestamp::new(0), 6i64),
+        ((1, 'x'), RootTimestamp::new(1), 4),
+        ((1, 'x'), RootTimestamp::new(2), -5),
+        ((2, 'y'), RootTimestamp::new(0), 7),
+    ]);
+}
+
+#[test]
+fn join_map_provisional() {
+
+    let output = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let (mut input1, mut input2, captured) = worker.dataflow(|scope| {
+            let (input1, data1) = scope.new_input();
+            let (input2, data2) = scope.new_input();
+            // results are held back until the other input is complete through the prior time.
+            let captured = data1.as_collection()
+                                .join_map_provisional(&data2.as_collection(), |t| RootTimestamp::new(t.inner.saturating_sub(1)), |k: &u64, v1: &char, v2: &char| (*k, *v1, *v2))
+                                .inner
+                                .capture();
+            (input1, input2, captured)
+        });
+
+        input1.send(((1u64, 'x'), RootTimestamp::new(0), 1));
+        input2.send(((1u64, 'p'), RootTimestamp::new(0), 1));
+        input1.advance_to(1u64);
+        input2.advance_to(1u64);
+        for _ in 0 .. 10 { worker.step(); }
+
+        // the second input trails, so the result at time 1 is provisional.
+        input1.send(((1, 'z'), RootTimestamp::new(1), 1));
+        input1.advance_to(2);
+        for _ in 0 .. 10 { worker.step(); }
+
+        // the second input catches up, retracting its record, which corrects the provisional result.
+        input2.send(((1, 'p'), RootTimestamp::new(1), -1));
+        input2.advance_to(2);
+        for _ in 0 .. 10 { worker.step(); }
+
+        input1.close();
+        input2.close();
+        while worker.step() { }
+
+        let mut output = captured.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+        output.sort();
+        output
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(output, vec![
+        (((1, 'x', 'p'), false), RootTimestamp::new(0), 1),
+        (((1, 'x', 'p'), false), RootTimestamp::new(1), -1),
+        (((1, 'z', 'p'), false), RootTimestamp::new(1), -1),
+        (((1, 'z', 'p'), true), RootTimestamp::new(1), 1),
+    ]);
+}
+
+#[test]
+fn join_arranged_by_key_cmp() {
+
+    let data = timely::example(|scope| {
+
+        // optional names, where one is later retracted.
+        let ids = vec![
+            ((Some("alice".to_owned()), 1u64), RootTimestamp::new(0), 1),
+            ((None, 2u64), RootTimestamp::new(0), 1),
+            ((Some("bob".to_owned()), 3u64), RootTimestamp::new(0), 1),
+            ((Some("alice".to_owned()), 1u64), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection().arrange_by_key_cmp();
+
+        let tags = vec![
+            ((Some("alice".to_owned()), 'x'), RootTimestamp::new(0), 1),
+            ((None, 'y'), RootTimestamp::new(0), 1),
+            ((Some("carol".to_owned()), 'z'), RootTimestamp::new(0), 1),
+        ].into_iter().to_stream(scope).as_collection().arrange_by_key_cmp();
+
+        ids.join_arranged(&tags, |name: &MemoCmp<Option<String>>, id: &u64, tag: &char| (name.item.clone(), *id, *tag))
+           .inner
+           .capture()
+    });
+
+    // keys match exactly, including `None`, and the retracted name's result is retracted.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((None, 2, 'y'), RootTimestamp::new(0), 1),
+        ((Some("alice".to_owned()), 1, 'x'), RootTimestamp::new(0), 1),
+        ((Some("alice".to_owned()), 1, 'x'), RootTimestamp::new(1), -1),
+    ]);
+}
+
+// the output of `join_map` constructed with `fuel`, over ten keys of which one is retracted, and the number of
+// messages in which it was produced.
+fn fueled(fuel: usize) -> (Vec<((u64, char, char), Product<RootTimestamp, u64>, isize)>, usize) {
+
+    let captured = timely::example(move |scope| {
+
+        let mut left = (0 .. 10u64).map(|key| ((key, 'l'), RootTimestamp::new(0), 1)).collect::<Vec<_>>();
+        left.push(((3, 'l'), RootTimestamp::new(1), -1));
+        let left = left.into_iter().to_stream(scope).as_collection();
+        let right = (0 .. 10u64).map(|key| ((key, 'r'), RootTimestamp::new(0), 1)).to_stream(scope).as_collection();
+
+        with_fuel(fuel, || left.join_map(&right, |k, l, r| (*k, *l, *r))).inner.capture()
+    });
+
+    let mut messages = 0;
+    let mut results = Vec::new();
+    for event in captured.try_iter() {
+        if let Event::Messages(_, data) = event {
+            messages += 1;
+            results.extend(data);
+        }
+    }
+    results.sort();
+    (results, messages)
+}
+
+#[test]
+fn join_map_fueled() {
+
+    let (unfueled, unfueled_messages) = fueled(1_000_000);
+    let (fueled, fueled_messages) = fueled(1);
+
+    // the same results, including the retraction, but produced a key at a time.
+    let mut expected = (0 .. 10u64).map(|key| ((key, 'l', 'r'), RootTimestamp::new(0), 1)).collect::<Vec<_>>();
+    expected.push(((3, 'l', 'r'), RootTimestamp::new(1), -1));
+    expected.sort();
+    assert_eq!(unfueled, expected);
+    assert_eq!(fueled, expected);
+    assert!(unfueled_messages < 10);
+    assert!(fueled_messages >= 10);
+}
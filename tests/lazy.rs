@@ -0,0 +1,101 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::plan;
+use differential_dataflow::operators::arrange::{ArrangeCached, LazyArranged};
+use differential_dataflow::trace::Trace;
+use differential_dataflow::trace::implementations::ord::OrdValSpine;
+
+// the number of arrange operators in `plan`.
+fn arranges(plan: &plan::Plan) -> usize {
+    operators(plan, "Arrange")
+}
+
+// the number of operators named `name` in `plan`.
+fn operators(plan: &plan::Plan, name: &str) -> usize {
+    plan.nodes.iter().filter(|node| node.operator == name).count()
+}
+
+#[test]
+fn lazy_without_consumers() {
+
+    // an unused lazy arrangement constructs no operator.
+    let (arranged, plan) = plan::record(|| timely::example(|scope| {
+        let collection = vec![((0u64, 1u64), Default::default(), 1)].into_iter().to_stream(scope).as_collection()
+                                                                     .map(|(k, v)| (OrdWrapper { item: k }, v));
+        let lazy = LazyArranged::new(&collection, OrdValSpine::<OrdWrapper<u64>, u64, _, isize>::new());
+        lazy.is_arranged()
+    }));
+
+    assert!(!arranged);
+    assert_eq!(arranges(&plan), 0);
+}
+
+#[test]
+fn lazy_with_consumers() {
+
+    // the first access builds the arrangement, and later accesses share it.
+    let ((arranged, data), plan) = plan::record(|| timely::example(|scope| {
+        let collection = vec![
+            ((0u64, 1u64), RootTimestamp::new(0), 1),
+            ((0, 2), RootTimestamp::new(0), 1),
+            ((0, 1), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection().map(|(k, v)| (OrdWrapper { item: k }, v));
+        let lazy = LazyArranged::new(&collection, OrdValSpine::<OrdWrapper<u64>, u64, _, isize>::new());
+        let _trace = lazy.trace();
+        let _stream = lazy.stream();
+        let data = lazy.arranged().as_collection(|k, v| (k.item, *v)).inner.capture();
+        (lazy.is_arranged(), data)
+    }));
+
+    assert!(arranged);
+    assert_eq!(arranges(&plan), 1);
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((0, 1), RootTimestamp::new(0), 1),
+        ((0, 1), RootTimestamp::new(1), -1),
+        ((0, 2), RootTimestamp::new(0), 1),
+    ]);
+}
+
+#[test]
+fn arrange_cached_shares_traces() {
+
+    // the second arrangement of the collection imports the trace of the first.
+    let ((data1, data2), plan) = plan::record(|| timely::example(|scope| {
+        let collection = vec![
+            ((0u64, 1u64), RootTimestamp::new(0), 1),
+            ((1, 2), RootTimestamp::new(0), 1),
+            ((0, 1), RootTimestamp::new(1), -1),
+            ((1, 3), RootTimestamp::new(2), 1),
+        ].into_iter().to_stream(scope).as_collection().map(|(k, v)| (OrdWrapper { item: k }, v));
+        let arranged1 = collection.arrange_cached(OrdValSpine::new());
+        let arranged2 = collection.arrange_cached(OrdValSpine::new());
+        let data1 = arranged1.as_collection(|k: &OrdWrapper<u64>, v: &u64| (k.item, *v)).inner.capture();
+        let data2 = arranged2.as_collection(|k: &OrdWrapper<u64>, v: &u64| (k.item, *v)).inner.capture();
+        (data1, data2)
+    }));
+
+    assert_eq!(arranges(&plan), 1);
+    assert_eq!(operators(&plan, "Import"), 1);
+
+    let mut results1 = data1.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    let mut results2 = data2.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results1.sort();
+    results2.sort();
+    assert_eq!(results1, vec![
+        ((0, 1), RootTimestamp::new(0), 1),
+        ((0, 1), RootTimestamp::new(1), -1),
+        ((1, 2), RootTimestamp::new(0), 1),
+        ((1, 3), RootTimestamp::new(2), 1),
+    ]);
+    assert_eq!(results1, results2);
+}
@@ -0,0 +1,80 @@
+extern crate differential_dataflow;
+
+use differential_dataflow::trace::{BatchReader, Builder, Cursor};
+use differential_dataflow::trace::implementations::ord::{OrdValBatch, OrdValBuilder};
+use differential_dataflow::operators::leapfrog::{intersect_keys, intersect_vals};
+
+// a batch of `(key, val)` pairs, which must be sorted.
+fn batch(pairs: Vec<(u64, u64)>) -> OrdValBatch<u64, u64, usize, isize> {
+    let mut builder = OrdValBuilder::new();
+    for (key, val) in pairs { builder.push((key, val, 0, 1)); }
+    builder.done(&[0], &[1], &[0])
+}
+
+fn pairs(keys: &[u64]) -> Vec<(u64, u64)> {
+    keys.iter().map(|&key| (key, key)).collect()
+}
+
+#[test]
+fn keys_intersect() {
+
+    let batch1 = batch(pairs(&[0, 1, 2, 3, 5, 8, 13, 21, 34]));
+    let batch2 = batch(pairs(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 34, 35]));
+    let batch3 = batch(pairs(&[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 34]));
+
+    let mut cursors = vec![batch1.cursor(), batch2.cursor(), batch3.cursor()];
+    let mut keys = Vec::new();
+    intersect_keys(&mut cursors[..], |key, cursors| {
+        // the cursors are positioned at the key.
+        assert!(cursors.iter().all(|c| c.key() == key));
+        keys.push(*key);
+    });
+
+    assert_eq!(keys, vec![2, 3, 5, 34]);
+}
+
+#[test]
+fn keys_disjoint() {
+
+    let batch1 = batch(pairs(&[0, 2, 4, 6]));
+    let batch2 = batch(pairs(&[1, 3, 5, 7]));
+
+    let mut cursors = vec![batch1.cursor(), batch2.cursor()];
+    let mut keys = Vec::new();
+    intersect_keys(&mut cursors[..], |key, _cursors| keys.push(*key));
+    assert!(keys.is_empty());
+
+    // an empty input intersects to nothing.
+    let empty = batch(vec![]);
+    let mut cursors = vec![batch1.cursor(), empty.cursor()];
+    intersect_keys(&mut cursors[..], |key, _cursors| keys.push(*key));
+    assert!(keys.is_empty());
+}
+
+#[test]
+fn keys_single_cursor() {
+    let batch1 = batch(pairs(&[1, 4, 9]));
+    let mut cursors = vec![batch1.cursor()];
+    let mut keys = Vec::new();
+    intersect_keys(&mut cursors[..], |key, _cursors| keys.push(*key));
+    assert_eq!(keys, vec![1, 4, 9]);
+}
+
+#[test]
+fn vals_intersect() {
+
+    let batch1 = batch(vec![(0, 1), (0, 2), (0, 3), (1, 1), (1, 4), (2, 5)]);
+    let batch2 = batch(vec![(0, 2), (0, 3), (0, 4), (1, 4), (2, 6)]);
+
+    // the values shared at each shared key.
+    let mut cursors = vec![batch1.cursor(), batch2.cursor()];
+    let mut found = Vec::new();
+    intersect_keys(&mut cursors[..], |key, cursors| {
+        intersect_vals(cursors, |val, cursors| {
+            assert!(cursors.iter().all(|c| c.val() == val));
+            found.push((*key, *val));
+        });
+    });
+
+    assert_eq!(found, vec![(0, 2), (0, 3), (1, 4)]);
+}
@@ -0,0 +1,66 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::progress::nested::product::Product;
+
+use differential_dataflow::trace::{Trace, TraceReader, Builder, Cursor, consolidate};
+use differential_dataflow::trace::implementations::ord::{OrdValBatch, OrdValBuilder, OrdValSpine};
+use differential_dataflow::trace::wrappers::map_time::TraceMapTime;
+use differential_dataflow::trace::wrappers::enter::TraceEnter;
+
+// a batch of updates, which must be sorted by key and value.
+fn batch(updates: Vec<(u64, u64, usize, isize)>, lower: usize, upper: usize) -> OrdValBatch<u64, u64, usize, isize> {
+    let mut builder = OrdValBuilder::new();
+    for update in updates { builder.push(update); }
+    builder.done(&[lower], &[upper], &[0])
+}
+
+// the consolidated updates presented by `cursor`.
+fn contents<T: Ord+Clone, C: Cursor<u64, u64, T, isize>>(mut cursor: C) -> Vec<((u64, u64), T, isize)> {
+    let mut result = Vec::new();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let (key, val) = (*cursor.key(), *cursor.val());
+            cursor.map_times(|time, diff| result.push(((key, val, time.clone()), diff)));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+    consolidate(&mut result, 0);
+    result.into_iter().map(|((key, val, time), diff)| ((key, val), time, diff)).collect()
+}
+
+fn trace() -> OrdValSpine<u64, u64, usize, isize> {
+    let mut trace = OrdValSpine::new();
+    trace.insert(batch(vec![(0, 0, 3, 1), (0, 0, 12, 1), (1, 0, 7, 1), (1, 0, 15, -1), (2, 0, 25, 1)], 0, 30));
+    trace
+}
+
+#[test]
+fn map_time_closures() {
+
+    // coarsens times to multiples of ten, and suppresses updates at times from twenty onward.
+    let mut coarse = TraceMapTime::new(trace(), |time: &usize| if *time < 20 { Some(time / 10) } else { None }, |time: &usize| time * 10);
+    assert_eq!(contents(coarse.cursor()), vec![
+        ((0, 0), 0, 1), ((0, 0), 1, 1),
+        ((1, 0), 0, 1), ((1, 0), 1, -1),
+    ]);
+
+    // frontiers are mapped back to times of the wrapped trace.
+    coarse.advance_by(&[2]);
+    assert_eq!(coarse.inner_mut().advance_frontier(), &[20]);
+}
+
+#[test]
+fn map_time_enter() {
+
+    let mut entered: TraceEnter<u64, u64, usize, isize, _, u64> = TraceEnter::make_from(trace());
+    assert_eq!(contents(entered.cursor()), vec![
+        ((0, 0), Product::new(3, 0), 1), ((0, 0), Product::new(12, 0), 1),
+        ((1, 0), Product::new(7, 0), 1), ((1, 0), Product::new(15, 0), -1),
+        ((2, 0), Product::new(25, 0), 1),
+    ]);
+
+    entered.distinguish_since(&[Product::new(10, 3)]);
+    assert_eq!(entered.inner_mut().distinguish_frontier(), &[10]);
+}
@@ -0,0 +1,56 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use differential_dataflow::AsCollection;
+use differential_dataflow::algorithms::motifs::{triangles, cliques};
+
+#[test]
+fn triangles_small() {
+
+    let data = timely::example(|scope| {
+
+        // a triangle (0,1,2) with a pendant edge (2,3), one edge presented in both directions.
+        let edges = vec![((0u32,1u32), Default::default(), 1), ((2,1), Default::default(), 1), ((0,2), Default::default(), 1), ((2,0), Default::default(), 1), ((2,3), Default::default(), 1)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection();
+
+        triangles(&edges).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0,1,2), Default::default(), 1)]);
+}
+
+#[test]
+fn four_cliques() {
+
+    let data = timely::example(|scope| {
+
+        // the complete graph on nodes 0 .. 5 has five 4-cliques.
+        let mut edges = Vec::new();
+        for a in 0 .. 5u32 {
+            for b in (a + 1) .. 5 {
+                edges.push(((a, b), Default::default(), 1));
+            }
+        }
+
+        let edges = edges.into_iter().to_stream(scope).as_collection();
+        cliques(&edges, 4).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    let mut cliques = extracted[0].1.clone();
+    cliques.sort();
+    assert_eq!(cliques, vec![
+        (vec![0,1,2,3], Default::default(), 1),
+        (vec![0,1,2,4], Default::default(), 1),
+        (vec![0,1,3,4], Default::default(), 1),
+        (vec![0,2,3,4], Default::default(), 1),
+        (vec![1,2,3,4], Default::default(), 1),
+    ]);
+}
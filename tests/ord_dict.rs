@@ -0,0 +1,183 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::{Consolidate, Distinct, Iterate};
+use differential_dataflow::operators::arrange::Arrange;
+use differential_dataflow::operators::join::JoinArranged;
+use differential_dataflow::trace::{Trace, TraceReader, BatchReader, Batch, Builder, Cursor, consolidate};
+use differential_dataflow::trace::implementations::ord::{OrdValBuilder, OrdValSpine};
+use differential_dataflow::trace::implementations::ord_dict::{OrdValDictBatch, OrdValDictBuilder, OrdValDictSpine};
+
+type Update = ((u64, u64), usize, isize);
+
+// a dictionary batch of updates, which must be sorted by key and value.
+fn dict_batch(updates: Vec<(u64, u64, usize, isize)>, lower: usize, upper: usize) -> OrdValDictBatch<u64, u64, usize, isize> {
+    let mut builder = OrdValDictBuilder::new();
+    for update in updates { builder.push(update); }
+    builder.done(&[lower], &[upper], &[0])
+}
+
+// the consolidated updates presented by `cursor`.
+fn contents<C: Cursor<u64, u64, usize, isize>>(mut cursor: C) -> Vec<Update> {
+    let mut result = Vec::new();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let (key, val) = (*cursor.key(), *cursor.val());
+            cursor.map_times(|time, diff| result.push(((key, val, *time), diff)));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+    consolidate(&mut result, 0);
+    result.into_iter().map(|((key, val, time), diff)| ((key, val), time, diff)).collect()
+}
+
+#[test]
+fn dict_batch_merge() {
+
+    let batch1 = dict_batch(vec![(0, 0, 0, 1), (1, 0, 0, 1), (1, 1, 0, 1)], 0, 1);
+    let batch2 = dict_batch(vec![(0, 0, 1, 1), (1, 1, 1, -1), (2, 0, 1, 1)], 1, 2);
+
+    // each distinct time is stored once.
+    assert_eq!(&batch1.times[..], &[0]);
+    assert_eq!(&batch2.times[..], &[1]);
+
+    let merged = batch1.merge(&batch2);
+    assert_eq!(merged.lower(), &[0]);
+    assert_eq!(merged.upper(), &[2]);
+    assert_eq!(merged.len(), 6);
+    assert_eq!(&merged.times[..], &[0, 1]);
+    assert_eq!(contents(merged.cursor()), vec![
+        ((0, 0), 0, 1), ((0, 0), 1, 1),
+        ((1, 0), 0, 1),
+        ((1, 1), 0, 1), ((1, 1), 1, -1),
+        ((2, 0), 1, 1),
+    ]);
+}
+
+#[test]
+fn dict_batch_merge_shared_times() {
+
+    // times present in both batches appear once in the merged dictionary.
+    let batch1 = dict_batch(vec![(0, 0, 0, 1), (0, 0, 1, 1), (1, 0, 1, 1)], 0, 2).advance_ref(&[3]);
+    let batch2 = dict_batch(vec![(0, 0, 4, 1), (1, 0, 2, -1), (2, 0, 3, 1)], 2, 5).advance_ref(&[3]);
+    assert_eq!(batch1.times.len(), 1);
+    assert_eq!(batch2.times.len(), 2);
+
+    let merged = batch1.merge(&batch2);
+    let mut times = (*merged.times).clone();
+    times.sort();
+    assert_eq!(times, vec![3, 4]);
+    assert_eq!(contents(merged.cursor()), vec![
+        ((0, 0), 3, 2), ((0, 0), 4, 1),
+        ((2, 0), 3, 1),
+    ]);
+}
+
+#[test]
+fn dict_batch_merge_consolidates() {
+
+    // advancing both batches to time 2 leaves updates that cancel once merged.
+    let batch1 = dict_batch(vec![(0, 0, 0, 1), (1, 0, 0, 1)], 0, 1).advance_ref(&[2]);
+    let batch2 = dict_batch(vec![(0, 0, 1, -1), (2, 0, 1, 1)], 1, 2).advance_ref(&[2]);
+
+    let merged = batch1.merge(&batch2);
+    assert_eq!(&merged.times[..], &[2]);
+    assert_eq!(contents(merged.cursor()), vec![((1, 0), 2, 1), ((2, 0), 2, 1)]);
+}
+
+#[test]
+#[should_panic]
+fn dict_batch_merge_requires_adjacent() {
+    let batch1 = dict_batch(vec![(0, 0, 0, 1)], 0, 1);
+    let batch2 = dict_batch(vec![(0, 0, 2, 1)], 2, 3);
+    batch1.merge(&batch2);
+}
+
+#[test]
+fn dict_spine_matches_ord_spine() {
+
+    let mut dict = OrdValDictSpine::<u64, u64, usize, isize>::new();
+    let mut ord = OrdValSpine::<u64, u64, usize, isize>::new();
+    for round in 0 .. 16 {
+        let updates = vec![(round as u64 % 3, 0, round, 1), (round as u64 % 5, 1, round, if round % 2 == 0 { 1 } else { -1 })];
+        let mut updates = updates.into_iter().map(|(k, v, t, d)| ((k, v, t), d)).collect::<Vec<_>>();
+        consolidate(&mut updates, 0);
+        let mut dict_builder = OrdValDictBuilder::new();
+        let mut ord_builder = OrdValBuilder::new();
+        for ((k, v, t), d) in updates {
+            dict_builder.push((k, v, t, d));
+            ord_builder.push((k, v, t, d));
+        }
+        dict.insert(dict_builder.done(&[round], &[round + 1], &[0]));
+        ord.insert(ord_builder.done(&[round], &[round + 1], &[0]));
+        dict.distinguish_since(&[round + 1]);
+        ord.distinguish_since(&[round + 1]);
+    }
+
+    assert_eq!(contents(dict.cursor()), contents(ord.cursor()));
+}
+
+// nodes reachable from the roots, through edges of which the last is removed at time 1.
+fn reachability(dictionary: bool) -> Vec<(u32, Product<RootTimestamp, u64>, isize)> {
+
+    let data = timely::example(move |scope| {
+
+        let edges = vec![
+            ((0u32, 1u32), RootTimestamp::new(0), 1),
+            ((1, 2), RootTimestamp::new(0), 1),
+            ((2, 3), RootTimestamp::new(0), 1),
+            ((3, 0), RootTimestamp::new(0), 1),
+            ((4, 5), RootTimestamp::new(0), 1),
+            ((2, 3), RootTimestamp::new(1), -1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let roots = vec![(0u32, RootTimestamp::new(0), 1)].into_iter().to_stream(scope).as_collection();
+
+        roots.iterate(|inner| {
+
+            let edges = edges.enter(&inner.scope()).map(|(src, dst)| (OrdWrapper { item: src }, dst));
+            let roots = roots.enter(&inner.scope());
+            let nodes = inner.map(|node| (OrdWrapper { item: node }, ()));
+
+            let next = if dictionary {
+                nodes.arrange(OrdValDictSpine::new())
+                     .join_arranged(&edges.arrange(OrdValDictSpine::new()), |_src, &(), &dst| dst)
+            }
+            else {
+                nodes.arrange(OrdValSpine::new())
+                     .join_arranged(&edges.arrange(OrdValSpine::new()), |_src, &(), &dst| dst)
+            };
+
+            next.concat(&roots).distinct()
+        })
+        .consolidate()
+        .inner
+        .capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    results
+}
+
+#[test]
+fn dict_spine_iterative() {
+
+    let results = reachability(true);
+    assert_eq!(results, vec![
+        (0, RootTimestamp::new(0), 1),
+        (1, RootTimestamp::new(0), 1),
+        (2, RootTimestamp::new(0), 1),
+        (3, RootTimestamp::new(0), 1),
+        (3, RootTimestamp::new(1), -1),
+    ]);
+    assert_eq!(results, reachability(false));
+}
@@ -0,0 +1,59 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::ToStream;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::plan;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::operators::join::JoinArranged;
+
+// records a dataflow in which one arrangement is read by a join and by `as_collection`.
+fn recorded() -> plan::Plan {
+    let (_, plan) = plan::record(|| timely::example(|scope| {
+        let edges = vec![((0u32, 1u32), Default::default(), 1)]
+                        .into_iter()
+                        .to_stream(scope)
+                        .as_collection()
+                        .arrange_by_key_hashed();
+        edges.join_arranged(&edges, |_k, &a, &b| (a, b));
+        edges.as_collection(|k, &v| (k.item, v));
+    }));
+    plan
+}
+
+#[test]
+fn plan_shared_arrangement() {
+
+    let plan = recorded();
+    let operators = plan.nodes.iter().map(|node| node.operator).collect::<Vec<_>>();
+    assert_eq!(operators, vec!["Arrange", "Join", "AsCollection"]);
+
+    let shared = plan.shared();
+    assert_eq!(shared.len(), 1);
+    assert_eq!(shared[0].operator, "Arrange");
+    assert_eq!(shared[0].trace, Some("OrdValSpine"));
+    assert_eq!(plan.readers(&shared[0].output).len(), 2);
+}
+
+#[test]
+fn plan_not_recording() {
+
+    // operators constructed outside `record` are not noted anywhere.
+    timely::example(|scope| {
+        vec![((0u32, 1u32), Default::default(), 1)].into_iter().to_stream(scope).as_collection().arrange_by_key_hashed();
+    });
+    let (_, plan) = plan::record(|| ());
+    assert!(plan.nodes.is_empty());
+}
+
+#[test]
+#[cfg(feature = "plan-json")]
+fn plan_json_escapes_names() {
+
+    let mut plan = recorded();
+    plan.nodes[0].name = Some("edges \"by\\src\"".to_owned());
+    let json = plan.to_json();
+    assert!(json.contains(r#""name":"edges \"by\\src\"""#));
+    assert!(json.contains(r#""trace":"OrdValSpine""#));
+}
@@ -0,0 +1,56 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::Consolidate;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::operators::purge::PurgeKeys;
+
+#[test]
+fn purge_keys() {
+
+    let data = timely::example(|scope| {
+
+        // key 1 gains a value while it is purged, and key 2 is never purged.
+        let profiles = vec![
+            ((1u64, 'a'), RootTimestamp::new(0), 1),
+            ((1, 'b'), RootTimestamp::new(0), 1),
+            ((2, 'c'), RootTimestamp::new(0), 1),
+            ((1, 'd'), RootTimestamp::new(2), 1),
+        ].into_iter().to_stream(scope).as_collection();
+
+        // key 1 is purged from time 1 until time 3, and named twice.
+        let keys = vec![
+            (1u64, RootTimestamp::new(1), 1),
+            (1, RootTimestamp::new(1), 1),
+            (1, RootTimestamp::new(3), -2),
+        ].into_iter().to_stream(scope).as_collection();
+
+        let profiles = profiles.arrange_by_key_hashed();
+        let erased = profiles.purge_keys(&keys);
+        profiles.as_collection(|key: &OrdWrapper<u64>, val: &char| (key.item, *val))
+                .concat(&erased)
+                .consolidate()
+                .inner
+                .capture()
+    });
+
+    // the values of key 1 are retracted while it is purged, and restored once it no longer is.
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort();
+    assert_eq!(results, vec![
+        ((1, 'a'), RootTimestamp::new(0), 1),
+        ((1, 'a'), RootTimestamp::new(1), -1),
+        ((1, 'a'), RootTimestamp::new(3), 1),
+        ((1, 'b'), RootTimestamp::new(0), 1),
+        ((1, 'b'), RootTimestamp::new(1), -1),
+        ((1, 'b'), RootTimestamp::new(3), 1),
+        ((1, 'd'), RootTimestamp::new(3), 1),
+        ((2, 'c'), RootTimestamp::new(0), 1),
+    ]);
+}
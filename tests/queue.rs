@@ -0,0 +1,73 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::queue::PopMin;
+
+#[test]
+fn pop_min() {
+
+    let data = timely::example(|scope| {
+
+        let queues = vec![((0, 5), Default::default(), 1), ((0, 3), Default::default(), 1), ((0, 9), Default::default(), 1),
+                          ((1, 2), Default::default(), 1),
+                          ((2, 7), Default::default(), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        // two elements from key 0, three from key 1 (which has only one), and none from key 2.
+        let requests = vec![(0, Default::default(), 2), (1, Default::default(), 3)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        queues.pop_min(&requests).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0, 3), Default::default(), 1), ((0, 5), Default::default(), 1), ((1, 2), Default::default(), 1)]);
+}
+
+#[test]
+fn pop_min_multiplicities() {
+
+    let data = timely::example(|scope| {
+
+        // a value present twice may be produced twice, and negative requests produce nothing.
+        let queues = vec![((0, 1), Default::default(), 2), ((0, 4), Default::default(), 1), ((1, 1), Default::default(), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+        let requests = vec![(0, Default::default(), 3), (1, Default::default(), -1)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        queues.pop_min(&requests).inner.capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![((0, 1), Default::default(), 2), ((0, 4), Default::default(), 1)]);
+}
+
+#[test]
+fn pop_min_changes() {
+
+    let data = timely::example(|scope| {
+
+        // a smaller value arrives, and then the request is withdrawn.
+        let queues = vec![((0, 5), RootTimestamp::new(0), 1), ((0, 8), RootTimestamp::new(0), 1), ((0, 2), RootTimestamp::new(1), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+        let requests = vec![(0, RootTimestamp::new(0), 1), (0, RootTimestamp::new(2), -1)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        queues.pop_min(&requests).inner.capture()
+    });
+
+    let mut results = data.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+    results.sort_by(|x, y| (&x.1, &x.0).cmp(&(&y.1, &y.0)));
+    assert_eq!(results, vec![
+        ((0, 5), RootTimestamp::new(0), 1),
+        ((0, 2), RootTimestamp::new(1), 1),
+        ((0, 5), RootTimestamp::new(1), -1),
+        ((0, 2), RootTimestamp::new(2), -1),
+    ]);
+}
@@ -0,0 +1,130 @@
+extern crate timely;
+extern crate abomonation;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::Join;
+use differential_dataflow::row::{Row, Datum};
+
+fn sample() -> Vec<Datum> {
+    vec![
+        Datum::Null,
+        Datum::Bool(false),
+        Datum::Bool(true),
+        Datum::Int(0),
+        Datum::Int(-1),
+        Datum::Int(63),
+        Datum::Int(-64),
+        Datum::Int(64),
+        Datum::Int(300),
+        Datum::Int(::std::i64::MAX),
+        Datum::Int(::std::i64::MIN),
+        Datum::String(String::new()),
+        Datum::String("differential".to_owned()),
+        Datum::String("ünïcödé".to_owned()),
+        Datum::String("x".repeat(200)),
+        Datum::Bytes(vec![]),
+        Datum::Bytes(vec![0, 127, 128, 255]),
+    ]
+}
+
+#[test]
+fn pack_unpack() {
+
+    let row = Row::pack(sample());
+    assert_eq!(row.unpack(), sample());
+    assert_eq!(row.len(), sample().len());
+
+    for (index, datum) in sample().into_iter().enumerate() {
+        assert_eq!(row.get(index), Some(datum.clone()));
+
+        // each value round-trips on its own, too.
+        assert_eq!(Row::pack(vec![datum.clone()]).unpack(), vec![datum]);
+    }
+    assert_eq!(row.get(sample().len()), None);
+}
+
+#[test]
+fn push_matches_pack() {
+    let mut row = Row::new();
+    assert_eq!(row.len(), 0);
+    for datum in sample() {
+        row.push(&datum);
+    }
+    assert_eq!(row, Row::pack(sample()));
+}
+
+#[test]
+fn equality_follows_values() {
+    assert_eq!(Row::pack(vec![Datum::Int(5), Datum::String("a".to_owned())]),
+               Row::pack(vec![Datum::Int(5), Datum::String("a".to_owned())]));
+    assert!(Row::pack(vec![Datum::Int(5)]) != Row::pack(vec![Datum::Int(-5)]));
+    assert!(Row::pack(vec![Datum::Bool(true)]) != Row::pack(vec![Datum::Int(1)]));
+    assert!(Row::pack(vec![Datum::String("ab".to_owned())]) != Row::pack(vec![Datum::Bytes(b"ab".to_vec())]));
+    assert!(Row::pack(vec![Datum::String("a".to_owned()), Datum::String("b".to_owned())]) != Row::pack(vec![Datum::String("ab".to_owned())]));
+}
+
+#[test]
+fn project() {
+    let row = Row::pack(sample());
+    let projected = row.project(&[12, 0, 9, 16, 12]);
+    assert_eq!(projected.unpack(), vec![
+        Datum::String("differential".to_owned()),
+        Datum::Null,
+        Datum::Int(::std::i64::MAX),
+        Datum::Bytes(vec![0, 127, 128, 255]),
+        Datum::String("differential".to_owned()),
+    ]);
+    assert_eq!(row.project(&[]), Row::new());
+}
+
+#[test]
+#[should_panic]
+fn project_out_of_bounds() {
+    Row::pack(vec![Datum::Int(1)]).project(&[1]);
+}
+
+#[test]
+fn abomonation() {
+
+    let rows = vec![Row::pack(sample()), Row::new(), Row::pack(vec![Datum::Int(7)])];
+
+    let mut bytes = Vec::new();
+    unsafe { abomonation::encode(&rows, &mut bytes); }
+
+    if let Some((result, remaining)) = unsafe { abomonation::decode::<Vec<Row>>(&mut bytes) } {
+        assert_eq!(result, &rows);
+        assert_eq!(remaining.len(), 0);
+        assert_eq!(result[0].unpack(), sample());
+    }
+    else {
+        panic!("failed to decode rows");
+    }
+}
+
+#[test]
+fn key_by_join() {
+
+    let data = timely::example(|scope| {
+
+        // (id, name) and (id, score) rows.
+        let names = vec![(Row::pack(vec![Datum::Int(1), Datum::String("alice".to_owned())]), Default::default(), 1),
+                         (Row::pack(vec![Datum::Int(2), Datum::String("bob".to_owned())]), Default::default(), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+        let scores = vec![(Row::pack(vec![Datum::Int(1), Datum::Int(90)]), Default::default(), 1),
+                          (Row::pack(vec![Datum::Int(3), Datum::Int(70)]), Default::default(), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        let names = names.key_by(vec![0], vec![1]);
+        let scores = scores.key_by(vec![0], vec![1]);
+        names.join_map(&scores, |id, name, score| Row::pack(id.iter().chain(name.iter()).chain(score.iter())))
+             .inner
+             .capture()
+    });
+
+    let extracted = data.extract();
+    assert_eq!(extracted.len(), 1);
+    assert_eq!(extracted[0].1, vec![(Row::pack(vec![Datum::Int(1), Datum::String("alice".to_owned()), Datum::Int(90)]), Default::default(), 1)]);
+}
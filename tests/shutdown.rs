@@ -0,0 +1,68 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{Input, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::operators::join::JoinArranged;
+use differential_dataflow::shutdown::{Close, shutdown};
+use differential_dataflow::trace::{TraceReader, BatchReader, consolidate};
+
+#[test]
+fn shutdown_completes_dataflows() {
+
+    let (output, uppers, steps) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let (mut edges, mut labels, mut trace, captured) = worker.dataflow(|scope| {
+            let (edges_input, edges) = scope.new_input();
+            let (labels_input, labels) = scope.new_input();
+            let edges = edges.as_collection().arrange_by_key_hashed();
+            let labels = labels.as_collection().arrange_by_key_hashed();
+            let captured = edges.join_arranged(&labels, |src: &OrdWrapper<u64>, &dst: &u64, &label: &char| (src.item, dst, label))
+                                .inner
+                                .capture();
+            (edges_input, labels_input, edges.trace, captured)
+        });
+
+        edges.send(((0u64, 1u64), RootTimestamp::new(0), 1));
+        edges.send(((1, 2), RootTimestamp::new(0), 1));
+        labels.send(((0u64, 'a'), RootTimestamp::new(0), 1));
+        edges.advance_to(1);
+        labels.advance_to(1);
+        edges.send(((0, 1), RootTimestamp::new(1), -1));
+        labels.send(((1, 'b'), RootTimestamp::new(1), 1));
+
+        // neither the join nor the trace complete until both inputs are closed.
+        let steps = shutdown(worker, vec![Box::new(edges) as Box<Close>, Box::new(labels)]);
+        assert!(!worker.step());
+
+        let mut uppers = Vec::new();
+        trace.map_batches(|batch| uppers.push(batch.upper().iter().map(|t| t.inner).collect::<Vec<_>>()));
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, uppers, steps)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert!(steps > 0);
+
+    // the trace is sealed through the empty frontier.
+    assert_eq!(uppers.last(), Some(&vec![]));
+
+    // the join has produced its output at both times, including the retraction.
+    assert_eq!(output, vec![
+        (((0, 1, 'a'), 0), 1),
+        (((0, 1, 'a'), 1), -1),
+        (((1, 2, 'b'), 1), 1),
+    ]);
+}
@@ -0,0 +1,154 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::sink::{FileSink, TransactionalSink, SinkTransactional};
+
+// an empty directory for the test named `name`.
+fn directory(name: &str) -> PathBuf {
+    let directory = ::std::env::temp_dir().join(format!("differential-dataflow-sink-{}", name));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+// the sorted names of the files in `directory`.
+fn files(directory: &Path) -> Vec<String> {
+    let mut files = fs::read_dir(directory).unwrap()
+                       .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+                       .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+fn contents(path: PathBuf) -> String {
+    let mut contents = String::new();
+    File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+    contents
+}
+
+fn prepare(sink: &mut FileSink<u64>, time: u64, updates: &[(String, isize)]) {
+    sink.prepare(&time, updates).unwrap();
+}
+
+fn commit(sink: &mut FileSink<u64>, time: u64) {
+    TransactionalSink::<String, u64, isize>::commit(sink, &time).unwrap();
+}
+
+fn is_committed(sink: &FileSink<u64>, time: u64) -> bool {
+    TransactionalSink::<String, u64, isize>::is_committed(sink, &time)
+}
+
+#[test]
+fn file_sink_commit() {
+
+    let directory = directory("commit");
+    let mut sink = FileSink::new(directory.clone(), 0, |time: &u64| time.to_string()).unwrap();
+
+    prepare(&mut sink, 0, &[("a".to_owned(), 1), ("b".to_owned(), -1)]);
+    assert_eq!(files(&directory), vec!["worker-0-0.pending", "worker-0.log"]);
+    assert!(!is_committed(&sink, 0));
+
+    commit(&mut sink, 0);
+    assert_eq!(files(&directory), vec!["worker-0-0.data", "worker-0.log"]);
+    assert_eq!(contents(directory.join("worker-0-0.data")), "\"a\"\t1\n\"b\"\t-1\n");
+    assert_eq!(contents(directory.join("worker-0.log")), "0\n");
+    assert!(is_committed(&sink, 0));
+
+    // committing a time not prepared does nothing.
+    commit(&mut sink, 1);
+    assert_eq!(contents(directory.join("worker-0.log")), "0\n");
+}
+
+#[test]
+fn file_sink_recovery() {
+
+    let directory = directory("recovery");
+    {
+        let mut sink = FileSink::new(directory.clone(), 0, |time: &u64| time.to_string()).unwrap();
+
+        // time 0 commits, and time 1 is prepared but not committed.
+        prepare(&mut sink, 0, &[("a".to_owned(), 1)]);
+        commit(&mut sink, 0);
+        prepare(&mut sink, 1, &[("b".to_owned(), 1)]);
+
+        // time 2 is recorded in the log, as if the run stopped before renaming its file.
+        prepare(&mut sink, 2, &[("c".to_owned(), 1)]);
+        let mut log = OpenOptions::new().append(true).open(directory.join("worker-0.log")).unwrap();
+        write!(log, "2\n3").unwrap();
+
+        // time 3 is only partially recorded, and so did not commit.
+        prepare(&mut sink, 3, &[("d".to_owned(), 1)]);
+    }
+
+    let sink = FileSink::new(directory.clone(), 0, |time: &u64| time.to_string()).unwrap();
+    assert!(is_committed(&sink, 0));
+    assert!(!is_committed(&sink, 1));
+    assert!(is_committed(&sink, 2));
+    assert!(!is_committed(&sink, 3));
+    assert_eq!(files(&directory), vec!["worker-0-0.data", "worker-0-2.data", "worker-0.log"]);
+    assert_eq!(contents(directory.join("worker-0-2.data")), "\"c\"\t1\n");
+
+    // the files of other workers are left alone.
+    File::create(directory.join("worker-1-4.pending")).unwrap();
+    FileSink::<u64>::new(directory.clone(), 0, |time: &u64| time.to_string()).unwrap();
+    assert!(directory.join("worker-1-4.pending").exists());
+}
+
+// runs a dataflow sinking `updates` into `directory`, and returns the committed times.
+fn run(directory: PathBuf, updates: Vec<(String, u64, isize)>) -> Vec<u64> {
+
+    let data = timely::example(move |scope| {
+        let sink = FileSink::new(directory.clone(), 0, |time: &Product<RootTimestamp, u64>| time.inner.to_string()).unwrap();
+        updates.clone()
+               .into_iter()
+               .map(|(datum, time, diff)| (datum, RootTimestamp::new(time), diff))
+               .to_stream(scope)
+               .as_collection()
+               .sink_transactional(sink)
+               .capture()
+    });
+
+    data.extract().into_iter().flat_map(|(_, times)| times).map(|time| time.inner).collect()
+}
+
+#[test]
+fn sink_transactional() {
+
+    let directory = directory("transactional");
+
+    // updates at time 1 cancel, and so time 1 is not committed.
+    let committed = run(directory.clone(), vec![
+        ("a".to_owned(), 0, 1), ("b".to_owned(), 0, 1), ("a".to_owned(), 0, 1),
+        ("c".to_owned(), 1, 1), ("c".to_owned(), 1, -1),
+        ("b".to_owned(), 2, -1),
+    ]);
+
+    assert_eq!(committed, vec![0, 2]);
+    assert_eq!(files(&directory), vec!["worker-0-0.data", "worker-0-2.data", "worker-0.log"]);
+    assert_eq!(contents(directory.join("worker-0-0.data")), "\"a\"\t2\n\"b\"\t1\n");
+    assert_eq!(contents(directory.join("worker-0-2.data")), "\"b\"\t-1\n");
+}
+
+#[test]
+fn sink_transactional_restart() {
+
+    let directory = directory("restart");
+
+    let committed = run(directory.clone(), vec![("a".to_owned(), 0, 1), ("b".to_owned(), 1, 1)]);
+    assert_eq!(committed, vec![0, 1]);
+
+    // a second run replaying the same times, and one more, commits only the new time.
+    let committed = run(directory.clone(), vec![("a".to_owned(), 0, 1), ("b".to_owned(), 1, 1), ("c".to_owned(), 2, 1)]);
+    assert_eq!(committed, vec![2]);
+    assert_eq!(files(&directory), vec!["worker-0-0.data", "worker-0-1.data", "worker-0-2.data", "worker-0.log"]);
+    assert_eq!(contents(directory.join("worker-0-1.data")), "\"b\"\t1\n");
+    assert_eq!(contents(directory.join("worker-0.log")), "0\n1\n2\n");
+}
@@ -0,0 +1,1073 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+
+use timely::dataflow::operators::{Input, Capture, ToStream, Map};
+use timely::dataflow::operators::capture::{Extract, Event};
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::arrange::Arrange;
+use differential_dataflow::trace::{Trace, TraceReader, Batch, BatchReader, Builder, Cursor, consolidate};
+use differential_dataflow::trace::implementations::ord::{OrdValBatch, OrdValBuilder, OrdValSpine};
+use differential_dataflow::trace::implementations::hash::HashValSpine;
+use differential_dataflow::trace::implementations::spine::{MergePolicy, MergeEvent};
+use differential_dataflow::trace::wrappers::boxed::TraceReaderBox;
+use differential_dataflow::trace::wrappers::rc::TraceChange;
+
+type Update = ((u64, u64), usize, isize);
+type Time = Product<RootTimestamp, usize>;
+type TestSpine = OrdValSpine<OrdWrapper<u64>, u64, Time, isize>;
+
+// a batch of updates, which must be sorted by key and value.
+fn batch(updates: Vec<(u64, u64, usize, isize)>, lower: usize, upper: usize) -> OrdValBatch<u64, u64, usize, isize> {
+    let mut builder = OrdValBuilder::new();
+    for update in updates { builder.push(update); }
+    builder.done(&[lower], &[upper], &[0])
+}
+
+// the consolidated updates presented by `cursor`.
+fn contents<C: Cursor<u64, u64, usize, isize>>(mut cursor: C) -> Vec<Update> {
+    let mut result = Vec::new();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let (key, val) = (*cursor.key(), *cursor.val());
+            cursor.map_times(|time, diff| result.push(((key, val, *time), diff)));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+    consolidate(&mut result, 0);
+    result.into_iter().map(|((key, val, time), diff)| ((key, val), time, diff)).collect()
+}
+
+#[test]
+fn compaction_filter() {
+
+    // drops updates to odd keys when the trace compacts.
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new().with_compaction_filter(|key, _val, _time| key % 2 == 1);
+
+    trace.insert(batch(vec![(1, 0, 0, 1), (2, 0, 0, 1)], 0, 1));
+    trace.insert(batch(vec![(3, 0, 1, 1), (4, 0, 1, 1)], 1, 2));
+
+    // without a merge, no updates are dropped.
+    assert_eq!(contents(trace.cursor()).len(), 4);
+
+    trace.advance_by(&[2]);
+    trace.distinguish_since(&[2]);
+    assert_eq!(contents(trace.cursor()), vec![((2, 0), 2, 1), ((4, 0), 2, 1)]);
+}
+
+#[test]
+fn compaction_filter_advanced_times() {
+
+    // the filter observes advanced times, here dropping updates once they are advanced to time 10.
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new().with_compaction_filter(|_key, val, time| *val < *time as u64);
+
+    trace.insert(batch(vec![(0, 5, 0, 1), (1, 20, 0, 1)], 0, 1));
+    trace.insert(batch(vec![(2, 5, 1, 1), (3, 20, 1, 1)], 1, 2));
+
+    trace.advance_by(&[10]);
+    trace.distinguish_since(&[2]);
+    assert_eq!(contents(trace.cursor()), vec![((1, 20), 10, 1), ((3, 20), 10, 1)]);
+}
+
+// arranges each round of updates at its own time into a trace made by `empty`, advancing the trace as rounds
+// complete, and then truncates the trace to the end of the rounds. returns the updates the arrangement produced,
+// the contents of the truncated trace, and the number of batches the trace held before truncation.
+fn arrange_rounds<F>(rounds: Vec<Vec<((u64, u64), isize)>>, empty: F) -> (Vec<Update>, Vec<Update>, usize)
+where F: Fn()->TestSpine+Send+Sync+'static {
+
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val)| (OrdWrapper { item: key }, val))
+                                  .arrange(empty());
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        for (round, updates) in rounds.clone().into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+            trace.advance_by(&[time.clone()]);
+            trace.distinguish_since(&[time]);
+        }
+
+        let mut batches = 0;
+        trace.map_batches(|_| batches += 1);
+
+        let truncation = trace.truncate_before(&[RootTimestamp::new(rounds.len())]);
+        assert!(truncation.is_complete());
+
+        let mut contents = Vec::new();
+        let mut cursor = trace.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let (key, val) = (cursor.key().item, *cursor.val());
+                cursor.map_times(|time, diff| contents.push(((key, val, time.inner), diff)));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+        consolidate(&mut contents, 0);
+        let contents = contents.into_iter().map(|((key, val, time), diff)| ((key, val), time, diff)).collect();
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+        let output = output.into_iter().map(|((data, time), diff)| (data, time, diff)).collect();
+
+        (output, contents, batches)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap()
+}
+
+// records `(key, expiry)`, where the record at key 2 is retracted before it expires.
+fn expiring() -> Vec<Vec<((u64, u64), isize)>> {
+    vec![
+        vec![((1, 2), 1), ((2, 10), 1)],
+        vec![((3, 1), 1), ((2, 10), -1)],
+        vec![((4, 10), 1)],
+    ]
+}
+
+#[test]
+fn compaction_filter_arranged() {
+
+    let (output, contents, _) = arrange_rounds(expiring(), || TestSpine::new().with_compaction_filter(|_key, expiry, time| (*expiry as usize) < time.inner));
+
+    // the arrangement produces every update, including the retraction.
+    assert_eq!(output, vec![
+        ((1, 2), 0, 1), ((2, 10), 0, 1), ((2, 10), 1, -1), ((3, 1), 1, 1), ((4, 10), 2, 1),
+    ]);
+
+    // once compacted to time 3, the trace holds only the record that has not expired.
+    assert_eq!(contents, vec![((4, 10), 3, 1)]);
+}
+
+#[test]
+fn compaction_filter_absent() {
+    let (output, contents, _) = arrange_rounds(expiring(), || TestSpine::new());
+    assert_eq!(output.len(), 5);
+    assert_eq!(contents, vec![((1, 2), 3, 1), ((3, 1), 3, 1), ((4, 10), 3, 1)]);
+}
+
+#[test]
+fn leveled_merging() {
+
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new().with_merge_policy(MergePolicy::Leveled { base: 4, ratio: 4 });
+    let mut expected = Vec::new();
+    for round in 0 .. 64 {
+        trace.insert(batch(vec![(round as u64, 0, round, 1)], round, round + 1));
+        expected.push(((round as u64, 0), round, 1));
+    }
+    trace.distinguish_since(&[64]);
+
+    // levels hold at most 4, 16, and 64 updates, and no two batches share a level.
+    let mut batches = 0;
+    trace.map_batches(|_| batches += 1);
+    assert!(batches <= 3);
+
+    let statistics = trace.merge_statistics();
+    assert!(statistics.merges >= 60);
+    assert!(statistics.largest <= 64);
+    assert_eq!(contents(trace.cursor()), expected);
+}
+
+#[test]
+fn leveled_merging_pending() {
+
+    // batches are not merged until the trace need no longer distinguish their times.
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new().with_merge_policy(MergePolicy::Leveled { base: 2, ratio: 2 });
+    for round in 0 .. 8 {
+        trace.insert(batch(vec![(0, round as u64, round, 1)], round, round + 1));
+    }
+    assert_eq!(trace.merge_statistics().merges, 0);
+
+    trace.distinguish_since(&[8]);
+    assert!(trace.merge_statistics().merges > 0);
+    assert_eq!(contents(trace.cursor()).len(), 8);
+}
+
+#[test]
+fn merge_logger_events() {
+
+    let events = ::std::rc::Rc::new(::std::cell::RefCell::new(Vec::new()));
+    let events2 = events.clone();
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new()
+        .with_merge_policy(MergePolicy::Leveled { base: 2, ratio: 2 })
+        .with_merge_logger(move |event| events2.borrow_mut().push(event.clone()));
+    for round in 0 .. 8 {
+        trace.insert(batch(vec![(0, round as u64, round, 1)], round, round + 1));
+    }
+    trace.distinguish_since(&[8]);
+
+    // each merge is reported as started and then completed, with the same number of updates.
+    let events = events.borrow();
+    assert!(events.len() > 0);
+    assert_eq!(events.len(), 2 * trace.merge_statistics().merges);
+    for pair in events.chunks(2) {
+        match (&pair[0], &pair[1]) {
+            (&MergeEvent::Started { updates: started }, &MergeEvent::Completed { updates, produced, .. }) => {
+                assert_eq!(started, updates);
+                assert_eq!(produced, updates);
+            },
+            _ => panic!("unexpected merge events: {:?}", pair),
+        }
+    }
+}
+
+#[test]
+fn merge_statistics_untimed() {
+
+    // without a merge logger, merges are counted but not timed.
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new().with_merge_policy(MergePolicy::Leveled { base: 2, ratio: 2 });
+    for round in 0 .. 8 {
+        trace.insert(batch(vec![(0, round as u64, round, 1)], round, round + 1));
+    }
+    trace.distinguish_since(&[8]);
+
+    let statistics = trace.merge_statistics();
+    assert!(statistics.merges > 0);
+    assert_eq!(statistics.elapsed, ::std::time::Duration::new(0, 0));
+}
+
+#[test]
+#[should_panic]
+fn leveled_merging_invalid_ratio() {
+    OrdValSpine::<u64, u64, usize, isize>::new().with_merge_policy(MergePolicy::Leveled { base: 4, ratio: 1 });
+}
+
+#[test]
+fn leveled_merging_arranged() {
+
+    // each round replaces the previous round's record with its own.
+    let rounds = (0 .. 40u64).map(|round| {
+        let mut updates = vec![((round, round), 1)];
+        if round > 0 { updates.push(((round - 1, round - 1), -1)); }
+        updates
+    }).collect();
+
+    let (output, contents, batches) = arrange_rounds(rounds, || TestSpine::new().with_merge_policy(MergePolicy::Leveled { base: 4, ratio: 4 }));
+
+    // the arrangement produces every insertion and retraction.
+    assert_eq!(output.len(), 79);
+    assert_eq!(output[0], ((0, 0), 0, 1));
+    assert_eq!(output[1], ((0, 0), 1, -1));
+
+    // levels hold at most 4, 16, 64, and 256 updates, and no two batches share a level.
+    assert!(batches <= 4);
+    assert_eq!(contents, vec![((39, 39), 40, 1)]);
+}
+
+// the key, values, and updates the cursor presents after seeking each of `keys` in turn.
+fn seeks<C: Cursor<u64, u64, usize, isize>>(mut cursor: C, keys: &[u64]) -> Vec<Option<(u64, Vec<(u64, usize, isize)>)>> {
+    let mut result = Vec::new();
+    for key in keys.iter() {
+        cursor.seek_key(key);
+        if cursor.key_valid() {
+            let mut updates = Vec::new();
+            while cursor.val_valid() {
+                let val = *cursor.val();
+                cursor.map_times(|time, diff| updates.push((val, *time, diff)));
+                cursor.step_val();
+            }
+            updates.sort();
+            result.push(Some((*cursor.key(), updates)));
+        }
+        else {
+            result.push(None);
+        }
+    }
+    result
+}
+
+#[test]
+fn key_filters_seek() {
+
+    let mut plain = OrdValSpine::<u64, u64, usize, isize>::new();
+    let mut filtered = OrdValSpine::<u64, u64, usize, isize>::new().with_key_filters(10);
+
+    // batches the trace will not merge, holding keys spread across them.
+    for trace in vec![&mut plain, &mut filtered] {
+        trace.insert(batch(vec![(0, 0, 0, 1), (10, 0, 0, 1), (20, 0, 0, 1)], 0, 1));
+        trace.insert(batch(vec![(5, 1, 1, 1), (10, 0, 1, -1), (10, 1, 1, 1)], 1, 2));
+        trace.insert(batch(vec![(15, 2, 2, 1), (25, 2, 2, 1)], 2, 3));
+    }
+
+    // a sought key absent from a batch lands on the key following it, and not on a key without values.
+    let keys = vec![0, 3, 5, 10, 11, 12, 20, 21, 25, 30];
+    let expected = vec![
+        Some((0, vec![(0, 0, 1)])),
+        Some((5, vec![(1, 1, 1)])),
+        Some((5, vec![(1, 1, 1)])),
+        Some((10, vec![(0, 0, 1), (0, 1, -1), (1, 1, 1)])),
+        Some((15, vec![(2, 2, 1)])),
+        Some((15, vec![(2, 2, 1)])),
+        Some((20, vec![(0, 0, 1)])),
+        Some((25, vec![(2, 2, 1)])),
+        Some((25, vec![(2, 2, 1)])),
+        None,
+    ];
+    assert_eq!(seeks(plain.cursor(), &keys[..]), expected);
+    assert_eq!(seeks(filtered.cursor(), &keys[..]), expected);
+
+    // stepping after a seek finds the keys of batches whose searches were deferred.
+    let mut cursor = filtered.cursor();
+    cursor.seek_key(&10);
+    cursor.step_key();
+    assert_eq!(*cursor.key(), 15);
+    cursor.step_key();
+    assert_eq!(*cursor.key(), 20);
+
+    assert_eq!(contents(plain.cursor()), contents(filtered.cursor()));
+}
+
+#[test]
+fn key_filters_arranged() {
+    let (output, contents, _) = arrange_rounds(expiring(), || TestSpine::new().with_key_filters(10));
+    assert_eq!(output.len(), 5);
+    assert_eq!(contents, vec![((1, 2), 3, 1), ((3, 1), 3, 1), ((4, 10), 3, 1)]);
+}
+
+#[test]
+fn advance_mut_in_place() {
+
+    let mut batch = batch(vec![(0, 0, 2, 1), (1, 0, 3, 1)], 0, 4);
+    let layer = batch.layer.clone();
+    assert_eq!(batch.times(), None);
+
+    // advancing by a frontier the times are beyond leaves the layer as it is, and records the time envelope.
+    batch.advance_mut(&[1]);
+    assert!(::std::rc::Rc::ptr_eq(&layer, &batch.layer));
+    assert_eq!(batch.times(), Some(&[2][..]));
+    assert_eq!(batch.description().since(), &[1]);
+
+    // advancing further rebuilds the batch.
+    batch.advance_mut(&[3]);
+    assert!(!::std::rc::Rc::ptr_eq(&layer, &batch.layer));
+    assert_eq!(contents(batch.cursor()), vec![((0, 0), 3, 1), ((1, 0), 3, 1)]);
+}
+
+#[test]
+fn set_read_frontiers_advances() {
+
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new();
+    trace.insert(batch(vec![(0, 0, 0, 1), (0, 0, 1, 1)], 0, 2));
+    trace.set_read_frontiers(&[2], &[2]);
+    assert_eq!(trace.advance_frontier(), &[2]);
+    assert_eq!(trace.distinguish_frontier(), &[2]);
+}
+
+#[test]
+#[should_panic]
+#[cfg(debug_assertions)]
+fn set_read_frontiers_regressed() {
+    let mut trace = OrdValSpine::<u64, u64, usize, isize>::new();
+    trace.set_read_frontiers(&[2], &[2]);
+    trace.set_read_frontiers(&[1], &[2]);
+}
+
+// the consolidated updates presented by `cursor` over an arrangement.
+fn arranged_contents<C: Cursor<OrdWrapper<u64>, u64, Time, isize>>(mut cursor: C) -> Vec<Update> {
+    let mut result = Vec::new();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let (key, val) = (cursor.key().item, *cursor.val());
+            cursor.map_times(|time, diff| result.push(((key, val, time.inner), diff)));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+    consolidate(&mut result, 0);
+    result.into_iter().map(|((key, val, time), diff)| ((key, val), time, diff)).collect()
+}
+
+#[test]
+fn pause_maintenance_arranged() {
+
+    let (output, paused, resumed) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                                  .arrange(TestSpine::new());
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        // each round replaces the previous round's record with its own, while maintenance is paused.
+        trace.pause_maintenance();
+        for round in 0 .. 8usize {
+            let time = *input.time();
+            input.send(((round as u64, 0u64), time, 1));
+            if round > 0 { input.send((((round - 1) as u64, 0), time, -1)); }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+            trace.advance_by(&[time.clone()]);
+            trace.distinguish_since(&[time]);
+        }
+
+        let mut batches = 0;
+        trace.map_batches(|_| batches += 1);
+        let paused = (batches, arranged_contents(trace.cursor()));
+
+        trace.resume_maintenance();
+        let mut batches = 0;
+        trace.map_batches(|_| batches += 1);
+        let resumed = (batches, arranged_contents(trace.cursor()));
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, paused, resumed)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    // the arrangement produces every insertion and retraction.
+    assert_eq!(output.len(), 15);
+    assert_eq!(output[0], (((0, 0), 0), 1));
+    assert_eq!(output[1], (((0, 0), 1), -1));
+
+    // while paused, each round's batch is held separately and its updates at their original times.
+    assert!(paused.0 >= 8);
+    assert_eq!(paused.1.len(), 15);
+    assert!(paused.1.contains(&((0, 0), 1, -1)));
+
+    // once resumed, the batches are merged and the retractions compacted away.
+    assert!(resumed.0 < paused.0);
+    assert_eq!(resumed.1, vec![((7, 0), 8, 1)]);
+}
+
+#[test]
+fn truncate_before_awaits_handles() {
+
+    let (output, withheld, truncated) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                                  .arrange(TestSpine::new());
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        // a second handle, which does not advance until the truncation has been requested.
+        let mut reader = trace.clone();
+
+        // the record at key 2 is inserted and then erased.
+        let rounds = vec![
+            vec![((1u64, 1u64), 1), ((2, 2), 1)],
+            vec![((2, 2), -1)],
+            vec![((3, 3), 1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+            trace.advance_by(&[time.clone()]);
+            trace.distinguish_since(&[time]);
+        }
+
+        // the truncation cannot complete while the second handle may distinguish earlier times.
+        let truncation = trace.truncate_before(&[RootTimestamp::new(3)]);
+        let withheld = (truncation.is_complete(), arranged_contents(reader.cursor()));
+
+        reader.advance_by(&[RootTimestamp::new(3)]);
+        reader.distinguish_since(&[RootTimestamp::new(3)]);
+        let truncated = (truncation.is_complete(), arranged_contents(reader.cursor()));
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, withheld, truncated)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    // the arrangement produces the erased record and its retraction.
+    assert_eq!(output, vec![
+        (((1, 1), 0), 1), (((2, 2), 0), 1), (((2, 2), 1), -1), (((3, 3), 2), 1),
+    ]);
+
+    // until the second handle advances, the erased record remains at its original times.
+    assert!(!withheld.0);
+    assert!(withheld.1.contains(&((2, 2), 0, 1)));
+    assert!(withheld.1.contains(&((2, 2), 1, -1)));
+
+    // once it has advanced, the erased record is physically gone.
+    assert!(truncated.0);
+    assert_eq!(truncated.1, vec![((1, 1), 3, 1), ((3, 3), 3, 1)]);
+}
+
+// arranges rounds of updates with retractions, coalescing batches of fewer than `threshold` updates. returns the
+// updates the arrangement produced, the number of batches the trace holds, and whether the output was complete
+// through the second round once it had been stepped.
+fn coalesced_rounds(threshold: usize) -> (Vec<((u64, u64), usize, isize)>, usize, bool) {
+
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                                  .arrange_coalesced(TestSpine::new(), threshold);
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        let rounds = vec![
+            vec![((0u64, 0u64), 1), ((1, 1), 1)],
+            vec![((0, 0), -1)],
+            vec![((2, 2), 1)],
+            vec![((1, 1), -1)],
+        ];
+
+        let mut complete = false;
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            for _ in 0 .. 10 { worker.step(); }
+            if round == 1 { complete = !probe.less_than(&RootTimestamp::new(2)); }
+        }
+        input.close();
+        while worker.step() { }
+
+        let mut batches = 0;
+        trace.map_batches(|batch| if batch.len() > 0 { batches += 1; });
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+        let output = output.into_iter().map(|((data, time), diff)| (data, time, diff)).collect();
+
+        (output, batches, complete)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap()
+}
+
+#[test]
+fn arrange_coalesced_batches() {
+
+    let (output, batches, complete) = coalesced_rounds(0);
+    let (coalesced_output, coalesced_batches, coalesced_complete) = coalesced_rounds(4);
+
+    // coalescing produces the same updates, including the retractions.
+    assert_eq!(output, vec![
+        ((0, 0), 0, 1), ((0, 0), 1, -1), ((1, 1), 0, 1), ((1, 1), 3, -1), ((2, 2), 2, 1),
+    ]);
+    assert_eq!(coalesced_output, output);
+
+    // the first three rounds are withheld until four updates have arrived, and the last until the input closes.
+    assert_eq!(batches, 4);
+    assert_eq!(coalesced_batches, 2);
+    assert!(complete);
+    assert!(!coalesced_complete);
+}
+
+#[test]
+fn batch_stats_arranged() {
+
+    let (output, stats) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                                  .arrange(TestSpine::new());
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        // the second round retracts a value, and the third introduces one update twice.
+        let rounds = vec![
+            vec![((1u64, 10u64), 1), ((1, 11), 1), ((2, 20), 1)],
+            vec![((1, 10), -1)],
+            vec![((3, 30), 1), ((3, 30), 1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+
+        // the statistics of each non-empty batch, by its lower bound, with key bounds as a sorted pair.
+        let mut stats = Vec::new();
+        trace.map_batches(|batch| {
+            if batch.len() > 0 {
+                let batch_stats = batch.stats();
+                let (min, max) = batch_stats.bounds.clone().unwrap();
+                let mut bounds = vec![min.item, max.item];
+                bounds.sort();
+                stats.push((batch.lower()[0].inner, batch_stats.keys, batch_stats.vals, batch_stats.updates, bounds));
+            }
+        });
+        stats.sort();
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, stats)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(output, vec![
+        (((1, 10), 0), 1), (((1, 10), 1), -1), (((1, 11), 0), 1), (((2, 20), 0), 1), (((3, 30), 2), 2),
+    ]);
+
+    // the retraction is a batch of its own, and the repeated update is consolidated.
+    assert_eq!(stats, vec![
+        (0, 2, 3, 3, vec![1, 2]),
+        (1, 1, 1, 1, vec![1, 1]),
+        (2, 1, 1, 1, vec![3, 3]),
+    ]);
+}
+
+// arranges rounds of updates with a retraction into a trace chosen at runtime, and reads it through a boxed trace.
+// returns the updates the arrangement produced, the contents of the trace, and the updates in its batches.
+fn boxed_rounds(hashed: bool) -> (Vec<Update>, Vec<Update>, usize) {
+
+    timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let updates = updates.as_collection().map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val));
+            let (trace, output) = if hashed {
+                let arranged = updates.arrange(HashValSpine::<OrdWrapper<u64>, u64, Time, isize>::new());
+                (TraceReaderBox::new(arranged.trace.clone()), arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val)))
+            }
+            else {
+                let arranged = updates.arrange(TestSpine::new());
+                (TraceReaderBox::new(arranged.trace.clone()), arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val)))
+            };
+            (input, trace, output.probe_with(&mut probe).inner.capture())
+        });
+
+        let rounds = vec![
+            vec![((1u64, 10u64), 1), ((2, 20), 1)],
+            vec![((1, 10), -1), ((1, 11), 1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+
+        let mut updates = 0;
+        trace.map_batches(|batch| updates += batch.len());
+        let contents = arranged_contents(trace.cursor());
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+        let output = output.into_iter().map(|((data, time), diff)| (data, time, diff)).collect();
+
+        (output, contents, updates)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap()
+}
+
+#[test]
+fn boxed_trace_runtime_choice() {
+
+    let ordered = boxed_rounds(false);
+    let hashed = boxed_rounds(true);
+
+    // either trace presents the insertions and the retraction, at their original times.
+    let expected = vec![((1, 10), 0, 1), ((1, 10), 1, -1), ((1, 11), 1, 1), ((2, 20), 0, 1)];
+    assert_eq!(ordered, (expected.clone(), expected.clone(), 4));
+    assert_eq!(hashed, (expected.clone(), expected, 4));
+}
+
+#[test]
+fn arrangement_statistics() {
+
+    let (output, before, after) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                                  .arrange(TestSpine::new().with_merge_policy(MergePolicy::Leveled { base: 8, ratio: 2 }));
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        // the second round retracts a value of key 1.
+        let rounds = vec![
+            vec![((1u64, 10u64), 1), ((1, 11), 1), ((2, 20), 1)],
+            vec![((1, 10), -1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+        let before = trace.statistics();
+
+        trace.advance_by(&[RootTimestamp::new(2)]);
+        trace.distinguish_since(&[RootTimestamp::new(2)]);
+        let after = trace.statistics();
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, before, after)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(output, vec![(((1, 10), 0), 1), (((1, 10), 1), -1), (((1, 11), 0), 1), (((2, 20), 0), 1)]);
+
+    // before merging, the retraction is a batch of its own, and key 1 is counted in both batches.
+    assert_eq!((before.batches, before.keys, before.vals, before.updates), (2, 3, 4, 4));
+    assert_eq!(before.vals_per_key(), 4.0 / 3.0);
+    assert_eq!((before.insertions.batches, before.insertions.updates), (2, 4));
+
+    // once merged and compacted, the retracted value is gone, while the counts of insertions remain.
+    assert_eq!((after.batches, after.keys, after.vals, after.updates), (1, 2, 2, 2));
+    assert_eq!(after.vals_per_key(), 1.0);
+    assert_eq!((after.insertions.batches, after.insertions.updates), (2, 4));
+}
+
+// the updates of an arrangement of ten keys, one of which is retracted, flattened in chunks of `chunk` updates
+// or all at once, and the number of messages in which they were produced.
+fn chunked(chunk: Option<usize>) -> (Vec<((u64, u64), Time, isize)>, usize) {
+
+    let captured = timely::example(move |scope| {
+
+        let mut updates = (0 .. 10u64).map(|key| ((key, key), RootTimestamp::new(0), 1)).collect::<Vec<_>>();
+        updates.push(((3, 3), RootTimestamp::new(1), -1));
+
+        let arranged = updates.into_iter()
+                              .to_stream(scope)
+                              .as_collection()
+                              .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                              .arrange(TestSpine::new());
+
+        let logic = |key: &OrdWrapper<u64>, val: &u64| (key.item, *val);
+        match chunk {
+            Some(chunk) => arranged.as_collection_chunked(chunk, logic).inner.capture(),
+            None => arranged.as_collection(logic).inner.capture(),
+        }
+    });
+
+    let mut messages = 0;
+    let mut results = Vec::new();
+    for event in captured.try_iter() {
+        if let Event::Messages(_, data) = event {
+            messages += 1;
+            results.extend(data);
+        }
+    }
+    results.sort();
+    (results, messages)
+}
+
+#[test]
+fn as_collection_chunked() {
+
+    let (whole, whole_messages) = chunked(None);
+    let (chunks, chunk_messages) = chunked(Some(2));
+
+    // the same updates, including the retraction, spread over at least one message for each two updates.
+    let mut expected = (0 .. 10u64).map(|key| ((key, key), RootTimestamp::new(0), 1)).collect::<Vec<_>>();
+    expected.push(((3, 3), RootTimestamp::new(1), -1));
+    expected.sort();
+    assert_eq!(whole, expected);
+    assert_eq!(chunks, expected);
+    assert!(whole_messages <= 2);
+    assert!(chunk_messages >= 5);
+}
+
+// the `(time, key, val, diff)` responses of the events in `captured` so far.
+fn responses(captured: &::std::sync::mpsc::Receiver<Event<Time, (u64, u64, isize)>>) -> Vec<(usize, u64, u64, isize)> {
+    let mut result = Vec::new();
+    for event in captured.try_iter() {
+        if let Event::Messages(time, data) = event {
+            result.extend(data.into_iter().map(|(key, val, diff)| (time.inner, key, val, diff)));
+        }
+    }
+    result.sort();
+    result
+}
+
+#[test]
+fn lookup_per_time() {
+
+    let (early, late) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let (mut data, mut queries, captured) = worker.dataflow(|scope| {
+            let (data_input, data) = scope.new_input();
+            let (query_input, queries) = scope.new_input();
+            let arranged = data.as_collection()
+                               .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+                               .arrange(TestSpine::new());
+            let captured = arranged.lookup(&queries)
+                                   .map(|(key, val, diff): (OrdWrapper<u64>, u64, isize)| (key.item, val, diff))
+                                   .capture();
+            (data_input, query_input, captured)
+        });
+
+        data.send(((1u64, 10u64), RootTimestamp::new(0), 1));
+        data.send(((1, 11), RootTimestamp::new(0), 1));
+        data.send(((2, 20), RootTimestamp::new(0), 1));
+        data.advance_to(1);
+
+        // queries at time 0, including one for an absent key, and at time 1.
+        queries.send(OrdWrapper { item: 1u64 });
+        queries.send(OrdWrapper { item: 3 });
+        queries.advance_to(1);
+        queries.send(OrdWrapper { item: 1 });
+        queries.send(OrdWrapper { item: 2 });
+        queries.advance_to(2);
+        for _ in 0 .. 10 { worker.step(); }
+
+        // the queries at time 0 are answered while those at time 1 await the data.
+        let early = responses(&captured);
+
+        data.send(((1, 10), RootTimestamp::new(1), -1));
+        data.advance_to(2);
+        data.send(((1, 12), RootTimestamp::new(2), 1));
+        data.advance_to(3);
+        queries.send(OrdWrapper { item: 1 });
+        data.close();
+        queries.close();
+        while worker.step() { }
+
+        (early, responses(&captured))
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(early, vec![(0, 1, 10, 1), (0, 1, 11, 1)]);
+
+    // responses reflect the retraction of value 10 at time 1.
+    assert_eq!(late, vec![(1, 1, 11, 1), (1, 2, 20, 1), (2, 1, 11, 1), (2, 1, 12, 1)]);
+}
+
+#[test]
+fn flat_map_cursor_batches() {
+
+    let captured = timely::example(|scope| {
+
+        let mut updates = (0 .. 4u64).map(|key| ((key, key), RootTimestamp::new(0), 1)).collect::<Vec<_>>();
+        updates.push(((2, 2), RootTimestamp::new(1), -1));
+        updates.push(((5, 5), RootTimestamp::new(1), 1));
+
+        // each batch becomes one record, of its bounds and its updates.
+        updates.into_iter()
+               .to_stream(scope)
+               .as_collection()
+               .map(|(key, val): (u64, u64)| (OrdWrapper { item: key }, val))
+               .arrange(TestSpine::new())
+               .flat_map_cursor(|description, cursor, buffer| {
+                   let lower = description.lower().iter().map(|t| t.inner).collect::<Vec<_>>();
+                   let upper = description.upper().iter().map(|t| t.inner).collect::<Vec<_>>();
+                   let mut updates = Vec::new();
+                   while cursor.key_valid() {
+                       while cursor.val_valid() {
+                           let (key, val) = (cursor.key().item, *cursor.val());
+                           cursor.map_times(|time, diff| updates.push((((key, val), time.inner), diff)));
+                           cursor.step_val();
+                       }
+                       cursor.step_key();
+                   }
+                   buffer.push((lower, upper, updates));
+               })
+               .capture()
+    });
+
+    let batches = captured.extract().into_iter().flat_map(|(_, data)| data).collect::<Vec<_>>();
+
+    // the batches describe contiguous intervals of time, from the start through completion.
+    assert_eq!(batches.first().map(|x| x.0.clone()), Some(vec![0]));
+    assert_eq!(batches.last().map(|x| x.1.clone()), Some(vec![]));
+    for pair in batches.windows(2) {
+        assert_eq!(pair[0].1, pair[1].0);
+    }
+
+    // the updates of all batches, including the retraction.
+    let mut updates = batches.into_iter().flat_map(|x| x.2).collect::<Vec<_>>();
+    consolidate(&mut updates, 0);
+    assert_eq!(updates, vec![
+        (((0, 0), 0), 1),
+        (((1, 1), 0), 1),
+        (((2, 2), 0), 1),
+        (((2, 2), 1), -1),
+        (((3, 3), 0), 1),
+        (((5, 5), 1), 1),
+    ]);
+}
+
+#[test]
+fn on_change_reports() {
+
+    let (output, changes) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val)| (OrdWrapper { item: key }, val))
+                                  .arrange(TestSpine::new());
+            let captured = arranged.as_collection(|key: &OrdWrapper<u64>, val: &u64| (key.item, *val))
+                                   .probe_with(&mut probe)
+                                   .inner
+                                   .capture();
+            (input, arranged.trace, captured)
+        });
+
+        let recorded = changes.clone();
+        trace.on_change(move |change| recorded.borrow_mut().push(change.clone()));
+
+        // the second round retracts a value of the first.
+        let rounds = vec![
+            vec![((1u64, 10u64), 1), ((1, 11), 1)],
+            vec![((1, 10), -1)],
+            vec![((2, 20), 1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+            trace.advance_by(&[time.clone()]);
+            trace.distinguish_since(&[time]);
+        }
+
+        input.close();
+        while worker.step() { }
+        let changes = changes.borrow().clone();
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, changes)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(output, vec![(((1, 10), 0), 1), (((1, 10), 1), -1), (((1, 11), 0), 1), (((2, 20), 2), 1)]);
+
+    // insertions span contiguous intervals, and hold every update including the retraction.
+    let mut inserted = Vec::new();
+    let mut compacted = Vec::new();
+    let mut distinguished = Vec::new();
+    for change in changes {
+        match change {
+            TraceChange::Inserted { lower, upper, updates } => inserted.push((lower, upper, updates)),
+            TraceChange::Compacted(frontier) => compacted.push(frontier.iter().map(|t| t.inner).collect::<Vec<_>>()),
+            TraceChange::Distinguished(frontier) => distinguished.push(frontier.iter().map(|t| t.inner).collect::<Vec<_>>()),
+        }
+    }
+    for pair in inserted.windows(2) {
+        assert_eq!(pair[0].1, pair[1].0);
+    }
+    assert_eq!(inserted.iter().map(|x| x.2).sum::<usize>(), 4);
+
+    // each advance of the handle is reported once.
+    assert_eq!(compacted, vec![vec![1], vec![2], vec![3]]);
+    assert_eq!(distinguished, vec![vec![1], vec![2], vec![3]]);
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "sqlite")]
+
+extern crate timely;
+extern crate rusqlite;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{Input, Capture, Probe};
+use timely::dataflow::operators::capture::Extract;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::arrange::ArrangeByKey;
+use differential_dataflow::interop::sqlite;
+use differential_dataflow::trace::consolidate;
+
+#[test]
+fn export_accumulated_contents() {
+
+    let (output, exported) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let path = ::std::env::temp_dir().join("differential-sqlite-export.db");
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection().arrange_by_key_hashed();
+            let captured = arranged.as_collection(|key, val: &u64| (key.item, *val)).inner.probe_with(&mut probe).capture();
+            (input, arranged.trace, captured)
+        });
+
+        // value 10 is retracted at time 1, and value 30 arrives at time 2.
+        let rounds = vec![
+            vec![((1u64, 10u64), 1), ((1, 11), 1), ((2, 20), 1)],
+            vec![((1, 10), -1)],
+            vec![((3, 30), 1)],
+        ];
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+
+        // the contents through time 1, which exclude the retracted value and the value at time 2.
+        let rows = sqlite::export(&mut trace, &[RootTimestamp::new(2)], &path, "edges").unwrap();
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let mut statement = connection.prepare("SELECT key, val, count FROM edges ORDER BY val").unwrap();
+        let exported = statement.query_map(&[], |row| (row.get::<_, String>(0), row.get::<_, String>(1), row.get::<_, String>(2)))
+                                .unwrap()
+                                .map(|row| row.unwrap())
+                                .collect::<Vec<_>>();
+        assert_eq!(rows, exported.len());
+        let _ = ::std::fs::remove_file(&path);
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+
+        (output, exported)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(output, vec![(((1, 10), 0), 1), (((1, 10), 1), -1), (((1, 11), 0), 1), (((2, 20), 0), 1), (((3, 30), 2), 1)]);
+    assert_eq!(exported, vec![
+        ("OrdWrapper { item: 1 }".to_owned(), "11".to_owned(), "1".to_owned()),
+        ("OrdWrapper { item: 2 }".to_owned(), "20".to_owned(), "1".to_owned()),
+    ]);
+}
@@ -0,0 +1,79 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{ToStream, Capture};
+use timely::dataflow::operators::capture::Extract;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+use differential_dataflow::AsCollection;
+use differential_dataflow::operators::throttle::ThrottlePerKey;
+
+// the number of updates to each key at each time.
+fn counts<T: Ord+Clone>(extracted: Vec<(T, Vec<((u64, u64), T, isize)>)>) -> Vec<(T, u64, usize)> {
+    let mut times = extracted.into_iter().flat_map(|(_, data)| data).map(|((key, _), time, _)| (time, key)).collect::<Vec<_>>();
+    times.sort();
+    let mut result: Vec<(T, u64, usize)> = Vec::new();
+    for (time, key) in times {
+        let increment = match result.last_mut() {
+            Some(&mut (ref last_time, last_key, ref mut count)) if *last_time == time && last_key == key => { *count += 1; false },
+            _ => true,
+        };
+        if increment { result.push((time, key, 1)); }
+    }
+    result
+}
+
+#[test]
+fn throttle_defers_excess() {
+
+    let data = timely::example(|scope| {
+
+        // five updates to key 0 and one to key 1, at time 0.
+        let updates = vec![((0, 0), RootTimestamp::new(0), 1isize), ((0, 1), RootTimestamp::new(0), 1), ((0, 2), RootTimestamp::new(0), 1),
+                           ((0, 3), RootTimestamp::new(0), 1), ((0, 4), RootTimestamp::new(0), 1), ((1, 0), RootTimestamp::new(0), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        updates.throttle_per_key(2, |time: &Product<RootTimestamp, u64>| RootTimestamp::new(time.inner + 1))
+               .inner.capture()
+    });
+
+    let extracted = data.extract();
+
+    // no updates are lost or changed.
+    let mut updates = extracted.iter().flat_map(|x| x.1.iter().map(|&(data, _, diff)| (data, diff))).collect::<Vec<_>>();
+    updates.sort();
+    assert_eq!(updates, vec![((0, 0), 1), ((0, 1), 1), ((0, 2), 1), ((0, 3), 1), ((0, 4), 1), ((1, 0), 1)]);
+
+    // key 0 passes two updates at each time, and key 1 is not delayed.
+    assert_eq!(counts(extracted), vec![
+        (RootTimestamp::new(0), 0, 2),
+        (RootTimestamp::new(0), 1, 1),
+        (RootTimestamp::new(1), 0, 2),
+        (RootTimestamp::new(2), 0, 1),
+    ]);
+}
+
+#[test]
+fn throttle_deferred_precede() {
+
+    let data = timely::example(|scope| {
+
+        // three updates to key 0 at time 0, and two more at time 1.
+        let updates = vec![((0, 0), RootTimestamp::new(0), 1isize), ((0, 1), RootTimestamp::new(0), 1), ((0, 2), RootTimestamp::new(0), 1),
+                           ((0, 3), RootTimestamp::new(1), 1), ((0, 4), RootTimestamp::new(1), 1)]
+                        .into_iter().to_stream(scope).as_collection();
+
+        updates.throttle_per_key(2, |time: &Product<RootTimestamp, u64>| RootTimestamp::new(time.inner + 1))
+               .inner.capture()
+    });
+
+    // the update deferred from time 0 precedes those originally at time 1, and so one of those moves to time 2.
+    let extracted = data.extract();
+    let deferred = extracted.iter().flat_map(|x| x.1.iter()).filter(|x| x.1 == RootTimestamp::new(1)).any(|x| (x.0).1 == 2);
+    assert!(deferred);
+    assert_eq!(counts(extracted), vec![
+        (RootTimestamp::new(0), 0, 2),
+        (RootTimestamp::new(1), 0, 2),
+        (RootTimestamp::new(2), 0, 1),
+    ]);
+}
@@ -0,0 +1,99 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{Input, Capture};
+use timely::dataflow::operators::capture::Extract;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::upsert::ArrangeUpsert;
+use differential_dataflow::trace::consolidate;
+
+// applies each epoch of changes at its own time, and returns the consolidated updates to the arrangement.
+fn upsert(epochs: Vec<Vec<(u64, Option<char>)>>) -> Vec<(usize, (u64, char), isize)> {
+
+    let captured = timely::execute(timely::Configuration::Thread, move |worker| {
+
+        let (mut input, captured) = worker.dataflow(|scope| {
+            let (input, changes) = scope.new_input();
+            let captured = changes.arrange_upsert()
+                                  .as_collection(|k: &OrdWrapper<u64>, v: &char| (k.item, *v))
+                                  .inner
+                                  .capture();
+            (input, captured)
+        });
+
+        for (time, changes) in epochs.clone().into_iter().enumerate() {
+            if time != input.time().inner {
+                input.advance_to(time);
+            }
+            for change in changes {
+                input.send(change);
+            }
+        }
+        input.close();
+
+        captured
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    let mut updates = captured.extract()
+                              .into_iter()
+                              .flat_map(|(_, data)| data)
+                              .map(|(data, time, diff)| ((time.inner, data), diff))
+                              .collect::<Vec<_>>();
+    consolidate(&mut updates, 0);
+    updates.into_iter().map(|((time, data), diff)| (time, data, diff)).collect()
+}
+
+#[test]
+fn upsert_replaces_values() {
+
+    let updates = upsert(vec![
+        vec![(0, Some('a')), (1, Some('b'))],
+        vec![(0, Some('c')), (1, None), (2, None)],
+        vec![(1, Some('d')), (0, Some('c'))],
+        vec![(0, None)],
+    ]);
+
+    assert_eq!(updates, vec![
+        (0, (0, 'a'), 1),
+        (0, (1, 'b'), 1),
+        (1, (0, 'a'), -1),
+        (1, (0, 'c'), 1),
+        (1, (1, 'b'), -1),
+        (2, (1, 'd'), 1),
+        (3, (0, 'c'), -1),
+    ]);
+}
+
+#[test]
+fn upsert_last_write_wins() {
+
+    // of several changes to a key at one time, the last received wins.
+    let updates = upsert(vec![
+        vec![(0, Some('a')), (0, Some('b')), (1, Some('x')), (1, None)],
+        vec![(0, None), (0, Some('c'))],
+    ]);
+
+    assert_eq!(updates, vec![
+        (0, (0, 'b'), 1),
+        (1, (0, 'b'), -1),
+        (1, (0, 'c'), 1),
+    ]);
+}
+
+#[test]
+fn upsert_empty_epochs() {
+
+    // times without changes leave the arrangement as it was.
+    let updates = upsert(vec![
+        vec![(5, Some('a'))],
+        vec![],
+        vec![],
+        vec![(5, Some('b'))],
+    ]);
+
+    assert_eq!(updates, vec![
+        (0, (5, 'a'), 1),
+        (3, (5, 'a'), -1),
+        (3, (5, 'b'), 1),
+    ]);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "validate")]
+
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::{Input, Capture, Probe};
+use timely::dataflow::operators::capture::Extract;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::arrange::{ArrangeByKey, TraceAgent};
+use differential_dataflow::trace::{Trace, TraceReader, Builder, consolidate};
+use differential_dataflow::trace::implementations::ord::{OrdValBuilder, OrdValSpine};
+
+type Time = Product<RootTimestamp, usize>;
+type TestSpine = OrdValSpine<OrdWrapper<u64>, u64, Time, isize>;
+
+#[test]
+fn arranged_rounds_validate() {
+
+    let output = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, mut trace, captured) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection().arrange_by_key_hashed();
+            let captured = arranged.as_collection(|key, val: &u64| (key.item, *val)).inner.probe_with(&mut probe).capture();
+            (input, arranged.trace, captured)
+        });
+
+        // each round retracts a value of the previous round, and the handle follows the rounds.
+        for round in 0 .. 5usize {
+            let time = *input.time();
+            if round > 0 { input.send(((round as u64 - 1, round as u64 - 1), time, -1)); }
+            input.send(((round as u64, round as u64), time, 1));
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+            trace.advance_by(&[RootTimestamp::new(round)]);
+            trace.distinguish_since(&[RootTimestamp::new(round)]);
+        }
+
+        input.close();
+        while worker.step() { }
+
+        let mut output = captured.extract()
+                                 .into_iter()
+                                 .flat_map(|(_, data)| data)
+                                 .map(|(data, time, diff)| ((data, time.inner), diff))
+                                 .collect::<Vec<_>>();
+        consolidate(&mut output, 0);
+        output
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    let mut expected = Vec::new();
+    for round in 0 .. 5usize {
+        expected.push((((round as u64, round as u64), round), 1));
+        if round < 4 { expected.push((((round as u64, round as u64), round + 1), -1)); }
+    }
+    assert_eq!(output, expected);
+}
+
+#[test]
+#[should_panic(expected = "validate: reader frontier regressed")]
+fn reader_frontier_regresses() {
+    let (mut reader, _writer) = TraceAgent::<OrdWrapper<u64>, u64, Time, isize, TestSpine>::new(TestSpine::new());
+    reader.advance_by(&[RootTimestamp::new(2)]);
+    reader.advance_by(&[RootTimestamp::new(1)]);
+}
+
+#[test]
+#[should_panic(expected = "validate: sealed frontier regressed")]
+fn sealed_frontier_regresses() {
+    let (_reader, mut writer) = TraceAgent::<OrdWrapper<u64>, u64, Time, isize, TestSpine>::new(TestSpine::new());
+    writer.seal(&[RootTimestamp::new(2)], None);
+    writer.seal(&[RootTimestamp::new(1)], None);
+}
+
+#[test]
+#[should_panic(expected = "validate: batch lower bound does not match prior upper bound")]
+fn batch_skips_interval() {
+    let (_reader, mut writer) = TraceAgent::<OrdWrapper<u64>, u64, Time, isize, TestSpine>::new(TestSpine::new());
+    // the batch begins at time one, leaving time zero without a batch.
+    let mut builder = OrdValBuilder::new();
+    builder.push((OrdWrapper { item: 0 }, 0, RootTimestamp::new(1), 1));
+    builder.push((OrdWrapper { item: 0 }, 1, RootTimestamp::new(1), -1));
+    let batch = builder.done(&[RootTimestamp::new(1)], &[RootTimestamp::new(2)], &[RootTimestamp::new(0)]);
+    writer.seal(&[RootTimestamp::new(2)], Some((RootTimestamp::new(1), batch)));
+}
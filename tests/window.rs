@@ -0,0 +1,85 @@
+extern crate timely;
+extern crate differential_dataflow;
+
+use timely::dataflow::operators::Input;
+use timely::dataflow::operators::probe::Handle;
+use timely::progress::nested::product::Product;
+use timely::progress::timestamp::RootTimestamp;
+
+use differential_dataflow::AsCollection;
+use differential_dataflow::hashable::OrdWrapper;
+use differential_dataflow::operators::arrange::Arrange;
+use differential_dataflow::trace::{TraceReader, Cursor, consolidate};
+use differential_dataflow::trace::implementations::ord::OrdValSpine;
+use differential_dataflow::trace::wrappers::window::TraceWindow;
+
+type Time = Product<RootTimestamp, usize>;
+
+// the consolidated updates presented by `cursor`, with times as rounds.
+fn contents<C: Cursor<OrdWrapper<u64>, char, Time, isize>>(mut cursor: C) -> Vec<((u64, char), usize, isize)> {
+    let mut result = Vec::new();
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let (key, val) = (cursor.key().item, *cursor.val());
+            cursor.map_times(|time, diff| result.push(((key, val, time.inner), diff)));
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+    consolidate(&mut result, 0);
+    result.into_iter().map(|((key, val, time), diff)| ((key, val), time, diff)).collect()
+}
+
+#[test]
+fn window_hides_old_updates() {
+
+    let (four, five) = timely::execute(timely::Configuration::Thread, |worker| {
+
+        let mut probe = Handle::new();
+        let (mut input, trace) = worker.dataflow(|scope| {
+            let (input, updates) = scope.new_input();
+            let arranged = updates.as_collection()
+                                  .map(|(key, val): (u64, char)| (OrdWrapper { item: key }, val))
+                                  .arrange(OrdValSpine::new());
+            arranged.stream.probe_with(&mut probe);
+            (input, arranged.trace)
+        });
+
+        // the window trails the frontier by three rounds.
+        let mut window = TraceWindow::make_from(trace, |time: &Time| RootTimestamp::new(time.inner.saturating_sub(3)));
+
+        let rounds = vec![
+            vec![((1, 'a'), 1), ((2, 'b'), 1)],
+            vec![((3, 'c'), 1)],
+            vec![((2, 'b'), -1), ((4, 'd'), 1)],
+            vec![((5, 'e'), 1)],
+        ];
+
+        for (round, updates) in rounds.into_iter().enumerate() {
+            for (data, diff) in updates {
+                input.send((data, RootTimestamp::new(round), diff));
+            }
+            input.advance_to(round + 1);
+            let time = RootTimestamp::new(round + 1);
+            worker.step_while(|| probe.less_than(&time));
+        }
+
+        // updates at rounds two and three are in the window, including the retraction.
+        window.advance_by(&[RootTimestamp::new(4)]);
+        window.distinguish_since(&[RootTimestamp::new(4)]);
+        let four = contents(window.cursor());
+
+        // once the frontier moves on, the updates at round two leave the window without being retracted.
+        window.advance_by(&[RootTimestamp::new(5)]);
+        let five = contents(window.cursor());
+
+        input.close();
+        while worker.step() { }
+
+        (four, five)
+
+    }).unwrap().join().into_iter().map(|x| x.unwrap()).next().unwrap();
+
+    assert_eq!(four, vec![((2, 'b'), 2, -1), ((4, 'd'), 2, 1), ((5, 'e'), 3, 1)]);
+    assert_eq!(five, vec![((5, 'e'), 3, 1)]);
+}